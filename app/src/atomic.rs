@@ -0,0 +1,149 @@
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufWriter, IntoInnerError, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{Mutex, MutexGuard, Once, PoisonError},
+};
+
+/// The temporary files of writers that have been neither committed nor dropped, which are removed
+/// if the process is interrupted.
+static PENDING: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+static INSTALL_HANDLER: Once = Once::new();
+
+/// The exit status of a process terminated by SIGINT.
+const INTERRUPTED: i32 = 130;
+
+fn pending() -> MutexGuard<'static, Vec<PathBuf>> {
+    PENDING.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn forget(temp_path: &Path) {
+    pending().retain(|path| path != temp_path);
+}
+
+fn install_handler() {
+    INSTALL_HANDLER.call_once(|| {
+        // If the handler cannot be installed, an interrupt merely leaves temporary files behind.
+        let _ = ctrlc::set_handler(|| {
+            for path in pending().drain(..) {
+                let _ = fs::remove_file(path);
+            }
+            process::exit(INTERRUPTED);
+        });
+    });
+}
+
+/// A file writer that writes to a temporary file in the destination directory, and renames it
+/// over the destination only when [`AtomicWriter::commit`] is called.
+///
+/// The destination is therefore never left truncated: if the writer is dropped without being
+/// committed, or the process is interrupted, the temporary file is removed instead.
+pub struct AtomicWriter {
+    path: PathBuf,
+    temp_path: PathBuf,
+    maybe_writer: Option<BufWriter<File>>,
+}
+
+impl AtomicWriter {
+    /// Creates a temporary file next to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `path` has no file name, or the temporary file cannot be created.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<AtomicWriter> {
+        install_handler();
+        let path = path.as_ref().to_path_buf();
+        let Some(file_name) = path.file_name() else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("not a file path: {}", path.display()),
+            ));
+        };
+        let mut temp_name = OsString::from(".");
+        temp_name.push(file_name);
+        temp_name.push(format!(".{}.tmp", process::id()));
+        let temp_path = path.with_file_name(temp_name);
+        let file = File::create(&temp_path)?;
+        pending().push(temp_path.clone());
+        Ok(AtomicWriter {
+            path,
+            temp_path,
+            maybe_writer: Some(BufWriter::new(file)),
+        })
+    }
+
+    /// Flushes and syncs the temporary file, and renames it over the destination.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if flushing, syncing or renaming fails, in which case the destination is
+    /// left untouched.
+    pub fn commit(mut self) -> io::Result<()> {
+        let Some(writer) = self.maybe_writer.take() else {
+            return Ok(());
+        };
+        let file = writer.into_inner().map_err(IntoInnerError::into_error)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&self.temp_path, &self.path)?;
+        forget(&self.temp_path);
+        Ok(())
+    }
+
+    fn writer(&mut self) -> &mut BufWriter<File> {
+        self.maybe_writer
+            .as_mut()
+            .expect("AtomicWriter is only uncommitted while it can be written to")
+    }
+}
+
+impl Write for AtomicWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writer().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer().flush()
+    }
+}
+
+impl Drop for AtomicWriter {
+    fn drop(&mut self) {
+        if pending().contains(&self.temp_path) {
+            drop(self.maybe_writer.take());
+            let _ = fs::remove_file(&self.temp_path);
+            forget(&self.temp_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, io::Write, process};
+
+    use super::AtomicWriter;
+
+    #[test]
+    fn only_committed_output_replaces_the_destination() {
+        let dir = env::temp_dir().join(format!("hbt-atomic-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("output.yaml");
+        fs::write(&path, "old").unwrap();
+
+        let mut writer = AtomicWriter::create(&path).unwrap();
+        writer.write_all(b"partial").unwrap();
+        drop(writer);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old");
+
+        let mut writer = AtomicWriter::create(&path).unwrap();
+        writer.write_all(b"new").unwrap();
+        writer.commit().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    ffi::OsString,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+
+/// The suffix of backup file names.
+const SUFFIX: &str = ".bak";
+
+/// How to back up an existing output before it is replaced.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Backup {
+    /// The number of backups of each output to keep, oldest removed first. Every backup is kept
+    /// if unset, and the newest always is.
+    pub maybe_keep: Option<usize>,
+}
+
+impl Backup {
+    /// Copies `path`, if it exists, to a timestamped backup next to it, as in
+    /// `archive.yaml.20240101T120000.000000Z.bak`, and removes its oldest backups beyond
+    /// [`Backup::maybe_keep`]. Returns the path of the new backup.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the copy fails, or existing backups cannot be listed or removed.
+    pub fn create(self, path: &Path) -> io::Result<Option<PathBuf>> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let Some(file_name) = path.file_name() else {
+            return Ok(None);
+        };
+        let mut backup_name = file_name.to_os_string();
+        backup_name.push(Utc::now().format(".%Y%m%dT%H%M%S%.6fZ").to_string());
+        backup_name.push(SUFFIX);
+        let backup_path = path.with_file_name(backup_name);
+        fs::copy(path, &backup_path)?;
+        if let Some(keep) = self.maybe_keep {
+            let backups = backups(path)?;
+            let excess = backups.len().saturating_sub(keep.max(1));
+            for old in &backups[..excess] {
+                fs::remove_file(old)?;
+            }
+        }
+        Ok(Some(backup_path))
+    }
+}
+
+/// Returns whether `backup` is a backup of `path` made by [`Backup::create`].
+#[must_use]
+pub fn is_backup(backup: &Path, path: &Path) -> bool {
+    let (Some(name), Some(file_name)) = (backup.file_name(), path.file_name()) else {
+        return false;
+    };
+    let mut prefix = OsString::from(file_name);
+    prefix.push(".");
+    backup.parent() == path.parent()
+        && name
+            .to_string_lossy()
+            .strip_prefix(prefix.to_string_lossy().as_ref())
+            .and_then(|rest| rest.strip_suffix(SUFFIX))
+            .is_some_and(|stamp| {
+                stamp.ends_with('Z') && stamp.chars().next().is_some_and(|c| c.is_ascii_digit())
+            })
+}
+
+/// Returns the backups of `path` made by [`Backup::create`], oldest first.
+///
+/// # Errors
+///
+/// Returns an error if the directory containing `path` cannot be read.
+pub fn backups(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if path.file_name().is_none() {
+        return Ok(Vec::new());
+    }
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let mut ret = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let candidate = path.with_file_name(entry?.file_name());
+        if is_backup(&candidate, path) {
+            ret.push(candidate);
+        }
+    }
+    // The timestamps sort chronologically.
+    ret.sort();
+    Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::{Backup, backups, is_backup};
+
+    #[test]
+    fn backups_are_rotated() {
+        let dir = env::temp_dir().join(format!("hbt-backup-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("archive.yaml");
+
+        let backup = Backup {
+            maybe_keep: Some(2),
+        };
+        assert_eq!(backup.create(&path).unwrap(), None);
+
+        for contents in ["1", "2", "3"] {
+            fs::write(&path, contents).unwrap();
+            assert!(backup.create(&path).unwrap().is_some());
+        }
+        let kept = backups(&path).unwrap();
+        assert_eq!(kept.len(), 2);
+        assert_eq!(fs::read_to_string(&kept[0]).unwrap(), "2");
+        assert_eq!(fs::read_to_string(&kept[1]).unwrap(), "3");
+
+        Backup::default().create(&path).unwrap();
+        assert_eq!(backups(&path).unwrap().len(), 3);
+        assert!(is_backup(&kept[0], &path));
+        assert!(!is_backup(&path, &path));
+        assert!(!is_backup(
+            &dir.join("nested").join(kept[0].file_name().unwrap()),
+            &path
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
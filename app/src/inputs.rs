@@ -0,0 +1,139 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use glob::{MatchOptions, Pattern};
+
+use hbt_core::InputFormat;
+
+/// Returns whether `path` names several inputs: a directory, or a glob pattern such as
+/// `notes/**/*.md`.
+#[must_use]
+pub fn is_multiple(path: &Path) -> bool {
+    path.is_dir() || is_pattern(path)
+}
+
+fn is_pattern(path: &Path) -> bool {
+    path.to_str()
+        .is_some_and(|s| s.contains(['*', '?', '[']) && !path.exists())
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.'))
+}
+
+fn match_options() -> MatchOptions {
+    MatchOptions {
+        require_literal_leading_dot: true,
+        ..MatchOptions::new()
+    }
+}
+
+fn walk(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if is_hidden(&path) {
+            continue;
+        }
+        if path.is_dir() {
+            walk(&path, paths)?;
+        } else if InputFormat::detect(&path).is_some() {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Expands a directory or glob pattern into the files it names, in sorted order.
+///
+/// A directory is searched recursively for files in a recognized input format. A glob pattern
+/// matches any files, whatever their format. Either way, hidden files and directories are skipped.
+///
+/// # Errors
+///
+/// Returns an error if a directory cannot be read or the pattern is malformed.
+pub fn expand(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut ret = Vec::new();
+    if path.is_dir() {
+        walk(path, &mut ret)?;
+    } else {
+        let pattern = path.to_string_lossy();
+        let paths = glob::glob_with(&pattern, match_options())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        for entry in paths {
+            let path = entry.map_err(io::Error::from)?;
+            if path.is_file() {
+                ret.push(path);
+            }
+        }
+    }
+    ret.sort();
+    Ok(ret)
+}
+
+/// Returns whether `path` is one of the files that `input`, a directory or glob pattern, expands
+/// to. Only the name of `path` is considered, so a file that has just been created or removed
+/// matches too.
+#[must_use]
+pub fn matches(input: &Path, path: &Path) -> bool {
+    if input.is_dir() {
+        path.strip_prefix(input).is_ok_and(|rest| {
+            !rest.ancestors().any(is_hidden) && InputFormat::detect(path).is_some()
+        })
+    } else {
+        Pattern::new(&input.to_string_lossy())
+            .is_ok_and(|pattern| pattern.matches_path_with(path, match_options()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, process};
+
+    use super::{expand, is_multiple, matches};
+
+    #[test]
+    fn directories_and_patterns_expand_to_files() {
+        let dir = env::temp_dir().join(format!("hbt-inputs-{}", process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::create_dir_all(dir.join(".hidden")).unwrap();
+        for file in ["a.md", "b.csv", "nested/c.html", ".hidden/d.md"] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+
+        assert!(is_multiple(&dir));
+        assert_eq!(
+            expand(&dir).unwrap(),
+            [dir.join("a.md"), dir.join("nested/c.html")]
+        );
+
+        let pattern = dir.join("**").join("*.md");
+        assert!(is_multiple(&pattern));
+        assert_eq!(expand(&pattern).unwrap(), [dir.join("a.md")]);
+        assert!(!is_multiple(&dir.join("a.md")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn paths_match_without_existing() {
+        let dir = env::temp_dir().join(format!("hbt-inputs-matches-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        assert!(matches(&dir, &dir.join("nested/new.md")));
+        assert!(!matches(&dir, &dir.join("new.bak")));
+        assert!(!matches(&dir, &dir.join(".hidden/new.md")));
+        assert!(!matches(&dir, &dir.join(".new.md.1.tmp")));
+        assert!(!matches(&dir, &env::temp_dir().join("new.md")));
+
+        let pattern = dir.join("**").join("*.md");
+        assert!(matches(&pattern, &dir.join("nested/new.md")));
+        assert!(!matches(&pattern, &dir.join("new.html")));
+        assert!(!matches(&pattern, &dir.join(".hidden/new.md")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,879 @@
+#![cfg_attr(not(feature = "mmap"), forbid(unsafe_code))]
+#![cfg_attr(feature = "mmap", deny(unsafe_code))]
+#![warn(clippy::pedantic)]
+#![deny(clippy::unwrap_in_result)]
+
+pub mod atomic;
+pub mod backup;
+pub mod inputs;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod select;
+pub mod update;
+
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use thiserror::Error;
+
+use hbt_core::{
+    Anomaly, InputFormat, OutputFormat, ParseError, ParseOptions, ParseReport, UnparseError,
+    UnparseOptions,
+    collection::{Collection, CrossYearEdge},
+    embedding, entity, markdown, notes, redact, shortcuts, transform,
+};
+#[cfg(feature = "snapshot")]
+use hbt_core::{entity::Url, snapshot};
+
+use crate::{atomic::AtomicWriter, backup::Backup, select::Selection, update::Updates};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+
+    #[error(transparent)]
+    Unparse(#[from] UnparseError),
+
+    #[error(transparent)]
+    Notes(#[from] notes::Error),
+
+    #[error(transparent)]
+    Shortcuts(#[from] shortcuts::Error),
+
+    #[error(transparent)]
+    Archive(#[from] serde_norway::Error),
+
+    #[error(transparent)]
+    Entity(#[from] entity::Error),
+
+    #[error(transparent)]
+    Embedding(#[from] embedding::Error),
+
+    #[error(transparent)]
+    Transform(#[from] transform::Error),
+
+    #[error("No parser for file: {}", .0.display())]
+    NoParser(PathBuf),
+
+    #[error("{}: {}", .0.display(), .1)]
+    File(PathBuf, Box<Error>),
+
+    #[error("Cannot detect the output format of {}; specify it explicitly", .0.display())]
+    UnknownOutputFormat(PathBuf),
+
+    #[error(
+        "Cannot detect the output format of {}, which may be {}; specify it explicitly",
+        .0.display(),
+        .1
+    )]
+    AmbiguousOutputFormat(PathBuf, String),
+
+    #[error("Only one output format can be written to stdout; give each an output file")]
+    SeveralToStdout,
+
+    #[error("Each output format must be paired with an output file")]
+    UnpairedFormat,
+
+    #[error("SQLite output requires the `sqlite` feature")]
+    SqliteUnsupported,
+
+    #[error("Sync would add {0} entities, more than the maximum of {1}")]
+    TooManyAdded(usize, usize),
+
+    #[error("Sync would remove {0} entities, more than the maximum of {1}")]
+    TooManyRemoved(usize, usize),
+
+    #[error("Input contains no entities")]
+    NoEntities,
+
+    #[error("Expected {0} entities, found {1}")]
+    UnexpectedCount(CountRange, usize),
+
+    #[error("URL not found: {0}")]
+    UrlNotFound(String),
+
+    #[error("No path from {0} to {1}")]
+    NoPath(String, String),
+}
+
+/// What to read, and how to find the files it names.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Input {
+    /// A file, a directory, or a glob pattern such as `notes/**/*.md`.
+    pub path: PathBuf,
+    /// The format of the file, or of every file in the directory or pattern. If `None`, each
+    /// file's format is detected from its extension.
+    pub maybe_format: Option<InputFormat>,
+    /// Read `path` as a directory of markdown notes, linking notes that link to each other.
+    pub notes: bool,
+    /// Read `path` as a directory of `.webloc` and `.url` shortcut files.
+    pub shortcuts: bool,
+    /// A `strftime` pattern with which to infer the default date of each file from the start of
+    /// its name, falling back to the default date in the parse options.
+    pub maybe_date_pattern: Option<String>,
+    /// When `path` names several files, skip those that fail to parse instead of failing.
+    pub lenient: bool,
+}
+
+impl Input {
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Input {
+        Input {
+            path: path.into(),
+            ..Input::default()
+        }
+    }
+
+    /// Returns `options` with the default date inferred from the name of `file`, if requested.
+    fn dated(&self, file: &Path, options: &ParseOptions) -> ParseOptions {
+        let mut ret = options.clone();
+        if let Some(pattern) = &self.maybe_date_pattern {
+            ret.default_date =
+                markdown::date_from_file_name(file, pattern).or(options.default_date);
+        }
+        ret
+    }
+}
+
+/// A collection read from an [`Input`].
+#[derive(Debug)]
+pub struct Loaded {
+    pub coll: Collection,
+    pub anomalies: Vec<Anomaly>,
+    /// The files that failed to parse and were skipped, in a lenient [`Input`].
+    pub skipped: Vec<(PathBuf, Error)>,
+}
+
+/// Where to write a collection, and in which format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Output {
+    pub format: OutputFormat,
+    /// The output file, or `None` for stdout.
+    pub maybe_path: Option<PathBuf>,
+}
+
+/// Pairs each output file with the output format in the same position, or else the format
+/// detected from its extension. Without output files, a single format is written to stdout.
+///
+/// # Errors
+///
+/// Returns an error if there are more formats than files, if several formats would be written to
+/// stdout, or if a file's format cannot be detected.
+pub fn outputs(formats: &[OutputFormat], paths: &[PathBuf]) -> Result<Vec<Output>, Error> {
+    if paths.is_empty() {
+        return match formats {
+            [] => Ok(Vec::new()),
+            [format] => Ok(vec![Output {
+                format: *format,
+                maybe_path: None,
+            }]),
+            _ => Err(Error::SeveralToStdout),
+        };
+    }
+    if formats.len() > paths.len() {
+        return Err(Error::UnpairedFormat);
+    }
+    paths
+        .iter()
+        .enumerate()
+        .map(|(index, path)| {
+            let format = match formats.get(index) {
+                Some(&format) => format,
+                None => detect_output(path)?,
+            };
+            Ok(Output {
+                format,
+                maybe_path: Some(path.clone()),
+            })
+        })
+        .collect()
+}
+
+/// Detects the output format of `path` from its extension, naming the formats it may be meant to
+/// be written in if the extension is not enough to tell.
+fn detect_output(path: &Path) -> Result<OutputFormat, Error> {
+    if let Some(format) = OutputFormat::detect(path) {
+        return Ok(format);
+    }
+    let maybe_ext = path.extension().and_then(|ext| ext.to_str());
+    let candidates: Vec<&'static str> = maybe_ext
+        .into_iter()
+        .flat_map(OutputFormat::with_extension)
+        .map(Into::into)
+        .collect();
+    match candidates.split_last() {
+        None => Err(Error::UnknownOutputFormat(path.to_path_buf())),
+        Some((last, [])) => Err(Error::AmbiguousOutputFormat(
+            path.to_path_buf(),
+            (*last).to_string(),
+        )),
+        Some((last, rest)) => Err(Error::AmbiguousOutputFormat(
+            path.to_path_buf(),
+            format!("{} or {last}", rest.join(", ")),
+        )),
+    }
+}
+
+/// Parses a single file, adding the anomalies found in it to `anomalies`. Without a format, `file`
+/// is read as a directory of markdown notes.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or parsed.
+pub fn load(
+    file: &Path,
+    maybe_format: Option<InputFormat>,
+    options: &ParseOptions,
+    anomalies: &mut Vec<Anomaly>,
+) -> Result<Collection, Error> {
+    let Some(format) = maybe_format else {
+        return Ok(configured(Collection::from_notes(file)?, options));
+    };
+    let f = File::open(file)?;
+    #[cfg(feature = "mmap")]
+    if let Some(map) = mmap::map_if_large(&f)? {
+        let (coll, report) = format.parse_with_report(&mut &map[..], options)?;
+        anomalies.extend(report.anomalies);
+        return Ok(configured(coll, options));
+    }
+    let mut reader = BufReader::new(f);
+    let (coll, report) = format.parse_with_report(&mut reader, options)?;
+    anomalies.extend(report.anomalies);
+    Ok(configured(coll, options))
+}
+
+/// Reads an archive written in YAML, e.g. by `-t yaml`.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or is not a valid archive.
+pub fn load_archive(path: &Path) -> Result<Collection, Error> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(serde_norway::from_reader(reader)?)
+}
+
+/// Applies the parse options to a loaded collection, whatever its format.
+fn configured(mut coll: Collection, options: &ParseOptions) -> Collection {
+    coll.set_privacy_policy(options.privacy_policy);
+    coll.set_merge_strategies(options.merge_strategies);
+    coll.remove_matching(&options.ignore);
+    coll
+}
+
+/// Loads the input, merging the files it names if it is a directory or glob pattern.
+///
+/// # Errors
+///
+/// Returns an error if the input cannot be read, or if any file it names cannot be parsed and the
+/// input is not lenient.
+pub fn load_input(input: &Input, options: &ParseOptions) -> Result<Loaded, Error> {
+    let mut ret = Loaded {
+        coll: Collection::new(),
+        anomalies: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let file = input.path.as_path();
+    if input.shortcuts {
+        ret.coll = configured(Collection::from_shortcuts(file)?, options);
+        return Ok(ret);
+    }
+    if input.notes {
+        ret.coll = load(file, None, options, &mut ret.anomalies)?;
+        return Ok(ret);
+    }
+    if !inputs::is_multiple(file) {
+        let maybe_format = input.maybe_format.or_else(|| InputFormat::detect(file));
+        let Some(format) = maybe_format else {
+            return Err(Error::NoParser(file.to_path_buf()));
+        };
+        ret.coll = load(
+            file,
+            Some(format),
+            &input.dated(file, options),
+            &mut ret.anomalies,
+        )?;
+        return Ok(ret);
+    }
+
+    ret.coll.set_privacy_policy(options.privacy_policy);
+    ret.coll.set_merge_strategies(options.merge_strategies);
+    for path in inputs::expand(file)? {
+        let result = match input.maybe_format.or_else(|| InputFormat::detect(&path)) {
+            Some(format) => load(
+                &path,
+                Some(format),
+                &input.dated(&path, options),
+                &mut ret.anomalies,
+            ),
+            None => Err(Error::NoParser(path.clone())),
+        };
+        match result {
+            Ok(mut part) => {
+                part.set_source(&path.to_string_lossy());
+                ret.coll.merge(part);
+            }
+            Err(err) if input.lenient => ret.skipped.push((path, err)),
+            Err(err) => return Err(Error::File(path, Box::new(err))),
+        }
+    }
+    Ok(ret)
+}
+
+/// Writes `coll` to `output`. Files are replaced atomically, except `SQLite` databases, which are
+/// appended to if `append` is set. An existing file is first backed up if `maybe_backup` is set.
+///
+/// # Errors
+///
+/// Returns an error if serialization or writing fails, or if `SQLite` output is requested without
+/// the `sqlite` feature.
+#[cfg_attr(not(feature = "sqlite"), allow(unused_variables))]
+pub fn write(
+    output: &Output,
+    coll: &Collection,
+    options: &UnparseOptions,
+    append: bool,
+    maybe_backup: Option<Backup>,
+) -> Result<(), Error> {
+    #[cfg(not(feature = "sqlite"))]
+    if output.format == OutputFormat::Sqlite {
+        return Err(Error::SqliteUnsupported);
+    }
+    let Some(path) = &output.maybe_path else {
+        let stdout = io::stdout();
+        let mut writer = BufWriter::new(stdout);
+        output
+            .format
+            .unparse_with_options(&mut writer, coll, options)?;
+        writer.flush()?;
+        return Ok(());
+    };
+    #[cfg(feature = "sqlite")]
+    if output.format == OutputFormat::Sqlite {
+        if let Some(backup) = maybe_backup {
+            backup.create(path)?;
+        }
+        coll.to_sqlite(path, append)
+            .map_err(|err| Error::Unparse(err.into()))?;
+        return Ok(());
+    }
+    if output.format == OutputFormat::Karakeep
+        && let Some(size) = options.karakeep_chunk_size
+    {
+        return write_karakeep_chunks(path, coll, size, maybe_backup);
+    }
+    let mut writer = AtomicWriter::create(path)?;
+    output
+        .format
+        .unparse_with_options(&mut writer, coll, options)?;
+    if let Some(backup) = maybe_backup {
+        backup.create(path)?;
+    }
+    writer.commit()?;
+    Ok(())
+}
+
+/// Writes `coll` in Karakeep's import format to files of at most `size` bookmarks, named after
+/// `path` with the chunk's number appended to its stem, e.g. `archive-1.json`. An empty collection
+/// is written to a single empty chunk. Numbered chunks left over from a previous, longer export
+/// are removed.
+///
+/// # Panics
+///
+/// Panics if `size` is zero.
+fn write_karakeep_chunks(
+    path: &Path,
+    coll: &Collection,
+    size: usize,
+    maybe_backup: Option<Backup>,
+) -> Result<(), Error> {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map_or(String::new(), |ext| format!(".{}", ext.to_string_lossy()));
+    let chunk_path = |n: usize| path.with_file_name(format!("{stem}-{n}{extension}"));
+    let chunks = coll.len().div_ceil(size).max(1);
+    for n in 0..chunks {
+        let chunk_path = chunk_path(n + 1);
+        let start = n * size;
+        let mut writer = AtomicWriter::create(&chunk_path)?;
+        coll.to_karakeep_json_range(&mut writer, start..coll.len().min(start + size))
+            .map_err(|err| Error::Unparse(err.into()))?;
+        if let Some(backup) = maybe_backup {
+            backup.create(&chunk_path)?;
+        }
+        writer.commit()?;
+    }
+    let mut n = chunks + 1;
+    while chunk_path(n).exists() {
+        fs::remove_file(chunk_path(n))?;
+        n += 1;
+    }
+    Ok(())
+}
+
+/// An expected number of entities: exactly `N`, written `N`, or within `MIN..MAX`, where either
+/// bound may be left out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountRange {
+    pub min: Option<usize>,
+    pub max: Option<usize>,
+}
+
+impl CountRange {
+    #[must_use]
+    pub fn contains(self, count: usize) -> bool {
+        self.min.is_none_or(|min| count >= min) && self.max.is_none_or(|max| count <= max)
+    }
+}
+
+impl fmt::Display for CountRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => write!(f, "{min}"),
+            (min, max) => {
+                if let Some(min) = min {
+                    write!(f, "{min}")?;
+                }
+                f.write_str("..")?;
+                if let Some(max) = max {
+                    write!(f, "{max}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl FromStr for CountRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<CountRange, String> {
+        let parse = |s: &str| -> Result<Option<usize>, String> {
+            let s = s.trim();
+            if s.is_empty() {
+                return Ok(None);
+            }
+            s.parse()
+                .map(Some)
+                .map_err(|err| format!("invalid count '{s}': {err}"))
+        };
+        let (min, max) = if let Some((min, max)) = s.split_once("..") {
+            (parse(min)?, parse(max)?)
+        } else {
+            let n = parse(s)?.ok_or_else(|| "empty count".to_string())?;
+            (Some(n), Some(n))
+        };
+        if let (Some(min), Some(max)) = (min, max)
+            && min > max
+        {
+            return Err(format!("invalid range: {min} > {max}"));
+        }
+        Ok(CountRange { min, max })
+    }
+}
+
+/// Writing one file per year of creation, named after the year, e.g. `2024.yaml`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitByYear {
+    pub dir: PathBuf,
+    pub format: OutputFormat,
+}
+
+/// A conversion: an input, how to read it, the changes to make to it and the entities to keep,
+/// and where to write the result.
+#[derive(Default)]
+pub struct Config {
+    pub input: Input,
+    pub parse_options: ParseOptions,
+    pub updates: Updates,
+    /// Fail if the updated collection is empty.
+    pub fail_on_empty: bool,
+    /// Fail unless the number of entities in the updated collection is in this range.
+    pub maybe_expected_count: Option<CountRange>,
+    pub selection: Selection,
+    /// Save a snapshot of each entity's page to this directory, in this format.
+    #[cfg(feature = "snapshot")]
+    pub maybe_snapshot: Option<(PathBuf, snapshot::Format)>,
+    pub maybe_redact: Option<redact::Policy>,
+    pub outputs: Vec<Output>,
+    /// Write one file per year instead of the outputs.
+    pub maybe_split_by_year: Option<SplitByYear>,
+    pub unparse_options: UnparseOptions,
+    /// Append to `SQLite` outputs rather than replacing them.
+    pub append: bool,
+    /// Back up existing outputs before replacing them.
+    pub maybe_backup: Option<Backup>,
+    /// Make the changes without writing anything: neither the outputs nor the sync report or
+    /// label changelog.
+    pub dry_run: bool,
+}
+
+/// The outcome of [`run`].
+#[derive(Debug)]
+pub struct Summary {
+    /// The collection as written to the outputs.
+    pub coll: Collection,
+    /// On a dry run, the collection as read, before it was changed.
+    pub maybe_before: Option<Collection>,
+    /// What was found while reading the input.
+    pub report: ParseReport,
+    /// The files that failed to parse and were skipped, in a lenient [`Input`].
+    pub skipped: Vec<(PathBuf, Error)>,
+    pub updates: update::Report,
+    /// The seed of the sample, if one was taken.
+    pub maybe_seed: Option<u64>,
+    /// The pages that could not be saved, and why.
+    #[cfg(feature = "snapshot")]
+    pub snapshot_failures: Vec<(Url, snapshot::Error)>,
+    pub maybe_redaction: Option<redact::Report>,
+    /// The edges between entities of different years, which were dropped by
+    /// [`Config::maybe_split_by_year`].
+    pub cross_year_edges: Vec<CrossYearEdge>,
+}
+
+fn validate(config: &Config, coll: &Collection) -> Result<(), Error> {
+    let length = coll.len();
+    if config.fail_on_empty && length == 0 {
+        return Err(Error::NoEntities);
+    }
+    if let Some(expected) = config.maybe_expected_count
+        && !expected.contains(length)
+    {
+        return Err(Error::UnexpectedCount(expected, length));
+    }
+    Ok(())
+}
+
+/// Writes `coll` to the outputs of `config`, or one file per year, returning the edges dropped
+/// between years.
+fn write_outputs(config: &Config, coll: &Collection) -> Result<Vec<CrossYearEdge>, Error> {
+    let Some(split) = &config.maybe_split_by_year else {
+        for output in &config.outputs {
+            write(
+                output,
+                coll,
+                &config.unparse_options,
+                config.append,
+                config.maybe_backup,
+            )?;
+        }
+        return Ok(Vec::new());
+    };
+    let extension = split.format.extension();
+    let partition = coll.partition_by_year();
+    fs::create_dir_all(&split.dir)?;
+    for (year, part) in &partition.years {
+        let output = Output {
+            format: split.format,
+            maybe_path: Some(split.dir.join(format!("{year}.{extension}"))),
+        };
+        write(
+            &output,
+            part,
+            &config.unparse_options,
+            config.append,
+            config.maybe_backup,
+        )?;
+    }
+    Ok(partition.cross_year_edges)
+}
+
+/// Reads the input, makes the updates, checks the number of entities, selects the entities to
+/// keep, and writes them to each output.
+///
+/// # Errors
+///
+/// Returns the first error from loading, updating, checking, selecting or writing.
+pub fn run(config: &Config) -> Result<Summary, Error> {
+    let Loaded {
+        mut coll,
+        anomalies,
+        skipped,
+    } = load_input(&config.input, &config.parse_options)?;
+    let mut report = ParseReport::from(&coll);
+    report.anomalies = anomalies;
+    let maybe_before = config.dry_run.then(|| coll.clone());
+
+    let updates = config.updates.apply(&mut coll, config.dry_run)?;
+    validate(config, &coll)?;
+    let (mut coll, maybe_seed) = config.selection.apply(coll)?;
+
+    #[cfg(feature = "snapshot")]
+    let snapshot_failures = match &config.maybe_snapshot {
+        Some((dir, format)) if !config.dry_run => coll.snapshot(dir, *format),
+        _ => Vec::new(),
+    };
+
+    let maybe_redaction = config
+        .maybe_redact
+        .as_ref()
+        .map(|policy| coll.redact(policy));
+
+    let cross_year_edges = if config.dry_run {
+        Vec::new()
+    } else {
+        write_outputs(config, &coll)?
+    };
+
+    Ok(Summary {
+        coll,
+        maybe_before,
+        report,
+        skipped,
+        updates,
+        maybe_seed,
+        #[cfg(feature = "snapshot")]
+        snapshot_failures,
+        maybe_redaction,
+        cross_year_edges,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        env, fs,
+        path::{Path, PathBuf},
+        process,
+    };
+
+    use hbt_core::{
+        OutputFormat, ParseOptions, UnparseOptions,
+        collection::Collection,
+        entity::{Entity, Label, Time, Url},
+        mappings::Rule,
+    };
+
+    use super::{
+        Config, CountRange, Error, Input, Output, load_input, outputs, run, select::Selection,
+        update::Updates, write,
+    };
+
+    #[test]
+    fn outputs_pair_formats_with_files() {
+        assert_eq!(outputs(&[], &[]).unwrap(), Vec::new());
+        assert!(matches!(
+            outputs(&[OutputFormat::Yaml, OutputFormat::Html], &[]),
+            Err(Error::SeveralToStdout)
+        ));
+
+        let paths = [PathBuf::from("a.html"), PathBuf::from("b.yaml")];
+        assert_eq!(
+            outputs(&[OutputFormat::PinboardJson], &paths).unwrap(),
+            vec![
+                Output {
+                    format: OutputFormat::PinboardJson,
+                    maybe_path: Some(PathBuf::from("a.html")),
+                },
+                Output {
+                    format: OutputFormat::Yaml,
+                    maybe_path: Some(PathBuf::from("b.yaml")),
+                },
+            ]
+        );
+        assert!(matches!(
+            outputs(&[], &[PathBuf::from("a.unknown")]),
+            Err(Error::UnknownOutputFormat(_))
+        ));
+        let err = outputs(&[], &[PathBuf::from("a.json")]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot detect the output format of a.json, which may be pinboard-json, launcher or \
+             karakeep; specify it explicitly"
+        );
+        assert!(matches!(
+            outputs(&[], &[PathBuf::from("a.xml")]),
+            Err(Error::AmbiguousOutputFormat(_, formats)) if formats == "pinboard-xml"
+        ));
+    }
+
+    #[test]
+    fn load_input_and_write_convert_between_formats() {
+        let dir = env::temp_dir().join(format!("hbt-app-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("bookmarks.md");
+        fs::write(
+            &input,
+            "# November 15, 2023\n\n- [Example](https://example.com/)\n",
+        )
+        .unwrap();
+        let output = dir.join("bookmarks.html");
+
+        let loaded = load_input(&Input::new(&input), &ParseOptions::default()).unwrap();
+        let html_output = Output {
+            format: OutputFormat::Html,
+            maybe_path: Some(output.clone()),
+        };
+        write(
+            &html_output,
+            &loaded.coll,
+            &UnparseOptions::default(),
+            false,
+            None,
+        )
+        .unwrap();
+        let html = fs::read_to_string(&output).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.coll.len(), 1);
+        assert!(html.contains("HREF=\"https://example.com/\""));
+    }
+
+    /// Returns a config that reads two bookmarks, relabels `rust` as `programming`, keeps only
+    /// the bookmarks labelled `programming`, and writes them as YAML.
+    fn relabelling_config(dir: &Path) -> Config {
+        let input = dir.join("bookmarks.json");
+        fs::write(
+            &input,
+            r#"[
+                {"href": "https://a.example.com/", "description": "A", "extended": "",
+                 "meta": "", "hash": "", "time": "2023-11-15T00:00:00Z", "shared": "yes",
+                 "toread": "no", "tags": "rust web"},
+                {"href": "https://b.example.com/", "description": "B", "extended": "",
+                 "meta": "", "hash": "", "time": "2023-11-15T00:00:00Z", "shared": "yes",
+                 "toread": "no", "tags": "go"}
+            ]"#,
+        )
+        .unwrap();
+        Config {
+            input: Input::new(input),
+            updates: Updates {
+                maybe_mappings: Some(vec![Rule::from((
+                    "rust".to_string(),
+                    "programming".to_string(),
+                ))]),
+                ..Updates::default()
+            },
+            selection: Selection {
+                any_labels: BTreeSet::from([Label::from("programming")]),
+                ..Selection::default()
+            },
+            outputs: vec![Output {
+                format: OutputFormat::Yaml,
+                maybe_path: Some(dir.join("archive.yaml")),
+            }],
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn run_updates_selects_and_writes() {
+        let dir = env::temp_dir().join(format!("hbt-app-run-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let summary = run(&relabelling_config(&dir)).unwrap();
+        let yaml = fs::read_to_string(dir.join("archive.yaml")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(summary.coll.len(), 1);
+        assert_eq!(summary.updates.changelog.changes.len(), 1);
+        assert!(summary.maybe_before.is_none());
+        assert!(yaml.contains("https://a.example.com/"));
+        assert!(yaml.contains("programming"));
+        assert!(!yaml.contains("https://b.example.com/"));
+    }
+
+    #[test]
+    fn run_checks_the_count_and_writes_nothing_on_a_dry_run() {
+        let dir = env::temp_dir().join(format!("hbt-app-dry-run-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let config = Config {
+            maybe_expected_count: Some("3..".parse().unwrap()),
+            ..relabelling_config(&dir)
+        };
+        let err = run(&config).unwrap_err();
+        assert_eq!(err.to_string(), "Expected 3.. entities, found 2");
+
+        let config = Config {
+            dry_run: true,
+            ..relabelling_config(&dir)
+        };
+        let summary = run(&config).unwrap();
+        let written = dir.join("archive.yaml").exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(summary.maybe_before.map(|before| before.len()), Some(2));
+        assert_eq!(summary.coll.len(), 1);
+        assert!(!written);
+    }
+
+    #[test]
+    fn count_ranges_are_parsed() {
+        let range = |s: &str| s.parse::<CountRange>();
+        assert_eq!(range("2").unwrap().to_string(), "2");
+        assert!(range("1..3").unwrap().contains(3));
+        assert!(!range("..3").unwrap().contains(4));
+        assert!(range("3..").unwrap().contains(usize::MAX));
+        assert_eq!(range("3..1").unwrap_err(), "invalid range: 3 > 1");
+        assert!(range("").is_err());
+    }
+
+    #[test]
+    fn karakeep_output_is_chunked() {
+        let dir = env::temp_dir().join(format!("hbt-app-karakeep-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut coll = Collection::new();
+        for i in 0..5 {
+            let url = Url::parse(&format!("https://example.com/{i}")).unwrap();
+            coll.insert(Entity::new(url, Time::default(), None, BTreeSet::new()));
+        }
+        let output = Output {
+            format: OutputFormat::Karakeep,
+            maybe_path: Some(dir.join("archive.json")),
+        };
+        let options = UnparseOptions {
+            karakeep_chunk_size: Some(2),
+            ..UnparseOptions::default()
+        };
+        fs::write(dir.join("archive-4.json"), "[]").unwrap();
+        write(&output, &coll, &options, false, None).unwrap();
+
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        let last = fs::read_to_string(dir.join("archive-3.json")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            names,
+            ["archive-1.json", "archive-2.json", "archive-3.json"]
+        );
+        assert!(last.contains("https://example.com/4"));
+    }
+
+    #[test]
+    fn small_karakeep_output_is_numbered() {
+        let dir = env::temp_dir().join(format!("hbt-app-karakeep-small-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let output = Output {
+            format: OutputFormat::Karakeep,
+            maybe_path: Some(dir.join("archive.json")),
+        };
+        let options = UnparseOptions {
+            karakeep_chunk_size: Some(2),
+            ..UnparseOptions::default()
+        };
+        write(&output, &Collection::new(), &options, false, None).unwrap();
+
+        let names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(names, ["archive-1.json"]);
+    }
+}
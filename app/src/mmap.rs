@@ -0,0 +1,25 @@
+use std::{fs::File, io};
+
+use memmap2::Mmap;
+
+/// Regular files at least this large are memory-mapped rather than read through a buffer.
+pub const THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// Memory-maps `file` if it is a regular file of at least [`THRESHOLD`] bytes, so that parsers
+/// can read it as a slice without copying it into a buffer first.
+///
+/// # Errors
+///
+/// Returns an error if the file's metadata cannot be read or the file cannot be mapped.
+pub fn map_if_large(file: &File) -> io::Result<Option<Mmap>> {
+    let metadata = file.metadata()?;
+    if !metadata.is_file() || metadata.len() < THRESHOLD {
+        return Ok(None);
+    }
+    // SAFETY: the map is only read. As with any memory map, the file being truncated or modified
+    // by another process while it is parsed is undefined behaviour; inputs are not expected to
+    // change while hbt reads them.
+    #[allow(unsafe_code)]
+    let map = unsafe { Mmap::map(file)? };
+    Ok(Some(map))
+}
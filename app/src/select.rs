@@ -0,0 +1,111 @@
+use std::{
+    collections::BTreeSet,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use hbt_core::{
+    collection::{Collection, Id},
+    entity::{Label, Profile, Time, Url},
+};
+
+use crate::Error;
+
+/// The entities to keep from a collection, once it is updated.
+#[derive(Debug, Default, Clone)]
+pub struct Selection {
+    /// Keep only the entities in this profile.
+    pub maybe_profile: Option<Profile>,
+    /// Keep only the entities related to the one with this URL: those within
+    /// [`Selection::maybe_depth`] edges of it, or on the shortest path from it to
+    /// [`Selection::maybe_path_to`].
+    pub maybe_related: Option<String>,
+    pub maybe_path_to: Option<String>,
+    /// The number of edges within which entities are related; 1 if unset.
+    pub maybe_depth: Option<usize>,
+    /// Keep only the entities with any of these labels, if there are any.
+    pub any_labels: BTreeSet<Label>,
+    pub pinned: bool,
+    /// Keep only the entities given this label since this time.
+    pub maybe_label_added: Option<(Label, Time)>,
+    /// Remove every label not in this set, if there are any.
+    pub only_labels: BTreeSet<Label>,
+    /// Remove the entities left without labels by [`Selection::only_labels`].
+    pub drop_unlabelled: bool,
+    pub maybe_head: Option<usize>,
+    pub maybe_tail: Option<usize>,
+    /// Keep a random sample of this many entities.
+    pub maybe_sample: Option<usize>,
+    /// The seed of the sample, which is taken from the clock if unset.
+    pub maybe_seed: Option<u64>,
+}
+
+fn find(coll: &Collection, profile: Option<&Profile>, url: &str) -> Result<Id, Error> {
+    coll.id_in(profile, &Url::parse(url)?)
+        .ok_or_else(|| Error::UrlNotFound(url.to_string()))
+}
+
+impl Selection {
+    /// Returns the selected entities of `coll`, along with the seed of the sample, if one was
+    /// taken.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a related URL is invalid or not found, or there is no path between the
+    /// related URLs.
+    pub fn apply(&self, coll: Collection) -> Result<(Collection, Option<u64>), Error> {
+        let maybe_profile = self.maybe_profile.as_ref();
+        let mut coll = match maybe_profile {
+            Some(profile) => coll.in_profile(Some(profile)),
+            None => coll,
+        };
+
+        if let Some(related) = &self.maybe_related {
+            let start = find(&coll, maybe_profile, related)?;
+            coll = match &self.maybe_path_to {
+                Some(path_to) => {
+                    let end = find(&coll, maybe_profile, path_to)?;
+                    let path = coll
+                        .shortest_path(&start, &end)
+                        .ok_or_else(|| Error::NoPath(related.clone(), path_to.clone()))?;
+                    coll.take(&path)
+                }
+                None => coll.neighborhood(&start, self.maybe_depth.unwrap_or(1)),
+            };
+        }
+
+        if !self.any_labels.is_empty() {
+            coll = coll.with_any_label(&self.any_labels);
+        }
+
+        if self.pinned {
+            coll = coll.pinned();
+        }
+
+        if let Some((label, since)) = &self.maybe_label_added {
+            coll = coll.label_added_since(label, *since);
+        }
+
+        if !self.only_labels.is_empty() {
+            coll.retain_labels(&self.only_labels, self.drop_unlabelled);
+        }
+
+        if let Some(n) = self.maybe_head {
+            return Ok((coll.head(n), None));
+        }
+
+        if let Some(n) = self.maybe_tail {
+            return Ok((coll.tail(n), None));
+        }
+
+        if let Some(n) = self.maybe_sample {
+            let seed = self.maybe_seed.unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |elapsed| elapsed.as_secs())
+            });
+            return Ok((coll.sample(n, seed), Some(seed)));
+        }
+
+        Ok((coll, None))
+    }
+}
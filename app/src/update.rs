@@ -0,0 +1,217 @@
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+
+use regex::Regex;
+
+use hbt_core::{
+    collection::{Collection, LabelChangelog, MergePolicy},
+    entity::{Profile, Time},
+    implications::Implications,
+    mappings,
+    replace::{self, TextField},
+    rewrite, sync,
+    transform::{self, Pipeline},
+};
+
+use crate::{Error, load_archive};
+
+/// Syncing an archive with the input, which is treated as a fresh remote export.
+#[derive(Debug, Clone)]
+pub struct Sync {
+    /// The YAML archive to sync, which takes the place of the input.
+    pub archive: PathBuf,
+    /// When the archive was last synced.
+    pub since: Time,
+    pub policy: MergePolicy,
+    /// A file to write the sync report to, even if the sync then fails for exceeding a limit.
+    pub maybe_report: Option<PathBuf>,
+    /// Fail if the sync would add more entities than this.
+    pub maybe_max_new: Option<usize>,
+    /// Fail if the sync would remove more entities than this.
+    pub maybe_max_removed: Option<usize>,
+}
+
+/// Replacing matches of a pattern in text fields; see [`Collection::replace_text`].
+#[derive(Debug, Clone)]
+pub struct Replace {
+    pub pattern: Regex,
+    pub replacement: String,
+    pub fields: BTreeSet<TextField>,
+    /// Only report what would be replaced.
+    pub preview: bool,
+}
+
+/// The changes to make to a collection once it is read, in the order they are made.
+#[derive(Default)]
+pub struct Updates {
+    pub maybe_sync: Option<Sync>,
+    /// Move the entities in the default profile to this one.
+    pub maybe_profile: Option<Profile>,
+    pub normalize_urls: bool,
+    pub maybe_rewrite_rules: Option<Vec<rewrite::Rule>>,
+    /// Label changes to undo, as recorded when label mappings were applied.
+    pub maybe_undo: Option<LabelChangelog>,
+    pub maybe_mappings: Option<Vec<mappings::Rule>>,
+    /// A file to write the changes made by the label mappings to.
+    pub maybe_changelog: Option<PathBuf>,
+    /// A file of embeddings to attach; see [`Collection::attach_embeddings`].
+    pub maybe_embeddings: Option<PathBuf>,
+    pub date_labels: bool,
+    pub maybe_implications: Option<Implications>,
+    pub maybe_pipeline: Option<Pipeline>,
+    pub maybe_replace: Option<Replace>,
+    /// Remove the edges implied by others; see [`Collection::compact`].
+    pub compact: bool,
+}
+
+/// The effect of a compaction on the number of edges and the size of the YAML output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compaction {
+    pub edges_before: usize,
+    pub edges_after: usize,
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+}
+
+/// What [`Updates::apply`] did.
+#[derive(Debug, Default)]
+pub struct Report {
+    pub maybe_sync: Option<sync::Report>,
+    /// The label changes made by the mappings.
+    pub changelog: LabelChangelog,
+    pub embeddings_attached: usize,
+    pub date_labels_added: usize,
+    pub implied_labels_added: usize,
+    pub steps: Vec<transform::Summary>,
+    /// The values replaced, or that would have been if [`Replace::preview`] is set.
+    pub replacements: Vec<replace::Change>,
+    pub maybe_compaction: Option<Compaction>,
+}
+
+impl Sync {
+    fn apply(&self, coll: &mut Collection, dry_run: bool) -> Result<sync::Report, Error> {
+        let (synced, report) =
+            load_archive(&self.archive)?.sync_with_policy(coll, self.since, self.policy);
+
+        if let Some(report_file) = &self.maybe_report
+            && !dry_run
+        {
+            let mut writer = BufWriter::new(File::create(report_file)?);
+            serde_norway::to_writer(&mut writer, &report)?;
+            writer.flush()?;
+        }
+
+        if let Some(max) = self.maybe_max_new
+            && report.added() > max
+        {
+            return Err(Error::TooManyAdded(report.added(), max));
+        }
+
+        if let Some(max) = self.maybe_max_removed
+            && report.removed() > max
+        {
+            return Err(Error::TooManyRemoved(report.removed(), max));
+        }
+
+        *coll = synced;
+        Ok(report)
+    }
+}
+
+fn compact(coll: &mut Collection) -> Result<Compaction, Error> {
+    let mut buf = Vec::new();
+    serde_norway::to_writer(&mut buf, coll)?;
+    let (edges_before, bytes_before) = (coll.edge_count(), buf.len());
+    // The YAML output only changes if edges were removed, in which case the buffer is reused
+    let bytes_after = if coll.compact() == 0 {
+        bytes_before
+    } else {
+        buf.clear();
+        serde_norway::to_writer(&mut buf, coll)?;
+        buf.len()
+    };
+    Ok(Compaction {
+        edges_before,
+        edges_after: coll.edge_count(),
+        bytes_before,
+        bytes_after,
+    })
+}
+
+impl Updates {
+    /// Makes the updates to `coll`. On a dry run, the sync report and the label changelog are not
+    /// written.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from an update, or from writing its report.
+    pub fn apply(&self, coll: &mut Collection, dry_run: bool) -> Result<Report, Error> {
+        let mut ret = Report::default();
+
+        if let Some(sync) = &self.maybe_sync {
+            ret.maybe_sync = Some(sync.apply(coll, dry_run)?);
+        }
+
+        if let Some(profile) = &self.maybe_profile {
+            coll.move_profile(None, Some(profile));
+        }
+
+        if self.normalize_urls {
+            coll.normalize_urls();
+        }
+
+        if let Some(rules) = &self.maybe_rewrite_rules {
+            coll.rewrite_urls(rules)?;
+        }
+
+        if let Some(changelog) = &self.maybe_undo {
+            coll.undo_labels(changelog);
+        }
+
+        if let Some(rules) = &self.maybe_mappings {
+            ret.changelog = coll.update_labels_with(rules);
+            if let Some(changelog_file) = &self.maybe_changelog
+                && !dry_run
+            {
+                let mut writer = BufWriter::new(File::create(changelog_file)?);
+                serde_norway::to_writer(&mut writer, &ret.changelog)?;
+                writer.flush()?;
+            }
+        }
+
+        if let Some(embeddings) = &self.maybe_embeddings {
+            ret.embeddings_attached =
+                coll.attach_embeddings(BufReader::new(File::open(embeddings)?))?;
+        }
+
+        if self.date_labels {
+            ret.date_labels_added = coll.add_date_labels();
+        }
+
+        if let Some(implications) = &self.maybe_implications {
+            ret.implied_labels_added = coll.apply_implications(implications);
+        }
+
+        if let Some(pipeline) = &self.maybe_pipeline {
+            ret.steps = pipeline.run(coll)?;
+        }
+
+        if let Some(replace) = &self.maybe_replace {
+            ret.replacements = if replace.preview {
+                coll.preview_replace_text(&replace.pattern, &replace.replacement, &replace.fields)
+            } else {
+                coll.replace_text(&replace.pattern, &replace.replacement, &replace.fields)
+            };
+        }
+
+        if self.compact {
+            ret.maybe_compaction = Some(compact(coll)?);
+        }
+
+        Ok(ret)
+    }
+}
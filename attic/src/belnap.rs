@@ -140,8 +140,8 @@ impl std::ops::Not for Belnap {
 
 /// Lattice ordering from a meet: `a <= b` iff `meet(a, b) == a`. Returns
 /// `None` when neither operand absorbs the other (incomparable elements).
-fn lattice_partial_cmp<T: PartialEq>(a: &T, b: &T, meet: T) -> Option<Ordering> {
-    match (meet == *a, meet == *b) {
+fn lattice_partial_cmp<T: PartialEq>(a: &T, b: &T, meet: &T) -> Option<Ordering> {
+    match (meet == a, meet == b) {
         (true, true) => Some(Ordering::Equal),
         (true, false) => Some(Ordering::Less),
         (false, true) => Some(Ordering::Greater),
@@ -161,7 +161,7 @@ pub struct AsTruth<T>(pub T);
 
 impl PartialOrd for AsTruth<Belnap> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        lattice_partial_cmp(&self.0, &other.0, self.0.and(other.0))
+        lattice_partial_cmp(&self.0, &other.0, &self.0.and(other.0))
     }
 }
 
@@ -193,7 +193,7 @@ pub struct AsKnowledge<T>(pub T);
 
 impl PartialOrd for AsKnowledge<Belnap> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        lattice_partial_cmp(&self.0, &other.0, self.0.consensus(other.0))
+        lattice_partial_cmp(&self.0, &other.0, &self.0.consensus(other.0))
     }
 }
 
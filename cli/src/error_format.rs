@@ -0,0 +1,70 @@
+use std::{io, path::Path};
+
+use anyhow::Error;
+use clap::ValueEnum;
+use serde_json::{Value, json};
+
+use hbt_core::{ParseError, UnparseError};
+
+/// How errors are written to stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// A human-readable message with its causes
+    #[default]
+    Text,
+    /// A JSON object with the error's kind, message, causes, and location when available
+    Json,
+}
+
+fn kind(err: &Error) -> &'static str {
+    if err.downcast_ref::<ParseError>().is_some() {
+        "parse"
+    } else if err.downcast_ref::<UnparseError>().is_some() {
+        "unparse"
+    } else if err.downcast_ref::<io::Error>().is_some() {
+        "io"
+    } else {
+        "other"
+    }
+}
+
+/// Returns the line in the input at which the error occurred, if any cause records one.
+fn line(err: &Error) -> Option<usize> {
+    err.chain().find_map(|cause| {
+        if let Some(err) = cause.downcast_ref::<serde_json::Error>() {
+            Some(err.line())
+        } else if let Some(err) = cause.downcast_ref::<serde_norway::Error>() {
+            err.location().map(|location| location.line())
+        } else {
+            None
+        }
+    })
+}
+
+/// Describes an error as a JSON object, e.g.:
+///
+/// ```json
+/// {"kind": "parse", "message": "...", "causes": ["..."], "file": "bookmarks.json", "line": 3}
+/// ```
+///
+/// `file` and `line` are `null` when unknown.
+#[must_use]
+pub fn to_json(err: &Error, maybe_file: Option<&Path>) -> Value {
+    let causes: Vec<String> = err.chain().skip(1).map(ToString::to_string).collect();
+    json!({
+        "kind": kind(err),
+        "message": err.to_string(),
+        "causes": causes,
+        "file": maybe_file.map(|file| file.display().to_string()),
+        "line": line(err),
+    })
+}
+
+/// Renders an error in the given format, without a trailing newline.
+#[must_use]
+pub fn format(err: &Error, maybe_file: Option<&Path>, format: ErrorFormat) -> String {
+    match format {
+        ErrorFormat::Text => format!("Error: {err:#}"),
+        ErrorFormat::Json => to_json(err, maybe_file).to_string(),
+    }
+}
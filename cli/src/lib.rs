@@ -2,4 +2,10 @@
 #![warn(clippy::pedantic)]
 #![deny(clippy::unwrap_in_result)]
 
+pub mod error_format;
+#[cfg(feature = "open")]
+pub mod picker;
+pub mod tags;
 pub mod version;
+#[cfg(feature = "watch")]
+pub mod watch;
@@ -3,160 +3,1381 @@
 #![deny(clippy::unwrap_in_result)]
 
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fs::{self, File},
     io::{self, BufReader, BufWriter, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::ExitCode,
 };
 
 use anyhow::Error;
-use clap::Parser;
+use chrono::NaiveDate;
+use clap::{Parser, ValueEnum, builder::RangedU64ValueParser};
+use regex::Regex;
 use schemars::schema_for;
 
-use hbt_core::collection::{Collection, CollectionRepr};
-use hbt_core::entity::Label;
-use hbt_core::{InputFormat, OutputFormat};
+#[cfg(feature = "bundle")]
+use hbt_core::bundle::Bundle;
+use hbt_core::collection::{Collection, CollectionRepr, DuplicatePolicy, IdScheme, MergePolicy};
+use hbt_core::entity::{
+    Label, MergeStrategies, MergeStrategy, Name, PrivacyPolicy, Profile, Time, UrlPolicy,
+};
+use hbt_core::graph::{self, GraphStats};
+use hbt_core::limits::{self, Limits};
+use hbt_core::locale::Locale;
+#[cfg(feature = "snapshot")]
+use hbt_core::snapshot;
+use hbt_core::{Info, InputFormat, OutputFormat, ParseOptions, UnparseOptions};
+use hbt_core::{diff, html, label_meta::LabelMeta, mappings};
+use hbt_core::{markdown, replace::TextField, rewrite, static_site};
 
-use hbt::version;
+#[cfg(feature = "open")]
+use hbt::picker;
+use hbt::{
+    error_format::{self, ErrorFormat},
+    tags::{self, TagsFormat},
+    version,
+};
+use hbt_app::{
+    Config, CountRange, Input, Output, SplitByYear, Summary,
+    atomic::AtomicWriter,
+    backup::Backup,
+    inputs,
+    select::Selection,
+    update::{self, Replace, Updates},
+};
 
 #[derive(Parser, Debug)]
 #[command(about, long_about = None, version = version::version_info().to_string())]
+#[allow(clippy::struct_excessive_bools)]
 struct Args {
     /// Input format
     #[arg(short = 'f', long = "from", value_enum)]
     from: Option<InputFormat>,
 
-    /// Output format
+    /// Import <FILE> as a directory of markdown notes, linking notes that link to each other
+    #[arg(long = "notes", conflicts_with = "from")]
+    notes: bool,
+
+    /// Import <FILE> as a directory of .webloc and .url bookmark files, naming each bookmark after
+    /// its file
+    #[arg(long = "shortcuts", conflicts_with_all = ["from", "notes"])]
+    shortcuts: bool,
+
+    /// Output format. May be repeated, each paired with the -o in the same position, to write
+    /// several outputs from a single parse (e.g. '-t yaml -o a.yaml -t html -o a.html')
     #[arg(short = 't', long = "to", value_enum)]
-    to: Option<OutputFormat>,
+    to: Vec<OutputFormat>,
 
-    /// Output file (defaults to stdout)
+    /// Output file (defaults to stdout). May be repeated; the format of a file without a
+    /// corresponding -t is detected from its extension, except for .json and .xml files
     #[arg(short = 'o', long = "output")]
-    output: Option<PathBuf>,
+    output: Vec<PathBuf>,
+
+    /// Give each HTML bookmark a stable anchor ID
+    #[arg(long = "html-anchors")]
+    html_anchors: bool,
+
+    /// Emit an index of labels linking to anchored HTML bookmarks
+    #[arg(long = "html-label-index")]
+    html_label_index: bool,
+
+    /// Emit a table of contents of labels linking to collapsible, color-coded HTML sections
+    #[arg(long = "html-toc")]
+    html_toc: bool,
+
+    /// Nest HTML bookmarks in folders following the collection's edges, e.g. from nested markdown
+    /// lists
+    #[arg(long = "html-nest", conflicts_with = "html_toc")]
+    html_nest: bool,
+
+    /// In YAML output, list the URL and name of each edge's target alongside its id
+    #[arg(long = "readable-edges")]
+    readable_edges: bool,
+
+    /// How entities are identified in YAML output: by position, by a UUID derived from the
+    /// normalized URL, or both
+    #[arg(long = "id-scheme", value_enum, default_value_t = IdScheme::Positional)]
+    id_scheme: IdScheme,
+
+    /// Split karakeep output into files of at most <N> bookmarks, numbered after the output file
+    /// (e.g. archive-1.json), for importers with size limits. Only valid with karakeep output
+    #[arg(
+        long = "karakeep-chunk-size",
+        value_name = "N",
+        requires = "output",
+        value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    karakeep_chunk_size: Option<usize>,
+
+    /// Update an existing `SQLite` output database instead of replacing its contents
+    #[cfg(feature = "sqlite")]
+    #[arg(long = "append")]
+    append: bool,
+
+    /// Copy each existing output file to a timestamped backup next to it before replacing it,
+    /// keeping the <N> most recent backups, or every backup if <N> is omitted
+    #[arg(long = "backup", value_name = "N", num_args = 0..=1, require_equals = true)]
+    #[allow(clippy::option_option)]
+    backup: Option<Option<usize>>,
+
+    /// Write the collection as an Obsidian vault in <DIR>
+    #[arg(long = "export-obsidian", value_name = "DIR")]
+    export_obsidian: Option<PathBuf>,
+
+    /// Write the collection as data files, with an example template, for a static site in <DIR>
+    #[arg(long = "export-site", value_name = "DIR")]
+    export_site: Option<PathBuf>,
+
+    /// Static site generator for --export-site
+    #[arg(
+        long = "site-generator",
+        value_enum,
+        default_value_t,
+        requires = "export_site"
+    )]
+    site_generator: static_site::Generator,
+
+    /// With --export-site, also write a data file for each label
+    #[arg(long = "site-per-label", requires = "export_site")]
+    site_per_label: bool,
+
+    /// Write one file per creation year to <DIR>, named <YEAR>.<EXT> after the output format
+    /// (default: yaml); edges between years are dropped and reported
+    #[arg(long = "split-by-year", value_name = "DIR")]
+    split_by_year: Option<PathBuf>,
+
+    /// Fuzzy-pick a bookmark interactively and open it in the default browser
+    #[cfg(feature = "open")]
+    #[arg(long = "open")]
+    open: bool,
+
+    /// Store a snapshot of each bookmarked page in <DIR>, recording its path on the entity
+    #[cfg(feature = "snapshot")]
+    #[arg(long = "snapshot", value_name = "DIR")]
+    snapshot: Option<PathBuf>,
+
+    /// Format for --snapshot
+    #[cfg(feature = "snapshot")]
+    #[arg(
+        long = "snapshot-format",
+        value_enum,
+        default_value_t,
+        requires = "snapshot"
+    )]
+    snapshot_format: snapshot::Format,
 
     /// Show collection info (entity count)
     #[arg(long = "info")]
     info: bool,
 
+    /// Format for --info. With --schema, `json` outputs the schema of its output
+    #[arg(long = "info-format", value_enum, default_value_t)]
+    info_format: InfoFormat,
+
     /// List all tags
     #[arg(long = "list-tags")]
     list_tags: bool,
 
-    /// Output Collection JSON schema
+    /// With --info or --list-tags, read the input one bookmark at a time instead of building a
+    /// collection, for huge files. Bookmarks sharing a URL are counted separately, and no updates
+    /// or filters apply
+    #[arg(long = "stream")]
+    stream: bool,
+
+    /// List the registrable domains of all entities, most common first
+    #[arg(long = "list-domains")]
+    list_domains: bool,
+
+    /// Print the degree distribution, connected component sizes and most-connected entities,
+    /// treating edges as undirected. With --info-format json, print them as JSON
+    #[arg(long = "graph-stats")]
+    graph_stats: bool,
+
+    /// The number of most-connected entities listed by --graph-stats
+    #[arg(long = "graph-top", value_name = "N", requires = "graph_stats", default_value_t = graph::DEFAULT_HUBS)]
+    graph_top: usize,
+
+    /// The number of entities betweenness centrality is sampled from by --graph-stats
+    #[arg(long = "graph-samples", value_name = "N", requires = "graph_stats", default_value_t = graph::DEFAULT_SAMPLES)]
+    graph_samples: usize,
+
+    /// List each entity on one line: its date, name, URL and tags
+    #[arg(long = "list", conflicts_with_all = ["list_urls", "list_names"])]
+    list: bool,
+
+    /// Truncate the lines of --list to <WIDTH> characters
+    #[arg(long = "width", value_name = "WIDTH", requires = "list")]
+    width: Option<usize>,
+
+    /// List the URL of each entity, one per line
+    #[arg(long = "list-urls")]
+    list_urls: bool,
+
+    /// List the names of all entities, one per line
+    #[arg(long = "list-names", conflicts_with = "list_urls")]
+    list_names: bool,
+
+    /// Sort the lines of --list-urls or --list-names, removing duplicates
+    #[arg(long = "unique")]
+    unique: bool,
+
+    /// Suggest merging labels that are inflections of one another, such as `test`, `tests` and
+    /// `testing`, writing them as a YAML mappings file for --mappings
+    #[arg(long = "suggest-merges")]
+    suggest_merges: bool,
+
+    /// Write an SVG badge showing the number of bookmarks and the date of the last update to
+    /// <FILE>, and a markdown snippet embedding it, for a README or blog, to the output
+    #[arg(long = "badge", value_name = "FILE")]
+    badge: Option<PathBuf>,
+
+    /// Write each entity's id, URL, labels and text (names and notes) as newline-delimited JSON,
+    /// for computing embeddings
+    #[arg(long = "export-embeddings")]
+    export_embeddings: bool,
+
+    /// Attach the embeddings in the newline-delimited JSON <FILE>, each an object with a `url`
+    /// (and optionally a `profile`) and an `embedding` array
+    #[arg(long = "attach-embeddings", value_name = "FILE")]
+    attach_embeddings: Option<PathBuf>,
+
+    /// Compare the input with <FILE>, an input or a YAML archive, listing added, removed and
+    /// modified entities
+    #[arg(long = "diff", value_name = "FILE")]
+    diff: Option<PathBuf>,
+
+    /// How to render the output of --diff
+    #[arg(long = "diff-format", value_enum, default_value_t, requires = "diff")]
+    diff_format: diff::Format,
+
+    /// Format for --list-tags
+    #[arg(
+        long = "tags-format",
+        value_enum,
+        default_value_t,
+        requires = "list_tags"
+    )]
+    tags_format: TagsFormat,
+
+    /// Show --list-tags as a tree of hierarchical labels, with each label's count and the total
+    /// including its descendants
+    #[arg(long = "tags-tree", requires = "list_tags")]
+    tags_tree: bool,
+
+    /// Delimiter between the levels of hierarchical labels for --tags-tree
+    #[arg(
+        long = "tag-delimiter",
+        value_name = "DELIM",
+        default_value = "/",
+        requires = "tags_tree"
+    )]
+    tag_delimiter: String,
+
+    /// Read label colors, emoji and descriptions from <FILE>
+    #[arg(long = "label-meta", value_name = "FILE")]
+    label_meta: Option<PathBuf>,
+
+    /// Print more detail, e.g. warnings about merged duplicate URLs, or a legend of label metadata
+    /// with --list-tags
+    #[arg(short = 'v', long = "verbose")]
+    verbose: bool,
+
+    /// Apply every update (e.g. --sync-against, --mappings, --normalize-urls, --rewrite-urls and
+    /// --pipeline), selection and redaction, and print a summary of the changes, but write no
+    /// output, report or changelog files. With --verbose, the changes are also printed as a diff
+    #[arg(long = "dry-run")]
+    dry_run: bool,
+
+    /// Write the collection, the --mappings and --label-meta files, and snapshots to a bundle
+    /// (.hbtz) at <FILE>
+    #[cfg(feature = "bundle")]
+    #[arg(long = "bundle", value_name = "FILE")]
+    bundle: Option<PathBuf>,
+
+    /// Treat the input file as a bundle and extract it into <DIR>, verifying its checksums
+    #[cfg(feature = "bundle")]
+    #[arg(long = "extract-bundle", value_name = "DIR")]
+    extract_bundle: Option<PathBuf>,
+
+    /// Format of error messages written to stderr
+    #[arg(long = "error-format", value_enum, default_value_t = ErrorFormat::Text)]
+    error_format: ErrorFormat,
+
+    /// Check the checksum of the YAML archive <FILE>, and exit
+    #[arg(long = "verify")]
+    verify: bool,
+
+    /// Output Collection JSON schema, or with --info-format json, the schema of --info
     #[arg(long = "schema")]
     schema: bool,
 
-    /// Read mappings from <FILE>
+    /// Exclude URLs matching the patterns in <FILE>
+    #[arg(long = "ignore", value_name = "FILE")]
+    ignore: Option<PathBuf>,
+
+    /// Apply the transform steps in <FILE> in order, after the other update flags, printing a
+    /// summary of each
+    #[arg(long = "pipeline", value_name = "FILE")]
+    pipeline: Option<PathBuf>,
+
+    /// Replace matches of the regular expression <PATTERN> in names, or in the --replace-fields,
+    /// with --replacement, after running --pipeline
+    #[arg(long = "replace-text", value_name = "PATTERN", value_parser = Regex::new)]
+    replace_text: Option<Regex>,
+
+    /// The replacement for --replace-text, which may refer to capture groups (e.g. `$1`)
+    #[arg(
+        long = "replacement",
+        value_name = "TEXT",
+        default_value = "",
+        requires = "replace_text"
+    )]
+    replacement: String,
+
+    /// The fields in which --replace-text replaces text
+    #[arg(
+        long = "replace-fields",
+        value_enum,
+        value_delimiter = ',',
+        default_value = "names",
+        requires = "replace_text"
+    )]
+    replace_fields: Vec<TextField>,
+
+    /// Print the changes --replace-text would make, without making them
+    #[arg(long = "replace-dry-run", requires = "replace_text")]
+    replace_dry_run: bool,
+
+    /// Before output, remove private entities, notes and the labels and URLs listed in the YAML
+    /// redaction policy <FILE>, for sharing the output publicly
+    #[arg(long = "redact", value_name = "FILE")]
+    redact: Option<PathBuf>,
+
+    /// Remove redundant edges and print the size of the YAML output before and after, as the last
+    /// update
+    #[arg(long = "compact")]
+    compact: bool,
+
+    /// Label each bookmark with the year and month it was created, e.g. 'year:2023' and
+    /// 'month:2023-11', before applying --implications
+    #[arg(long = "auto-date-labels")]
+    auto_date_labels: bool,
+
+    /// Add the labels implied by the rules in <FILE>, after applying --mappings
+    #[arg(long = "implications", value_name = "FILE")]
+    implications: Option<PathBuf>,
+
+    /// How to merge the privacy of bookmarks that appear more than once in the input, or that are
+    /// merged by later steps such as --normalize-urls
+    #[arg(long = "privacy-policy", value_enum, default_value_t = PrivacyPolicy::MostPermissive)]
+    privacy_policy: PrivacyPolicy,
+
+    /// How to merge the names of bookmarks that appear more than once
+    #[arg(long = "merge-names", value_enum, default_value_t = MergeStrategy::Union)]
+    merge_names: MergeStrategy,
+
+    /// How to merge the labels of bookmarks that appear more than once
+    #[arg(long = "merge-labels", value_enum, default_value_t = MergeStrategy::Union)]
+    merge_labels: MergeStrategy,
+
+    /// How to merge the extended notes of bookmarks that appear more than once
+    #[arg(long = "merge-extended", value_enum, default_value_t = MergeStrategy::PreferSelf)]
+    merge_extended: MergeStrategy,
+
+    /// Whether Pinboard posts with the same URL are kept separate or merged; merged duplicates are
+    /// listed by --report and --verbose
+    #[arg(long = "duplicates", value_enum, default_value_t = DuplicatePolicy::Upsert)]
+    duplicates: DuplicatePolicy,
+
+    /// Keep bookmarks whose URLs cannot be parsed, wrapped as `hbt-raw:` URLs, instead of failing;
+    /// they are listed by --report and --verbose
+    #[arg(long = "keep-invalid-urls")]
+    keep_invalid_urls: bool,
+
+    /// Record the source record of each bookmark (a JSON object, XML attributes, HTML element or
+    /// markdown line) in its `hbt:raw` extra, for debugging conversions
+    #[arg(long = "keep-raw")]
+    keep_raw: bool,
+
+    /// Fail on input whose folders, lists or blockquotes nest deeper than <N> levels
+    #[arg(long = "max-depth", value_name = "N", default_value_t = limits::DEFAULT_MAX_DEPTH)]
+    max_depth: usize,
+
+    /// Fail on input with more than <N> bookmarks
+    #[arg(long = "max-entities", value_name = "N", default_value_t = limits::DEFAULT_MAX_ENTITIES)]
+    max_entities: usize,
+
+    /// Fail on input with a URL, name, label, note or attribute longer than <N> bytes
+    #[arg(
+        long = "max-field-len",
+        value_name = "N",
+        default_value_t = limits::DEFAULT_MAX_FIELD_LEN
+    )]
+    max_field_len: usize,
+
+    /// Read mappings from <FILE>, in YAML, JSON, TOML or two-column CSV by extension. In YAML,
+    /// JSON and TOML, a mapping may apply only to bookmarks with other labels, e.g.
+    /// 'misc: {to: reading, when: [books]}'
     #[arg(long = "mappings", value_name = "FILE")]
     mappings: Option<PathBuf>,
 
-    /// Input file
+    /// Write a changelog of the labels changed by --mappings to <FILE>
+    #[arg(
+        long = "mappings-changelog",
+        value_name = "FILE",
+        requires = "mappings"
+    )]
+    mappings_changelog: Option<PathBuf>,
+
+    /// Undo the label changes recorded in the changelog <FILE>
+    #[arg(long = "undo-mappings", value_name = "FILE")]
+    undo_mappings: Option<PathBuf>,
+
+    /// Normalize URLs (IDN hosts, default ports and percent-encoding), merging duplicates
+    #[arg(long = "normalize-urls")]
+    normalize_urls: bool,
+
+    /// Rewrite URLs according to the rules in <FILE>
+    #[arg(long = "rewrite-urls", value_name = "FILE")]
+    rewrite_urls: Option<PathBuf>,
+
+    /// Exit with an error if the input contains no entities
+    #[arg(long = "fail-on-empty")]
+    fail_on_empty: bool,
+
+    /// Exit with an error unless the entity count is <N> or within <MIN>..<MAX>
+    #[arg(long = "expect-count", value_name = "N|MIN..MAX")]
+    expect_count: Option<CountRange>,
+
+    /// Regenerate the output whenever the input or mappings file changes
+    #[cfg(feature = "watch")]
+    #[arg(long = "watch")]
+    watch: bool,
+
+    /// Sync <ARCHIVE>, a YAML archive, with the input, treating the input as a fresh remote
+    /// export
+    #[arg(long = "sync-against", value_name = "ARCHIVE", requires = "sync_since")]
+    sync_against: Option<PathBuf>,
+
+    /// Time of the last sync, used to tell local changes from remote ones
+    #[arg(
+        long = "sync-since",
+        value_name = "TIME",
+        value_parser = Time::parse_flexible,
+        requires = "sync_against"
+    )]
+    sync_since: Option<Time>,
+
+    /// Only update entities already in the archive when syncing, adding none
+    #[arg(
+        long = "update-only",
+        requires = "sync_against",
+        conflicts_with = "create_only"
+    )]
+    update_only: bool,
+
+    /// Only add new entities to the archive when syncing, leaving existing ones unchanged
+    #[arg(long = "create-only", requires = "sync_against")]
+    create_only: bool,
+
+    /// Write a report of anomalies found while parsing (e.g. merged duplicate URLs, skipped
+    /// records, or unknown attributes in HTML) to <FILE> as JSON
+    #[arg(long = "report", value_name = "FILE")]
+    report: Option<PathBuf>,
+
+    /// Fail without syncing if more than <N> entities would be added to the archive
+    #[arg(long = "max-new", value_name = "N", requires = "sync_against")]
+    max_new: Option<usize>,
+
+    /// Fail without syncing if more than <N> entities would be removed from the archive
+    #[arg(long = "max-removed", value_name = "N", requires = "sync_against")]
+    max_removed: Option<usize>,
+
+    /// Write the sync report (applied changes and conflicts) to <FILE> as YAML
+    #[arg(long = "sync-report", value_name = "FILE", requires = "sync_against")]
+    sync_report: Option<PathBuf>,
+
+    /// Move the input's entities into profile <NAME>
+    #[arg(long = "set-profile", value_name = "NAME")]
+    set_profile: Option<String>,
+
+    /// Only output entities labelled <TAG>; repeat to select several tags. HTML output places
+    /// the entities in one folder per tag
+    #[arg(long = "filter-tag", value_name = "TAG")]
+    filter_tags: Vec<String>,
+
+    /// Only output pinned entities, such as those on the browser's bookmarks bar or starred in
+    /// markdown
+    #[arg(long = "only-pinned")]
+    only_pinned: bool,
+
+    /// Only output entities to which <LABEL> was added at or after --label-added-since
+    #[arg(
+        long = "label-added",
+        value_name = "LABEL",
+        requires = "label_added_since"
+    )]
+    label_added: Option<String>,
+
+    /// Time from which --label-added counts, as a Unix timestamp or e.g. '2024-01-01T00:00:00Z'
+    #[arg(
+        long = "label-added-since",
+        value_name = "TIME",
+        value_parser = Time::parse_flexible,
+        requires = "label_added"
+    )]
+    label_added_since: Option<Time>,
+
+    /// Keep only the labels <TAGS> (comma-separated) on every entity, dropping all others, e.g.
+    /// to publish a themed subset
+    #[arg(long = "only-tags", value_name = "TAGS", value_delimiter = ',')]
+    only_tags: Vec<String>,
+
+    /// With --only-tags, also drop entities left without labels
+    #[arg(long = "drop-unlabelled", requires = "only_tags")]
+    drop_unlabelled: bool,
+
+    /// Only output entities in profile <NAME>
+    #[arg(long = "profile", value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Only output the entities connected to <URL>, in either direction
+    #[arg(long = "related", value_name = "URL")]
+    related: Option<String>,
+
+    /// How many edges away from --related to look (default: 1), or how many levels of
+    /// --tags-tree to show (default: all)
+    #[arg(long = "depth", value_name = "N")]
+    depth: Option<usize>,
+
+    /// Only output a shortest path of edges from --related to <URL>
+    #[arg(long = "path-to", value_name = "URL", requires = "related")]
+    path_to: Option<String>,
+
+    /// Only output the first <N> entities
+    #[arg(long = "head", value_name = "N", conflicts_with_all = ["tail", "sample"])]
+    head: Option<usize>,
+
+    /// Only output the last <N> entities
+    #[arg(long = "tail", value_name = "N", conflicts_with = "sample")]
+    tail: Option<usize>,
+
+    /// Only output <N> entities chosen at random
+    #[arg(long = "sample", value_name = "N")]
+    sample: Option<usize>,
+
+    /// Seed for --sample (defaults to the current time, which is printed to stderr)
+    #[arg(long = "seed", value_name = "SEED", requires = "sample")]
+    seed: Option<u64>,
+
+    /// Date markdown bookmarks that precede every date heading by the input's file name, parsed
+    /// with the strftime <PATTERN> (default: %Y-%m-%d)
+    #[arg(
+        long = "date-from-file-name",
+        value_name = "PATTERN",
+        num_args = 0..=1,
+        default_missing_value = markdown::FILE_NAME_DATE_PATTERN
+    )]
+    date_from_file_name: Option<String>,
+
+    /// Date text bookmarks, and markdown bookmarks that precede every date heading, by <DATE>
+    /// (YYYY-MM-DD) rather than now
+    #[arg(long = "default-date", value_name = "DATE")]
+    default_date: Option<NaiveDate>,
+
+    /// Also read markdown date headings with month names in <LOCALE>, e.g. '15 de noviembre de
+    /// 2023' with 'es'
+    #[arg(long = "locale", value_enum, value_name = "LOCALE")]
+    locale: Option<Locale>,
+
+    /// When the input is a directory or glob pattern, report files that fail to parse and carry on
+    /// with the rest
+    #[arg(long = "lenient")]
+    lenient: bool,
+
+    /// Input file, directory or glob pattern (e.g. 'notes/**/*.md'). The files in a directory or
+    /// matching a pattern are merged, recording each entity's file in its `hbt:source` extra
     file: Option<PathBuf>,
 }
 
-fn update(args: &Args, coll: &mut Collection) -> Result<(), Error> {
-    let Some(mappings) = &args.mappings else {
-        return Ok(());
-    };
-
-    let contents = fs::read_to_string(mappings)?;
-    let yaml: serde_norway::Value = serde_norway::from_str(&contents)?;
+/// Output formats for --info.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum InfoFormat {
+    /// The entity count, as `FILE: N entities`
+    #[default]
+    Text,
+    /// A JSON object of counts and the date range
+    Json,
+}
 
-    let mappings = yaml
-        .as_mapping()
-        .ok_or_else(|| Error::msg("Mapping file must contain a YAML mapping"))?
-        .iter()
-        .filter_map(|(k, v)| {
-            let key = k.as_str()?.to_string();
-            let value = v.as_str()?.to_string();
-            Some((key, value))
-        })
-        .collect::<Vec<_>>();
+impl Args {
+    /// Returns the output file of modes that write a single output, such as --schema.
+    fn output(&self) -> Option<&PathBuf> {
+        self.output.first()
+    }
 
-    coll.update_labels(mappings);
+    fn backup(&self) -> Option<Backup> {
+        self.backup.map(|maybe_keep| Backup { maybe_keep })
+    }
 
-    Ok(())
+    fn merge_policy(&self) -> MergePolicy {
+        if self.update_only {
+            MergePolicy::UpdateOnly
+        } else if self.create_only {
+            MergePolicy::CreateOnly
+        } else {
+            MergePolicy::All
+        }
+    }
 }
 
-fn print(args: &Args, coll: &Collection) -> Result<(), Error> {
-    if args.info {
-        let length = coll.len();
+/// Prints --info, as JSON if requested.
+fn print_info(args: &Args, mut info: Info) -> Result<(), Error> {
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout);
+    if args.info_format == InfoFormat::Json {
+        info.format = if args.notes {
+            Some("notes".to_string())
+        } else if args.shortcuts {
+            Some("shortcuts".to_string())
+        } else {
+            args.from
+                .or_else(|| args.file.as_deref().and_then(InputFormat::detect))
+                .map(|format| <&'static str>::from(format).to_string())
+        };
+        serde_json::to_writer_pretty(&mut writer, &info)?;
+        writeln!(writer)?;
+    } else {
         let file_name = args
             .file
             .as_ref()
             .map_or("input".into(), |f| f.to_string_lossy());
-        let output = format!("{file_name}: {length} entities\n");
+        writeln!(writer, "{file_name}: {} entities", info.entities)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Prints --graph-stats, as JSON if requested.
+fn print_graph_stats(args: &Args, stats: &GraphStats) -> Result<(), Error> {
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout);
+    if args.info_format == InfoFormat::Json {
+        serde_json::to_writer_pretty(&mut writer, stats)?;
+        writeln!(writer)?;
+    } else {
+        writeln!(writer, "isolated\t{}", stats.isolated)?;
+        writeln!(writer, "components\t{}", stats.component_sizes.len())?;
+        if let Some(largest) = stats.component_sizes.first() {
+            writeln!(writer, "largest component\t{largest}")?;
+        }
+        writeln!(writer, "\ndegree\tentities")?;
+        for (degree, count) in &stats.degree_distribution {
+            writeln!(writer, "{degree}\t{count}")?;
+        }
+        writeln!(writer, "\ndegree\tbetweenness\turl")?;
+        for hub in &stats.hubs {
+            writeln!(
+                writer,
+                "{}\t{:.1}\t{}",
+                hub.degree,
+                hub.betweenness,
+                hub.url.as_str()
+            )?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Prints --list-tags.
+fn print_tags(
+    args: &Args,
+    counts: &BTreeMap<&Label, usize>,
+    label_meta: &LabelMeta,
+) -> Result<(), Error> {
+    let output = if args.tags_tree {
+        let root = tags::tree(counts, &args.tag_delimiter);
+        tags::format_tree(&root, args.depth, args.tags_format)?
+    } else if args.verbose {
+        tags::legend(counts, label_meta)
+    } else {
+        tags::format(counts, args.tags_format)?
+    };
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout);
+    writer.write_all(output.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Prints --info or --list-tags for --stream, reading the input one entity at a time instead of
+/// building a collection.
+fn analyze(args: &Args, file: &Path, input_format: InputFormat) -> Result<(), Error> {
+    let mut reader = BufReader::new(File::open(file)?);
+    let analysis = input_format.analyze(&mut reader, &parse_options(args)?)?;
+    if args.list_tags {
+        return print_tags(args, &analysis.label_counts(), &load_label_meta(args)?);
+    }
+    print_info(args, analysis.info())
+}
+
+fn load_label_meta(args: &Args) -> Result<LabelMeta, Error> {
+    match &args.label_meta {
+        Some(path) => Ok(serde_norway::from_str(&fs::read_to_string(path)?)?),
+        None => Ok(LabelMeta::default()),
+    }
+}
+
+/// Writes with `write` to `path`, which is replaced atomically after being backed up as requested
+/// by --backup.
+fn replace_file(
+    args: &Args,
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let mut writer = AtomicWriter::create(path)?;
+    write(&mut writer)?;
+    if let Some(backup) = args.backup() {
+        backup.create(path)?;
+    }
+    writer.commit()?;
+    Ok(())
+}
+
+/// Writes with `write` to the output file, which is replaced as by [`replace_file`], or else to
+/// stdout.
+fn write_output(
+    args: &Args,
+    write: impl FnOnce(&mut dyn Write) -> Result<(), Error>,
+) -> Result<(), Error> {
+    if let Some(output_file) = args.output() {
+        replace_file(args, output_file, write)?;
+    } else {
+        let mut writer = BufWriter::new(io::stdout());
+        write(&mut writer)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+/// Prints each entity, or their URLs or names, one per line.
+fn print_entities(args: &Args, coll: &Collection) -> Result<(), Error> {
+    if args.list {
         let stdout = io::stdout();
         let mut writer = BufWriter::new(stdout);
-        writer.write_all(output.as_bytes())?;
+        for entity in coll.entities() {
+            match args.width {
+                Some(width) => writeln!(writer, "{}", entity.summary(width))?,
+                None => writeln!(writer, "{entity}")?,
+            }
+        }
         writer.flush()?;
         return Ok(());
     }
 
-    if args.list_tags {
-        let mut all_tags = BTreeSet::new();
-        for entity in coll.entities() {
-            all_tags.extend(entity.labels());
-        }
-        let tags_output = all_tags
-            .into_iter()
-            .map(Label::as_str)
-            .collect::<Vec<_>>()
-            .join("\n");
-        let output = if tags_output.is_empty() {
-            String::new()
-        } else {
-            format!("{tags_output}\n")
+    let mut lines: Vec<&str> = if args.list_urls {
+        coll.entities()
+            .iter()
+            .map(|entity| entity.url().as_str())
+            .collect()
+    } else {
+        coll.entities()
+            .iter()
+            .flat_map(|entity| entity.names().iter().map(Name::as_str))
+            .collect()
+    };
+    if args.unique {
+        lines.sort_unstable();
+        lines.dedup();
+    }
+    let stdout = io::stdout();
+    let mut writer = BufWriter::new(stdout);
+    for line in lines {
+        writeln!(writer, "{line}")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Compares `coll` with `new_file`, read as a YAML archive if it has a YAML extension, and as an
+/// input otherwise.
+fn diff(args: &Args, coll: &Collection, new_file: &Path) -> Result<(), Error> {
+    let new = if OutputFormat::detect(new_file) == Some(OutputFormat::Yaml) {
+        hbt_app::load_archive(new_file)
+    } else {
+        let no_parser = || Error::msg(format!("No parser for file: {}", new_file.display()));
+        let new_format = InputFormat::detect(new_file).ok_or_else(no_parser)?;
+        hbt_app::load(
+            new_file,
+            Some(new_format),
+            &parse_options(args)?,
+            &mut Vec::new(),
+        )
+    }
+    .map_err(app_error)?;
+    let diff = coll.diff(&new)?;
+    write_output(args, |mut writer| {
+        Ok(diff.write(&mut writer, args.diff_format)?)
+    })
+}
+
+fn print(args: &Args, coll: &Collection) -> Result<(), Error> {
+    #[cfg(feature = "open")]
+    if args.open {
+        if let Some(id) = picker::pick(coll)? {
+            open::that(coll.entity(&id).url().as_str())?;
+        }
+        return Ok(());
+    }
+
+    #[cfg(feature = "bundle")]
+    if let Some(bundle_file) = &args.bundle {
+        // Mappings are stored as YAML whatever format they were read in.
+        let mappings = match &args.mappings {
+            Some(path) => {
+                let mappings: std::collections::BTreeMap<String, String> =
+                    mappings::load(path)?.into_iter().collect();
+                Some(serde_norway::to_string(&mappings)?)
+            }
+            None => None,
+        };
+        let label_meta: Option<LabelMeta> = match &args.label_meta {
+            Some(path) => Some(serde_norway::from_str(&fs::read_to_string(path)?)?),
+            None => None,
         };
+        let file = File::create(bundle_file)?;
+        coll.to_bundle(
+            BufWriter::new(file),
+            mappings.as_deref(),
+            label_meta.as_ref(),
+        )?;
+        return Ok(());
+    }
+
+    if args.export_embeddings {
+        write_output(args, |mut writer| {
+            Ok(coll.write_embedding_payloads(&mut writer)?)
+        })?;
+        return Ok(());
+    }
+
+    if let Some(new_file) = &args.diff {
+        return diff(args, coll, new_file);
+    }
+
+    if args.info {
+        return print_info(args, Info::from(coll));
+    }
+
+    if args.list_domains {
+        let mut counts: Vec<(String, usize)> = coll.domain_counts().into_iter().collect();
+        counts.sort_by(|(_, a), (_, b)| b.cmp(a));
         let stdout = io::stdout();
         let mut writer = BufWriter::new(stdout);
-        writer.write_all(output.as_bytes())?;
+        for (domain, count) in counts {
+            writeln!(writer, "{count}\t{domain}")?;
+        }
         writer.flush()?;
         return Ok(());
     }
 
-    let format = match args.to {
-        Some(format) => Some(format),
-        None => args.output.as_ref().and_then(OutputFormat::detect),
-    };
+    if args.graph_stats {
+        return print_graph_stats(args, &coll.graph_stats(args.graph_top, args.graph_samples));
+    }
+
+    if args.list || args.list_urls || args.list_names {
+        return print_entities(args, coll);
+    }
+
+    if let Some(badge_file) = &args.badge {
+        let stats = coll.stats();
+        replace_file(args, badge_file, |writer| Ok(stats.to_badge(writer)?))?;
+        let badge = badge_file.to_string_lossy();
+        write_output(args, |writer| Ok(stats.to_badge_summary(writer, &badge)?))?;
+        return Ok(());
+    }
+
+    if args.suggest_merges {
+        let merges = coll.suggest_label_merges();
+        write_output(args, |writer| Ok(serde_norway::to_writer(writer, &merges)?))?;
+        return Ok(());
+    }
+
+    if args.list_tags {
+        return print_tags(args, &coll.label_counts(), &load_label_meta(args)?);
+    }
+
+    if let Some(dir) = &args.export_obsidian {
+        coll.to_obsidian(dir)?;
+        return Ok(());
+    }
+
+    if let Some(dir) = &args.export_site {
+        coll.to_static_site(dir, args.site_generator, args.site_per_label)?;
+    }
+
+    Ok(())
+}
 
-    if let Some(format) = format {
-        if let Some(output_file) = &args.output {
-            let file = File::create(output_file)?;
-            let mut writer = BufWriter::new(file);
-            format.unparse(&mut writer, coll)?;
-            writer.flush()?;
+fn parse_options(args: &Args) -> Result<ParseOptions, Error> {
+    let mut options = ParseOptions {
+        privacy_policy: args.privacy_policy,
+        merge_strategies: MergeStrategies {
+            names: args.merge_names,
+            labels: args.merge_labels,
+            extended: args.merge_extended,
+        },
+        duplicate_policy: args.duplicates,
+        url_policy: if args.keep_invalid_urls {
+            UrlPolicy::KeepInvalid
         } else {
-            let stdout = io::stdout();
-            let mut writer = BufWriter::new(stdout);
-            format.unparse(&mut writer, coll)?;
-            writer.flush()?;
+            UrlPolicy::Strict
+        },
+        default_date: args.default_date,
+        locale: args.locale,
+        keep_raw: args.keep_raw,
+        limits: Limits {
+            max_depth: args.max_depth,
+            max_entities: args.max_entities,
+            max_field_len: args.max_field_len,
+        },
+        ..ParseOptions::default()
+    };
+    if let Some(ignore) = &args.ignore {
+        options.ignore = fs::read_to_string(ignore)?.parse()?;
+    }
+    Ok(options)
+}
+
+/// Converts an error from hbt-app into its underlying error, so that --error-format reports the
+/// kind of the error rather than its wrapper, and names the flags behind the sync limits.
+fn app_error(err: hbt_app::Error) -> Error {
+    match err {
+        hbt_app::Error::Io(err) => err.into(),
+        hbt_app::Error::Parse(err) => err.into(),
+        hbt_app::Error::Unparse(err) => err.into(),
+        hbt_app::Error::Notes(err) => err.into(),
+        hbt_app::Error::Shortcuts(err) => err.into(),
+        hbt_app::Error::Archive(err) => err.into(),
+        hbt_app::Error::Entity(err) => err.into(),
+        hbt_app::Error::Embedding(err) => err.into(),
+        hbt_app::Error::Transform(err) => err.into(),
+        hbt_app::Error::TooManyAdded(added, max) => Error::msg(format!(
+            "Sync would add {added} entities, more than --max-new {max}"
+        )),
+        hbt_app::Error::TooManyRemoved(removed, max) => Error::msg(format!(
+            "Sync would remove {removed} entities, more than --max-removed {max}"
+        )),
+        err => err.into(),
+    }
+}
+
+/// Returns whether the collection is printed or exported by [`print`] rather than written to
+/// the outputs.
+fn prints(args: &Args) -> bool {
+    #[cfg(feature = "open")]
+    if args.open {
+        return true;
+    }
+    #[cfg(feature = "bundle")]
+    if args.bundle.is_some() {
+        return true;
+    }
+    args.export_embeddings
+        || args.diff.is_some()
+        || args.info
+        || args.list_domains
+        || args.graph_stats
+        || args.list
+        || args.list_urls
+        || args.list_names
+        || args.badge.is_some()
+        || args.suggest_merges
+        || args.list_tags
+        || args.export_obsidian.is_some()
+        || args.export_site.is_some()
+}
+
+fn updates(args: &Args) -> Result<Updates, Error> {
+    let maybe_sync = match (&args.sync_against, args.sync_since) {
+        (Some(archive), Some(since)) => Some(update::Sync {
+            archive: archive.clone(),
+            since,
+            policy: args.merge_policy(),
+            maybe_report: args.sync_report.clone(),
+            maybe_max_new: args.max_new,
+            maybe_max_removed: args.max_removed,
+        }),
+        _ => None,
+    };
+    let maybe_rewrite_rules = match &args.rewrite_urls {
+        Some(rules) => Some(rewrite::parse_rules(&fs::read_to_string(rules)?)?),
+        None => None,
+    };
+    let maybe_undo = match &args.undo_mappings {
+        Some(changelog) => Some(serde_norway::from_str(&fs::read_to_string(changelog)?)?),
+        None => None,
+    };
+    let maybe_implications = match &args.implications {
+        Some(implications) => Some(fs::read_to_string(implications)?.parse()?),
+        None => None,
+    };
+    let maybe_pipeline = match &args.pipeline {
+        Some(pipeline) => Some(serde_norway::from_str(&fs::read_to_string(pipeline)?)?),
+        None => None,
+    };
+    Ok(Updates {
+        maybe_sync,
+        maybe_profile: args.set_profile.as_deref().map(Profile::from),
+        normalize_urls: args.normalize_urls,
+        maybe_rewrite_rules,
+        maybe_undo,
+        maybe_mappings: args
+            .mappings
+            .as_deref()
+            .map(mappings::load_rules)
+            .transpose()?,
+        maybe_changelog: args.mappings_changelog.clone(),
+        maybe_embeddings: args.attach_embeddings.clone(),
+        date_labels: args.auto_date_labels,
+        maybe_implications,
+        maybe_pipeline,
+        maybe_replace: args.replace_text.as_ref().map(|pattern| Replace {
+            pattern: pattern.clone(),
+            replacement: args.replacement.clone(),
+            fields: args.replace_fields.iter().copied().collect(),
+            preview: args.replace_dry_run,
+        }),
+        compact: args.compact,
+    })
+}
+
+fn labels(tags: &[String]) -> BTreeSet<Label> {
+    tags.iter().map(|tag| Label::from(tag.as_str())).collect()
+}
+
+fn selection(args: &Args) -> Selection {
+    Selection {
+        maybe_profile: args.profile.as_deref().map(Profile::from),
+        maybe_related: args.related.clone(),
+        maybe_path_to: args.path_to.clone(),
+        maybe_depth: args.depth,
+        any_labels: labels(&args.filter_tags),
+        pinned: args.only_pinned,
+        maybe_label_added: args
+            .label_added
+            .as_deref()
+            .map(Label::from)
+            .zip(args.label_added_since),
+        only_labels: labels(&args.only_tags),
+        drop_unlabelled: args.drop_unlabelled,
+        maybe_head: args.head,
+        maybe_tail: args.tail,
+        maybe_sample: args.sample,
+        maybe_seed: args.seed,
+    }
+}
+
+fn unparse_options(args: &Args, label_meta: LabelMeta) -> UnparseOptions {
+    UnparseOptions {
+        html: html::Options {
+            anchors: args.html_anchors,
+            label_index: args.html_label_index,
+            label_meta,
+            folders: args
+                .filter_tags
+                .iter()
+                .map(|tag| Label::from(tag.as_str()))
+                .collect(),
+            layout: if args.html_nest {
+                html::Layout::Nest
+            } else if args.html_toc {
+                html::Layout::Toc
+            } else {
+                html::Layout::List
+            },
+        },
+        readable_edges: args.readable_edges,
+        id_scheme: args.id_scheme,
+        karakeep_chunk_size: args.karakeep_chunk_size,
+    }
+}
+
+/// Returns the outputs to write the collection to, or the directory to split it into by year.
+fn outputs(args: &Args) -> Result<(Vec<Output>, Option<SplitByYear>), Error> {
+    let check_chunked = |format: OutputFormat| {
+        if args.karakeep_chunk_size.is_some() && format != OutputFormat::Karakeep {
+            let name: &'static str = format.into();
+            return Err(Error::msg(format!(
+                "--karakeep-chunk-size cannot be used with {name} output"
+            )));
         }
-        return Ok(());
+        Ok(())
+    };
+
+    if prints(args) {
+        return Ok((Vec::new(), None));
+    }
+
+    if let Some(dir) = &args.split_by_year {
+        let format = args.to.first().copied().unwrap_or(OutputFormat::Yaml);
+        check_chunked(format)?;
+        let split = SplitByYear {
+            dir: dir.clone(),
+            format,
+        };
+        return Ok((Vec::new(), Some(split)));
     }
 
-    Err(Error::msg(
-        "Must specify an output format (-t) or analysis flag (--info, --list-tags)",
-    ))
+    let outputs = hbt_app::outputs(&args.to, &args.output).map_err(app_error)?;
+    if outputs.is_empty() && !args.dry_run {
+        return Err(Error::msg(
+            "Must specify an output format (-t), --export-obsidian, --export-site, or analysis flag (--info, --list, --list-tags, --list-domains, --badge)",
+        ));
+    }
+    for output in &outputs {
+        check_chunked(output.format)?;
+    }
+    Ok((outputs, None))
 }
 
-fn main() -> Result<ExitCode, Error> {
-    let args = Args::parse();
+fn config(
+    args: &Args,
+    file: &Path,
+    maybe_input_format: Option<InputFormat>,
+) -> Result<Config, Error> {
+    let (outputs, maybe_split_by_year) = outputs(args)?;
+    let maybe_redact = match &args.redact {
+        Some(policy) => Some(serde_norway::from_str(&fs::read_to_string(policy)?)?),
+        None => None,
+    };
+
+    #[cfg(feature = "sqlite")]
+    let append = args.append;
+    #[cfg(not(feature = "sqlite"))]
+    let append = false;
+
+    Ok(Config {
+        input: Input {
+            path: file.to_path_buf(),
+            maybe_format: maybe_input_format,
+            notes: args.notes,
+            shortcuts: args.shortcuts,
+            maybe_date_pattern: args.date_from_file_name.clone(),
+            lenient: args.lenient,
+        },
+        parse_options: parse_options(args)?,
+        updates: updates(args)?,
+        fail_on_empty: args.fail_on_empty,
+        maybe_expected_count: args.expect_count,
+        selection: selection(args),
+        #[cfg(feature = "snapshot")]
+        maybe_snapshot: args.snapshot.clone().map(|dir| (dir, args.snapshot_format)),
+        maybe_redact,
+        outputs,
+        maybe_split_by_year,
+        unparse_options: unparse_options(args, load_label_meta(args)?),
+        append,
+        maybe_backup: args.backup(),
+        dry_run: args.dry_run,
+    })
+}
+
+/// Prints what the updates did.
+fn report_updates(args: &Args, updates: &update::Report) {
+    if let Some(sync) = &updates.maybe_sync
+        && (args.sync_report.is_none() || args.dry_run)
+    {
+        for conflict in &sync.conflicts {
+            eprintln!("conflict: {}", conflict.local.url().as_str());
+        }
+    }
+    if args.verbose {
+        if args.attach_embeddings.is_some() {
+            eprintln!(
+                "Embeddings attached to {} entities",
+                updates.embeddings_attached
+            );
+        }
+        if args.auto_date_labels {
+            eprintln!(
+                "Date labels added to {} entities",
+                updates.date_labels_added
+            );
+        }
+        if args.implications.is_some() {
+            eprintln!(
+                "Implied labels added to {} entities",
+                updates.implied_labels_added
+            );
+        }
+    }
+    for step in &updates.steps {
+        eprintln!("{step}");
+    }
+    for change in &updates.replacements {
+        if args.replace_dry_run {
+            eprintln!("would replace: {change}");
+        } else if args.verbose {
+            eprintln!("replaced: {change}");
+        }
+    }
+    if let Some(compaction) = &updates.maybe_compaction {
+        eprintln!(
+            "edges: {} -> {}",
+            compaction.edges_before, compaction.edges_after
+        );
+        eprintln!(
+            "bytes: {} -> {}",
+            compaction.bytes_before, compaction.bytes_after
+        );
+    }
+}
+
+/// Prints the warnings found while reading the input, writing them to the --report file, and
+/// what the updates did.
+fn report(args: &Args, summary: &mut Summary) -> Result<(), Error> {
+    for (path, err) in summary.skipped.drain(..) {
+        eprintln!(
+            "{}",
+            error_format::format(&app_error(err), Some(&path), args.error_format)
+        );
+    }
+
+    let report = &summary.report;
+    if args.verbose {
+        for duplicate in &report.duplicates {
+            let fields: Vec<&str> = duplicate.fields.iter().map(String::as_str).collect();
+            if fields.is_empty() {
+                eprintln!(
+                    "warning: merged {} occurrences of {}",
+                    duplicate.count,
+                    duplicate.url.as_str()
+                );
+            } else {
+                eprintln!(
+                    "warning: merged {} occurrences of {} (differing: {})",
+                    duplicate.count,
+                    duplicate.url.as_str(),
+                    fields.join(", ")
+                );
+            }
+        }
+        for raw in &report.invalid_urls {
+            eprintln!("warning: kept invalid URL: {}", raw.as_str());
+        }
+        for anomaly in &report.anomalies {
+            eprintln!("warning: {anomaly}");
+        }
+    }
+
+    if let Some(report_file) = &args.report
+        && !args.dry_run
+    {
+        let file = File::create(report_file)?;
+        let mut writer = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut writer, report)?;
+        writer.flush()?;
+    }
+
+    report_updates(args, &summary.updates);
+
+    if let Some(seed) = summary.maybe_seed
+        && args.seed.is_none()
+    {
+        eprintln!("sampled with --seed {seed}");
+    }
+    #[cfg(feature = "snapshot")]
+    for (url, err) in &summary.snapshot_failures {
+        eprintln!("snapshot failed: {}: {err}", url.as_str());
+    }
+    if let Some(redaction) = &summary.maybe_redaction
+        && args.verbose
+    {
+        eprintln!(
+            "Redacted: {} entities removed, {} URLs hashed",
+            redaction.removed, redaction.hashed
+        );
+    }
+    if !summary.cross_year_edges.is_empty() {
+        eprintln!(
+            "warning: dropped {} edges between years",
+            summary.cross_year_edges.len()
+        );
+        if args.verbose {
+            for edge in &summary.cross_year_edges {
+                eprintln!("  {} -> {}", edge.from.as_str(), edge.to.as_str());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run(args: &Args, file: &Path, maybe_input_format: Option<InputFormat>) -> Result<(), Error> {
+    let config = config(args, file, maybe_input_format)?;
+    let mut summary = hbt_app::run(&config).map_err(app_error)?;
+    report(args, &mut summary)?;
+    if let Some(before) = &summary.maybe_before {
+        return dry_run(args, before, &summary.coll);
+    }
+    if prints(args) {
+        print(args, &summary.coll)?;
+    }
+    Ok(())
+}
 
+/// Prints a summary of the changes made by the updates, for --dry-run.
+fn dry_run(args: &Args, before: &Collection, after: &Collection) -> Result<(), Error> {
+    let diff = before.diff(after)?;
+    eprintln!(
+        "entities: {} added, {} modified, {} removed",
+        diff.added.len(),
+        diff.modified.len(),
+        diff.removed.len()
+    );
+
+    let before_labels = before.label_counts();
+    let after_labels = after.label_counts();
+    let removed: Vec<&str> = before_labels
+        .keys()
+        .filter(|label| !after_labels.contains_key(*label))
+        .map(|label| label.as_str())
+        .collect();
+    let added: Vec<&str> = after_labels
+        .keys()
+        .filter(|label| !before_labels.contains_key(*label))
+        .map(|label| label.as_str())
+        .collect();
+    eprintln!("labels: {} added, {} removed", added.len(), removed.len());
+
+    if args.verbose {
+        for label in &removed {
+            eprintln!("  -{label}");
+        }
+        for label in &added {
+            eprintln!("  +{label}");
+        }
+        let mut writer = BufWriter::new(io::stderr());
+        diff.write(&mut writer, diff::Format::Text)?;
+        writer.flush()?;
+    }
+    eprintln!("dry run: nothing written");
+    Ok(())
+}
+
+fn try_main(args: &Args) -> Result<ExitCode, Error> {
     if args.schema {
-        let schema = schema_for!(CollectionRepr);
-        if let Some(output_file) = &args.output {
-            let file = File::create(output_file)?;
-            let mut writer = BufWriter::new(file);
-            serde_json::to_writer_pretty(&mut writer, &schema)?;
-            writer.flush()?;
+        let schema = if args.info_format == InfoFormat::Json {
+            schema_for!(Info)
         } else {
-            let stdout = io::stdout();
-            let mut writer = BufWriter::new(stdout);
-            serde_json::to_writer_pretty(&mut writer, &schema)?;
-            writer.flush()?;
-        }
+            schema_for!(CollectionRepr)
+        };
+        write_output(args, |writer| {
+            Ok(serde_json::to_writer_pretty(writer, &schema)?)
+        })?;
         return Ok(ExitCode::SUCCESS);
     }
 
@@ -165,18 +1386,88 @@ fn main() -> Result<ExitCode, Error> {
         .as_ref()
         .ok_or_else(|| Error::msg("Input file required"))?;
 
-    let input_format = if let Some(format) = args.from {
-        format
+    #[cfg(feature = "bundle")]
+    if let Some(dir) = &args.extract_bundle {
+        let bundle = Bundle::extract(BufReader::new(File::open(file)?), dir)?;
+        eprintln!(
+            "{}: extracted {} entities to {}",
+            file.display(),
+            bundle.collection.len(),
+            dir.display()
+        );
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if args.verify {
+        let repr: CollectionRepr = serde_norway::from_reader(BufReader::new(File::open(file)?))?;
+        repr.verify()?;
+        eprintln!("{}: ok ({} entities)", file.display(), repr.len());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let maybe_input_format = if args.notes || args.shortcuts {
+        None
+    } else if let Some(format) = args.from {
+        Some(format)
+    } else if inputs::is_multiple(file) {
+        // Detected for each file by load_input.
+        None
     } else {
         let no_parser = || Error::msg(format!("No parser for file: {}", file.display()));
-        InputFormat::detect(file).ok_or_else(no_parser)?
+        Some(InputFormat::detect(file).ok_or_else(no_parser)?)
     };
 
-    let f = File::open(file)?;
-    let mut reader = BufReader::new(f);
-    let mut coll = input_format.parse(&mut reader)?;
-    update(&args, &mut coll)?;
-    print(&args, &coll)?;
+    if args.stream {
+        if !args.info && !args.list_tags {
+            return Err(Error::msg("--stream requires --info or --list-tags"));
+        }
+        let input_format = maybe_input_format
+            .ok_or_else(|| Error::msg("--stream requires a single input file"))?;
+        analyze(args, file, input_format)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    #[cfg(feature = "watch")]
+    if args.watch {
+        let mut paths = vec![file.clone()];
+        paths.extend(args.mappings.iter().cloned());
+        paths.extend(args.pipeline.iter().cloned());
+        paths.extend(args.redact.iter().cloned());
+        paths.extend(args.implications.iter().cloned());
+        paths.extend(args.ignore.iter().cloned());
+        paths.extend(args.label_meta.iter().cloned());
+        paths.extend(args.rewrite_urls.iter().cloned());
+        paths.extend(args.undo_mappings.iter().cloned());
+        paths.extend(args.sync_against.iter().cloned());
+        let mut outputs = args.output.clone();
+        outputs.extend(args.badge.iter().cloned());
+        hbt::watch::watch(&paths, &outputs, || {
+            if let Err(err) = run(args, file, maybe_input_format) {
+                eprintln!(
+                    "{}",
+                    error_format::format(&err, Some(file), args.error_format)
+                );
+            }
+        })?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    run(args, file, maybe_input_format)?;
 
     Ok(ExitCode::SUCCESS)
 }
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    match try_main(&args) {
+        Ok(code) => code,
+        Err(err) => {
+            let maybe_file = args.file.as_deref();
+            eprintln!(
+                "{}",
+                error_format::format(&err, maybe_file, args.error_format)
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
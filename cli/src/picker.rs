@@ -0,0 +1,127 @@
+use std::io::{self, Write};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, ClearType},
+};
+
+use hbt_core::collection::{Collection, Id};
+
+const PROMPT: &str = "> ";
+
+/// Puts the terminal in raw mode on an alternate screen, restoring it when dropped.
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Screen> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stderr(), terminal::EnterAlternateScreen)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stderr(), terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn to_u16(n: usize) -> u16 {
+    u16::try_from(n).unwrap_or(u16::MAX)
+}
+
+fn render(
+    out: &mut impl Write,
+    coll: &Collection,
+    query: &str,
+    matches: &[(Id, u32)],
+    selected: usize,
+) -> io::Result<()> {
+    let (width, height) = terminal::size()?;
+    let rows = usize::from(height).saturating_sub(1).max(1);
+    let offset = selected.saturating_sub(rows - 1);
+
+    queue!(
+        out,
+        cursor::MoveTo(0, 0),
+        terminal::Clear(ClearType::All),
+        Print(PROMPT),
+        Print(query),
+    )?;
+    for (row, (id, _)) in matches.iter().skip(offset).take(rows).enumerate() {
+        let line = coll.entity(id).summary(usize::from(width));
+        queue!(out, cursor::MoveTo(0, to_u16(row + 1)))?;
+        if offset + row == selected {
+            queue!(
+                out,
+                SetAttribute(Attribute::Reverse),
+                Print(line),
+                SetAttribute(Attribute::Reset)
+            )?;
+        } else {
+            queue!(out, Print(line))?;
+        }
+    }
+    let column = PROMPT.chars().count() + query.chars().count();
+    queue!(out, cursor::MoveTo(to_u16(column), 0))?;
+    out.flush()
+}
+
+/// Interactively fuzzy-searches `coll`, returning the chosen entity, or `None` if the user
+/// cancelled.
+///
+/// Typing refines the query, the arrow keys (or Ctrl-P and Ctrl-N) move the selection, Enter
+/// chooses the selected entity, and Escape or Ctrl-C cancels. The picker is drawn on stderr, so
+/// that stdout is left untouched.
+///
+/// # Errors
+///
+/// Returns an error if the terminal cannot be controlled.
+pub fn pick(coll: &Collection) -> io::Result<Option<Id>> {
+    let _screen = Screen::enter()?;
+    let mut out = io::stderr();
+    let mut query = String::new();
+    let mut matches = coll.fuzzy_search(&query);
+    let mut selected = 0;
+
+    loop {
+        render(&mut out, coll, &query, &matches, selected)?;
+
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event::read()?
+        else {
+            continue;
+        };
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+
+        match (code, ctrl) {
+            (KeyCode::Esc, _) | (KeyCode::Char('c'), true) => return Ok(None),
+            (KeyCode::Enter, _) => {
+                return Ok(matches.into_iter().nth(selected).map(|(id, _)| id));
+            }
+            (KeyCode::Up, _) | (KeyCode::Char('p'), true) => selected = selected.saturating_sub(1),
+            (KeyCode::Down, _) | (KeyCode::Char('n'), true) => {
+                selected = (selected + 1).min(matches.len().saturating_sub(1));
+            }
+            (KeyCode::Backspace, _) => {
+                query.pop();
+                matches = coll.fuzzy_search(&query);
+                selected = 0;
+            }
+            (KeyCode::Char(c), false) => {
+                query.push(c);
+                matches = coll.fuzzy_search(&query);
+                selected = 0;
+            }
+            _ => {}
+        }
+    }
+}
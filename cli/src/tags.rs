@@ -0,0 +1,178 @@
+use std::collections::BTreeMap;
+
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+
+use hbt_core::{entity::Label, label_meta::LabelMeta};
+
+/// Output formats for the tag vocabulary.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TagsFormat {
+    /// One tag per line
+    #[default]
+    Plain,
+    /// A JSON object mapping each tag to its count
+    Json,
+    /// fish completion candidates (`tag<TAB>description`)
+    Fish,
+    /// zsh `_describe` candidates (`tag:description`)
+    Zsh,
+}
+
+fn description(count: usize) -> String {
+    if count == 1 {
+        "1 bookmark".to_string()
+    } else {
+        format!("{count} bookmarks")
+    }
+}
+
+/// Renders label counts in the given format.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization fails.
+pub fn format(counts: &BTreeMap<&Label, usize>, format: TagsFormat) -> serde_json::Result<String> {
+    let lines: Vec<String> = match format {
+        TagsFormat::Plain => counts
+            .keys()
+            .map(|label| label.as_str().to_string())
+            .collect(),
+        TagsFormat::Json => {
+            let object: BTreeMap<&str, usize> = counts
+                .iter()
+                .map(|(label, &count)| (label.as_str(), count))
+                .collect();
+            vec![serde_json::to_string_pretty(&object)?]
+        }
+        TagsFormat::Fish => counts
+            .iter()
+            .map(|(label, &count)| format!("{}\t{}", label.as_str(), description(count)))
+            .collect(),
+        TagsFormat::Zsh => counts
+            .iter()
+            .map(|(label, &count)| {
+                let label = label.as_str().replace(':', "\\:");
+                format!("{label}:{}", description(count))
+            })
+            .collect(),
+    };
+
+    if lines.is_empty() {
+        return Ok(String::new());
+    }
+
+    let mut output = lines.join("\n");
+    output.push('\n');
+    Ok(output)
+}
+
+/// Renders label counts as a legend, one tab-separated line per label with its count, emoji,
+/// color and description from `meta` (empty where not set).
+#[must_use]
+pub fn legend(counts: &BTreeMap<&Label, usize>, meta: &LabelMeta) -> String {
+    let mut output = String::new();
+    for (label, &count) in counts {
+        let info = meta.get(label).cloned().unwrap_or_default();
+        let columns = [
+            label.as_str(),
+            &description(count),
+            info.emoji.as_deref().unwrap_or_default(),
+            info.color.as_deref().unwrap_or_default(),
+            info.description.as_deref().unwrap_or_default(),
+        ];
+        output.push_str(columns.join("\t").trim_end());
+        output.push('\n');
+    }
+    output
+}
+
+/// A node in the tree of hierarchical labels, e.g. `lang/rust` is the child `rust` of `lang`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TagNode {
+    /// The number of bookmarks carrying exactly this label.
+    pub count: usize,
+    /// `count` plus the totals of all children.
+    pub total: usize,
+    pub children: BTreeMap<String, TagNode>,
+}
+
+/// Builds a tree of labels by splitting each on `delimiter`, returning its unnamed root.
+///
+/// Empty segments are skipped, so `lang//rust` and `/lang/rust` are both placed under `lang`.
+#[must_use]
+pub fn tree(counts: &BTreeMap<&Label, usize>, delimiter: &str) -> TagNode {
+    let mut root = TagNode::default();
+    for (label, &count) in counts {
+        let mut node = &mut root;
+        node.total += count;
+        for segment in label.as_str().split(delimiter).filter(|s| !s.is_empty()) {
+            node = node.children.entry(segment.to_string()).or_default();
+            node.total += count;
+        }
+        node.count += count;
+    }
+    root
+}
+
+fn render_node(output: &mut Vec<String>, node: &TagNode, level: usize, maybe_depth: Option<usize>) {
+    if maybe_depth.is_some_and(|depth| level >= depth) {
+        return;
+    }
+    for (name, child) in &node.children {
+        let indent = "  ".repeat(level);
+        output.push(format!("{indent}{name}\t{}\t{}", child.count, child.total));
+        render_node(output, child, level + 1, maybe_depth);
+    }
+}
+
+fn node_to_json(node: &TagNode, level: usize, maybe_depth: Option<usize>) -> Map<String, Value> {
+    let mut object = Map::new();
+    object.insert("count".to_string(), node.count.into());
+    object.insert("total".to_string(), node.total.into());
+    if maybe_depth.is_none_or(|depth| level < depth) && !node.children.is_empty() {
+        let children: Map<String, Value> = node
+            .children
+            .iter()
+            .map(|(name, child)| {
+                let child = node_to_json(child, level + 1, maybe_depth);
+                (name.clone(), Value::Object(child))
+            })
+            .collect();
+        object.insert("children".to_string(), Value::Object(children));
+    }
+    object
+}
+
+/// Renders a tree of labels, showing at most `maybe_depth` levels.
+///
+/// As JSON, the tree is an object mapping each top-level name to its `count`, `total` and
+/// `children`. Otherwise, each line holds an indented name, its count and its total, separated by
+/// tabs.
+///
+/// # Errors
+///
+/// Returns an error if JSON serialization fails.
+pub fn format_tree(
+    root: &TagNode,
+    maybe_depth: Option<usize>,
+    format: TagsFormat,
+) -> serde_json::Result<String> {
+    if format == TagsFormat::Json {
+        let children = node_to_json(root, 0, maybe_depth)
+            .remove("children")
+            .unwrap_or_else(|| Value::Object(Map::new()));
+        let mut output = serde_json::to_string_pretty(&children)?;
+        output.push('\n');
+        return Ok(output);
+    }
+
+    let mut lines = Vec::new();
+    render_node(&mut lines, root, 0, maybe_depth);
+    if lines.is_empty() {
+        return Ok(String::new());
+    }
+    let mut output = lines.join("\n");
+    output.push('\n');
+    Ok(output)
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::BTreeMap,
+    path::{self, Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use hbt_app::{backup, inputs};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Returns whether a change to `path` affects `target`, a file, directory or glob pattern.
+fn affects(target: &Path, path: &Path) -> bool {
+    if inputs::is_multiple(target) {
+        inputs::matches(target, path)
+    } else {
+        target == path
+    }
+}
+
+/// Returns whether `path` is one of `outputs`, or a backup of one, which are written by
+/// `on_change` itself and so must not trigger it again.
+fn is_output(path: &Path, outputs: &[PathBuf]) -> bool {
+    outputs
+        .iter()
+        .any(|output| output == path || backup::is_backup(path, output))
+}
+
+fn is_relevant(result: &notify::Result<Event>, targets: &[PathBuf], outputs: &[PathBuf]) -> bool {
+    match result {
+        Ok(event) => {
+            !matches!(event.kind, EventKind::Access(_))
+                && event.paths.iter().any(|path| {
+                    !is_output(path, outputs) && targets.iter().any(|target| affects(target, path))
+                })
+        }
+        Err(_) => false,
+    }
+}
+
+/// Returns the directories to watch for changes to `targets`, and whether to watch each one
+/// recursively.
+fn watched_dirs(targets: &[PathBuf]) -> notify::Result<BTreeMap<PathBuf, bool>> {
+    let mut ret = BTreeMap::new();
+    for target in targets {
+        if target.is_dir() {
+            ret.insert(target.clone(), true);
+            continue;
+        }
+        let files = if inputs::is_multiple(target) {
+            inputs::expand(target)?
+        } else {
+            vec![target.clone()]
+        };
+        for file in files {
+            if let Some(parent) = file.parent() {
+                ret.entry(parent.to_owned()).or_insert(false);
+            }
+        }
+    }
+    // Directories within one watched recursively are covered by it already.
+    let recursive: Vec<PathBuf> = ret
+        .iter()
+        .filter(|&(_, &recursive)| recursive)
+        .map(|(dir, _)| dir.clone())
+        .collect();
+    ret.retain(|dir, _| {
+        !recursive
+            .iter()
+            .any(|other| dir != other && dir.starts_with(other))
+    });
+    Ok(ret)
+}
+
+/// Calls `on_change` once, and then again every time one of `paths` changes.
+///
+/// The parent directories of files are watched rather than the files themselves, so that editors
+/// which save by renaming a temporary file over the original are handled. A directory is watched
+/// recursively, and a glob pattern by watching the directories of the files it matches. Bursts of
+/// events arriving within a short window are coalesced into a single call.
+///
+/// Changes to `outputs`, the files `on_change` writes, and to their backups are ignored, even
+/// where they are among `paths` or within a watched directory, so that writing them does not
+/// trigger another call.
+///
+/// # Errors
+///
+/// Returns an error if the watcher cannot be created, a glob pattern cannot be expanded, or one of
+/// the directories cannot be watched.
+pub fn watch(
+    paths: &[PathBuf],
+    outputs: &[PathBuf],
+    mut on_change: impl FnMut(),
+) -> notify::Result<()> {
+    let outputs = outputs
+        .iter()
+        .map(path::absolute)
+        .collect::<Result<Vec<PathBuf>, _>>()?;
+    let mut targets = paths
+        .iter()
+        .map(path::absolute)
+        .collect::<Result<Vec<PathBuf>, _>>()?;
+    targets.retain(|target| !outputs.contains(target));
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for (dir, recursive) in watched_dirs(&targets)? {
+        let mode = if recursive {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        watcher.watch(&dir, mode)?;
+    }
+
+    on_change();
+
+    while let Ok(result) = rx.recv() {
+        if !is_relevant(&result, &targets, &outputs) {
+            continue;
+        }
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+        on_change();
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        env, fs,
+        path::{Path, PathBuf},
+        process, slice,
+    };
+
+    use notify::{
+        Event, EventKind,
+        event::{ModifyKind, RemoveKind},
+    };
+
+    use super::{affects, is_relevant, watched_dirs};
+
+    fn setup(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("hbt-watch-{name}-{}", process::id()));
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        for file in ["a.md", "nested/b.md", "nested/c.html"] {
+            fs::write(dir.join(file), "").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn directory_inputs_are_watched_recursively() {
+        let dir = setup("dir");
+        let mappings = dir.join("nested").join("mappings.yaml");
+
+        assert_eq!(
+            watched_dirs(&[dir.clone(), mappings]).unwrap(),
+            BTreeMap::from([(dir.clone(), true)])
+        );
+        assert!(affects(&dir, &dir.join("nested/b.md")));
+        assert!(affects(&dir, &dir.join("nested/new.md")));
+        assert!(!affects(&dir, &dir.join("nested/.b.md.1.tmp")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn glob_inputs_watch_the_directories_of_matches() {
+        let dir = setup("glob");
+        let pattern = dir.join("**").join("*.md");
+
+        assert_eq!(
+            watched_dirs(slice::from_ref(&pattern)).unwrap(),
+            BTreeMap::from([(dir.clone(), false), (dir.join("nested"), false)])
+        );
+        assert!(affects(&pattern, &dir.join("nested/b.md")));
+        assert!(affects(&pattern, &dir.join("new.md")));
+        assert!(!affects(&pattern, &dir.join("nested/c.html")));
+        assert!(!affects(Path::new("a.md"), &dir.join("a.md")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn outputs_and_backups_do_not_trigger() {
+        let dir = setup("outputs");
+        let output = dir.join("nested").join("out.md");
+        let backup = dir
+            .join("nested")
+            .join("out.md.20240101T120000.000000Z.bak");
+        let targets = [dir.clone()];
+        let outputs = [output.clone()];
+        let event = |path: &Path| {
+            Ok(Event::new(EventKind::Modify(ModifyKind::Any)).add_path(path.to_owned()))
+        };
+
+        assert!(is_relevant(&event(&dir.join("a.md")), &targets, &outputs));
+        assert!(!is_relevant(&event(&output), &targets, &outputs));
+        assert!(!is_relevant(&event(&backup), &targets, &outputs));
+        let removed = Event::new(EventKind::Remove(RemoveKind::File))
+            .add_path(output.clone())
+            .add_path(dir.join("a.md"));
+        assert!(is_relevant(&Ok(removed), &targets, &outputs));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
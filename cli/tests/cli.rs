@@ -1,4 +1,4 @@
-use std::path::Path;
+use std::{env, fs, path::Path, process};
 
 use snapbox::cmd::Command;
 use snapbox::{cargo_bin, file};
@@ -28,6 +28,15 @@ fn info_flag() {
         .success();
 }
 
+#[test]
+fn info_json_flag() {
+    Command::new(cargo_bin!("hbt"))
+        .current_dir(workspace_root())
+        .args(["--info", "--info-format", "json", TEST_FILE])
+        .assert()
+        .success();
+}
+
 #[test]
 fn list_tags_flag() {
     Command::new(cargo_bin!("hbt"))
@@ -37,6 +46,15 @@ fn list_tags_flag() {
         .success();
 }
 
+#[test]
+fn list_urls_flag() {
+    Command::new(cargo_bin!("hbt"))
+        .current_dir(workspace_root())
+        .args(["--list-urls", "--unique", TEST_FILE])
+        .assert()
+        .success();
+}
+
 #[test]
 fn yaml_output() {
     Command::new(cargo_bin!("hbt"))
@@ -54,3 +72,367 @@ fn html_output() {
         .assert()
         .success();
 }
+
+#[test]
+fn fail_on_empty_flag() {
+    Command::new(cargo_bin!("hbt"))
+        .current_dir(workspace_root())
+        .args(["--fail-on-empty", "--info", TEST_FILE])
+        .assert()
+        .success();
+}
+
+#[test]
+fn fail_on_empty_input() {
+    let dir = env::temp_dir().join(format!("hbt-cli-empty-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.md");
+    fs::write(&input, "").unwrap();
+
+    Command::new(cargo_bin!("hbt"))
+        .args(["--fail-on-empty", "--info"])
+        .arg(&input)
+        .assert()
+        .failure()
+        .stderr_eq("Error: Input contains no entities\n");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn expect_count_mismatch() {
+    let dir = env::temp_dir().join(format!("hbt-cli-count-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.md");
+    fs::write(
+        &input,
+        "# November 15, 2023\n\n- [A](https://a.example.com/)\n- [B](https://b.example.com/)\n",
+    )
+    .unwrap();
+    let expect_count = |count: &str| {
+        Command::new(cargo_bin!("hbt"))
+            .args(["--expect-count", count, "--info"])
+            .arg(&input)
+            .assert()
+    };
+
+    expect_count("0")
+        .failure()
+        .stderr_eq("Error: Expected 0 entities, found 2\n");
+    expect_count("2").success();
+    expect_count("1..3").success();
+    expect_count("3..5")
+        .failure()
+        .stderr_eq("Error: Expected 3..5 entities, found 2\n");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn sample_flag() {
+    Command::new(cargo_bin!("hbt"))
+        .current_dir(workspace_root())
+        .args(["--sample", "2", "--seed", "1", "-t", "yaml", TEST_FILE])
+        .assert()
+        .success();
+}
+
+#[test]
+fn sample_seed_is_printed() {
+    let dir = env::temp_dir().join(format!("hbt-cli-sample-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.md");
+    fs::write(
+        &input,
+        "# November 15, 2023\n\n- [A](https://a.example.com/)\n- [B](https://b.example.com/)\n",
+    )
+    .unwrap();
+
+    Command::new(cargo_bin!("hbt"))
+        .args(["--sample", "1", "-t", "yaml"])
+        .arg(&input)
+        .assert()
+        .success()
+        .stderr_eq("sampled with --seed [..]\n");
+
+    Command::new(cargo_bin!("hbt"))
+        .args(["--sample", "1", "--seed", "1", "-t", "yaml"])
+        .arg(&input)
+        .assert()
+        .success()
+        .stderr_eq("");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn compact_prints_sizes() {
+    let dir = env::temp_dir().join(format!("hbt-cli-compact-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.md");
+    fs::write(
+        &input,
+        "# November 15, 2023\n\n- [A](https://a.example.com/)\n",
+    )
+    .unwrap();
+
+    let output = Command::new(cargo_bin!("hbt"))
+        .args(["--compact", "-t", "yaml"])
+        .arg(&input)
+        .assert()
+        .success()
+        .get_output()
+        .clone();
+    let yaml_len = output.stdout.len();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(
+        stderr,
+        format!("edges: 0 -> 0\nbytes: {yaml_len} -> {yaml_len}\n")
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn backup_replaced_outputs() {
+    let dir = env::temp_dir().join(format!("hbt-cli-backup-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.md");
+    fs::write(
+        &input,
+        "# November 15, 2023\n\n- [A](https://a.example.com/)\n",
+    )
+    .unwrap();
+    let merges = dir.join("merges.yaml");
+    let badge = dir.join("badge.svg");
+    fs::write(&merges, "old").unwrap();
+    fs::write(&badge, "old").unwrap();
+
+    Command::new(cargo_bin!("hbt"))
+        .args(["--suggest-merges", "--backup", "-o"])
+        .arg(&merges)
+        .arg(&input)
+        .assert()
+        .success();
+    Command::new(cargo_bin!("hbt"))
+        .arg("--badge")
+        .arg(&badge)
+        .arg("--backup")
+        .arg(&input)
+        .assert()
+        .success();
+
+    for path in [&merges, &badge] {
+        let name = path.file_name().unwrap().to_str().unwrap();
+        let backups: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|backup| {
+                let backup = backup.file_name().unwrap().to_str().unwrap();
+                backup.starts_with(&format!("{name}.")) && backup.ends_with(".bak")
+            })
+            .collect();
+        assert_eq!(backups.len(), 1, "{name}");
+        assert_eq!(fs::read_to_string(&backups[0]).unwrap(), "old");
+        assert_ne!(fs::read_to_string(path).unwrap(), "old");
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn list_tags_json() {
+    Command::new(cargo_bin!("hbt"))
+        .current_dir(workspace_root())
+        .args(["--list-tags", "--tags-format", "json", TEST_FILE])
+        .assert()
+        .success();
+}
+
+#[test]
+fn error_format_json() {
+    Command::new(cargo_bin!("hbt"))
+        .current_dir(workspace_root())
+        .args(["--error-format", "json", "--info", "does-not-exist.md"])
+        .assert()
+        .failure()
+        .stderr_eq(
+            "{\"causes\":[],\"file\":\"does-not-exist.md\",\"kind\":\"io\",\"line\":null,\"message\":\"[..]\"}\n",
+        );
+}
+
+#[test]
+fn list_tags_tree() {
+    Command::new(cargo_bin!("hbt"))
+        .current_dir(workspace_root())
+        .args(["--list-tags", "--tags-tree", "--depth", "1", TEST_FILE])
+        .assert()
+        .success();
+}
+
+#[test]
+fn several_formats_need_output_files() {
+    Command::new(cargo_bin!("hbt"))
+        .current_dir(workspace_root())
+        .args(["-t", "yaml", "-t", "html", TEST_FILE])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn dry_run_writes_nothing() {
+    Command::new(cargo_bin!("hbt"))
+        .current_dir(workspace_root())
+        .args(["--dry-run", "--normalize-urls", "-t", "yaml", TEST_FILE])
+        .assert()
+        .success()
+        .stdout_eq("");
+}
+
+#[test]
+fn sync_against_yaml_archive() {
+    let dir = env::temp_dir().join(format!("hbt-cli-sync-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let archive_input = dir.join("archive.md");
+    let archive = dir.join("archive.yaml");
+    let input = dir.join("input.md");
+    fs::write(
+        &archive_input,
+        "# November 15, 2023\n\n- [Old](https://old.example.com/)\n",
+    )
+    .unwrap();
+    fs::write(
+        &input,
+        "# November 15, 2023\n\n- [Old](https://old.example.com/)\n\n\
+         # November 16, 2023\n\n- [New](https://new.example.com/)\n",
+    )
+    .unwrap();
+    Command::new(cargo_bin!("hbt"))
+        .arg("-t")
+        .arg("yaml")
+        .arg("-o")
+        .arg(&archive)
+        .arg(&archive_input)
+        .assert()
+        .success();
+
+    Command::new(cargo_bin!("hbt"))
+        .args([
+            "--sync-since",
+            "2023-11-15T12:00:00Z",
+            "--info",
+            "--sync-against",
+        ])
+        .arg(&archive)
+        .arg(&input)
+        .assert()
+        .success()
+        .stdout_eq("[..]: 2 entities\n");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn diff_against_yaml_archive() {
+    let dir = env::temp_dir().join(format!("hbt-cli-diff-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let new_input = dir.join("new.md");
+    let new = dir.join("new.yaml");
+    let input = dir.join("input.md");
+    fs::write(
+        &input,
+        "# November 15, 2023\n\n- [Old](https://old.example.com/)\n",
+    )
+    .unwrap();
+    fs::write(
+        &new_input,
+        "# November 15, 2023\n\n- [Old](https://old.example.com/)\n\n\
+         # November 16, 2023\n\n- [New](https://new.example.com/)\n",
+    )
+    .unwrap();
+    Command::new(cargo_bin!("hbt"))
+        .arg("-t")
+        .arg("yaml")
+        .arg("-o")
+        .arg(&new)
+        .arg(&new_input)
+        .assert()
+        .success();
+
+    Command::new(cargo_bin!("hbt"))
+        .arg("--diff")
+        .arg(&new)
+        .arg(&input)
+        .assert()
+        .success()
+        .stdout_eq("@@ +https://new.example.com/ @@\n");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn dry_run_previews_redaction() {
+    let dir = env::temp_dir().join(format!("hbt-cli-dry-run-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.md");
+    let policy = dir.join("policy.yaml");
+    fs::write(
+        &input,
+        "# November 15, 2023\n\n- [A](https://a.example.com/)\n- [B](https://b.example.com/)\n",
+    )
+    .unwrap();
+    fs::write(
+        &policy,
+        "drop-private: false\nremove-urls: [b.example.com]\n",
+    )
+    .unwrap();
+
+    Command::new(cargo_bin!("hbt"))
+        .args(["--dry-run", "-t", "yaml", "--redact"])
+        .arg(&policy)
+        .arg(&input)
+        .assert()
+        .success()
+        .stdout_eq("")
+        .stderr_eq(
+            "entities: 0 added, 0 modified, 1 removed\n\
+             labels: 0 added, 0 removed\n\
+             dry run: nothing written\n",
+        );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn karakeep_chunk_size_is_validated() {
+    Command::new(cargo_bin!("hbt"))
+        .args([
+            "--karakeep-chunk-size",
+            "0",
+            "-t",
+            "karakeep",
+            "-o",
+            "archive.json",
+            "input.md",
+        ])
+        .assert()
+        .failure();
+
+    let dir = env::temp_dir().join(format!("hbt-cli-chunks-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("input.md");
+    fs::write(
+        &input,
+        "# November 15, 2023\n\n- [A](https://a.example.com/)\n",
+    )
+    .unwrap();
+    Command::new(cargo_bin!("hbt"))
+        .args(["--karakeep-chunk-size", "2", "-t", "yaml", "-o"])
+        .arg(dir.join("archive.yaml"))
+        .arg(&input)
+        .assert()
+        .failure()
+        .stderr_eq("Error: --karakeep-chunk-size cannot be used with yaml output\n");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,29 @@
+#![forbid(unsafe_code)]
+#![warn(clippy::pedantic)]
+
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use hbt_core::collection::Collection;
+use hbt_gen::Format;
+
+/// Enough bookmarks for a few dozen yearly folders, similar in shape to a large browser export.
+const COUNT: usize = 100_000;
+
+fn from_html(c: &mut Criterion) {
+    let html = hbt_gen::generate(Format::HtmlFolders, COUNT, 0);
+    let mut group = c.benchmark_group("from_html");
+    group.sample_size(10);
+    group.bench_function("sequential", |b| {
+        b.iter(|| Collection::from_html(black_box(&html)));
+    });
+    #[cfg(feature = "parallel")]
+    group.bench_function("parallel", |b| {
+        b.iter(|| Collection::from_html_parallel(black_box(&html)));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, from_html);
+criterion_main!(benches);
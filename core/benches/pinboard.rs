@@ -0,0 +1,45 @@
+#![forbid(unsafe_code)]
+#![warn(clippy::pedantic)]
+
+use std::hint::black_box;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use hbt_core::{ParseError, entity::Entity};
+use hbt_gen::Format;
+use hbt_pinboard::Post;
+
+const COUNT: usize = 20_000;
+
+/// Converts every post of a Pinboard XML export, either straight from the borrowed attributes or
+/// through an owned `Post`.
+fn from_xml(xml: &str, borrowed: bool) -> Vec<Entity> {
+    let mut entities = Vec::with_capacity(COUNT);
+    Post::for_each_xml(&mut xml.as_bytes(), |post| {
+        let entity = if borrowed {
+            Entity::try_from(post)?
+        } else {
+            Entity::try_from(Post::from(post))?
+        };
+        entities.push(entity);
+        Ok::<(), ParseError>(())
+    })
+    .expect("generated XML should parse");
+    entities
+}
+
+fn pinboard_xml(c: &mut Criterion) {
+    let xml = hbt_gen::generate(Format::Xml, COUNT, 0);
+    let mut group = c.benchmark_group("pinboard_xml");
+    group.sample_size(10);
+    group.bench_function("borrowed", |b| {
+        b.iter(|| from_xml(black_box(&xml), true));
+    });
+    group.bench_function("owned", |b| {
+        b.iter(|| from_xml(black_box(&xml), false));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, pinboard_xml);
+criterion_main!(benches);
@@ -0,0 +1,212 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::BufRead,
+};
+
+use hbt_pinboard::Post;
+
+use crate::{
+    Info, InputFormat, ParseError, ParseOptions, encoding,
+    entity::{Entity, Label, Profile, Time, UrlPolicy},
+    html,
+};
+
+/// Statistics about a stream of entities, accumulated one at a time without building a
+/// collection, for summarizing inputs too large to hold as a graph.
+///
+/// Unlike [`Info::from`], entities are not merged by URL, so a URL that appears more than once in
+/// the input is counted each time, and edges are not counted.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Analysis {
+    entities: usize,
+    label_counts: BTreeMap<Label, usize>,
+    profiles: BTreeSet<Profile>,
+    earliest: Option<Time>,
+    latest: Option<Time>,
+}
+
+impl Analysis {
+    #[must_use]
+    pub fn new() -> Analysis {
+        Analysis::default()
+    }
+
+    /// Adds an entity to the statistics.
+    pub fn visit(&mut self, entity: &Entity) {
+        self.entities += 1;
+        for label in entity.labels() {
+            *self.label_counts.entry(label.clone()).or_insert(0) += 1;
+        }
+        if let Some(profile) = entity.profile() {
+            self.profiles.insert(profile.clone());
+        }
+        let created_at = entity.created_at().get();
+        self.earliest = Some(
+            self.earliest
+                .map_or(created_at, |time| time.min(created_at)),
+        );
+        let last_modified = entity.last_modified();
+        self.latest = Some(
+            self.latest
+                .map_or(last_modified, |time| time.max(last_modified)),
+        );
+    }
+
+    /// Returns the number of entities visited.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entities
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entities == 0
+    }
+
+    /// Returns the number of entities carrying each label, as in
+    /// [`Collection::label_counts`](crate::collection::Collection::label_counts).
+    #[must_use]
+    pub fn label_counts(&self) -> BTreeMap<&Label, usize> {
+        self.label_counts
+            .iter()
+            .map(|(label, count)| (label, *count))
+            .collect()
+    }
+
+    /// Returns the statistics as an [`Info`], with no edges.
+    #[must_use]
+    pub fn info(&self) -> Info {
+        Info {
+            format: None,
+            entities: self.entities,
+            labels: self.label_counts.len(),
+            profiles: self.profiles.len(),
+            edges: 0,
+            earliest: self.earliest,
+            latest: self.latest,
+        }
+    }
+}
+
+impl InputFormat {
+    /// Parses input in the specified format, passing each entity to `f` in input order instead of
+    /// building a collection. Entities matching the ignore list in `options` are skipped, and
+    /// entities sharing a URL are not merged.
+    ///
+    /// Pinboard JSON and XML are read one post at a time. HTML is parsed whole, but without
+    /// indexing URLs or edges. Other formats are parsed into a collection first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input is malformed, exceeds the limits in `options`, or cannot be
+    /// parsed according to the format specification.
+    pub fn for_each_entity(
+        &self,
+        reader: &mut impl BufRead,
+        options: &ParseOptions,
+        mut f: impl FnMut(Entity),
+    ) -> Result<(), ParseError> {
+        let mut count = 0_usize;
+        let mut visit = |entity: Entity| {
+            if options.ignore.is_match(entity.url()) {
+                return Ok(());
+            }
+            count += 1;
+            options.limits.check_entities(count)?;
+            options.limits.check_entity(&entity)?;
+            f(entity);
+            Ok::<(), ParseError>(())
+        };
+        #[cfg(feature = "encoding")]
+        let bytes = {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+            bytes
+        };
+        #[cfg(feature = "encoding")]
+        let decoded = encoding::decode(&bytes);
+        #[cfg(feature = "encoding")]
+        let reader = &mut decoded.as_bytes();
+        #[cfg(not(feature = "encoding"))]
+        encoding::skip_bom(reader)?;
+        self.each_entity(reader, options, &mut visit)
+    }
+
+    fn each_entity(
+        self,
+        reader: &mut impl BufRead,
+        options: &ParseOptions,
+        visit: &mut impl FnMut(Entity) -> Result<(), ParseError>,
+    ) -> Result<(), ParseError> {
+        match self {
+            InputFormat::Json => Post::for_each_json(reader, |mut post| {
+                options.url_policy.prepare(&mut post.href);
+                visit(Entity::try_from(post)?)
+            }),
+            InputFormat::Xml => Post::for_each_xml(reader, |mut post| {
+                if options.url_policy == UrlPolicy::KeepInvalid {
+                    options.url_policy.prepare(post.href.to_mut());
+                }
+                visit(Entity::try_from(post)?)
+            }),
+            InputFormat::Html => {
+                let mut buf = String::new();
+                reader.read_to_string(&mut buf)?;
+                let html_options = html::ReadOptions {
+                    url_policy: options.url_policy,
+                    keep_raw: false,
+                    limits: options.limits,
+                };
+                let (entities, _) = html::parse_entities(&buf, &html_options, 0)?;
+                entities.into_iter().try_for_each(visit)
+            }
+            _ => {
+                let coll = self.parse_collection(reader, options, &mut Vec::new())?;
+                coll.entities().iter().cloned().try_for_each(visit)
+            }
+        }
+    }
+
+    /// Summarizes the input without building a collection; see [`Analysis`].
+    ///
+    /// # Errors
+    ///
+    /// See [`InputFormat::for_each_entity`].
+    pub fn analyze(
+        &self,
+        reader: &mut impl BufRead,
+        options: &ParseOptions,
+    ) -> Result<Analysis, ParseError> {
+        let mut ret = Analysis::new();
+        self.for_each_entity(reader, options, |entity| ret.visit(&entity))?;
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InputFormat, ParseOptions, entity::Label};
+
+    const POSTS: &str = r#"[
+  {"href": "https://example.com/", "time": "2023-11-15T00:00:00Z", "description": "",
+   "extended": "", "meta": "", "hash": "", "shared": "no", "toread": "no", "tags": "rust web"},
+  {"href": "https://example.org/", "time": "2020-09-13T00:00:00Z", "description": "",
+   "extended": "", "meta": "", "hash": "", "shared": "no", "toread": "no", "tags": "rust"},
+  {"href": "https://example.com/", "time": "2024-01-01T00:00:00Z", "description": "",
+   "extended": "", "meta": "", "hash": "", "shared": "no", "toread": "no", "tags": ""}
+]"#;
+
+    #[test]
+    fn analysis_streams_without_merging() {
+        let analysis = InputFormat::Json
+            .analyze(&mut POSTS.as_bytes(), &ParseOptions::default())
+            .unwrap();
+        assert_eq!(analysis.len(), 3);
+        assert_eq!(analysis.label_counts()[&Label::from("rust")], 2);
+
+        let info = analysis.info();
+        assert_eq!(info.labels, 2);
+        assert_eq!(info.earliest.unwrap().to_string(), "2020-09-13T00:00:00Z");
+        assert_eq!(info.latest.unwrap().to_string(), "2024-01-01T00:00:00Z");
+    }
+}
@@ -0,0 +1,77 @@
+use tokio::io::{AsyncBufRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::{
+    InputFormat, OutputFormat, ParseError, ParseOptions, UnparseError, UnparseOptions,
+    collection::Collection,
+};
+
+impl InputFormat {
+    /// Parses input read asynchronously from `reader`. See [`InputFormat::parse_with_options`].
+    ///
+    /// The input is read into memory before being parsed with the synchronous parser, so the
+    /// runtime is never blocked waiting on I/O.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, or if the input is malformed or cannot be parsed
+    /// according to the format specification.
+    pub async fn parse_async(
+        &self,
+        reader: &mut (impl AsyncBufRead + Unpin),
+        options: &ParseOptions,
+    ) -> Result<Collection, ParseError> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await?;
+        self.parse_with_options(&mut buf.as_slice(), options)
+    }
+}
+
+impl OutputFormat {
+    /// Writes a collection asynchronously to `writer`. See
+    /// [`OutputFormat::unparse_with_options`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the output fails or if serialization encounters an issue.
+    pub async fn unparse_async(
+        &self,
+        writer: &mut (impl AsyncWrite + Unpin),
+        coll: &Collection,
+        options: &UnparseOptions,
+    ) -> Result<(), UnparseError> {
+        let mut buf = Vec::new();
+        self.unparse_with_options(&mut buf, coll, options)?;
+        writer.write_all(&buf).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{InputFormat, OutputFormat, ParseOptions, UnparseOptions};
+
+    const MARKDOWN: &str = "# January 1, 2024\n\n- [Example](https://example.com/)\n";
+
+    #[tokio::test]
+    async fn async_matches_sync() {
+        let options = ParseOptions::default();
+        let coll = InputFormat::Markdown
+            .parse_async(&mut MARKDOWN.as_bytes(), &options)
+            .await
+            .unwrap();
+        let expected = InputFormat::Markdown
+            .parse(&mut MARKDOWN.as_bytes())
+            .unwrap();
+        assert_eq!(coll, expected);
+
+        let mut output = Vec::new();
+        OutputFormat::Yaml
+            .unparse_async(&mut output, &coll, &UnparseOptions::default())
+            .await
+            .unwrap();
+        let mut expected = Vec::new();
+        OutputFormat::Yaml.unparse(&mut expected, &coll).unwrap();
+        assert_eq!(output, expected);
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::{self, Write};
+
+use minijinja::{Environment, context};
+use thiserror::Error;
+
+use crate::Info;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Template error: {0}")]
+    Template(#[from] minijinja::Error),
+}
+
+const SVG_TEMPLATE: &str = include_str!("badge/badge.svg");
+const SUMMARY_TEMPLATE: &str = include_str!("badge/summary.md");
+
+const LABEL: &str = "bookmarks";
+
+/// The approximate width of `text` in the badge's 11px sans-serif font, with padding, in pixels.
+fn text_width(text: &str) -> usize {
+    text.chars().count() * 7 + 10
+}
+
+impl Info {
+    /// Returns the badge's value: the number of entities and, if any, the date of the latest
+    /// change, e.g. `1234 | 2024-05-01`.
+    fn badge_value(&self) -> String {
+        match self.latest {
+            Some(latest) => format!("{} | {}", self.entities, latest.date()),
+            None => self.entities.to_string(),
+        }
+    }
+
+    /// Writes a small SVG badge, in the style of shields.io, showing the number of bookmarks and
+    /// when the collection was last updated.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if template rendering fails or writing to the output fails.
+    pub fn to_badge(&self, mut writer: impl Write) -> Result<(), Error> {
+        let value = self.badge_value();
+        let mut env = Environment::new();
+        env.add_template("badge.svg", SVG_TEMPLATE)?;
+        let template = env.get_template("badge.svg")?;
+        template.render_captured_to(
+            context! {
+                label => LABEL,
+                value => &value,
+                label_width => text_width(LABEL),
+                value_width => text_width(&value),
+            },
+            &mut writer,
+        )?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    /// Writes a markdown snippet, for a README or blog post, embedding the badge at `badge` (a
+    /// path or URL, as written by [`Info::to_badge`]) above a sentence summarizing the
+    /// collection.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if template rendering fails or writing to the output fails.
+    pub fn to_badge_summary(&self, mut writer: impl Write, badge: &str) -> Result<(), Error> {
+        let mut env = Environment::new();
+        env.add_template("summary.md", SUMMARY_TEMPLATE)?;
+        let template = env.get_template("summary.md")?;
+        template.render_captured_to(
+            context! {
+                label => LABEL,
+                value => self.badge_value(),
+                badge => badge,
+                info => self,
+                earliest => self.earliest.map(|time| time.date().to_string()),
+                latest => self.latest.map(|time| time.date().to_string()),
+            },
+            &mut writer,
+        )?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Label, Time, Url},
+    };
+
+    #[test]
+    fn badge_shows_count_and_last_update() {
+        let mut coll = Collection::new();
+        for (url, timestamp) in [
+            ("https://example.com/", 1_700_006_400),
+            ("https://example.org/", 1_600_000_000),
+        ] {
+            let time = Time::from_timestamp(timestamp).unwrap();
+            let labels = BTreeSet::from([Label::from("rust")]);
+            coll.upsert(Entity::new(Url::parse(url).unwrap(), time, None, labels));
+        }
+        let stats = coll.stats();
+
+        let mut svg = Vec::new();
+        stats.to_badge(&mut svg).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(">2 | 2023-11-15</text>"));
+
+        let mut summary = Vec::new();
+        stats.to_badge_summary(&mut summary, "badge.svg").unwrap();
+        assert_eq!(
+            String::from_utf8(summary).unwrap(),
+            "![bookmarks: 2 | 2023-11-15](badge.svg)\n\n\
+             **2** bookmarks with **1** labels, saved since 2020-09-13, last updated 2023-11-15.\n"
+        );
+    }
+}
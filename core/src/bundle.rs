@@ -0,0 +1,326 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fmt::Write as _,
+    fs,
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+use crate::{collection::Collection, label_meta::LabelMeta};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_norway::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("unsupported bundle version: {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("bundle is missing {0}")]
+    MissingFile(String),
+
+    #[error("checksum mismatch for {0}")]
+    ChecksumMismatch(String),
+
+    #[error("unsafe path in bundle: {0}")]
+    UnsafePath(String),
+}
+
+/// The conventional extension of bundle files.
+pub const EXTENSION: &str = "hbtz";
+
+const VERSION: u32 = 1;
+
+const MANIFEST: &str = "manifest.json";
+const COLLECTION: &str = "collection.yaml";
+const MAPPINGS: &str = "mappings.yaml";
+const LABEL_META: &str = "label-meta.yaml";
+const SNAPSHOTS: &str = "snapshots";
+
+/// Lists the files in a bundle along with their SHA-256 digests.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    files: BTreeMap<String, String>,
+}
+
+fn digest(contents: &[u8]) -> String {
+    let mut hex = String::with_capacity(64);
+    for byte in Sha256::digest(contents) {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    hex
+}
+
+/// Returns the name of a snapshot within a bundle, keeping the last two components of its path
+/// (the content-addressed `xx/<digest>.<ext>` layout used by `snapshot`).
+fn snapshot_name(path: &Path) -> Option<String> {
+    let mut parts = path
+        .iter()
+        .rev()
+        .take(2)
+        .map(OsStr::to_str)
+        .collect::<Option<Vec<&str>>>()?;
+    parts.reverse();
+    Some(format!("{SNAPSHOTS}/{}", parts.join("/")))
+}
+
+/// The contents of an extracted bundle. See [`Collection::to_bundle`].
+#[derive(Debug, Default)]
+pub struct Bundle {
+    pub collection: Collection,
+    /// The contents of a mappings file, as read by `--mappings`.
+    pub mappings: Option<String>,
+    pub label_meta: Option<LabelMeta>,
+}
+
+struct BundleWriter<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    manifest: Manifest,
+}
+
+impl<W: Write + Seek> BundleWriter<W> {
+    fn add(&mut self, name: &str, contents: &[u8]) -> Result<(), Error> {
+        self.zip.start_file(name, SimpleFileOptions::default())?;
+        self.zip.write_all(contents)?;
+        self.manifest
+            .files
+            .insert(name.to_string(), digest(contents));
+        Ok(())
+    }
+}
+
+impl Collection {
+    /// Writes the collection as a bundle: a zip archive containing the collection as YAML, the
+    /// given mappings and label metadata, and the snapshots recorded on its entities, along with a
+    /// manifest of SHA-256 checksums.
+    ///
+    /// Snapshots that no longer exist on disk are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read, or if the archive cannot be written.
+    pub fn to_bundle(
+        &self,
+        writer: impl Write + Seek,
+        mappings: Option<&str>,
+        label_meta: Option<&LabelMeta>,
+    ) -> Result<(), Error> {
+        let mut bundle = BundleWriter {
+            zip: ZipWriter::new(writer),
+            manifest: Manifest {
+                version: VERSION,
+                files: BTreeMap::new(),
+            },
+        };
+
+        bundle.add(COLLECTION, serde_norway::to_string(self)?.as_bytes())?;
+        if let Some(mappings) = mappings {
+            bundle.add(MAPPINGS, mappings.as_bytes())?;
+        }
+        if let Some(label_meta) = label_meta {
+            bundle.add(LABEL_META, serde_norway::to_string(label_meta)?.as_bytes())?;
+        }
+        for entity in self.entities() {
+            let Some(path) = entity.snapshot_path().filter(|path| path.exists()) else {
+                continue;
+            };
+            let Some(name) = snapshot_name(path) else {
+                continue;
+            };
+            if !bundle.manifest.files.contains_key(&name) {
+                bundle.add(&name, &fs::read(path)?)?;
+            }
+        }
+
+        let manifest = serde_json::to_vec_pretty(&bundle.manifest)?;
+        bundle
+            .zip
+            .start_file(MANIFEST, SimpleFileOptions::default())?;
+        bundle.zip.write_all(&manifest)?;
+        bundle.zip.finish()?;
+        Ok(())
+    }
+}
+
+impl Bundle {
+    /// Reads a bundle, verifying the checksum of every file, and extracts it into `dir`.
+    ///
+    /// Snapshots are written under `dir/snapshots`, and the snapshot paths of the returned
+    /// collection point to them. The collection, mappings and label metadata are also written to
+    /// `dir`, so that it can be used without the bundle.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive is malformed, a file is missing or fails its checksum, or
+    /// the extracted files cannot be written.
+    pub fn extract(reader: impl Read + Seek, dir: &Path) -> Result<Bundle, Error> {
+        let mut zip = ZipArchive::new(reader)?;
+
+        let manifest: Manifest = {
+            let file = zip
+                .by_name(MANIFEST)
+                .map_err(|_| Error::MissingFile(MANIFEST.to_string()))?;
+            serde_json::from_reader(file)?
+        };
+        if manifest.version != VERSION {
+            return Err(Error::UnsupportedVersion(manifest.version));
+        }
+
+        let mut contents = BTreeMap::new();
+        for (name, expected) in &manifest.files {
+            let mut file = zip
+                .by_name(name)
+                .map_err(|_| Error::MissingFile(name.clone()))?;
+            if file.enclosed_name().is_none() {
+                return Err(Error::UnsafePath(name.clone()));
+            }
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf)?;
+            if digest(&buf) != *expected {
+                return Err(Error::ChecksumMismatch(name.clone()));
+            }
+            contents.insert(name.as_str(), buf);
+        }
+
+        let collection_yaml = contents
+            .remove(COLLECTION)
+            .ok_or_else(|| Error::MissingFile(COLLECTION.to_string()))?;
+        let mut collection: Collection = serde_norway::from_slice(&collection_yaml)?;
+        let mappings = contents
+            .remove(MAPPINGS)
+            .map(String::from_utf8)
+            .transpose()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let label_meta: Option<LabelMeta> = contents
+            .remove(LABEL_META)
+            .map(|yaml| serde_norway::from_slice(&yaml))
+            .transpose()?;
+
+        fs::create_dir_all(dir)?;
+        for (name, buf) in &contents {
+            let path = dir.join(name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(path, buf)?;
+        }
+
+        let ids: Vec<_> = collection.iter().map(|(id, _)| id).collect();
+        for id in ids {
+            let entity = collection.entity_mut(&id);
+            let maybe_path: Option<PathBuf> = entity
+                .snapshot_path()
+                .and_then(snapshot_name)
+                .filter(|name| contents.contains_key(name.as_str()))
+                .map(|name| dir.join(name));
+            if let Some(path) = maybe_path {
+                entity.set_snapshot_path(Some(path));
+            }
+        }
+
+        fs::write(dir.join(COLLECTION), serde_norway::to_string(&collection)?)?;
+        if let Some(mappings) = &mappings {
+            fs::write(dir.join(MAPPINGS), mappings)?;
+        }
+        if let Some(label_meta) = &label_meta {
+            fs::write(dir.join(LABEL_META), serde_norway::to_string(label_meta)?)?;
+        }
+
+        Ok(Bundle {
+            collection,
+            mappings,
+            label_meta,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        env, fs,
+        io::{Cursor, Seek, SeekFrom, Write},
+        process,
+    };
+
+    use zip::{ZipWriter, write::SimpleFileOptions};
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Time, Url},
+    };
+
+    use super::{Bundle, COLLECTION, Error, MANIFEST, Manifest, VERSION, digest};
+
+    fn make_bundle() -> Bundle {
+        let url = Url::parse("https://example.com/").unwrap();
+        let mut collection = Collection::new();
+        collection.insert(Entity::new(url, Time::default(), None, BTreeSet::new()));
+        Bundle {
+            collection,
+            mappings: Some("- [old, new]\n".to_string()),
+            label_meta: None,
+        }
+    }
+
+    #[test]
+    fn bundle_roundtrip() -> Result<(), Error> {
+        let dir = env::temp_dir().join(format!("hbt-bundle-{}", process::id()));
+        let bundle = make_bundle();
+        let mut buf = Cursor::new(Vec::new());
+        bundle.collection.to_bundle(
+            &mut buf,
+            bundle.mappings.as_deref(),
+            bundle.label_meta.as_ref(),
+        )?;
+
+        buf.seek(SeekFrom::Start(0))?;
+        let extracted = Bundle::extract(buf, &dir)?;
+        assert_eq!(extracted.collection, bundle.collection);
+        assert_eq!(extracted.mappings, bundle.mappings);
+        assert!(dir.join("collection.yaml").exists());
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_bundle_is_rejected() -> Result<(), Error> {
+        let mut manifest = Manifest {
+            version: VERSION,
+            files: BTreeMap::new(),
+        };
+        manifest
+            .files
+            .insert(COLLECTION.to_string(), digest(b"something else"));
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(COLLECTION, SimpleFileOptions::default())?;
+        zip.write_all(serde_norway::to_string(&make_bundle().collection)?.as_bytes())?;
+        zip.start_file(MANIFEST, SimpleFileOptions::default())?;
+        zip.write_all(&serde_json::to_vec(&manifest)?)?;
+        let mut buf = zip.finish()?;
+
+        buf.seek(SeekFrom::Start(0))?;
+        let dir = env::temp_dir().join(format!("hbt-bundle-tampered-{}", process::id()));
+        let result = Bundle::extract(buf, &dir);
+        assert!(matches!(result, Err(Error::ChecksumMismatch(name)) if name == COLLECTION));
+        assert!(!dir.exists());
+        Ok(())
+    }
+}
@@ -5,13 +5,21 @@ use std::{
     rc::{Rc, Weak},
 };
 
+#[cfg(feature = "clap")]
+use clap::{ValueEnum, builder::PossibleValue};
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use strum::{IntoStaticStr, VariantArray};
 use thiserror::Error;
+use uuid::Uuid;
 
 use hbt_pinboard::Post;
 
-use crate::entity::{self, Entity, Label, Url};
+use crate::{
+    entity::{self, Entity, Label, MergeStrategies, Name, PrivacyPolicy, Profile, Time, Url},
+    mappings::Rule,
+};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -23,6 +31,21 @@ pub enum Error {
 
     #[error("integer conversion error: {0}")]
     TryFromInt(#[from] std::num::TryFromIntError),
+
+    #[error("self-loop on: {0}")]
+    SelfLoop(String),
+
+    #[error("checksum mismatch: expected {expected}, computed {actual}")]
+    ChecksumMismatch { expected: String, actual: String },
+
+    #[error("missing checksum")]
+    MissingChecksum,
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error("edge to unknown entity: {0}")]
+    UnknownUuid(Uuid),
 }
 
 #[derive(Debug, Clone)]
@@ -39,22 +62,111 @@ impl PartialEq for Id {
 
 impl Eq for Id {}
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
-#[schemars(transparent)]
-struct Version(semver::Version);
+impl Id {
+    /// Returns the position of the entity in [`Collection::entities`].
+    #[must_use]
+    pub fn index(&self) -> usize {
+        self.index
+    }
+}
 
-impl Version {
-    const fn new(major: u64, minor: u64, patch: u64) -> Version {
-        Version(semver::Version::new(major, minor, patch))
+/// The labels removed from and added to one entity by [`Collection::update_labels`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelChange {
+    pub url: Url,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Profile>,
+    pub removed: BTreeSet<Label>,
+    pub added: BTreeSet<Label>,
+}
+
+/// How entities sharing a URL are added when a collection is built from Pinboard posts.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, IntoStaticStr, VariantArray)]
+#[strum(serialize_all = "kebab-case")]
+pub enum DuplicatePolicy {
+    /// Keep each as a separate entity.
+    Insert,
+    /// Merge into the first, recording a [`Duplicate`].
+    #[default]
+    Upsert,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for DuplicatePolicy {
+    fn value_variants<'a>() -> &'a [DuplicatePolicy] {
+        DuplicatePolicy::VARIANTS
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let s: &'static str = self.into();
+        Some(PossibleValue::new(s))
+    }
+}
+
+/// Which entities of a collection a merge or sync may change.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, IntoStaticStr, VariantArray)]
+#[strum(serialize_all = "kebab-case")]
+pub enum MergePolicy {
+    /// Add new URLs and update existing ones.
+    #[default]
+    All,
+    /// Only update entities whose URLs are already present, adding none.
+    UpdateOnly,
+    /// Only add new URLs, leaving existing entities unchanged.
+    CreateOnly,
+}
+
+impl MergePolicy {
+    /// Returns whether entities whose URLs are not yet present may be added.
+    #[must_use]
+    pub fn creates(self) -> bool {
+        self != MergePolicy::UpdateOnly
     }
 
-    fn matches_requirement(&self) -> Result<bool, semver::Error> {
-        let req = semver::VersionReq::parse(Version::EXPECTED_REQ)?;
-        Ok(req.matches(&self.0))
+    /// Returns whether entities whose URLs are already present may be changed.
+    #[must_use]
+    pub fn updates(self) -> bool {
+        self != MergePolicy::CreateOnly
+    }
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for MergePolicy {
+    fn value_variants<'a>() -> &'a [MergePolicy] {
+        MergePolicy::VARIANTS
     }
 
-    const EXPECTED: Version = Version::new(0, 1, 0);
-    const EXPECTED_REQ: &str = "^0.1.0";
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let s: &'static str = self.into();
+        Some(PossibleValue::new(s))
+    }
+}
+
+/// A URL that occurred more than once while building a collection, and was merged by
+/// [`Collection::upsert`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Duplicate {
+    pub url: Url,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Profile>,
+    /// The number of times the URL occurred, including the first.
+    pub count: usize,
+    /// The serialized names of the fields whose values differed between occurrences.
+    pub fields: BTreeSet<String>,
+}
+
+/// A reversible record of a label update.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelChangelog {
+    pub changes: Vec<LabelChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[schemars(transparent)]
+struct Version(semver::Version);
+
+impl Version {
+    const EXPECTED: Version = Version(CollectionRepr::VERSION);
 }
 
 impl fmt::Display for Version {
@@ -63,14 +175,81 @@ impl fmt::Display for Version {
     }
 }
 
+/// Which format versions are accepted when reading a [`CollectionRepr`].
+///
+/// By default, only versions matching [`CollectionRepr::VERSION_REQ`] are accepted. Long-lived
+/// tools may also accept newer minor versions, whose additions they ignore, rather than failing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VersionPolicy {
+    /// Accept versions with the same major version as [`CollectionRepr::VERSION`] and a newer
+    /// minor version, e.g. `0.3.0` when the current version is `0.2.0`.
+    pub allow_newer_minor: bool,
+}
+
+impl VersionPolicy {
+    /// Returns whether `version` is accepted under this policy.
+    #[must_use]
+    pub fn supports(self, version: &semver::Version) -> bool {
+        let current = &CollectionRepr::VERSION;
+        semver::VersionReq::parse(CollectionRepr::VERSION_REQ).is_ok_and(|req| req.matches(version))
+            || (self.allow_newer_minor
+                && version.major == current.major
+                && version.minor > current.minor)
+    }
+}
+
 type Edges = Vec<usize>;
 
+/// An edge between entities created in different years, dropped by
+/// [`Collection::partition_by_year`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CrossYearEdge {
+    pub from: Url,
+    pub to: Url,
+}
+
+/// A collection split by creation year; see [`Collection::partition_by_year`].
+#[derive(Debug, Default)]
+pub struct YearPartition {
+    pub years: BTreeMap<i32, Collection>,
+    pub cross_year_edges: Vec<CrossYearEdge>,
+}
+
+/// A small, seedable pseudo-random number generator.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // The remainder is less than `bound`, so it always fits in a usize.
+    #[allow(clippy::cast_possible_truncation)]
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+}
+
+type Urls = HashMap<Url, usize>;
+
+/// A graph of entities, keyed by URL.
+///
+/// Entities may belong to a [`Profile`]. Each profile is a separate namespace, so the same URL can
+/// appear once per profile, and entities are only merged with others in the same profile.
 #[derive(Debug)]
 pub struct Collection {
     token: Rc<()>,
     nodes: Vec<Entity>,
     edges: Vec<Edges>,
-    urls: HashMap<Url, usize>,
+    urls: Urls,
+    profiles: BTreeMap<Profile, Urls>,
+    duplicates: BTreeMap<usize, Duplicate>,
+    privacy_policy: PrivacyPolicy,
+    merge_strategies: MergeStrategies,
 }
 
 impl Index<&Id> for Vec<Entity> {
@@ -127,6 +306,10 @@ impl Collection {
             nodes: Vec::new(),
             edges: Vec::new(),
             urls: HashMap::new(),
+            profiles: BTreeMap::new(),
+            duplicates: BTreeMap::new(),
+            privacy_policy: PrivacyPolicy::default(),
+            merge_strategies: MergeStrategies::default(),
         }
     }
 
@@ -137,9 +320,51 @@ impl Collection {
             nodes: Vec::with_capacity(capacity),
             edges: Vec::with_capacity(capacity),
             urls: HashMap::with_capacity(capacity),
+            profiles: BTreeMap::new(),
+            duplicates: BTreeMap::new(),
+            privacy_policy: PrivacyPolicy::default(),
+            merge_strategies: MergeStrategies::default(),
         }
     }
 
+    /// Returns the format version in which collections are serialized.
+    #[must_use]
+    pub fn current_format_version() -> semver::Version {
+        CollectionRepr::VERSION
+    }
+
+    /// Returns whether collections serialized in format `version` can be deserialized, under the
+    /// default [`VersionPolicy`].
+    #[must_use]
+    pub fn supports_version(version: &semver::Version) -> bool {
+        VersionPolicy::default().supports(version)
+    }
+
+    /// Returns the policy used by [`Collection::upsert`] to merge the privacy of entities.
+    #[must_use]
+    pub fn privacy_policy(&self) -> PrivacyPolicy {
+        self.privacy_policy
+    }
+
+    /// Sets the policy used by [`Collection::upsert`] to merge the privacy of entities. It is
+    /// kept by collections derived from this one.
+    pub fn set_privacy_policy(&mut self, privacy_policy: PrivacyPolicy) {
+        self.privacy_policy = privacy_policy;
+    }
+
+    /// Returns the strategies used by [`Collection::upsert`] to merge the names, labels and
+    /// extended notes of entities.
+    #[must_use]
+    pub fn merge_strategies(&self) -> MergeStrategies {
+        self.merge_strategies
+    }
+
+    /// Sets the strategies used by [`Collection::upsert`] to merge the names, labels and extended
+    /// notes of entities. They are kept by collections derived from this one.
+    pub fn set_merge_strategies(&mut self, merge_strategies: MergeStrategies) {
+        self.merge_strategies = merge_strategies;
+    }
+
     /// Returns the number of entities in the collection.
     ///
     /// # Panics
@@ -164,42 +389,161 @@ impl Collection {
         is_empty
     }
 
+    fn urls(&self, profile: Option<&Profile>) -> Option<&Urls> {
+        match profile {
+            None => Some(&self.urls),
+            Some(profile) => self.profiles.get(profile),
+        }
+    }
+
+    fn urls_mut(&mut self, profile: Option<&Profile>) -> &mut Urls {
+        match profile {
+            None => &mut self.urls,
+            Some(profile) => self.profiles.entry(profile.clone()).or_default(),
+        }
+    }
+
+    /// Returns `true` if the default profile contains an entity with the given URL.
     #[must_use]
     pub fn contains(&self, url: &Url) -> bool {
-        self.urls.contains_key(url)
+        self.contains_in(None, url)
     }
 
+    /// Returns `true` if the given profile contains an entity with the given URL.
+    #[must_use]
+    pub fn contains_in(&self, profile: Option<&Profile>, url: &Url) -> bool {
+        self.urls(profile)
+            .is_some_and(|urls| urls.contains_key(url))
+    }
+
+    /// Returns the id of the entity with the given URL in the default profile.
     #[must_use]
     pub fn id(&self, url: &Url) -> Option<Id> {
-        self.urls.get(url).map(|&idx| self.make_id(idx))
+        self.id_in(None, url)
+    }
+
+    /// Returns the id of the entity with the given URL in the given profile.
+    #[must_use]
+    pub fn id_in(&self, profile: Option<&Profile>, url: &Url) -> Option<Id> {
+        let &idx = self.urls(profile)?.get(url)?;
+        Some(self.make_id(idx))
+    }
+
+    /// Returns the profiles that contain at least one entity, excluding the default profile.
+    pub fn profiles(&self) -> impl Iterator<Item = &Profile> {
+        self.profiles.keys()
     }
 
     pub fn insert(&mut self, entity: Entity) -> Id {
         let index = self.len();
+        let url = entity.url().to_owned();
+        let profile = entity.profile().cloned();
         self.nodes.push(entity);
         self.edges.push(Vec::new());
-        let url = self.nodes[index].url().to_owned();
-        self.urls.insert(url, index);
+        self.urls_mut(profile.as_ref()).insert(url, index);
         self.make_id(index)
     }
 
+    /// Inserts an entity, or merges it into the existing entity with the same URL in the same
+    /// profile, according to the collection's [`PrivacyPolicy`] and [`MergeStrategies`].
     pub fn upsert(&mut self, other: Entity) -> Id {
-        let Some(id) = self.id(other.url()) else {
+        let Some(id) = self.id_in(other.profile(), other.url()) else {
             return self.insert(other);
         };
         let entity = &mut self.nodes[&id];
-        entity.merge(other);
+        let fields = entity.differing_fields(&other);
+        let duplicate = self
+            .duplicates
+            .entry(id.index)
+            .or_insert_with(|| Duplicate {
+                url: entity.url().clone(),
+                profile: entity.profile().cloned(),
+                count: 1,
+                fields: BTreeSet::new(),
+            });
+        duplicate.count += 1;
+        duplicate
+            .fields
+            .extend(fields.into_iter().map(str::to_string));
+        entity.merge_with_strategies(other, self.privacy_policy, self.merge_strategies);
         id
     }
 
-    pub fn add_edge(&mut self, from: &Id, to: &Id) {
+    /// Merges `other` into this collection, upserting each of its entities and adding its edges.
+    /// Edges that become self-loops because their ends were merged are dropped.
+    pub fn merge(&mut self, other: Collection) {
+        self.merge_with_policy(other, MergePolicy::All);
+    }
+
+    /// Merges `other` into this collection as in [`Collection::merge`], but only adding or
+    /// updating the entities that `policy` allows. Edges of skipped entities are dropped. Returns
+    /// the number of entities skipped.
+    pub fn merge_with_policy(&mut self, other: Collection, policy: MergePolicy) -> usize {
+        let mut skipped = 0;
+        let ids: Vec<Option<Id>> = other
+            .nodes
+            .into_iter()
+            .map(|entity| {
+                let present = self.contains_in(entity.profile(), entity.url());
+                if (present && policy.updates()) || (!present && policy.creates()) {
+                    Some(self.upsert(entity))
+                } else {
+                    skipped += 1;
+                    None
+                }
+            })
+            .collect();
+        for (maybe_from, edges) in ids.iter().zip(other.edges) {
+            let Some(from) = maybe_from else {
+                continue;
+            };
+            for to in edges {
+                if let Some(to) = &ids[to] {
+                    self.add_edge(from, to);
+                }
+            }
+        }
+        skipped
+    }
+
+    /// Records `source` (typically the file an entity was read from) in the
+    /// [`entity::SOURCE_EXTRA`] extra of every entity.
+    pub fn set_source(&mut self, source: &str) {
+        for entity in &mut self.nodes {
+            entity
+                .extras_mut()
+                .insert(entity::SOURCE_EXTRA.to_string(), source.to_string());
+        }
+    }
+
+    /// Adds an edge from `from` to `to`, returning whether it was added. Existing edges and
+    /// self-loops are ignored; use [`Collection::try_add_edge`] to reject self-loops instead.
+    pub fn add_edge(&mut self, from: &Id, to: &Id) -> bool {
         self.check_id(from);
         self.check_id(to);
+        if from == to {
+            return false;
+        }
         let from_edges = &mut self.edges[from];
         if from_edges.contains(&to.index) {
-            return;
+            return false;
         }
         from_edges.push(to.index);
+        true
+    }
+
+    /// Adds an edge from `from` to `to` as in [`Collection::add_edge`], returning whether it was
+    /// added.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `from` and `to` are the same entity.
+    pub fn try_add_edge(&mut self, from: &Id, to: &Id) -> Result<bool, Error> {
+        if from == to {
+            let url = self.entity(from).url().as_str().to_string();
+            return Err(Error::SelfLoop(url));
+        }
+        Ok(self.add_edge(from, to))
     }
 
     pub fn add_edges(&mut self, from: &Id, to: &Id) {
@@ -207,6 +551,25 @@ impl Collection {
         self.add_edge(to, from);
     }
 
+    /// Removes the edge from `from` to `to`, returning whether it existed.
+    pub fn remove_edge(&mut self, from: &Id, to: &Id) -> bool {
+        self.check_id(from);
+        self.check_id(to);
+        let from_edges = &mut self.edges[from];
+        let len = from_edges.len();
+        from_edges.retain(|&index| index != to.index);
+        from_edges.len() != len
+    }
+
+    /// Removes the edges in both directions between the entities with URLs `a` and `b` in the
+    /// default profile, returning the number of edges removed.
+    pub fn remove_edges_between(&mut self, a: &Url, b: &Url) -> usize {
+        let (Some(a), Some(b)) = (self.id(a), self.id(b)) else {
+            return 0;
+        };
+        usize::from(self.remove_edge(&a, &b)) + usize::from(self.remove_edge(&b, &a))
+    }
+
     #[must_use]
     pub fn entity(&self, id: &Id) -> &Entity {
         self.check_id(id);
@@ -232,41 +595,540 @@ impl Collection {
         &self.nodes
     }
 
-    /// Updates entity labels according to the provided mappings.
+    /// Returns an iterator over the entities along with their ids.
+    pub fn iter(&self) -> impl Iterator<Item = (Id, &Entity)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(index, entity)| (self.make_id(index), entity))
+    }
+
+    /// Returns the number of edges in the collection, counting each direction separately.
+    #[must_use]
+    pub fn edge_count(&self) -> usize {
+        self.edges.iter().map(Vec::len).sum()
+    }
+
+    /// Removes each edge from A to B for which another entity C has edges from A to C and from C
+    /// to B, returning the number of edges removed.
     ///
-    /// Replaces labels matching the mapping keys with their corresponding values.
-    pub fn update_labels(&mut self, mappings: impl IntoIterator<Item = (String, String)>) {
-        let mapping: BTreeMap<Label, Label> = mappings
-            .into_iter()
-            .map(|(k, v)| (Label::from(k), Label::from(v)))
+    /// Each edge is checked against the edges that remain. When an edge is removed, its reverse is
+    /// removed too if it is also redundant, so links made with [`Collection::add_edges`] stay
+    /// symmetric.
+    pub fn transitive_reduction(&mut self) -> usize {
+        let mut removed = 0;
+        for from in 0..self.edges.len() {
+            let mut i = self.edges[from].len();
+            while i > 0 {
+                i -= 1;
+                let to = self.edges[from][i];
+                if !self.is_redundant_edge(from, to) {
+                    continue;
+                }
+                self.edges[from].remove(i);
+                removed += 1;
+                if self.edges[to].contains(&from) && self.is_redundant_edge(to, from) {
+                    self.edges[to].retain(|&index| index != from);
+                    removed += 1;
+                }
+            }
+        }
+        removed
+    }
+
+    fn is_redundant_edge(&self, from: usize, to: usize) -> bool {
+        self.edges[from]
+            .iter()
+            .any(|&via| via != to && via != from && self.edges[via].contains(&to))
+    }
+
+    /// Removes redundant edges with [`Collection::transitive_reduction`] and releases unused
+    /// capacity, returning the number of edges removed.
+    pub fn compact(&mut self) -> usize {
+        let removed = self.transitive_reduction();
+        for edges in &mut self.edges {
+            edges.shrink_to_fit();
+        }
+        self.edges.shrink_to_fit();
+        self.nodes.shrink_to_fit();
+        for urls in self.profiles.values_mut() {
+            urls.shrink_to_fit();
+        }
+        self.urls.shrink_to_fit();
+        removed
+    }
+
+    /// Returns the number of entities carrying each label.
+    #[must_use]
+    pub fn label_counts(&self) -> BTreeMap<&Label, usize> {
+        let mut counts = BTreeMap::new();
+        for label in self.nodes.iter().flat_map(Entity::labels) {
+            *counts.entry(label).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns the URLs merged by [`Collection::upsert`] while building this collection, in order
+    /// of first occurrence.
+    ///
+    /// Duplicates are not carried over to collections derived from this one, except by
+    /// [`Collection::retain`].
+    pub fn duplicates(&self) -> impl Iterator<Item = &Duplicate> {
+        self.duplicates.values()
+    }
+
+    /// Returns a new collection containing only the entities at the given indices.
+    ///
+    /// Entities keep their relative order, and only edges between retained entities are kept.
+    fn subset(&self, mut indices: Vec<usize>) -> Collection {
+        indices.sort_unstable();
+        indices.dedup();
+
+        let mut remap: Vec<Option<usize>> = vec![None; self.len()];
+        for (new, &old) in indices.iter().enumerate() {
+            remap[old] = Some(new);
+        }
+
+        let mut ret = Collection::with_capacity(indices.len());
+        ret.privacy_policy = self.privacy_policy;
+        ret.merge_strategies = self.merge_strategies;
+        for &old in &indices {
+            let id = ret.insert(self.nodes[old].clone());
+            ret.edges[&id] = self.edges[old]
+                .iter()
+                .filter_map(|&idx| remap[idx])
+                .collect();
+        }
+        ret
+    }
+
+    /// Keeps only the entities for which `f` returns true, along with the edges between them.
+    ///
+    /// Returns the number of entities removed. Because removal renumbers entities, any previously
+    /// obtained `Id`s are invalidated.
+    pub fn retain(&mut self, mut f: impl FnMut(&Entity) -> bool) -> usize {
+        let indices: Vec<usize> = (0..self.len()).filter(|&i| f(&self.nodes[i])).collect();
+        let removed = self.len() - indices.len();
+        if removed > 0 {
+            let duplicates = std::mem::take(&mut self.duplicates);
+            *self = self.subset(indices);
+            for duplicate in duplicates.into_values() {
+                if let Some(id) = self.id_in(duplicate.profile.as_ref(), &duplicate.url) {
+                    self.duplicates.insert(id.index, duplicate);
+                }
+            }
+        }
+        removed
+    }
+
+    /// Returns a new collection containing only the given entities, with the edges between them.
+    #[must_use]
+    pub fn take(&self, ids: &[Id]) -> Collection {
+        let indices = ids
+            .iter()
+            .map(|id| {
+                self.check_id(id);
+                id.index
+            })
+            .collect();
+        self.subset(indices)
+    }
+
+    /// Returns a new collection containing the first `n` entities.
+    #[must_use]
+    pub fn head(&self, n: usize) -> Collection {
+        self.subset((0..n.min(self.len())).collect())
+    }
+
+    /// Returns a new collection containing the last `n` entities.
+    #[must_use]
+    pub fn tail(&self, n: usize) -> Collection {
+        let len = self.len();
+        self.subset((len - n.min(len)..len).collect())
+    }
+
+    /// Returns a new collection containing `n` entities chosen at random.
+    ///
+    /// The choice is fully determined by `seed`, so the same seed always yields the same sample.
+    #[must_use]
+    pub fn sample(&self, n: usize, seed: u64) -> Collection {
+        let mut indices: Vec<usize> = (0..self.len()).collect();
+        let n = n.min(indices.len());
+        let mut rng = SplitMix64(seed);
+        for i in 0..n {
+            let j = i + rng.below(indices.len() - i);
+            indices.swap(i, j);
+        }
+        indices.truncate(n);
+        self.subset(indices)
+    }
+
+    /// Splits the collection by the year, in UTC, in which each entity was created.
+    ///
+    /// Each part keeps the edges between its own entities. Edges between entities created in
+    /// different years are listed in [`YearPartition::cross_year_edges`] instead.
+    #[must_use]
+    pub fn partition_by_year(&self) -> YearPartition {
+        let years: Vec<i32> = self
+            .nodes
+            .iter()
+            .map(|entity| entity.created_at().get().year())
             .collect();
 
+        let mut indices: BTreeMap<i32, Vec<usize>> = BTreeMap::new();
+        for (index, &year) in years.iter().enumerate() {
+            indices.entry(year).or_default().push(index);
+        }
+
+        let mut cross_year_edges = Vec::new();
+        for (from, edges) in self.edges.iter().enumerate() {
+            for &to in edges {
+                if years[from] != years[to] {
+                    cross_year_edges.push(CrossYearEdge {
+                        from: self.nodes[from].url().clone(),
+                        to: self.nodes[to].url().clone(),
+                    });
+                }
+            }
+        }
+
+        YearPartition {
+            years: indices
+                .into_iter()
+                .map(|(year, indices)| (year, self.subset(indices)))
+                .collect(),
+            cross_year_edges,
+        }
+    }
+
+    /// Updates entity labels according to the provided mappings.
+    ///
+    /// Replaces labels matching the mapping keys with their corresponding values. Returns a
+    /// changelog of the labels removed from and added to each entity, which can be passed to
+    /// [`Collection::undo_labels`] to reverse the update.
+    pub fn update_labels(
+        &mut self,
+        mappings: impl IntoIterator<Item = (String, String)>,
+    ) -> LabelChangelog {
+        let mapping: BTreeMap<String, String> = mappings.into_iter().collect();
+        let rules: Vec<Rule> = mapping.into_iter().map(Rule::from).collect();
+        self.update_labels_with(&rules)
+    }
+
+    /// Updates entity labels according to the provided rules, which may apply only to entities
+    /// carrying other labels.
+    ///
+    /// Each label is replaced according to the first rule for it that applies. Whether a rule
+    /// applies is decided by the entity's labels before the update, so rules do not affect each
+    /// other. Returns a changelog as in [`Collection::update_labels`].
+    pub fn update_labels_with(&mut self, rules: &[Rule]) -> LabelChangelog {
+        let mut by_label: BTreeMap<&Label, Vec<&Rule>> = BTreeMap::new();
+        for rule in rules {
+            by_label.entry(&rule.from).or_default().push(rule);
+        }
+
+        let mut changelog = LabelChangelog::default();
         for node in &mut self.nodes {
+            let url = node.url().clone();
+            let profile = node.profile().cloned();
             let labels = node.labels_mut();
-            let to_add: BTreeSet<Label> = labels
+            let targets: BTreeMap<Label, Label> = labels
                 .iter()
-                .filter_map(|label| mapping.get(label).cloned())
+                .filter_map(|label| {
+                    let rule = by_label
+                        .get(label)?
+                        .iter()
+                        .find(|rule| rule.applies_to(labels))?;
+                    Some((label.clone(), rule.to.clone()))
+                })
                 .collect();
-            labels.retain(|label| !mapping.contains_key(label));
-            labels.extend(to_add);
+            if targets.is_empty() {
+                continue;
+            }
+            labels.retain(|label| !targets.contains_key(label));
+            let removed: BTreeSet<Label> = targets.keys().cloned().collect();
+            let added: BTreeSet<Label> = targets
+                .into_values()
+                .filter(|label| labels.insert(label.clone()))
+                .collect();
+            node.prune_labels_added_at();
+            changelog.changes.push(LabelChange {
+                url,
+                profile,
+                removed,
+                added,
+            });
+        }
+        changelog
+    }
+
+    /// Reverses a label update recorded by [`Collection::update_labels`].
+    ///
+    /// Labels that were added are removed again, and labels that were removed are restored.
+    /// Entities in the changelog that are no longer present are skipped.
+    pub fn undo_labels(&mut self, changelog: &LabelChangelog) {
+        for change in changelog.changes.iter().rev() {
+            let Some(id) = self.id_in(change.profile.as_ref(), &change.url) else {
+                continue;
+            };
+            let node = &mut self.nodes[&id];
+            let labels = node.labels_mut();
+            labels.retain(|label| !change.added.contains(label));
+            labels.extend(change.removed.iter().cloned());
+            node.prune_labels_added_at();
+        }
+    }
+
+    /// Removes every label not in `allowlist` from each entity, e.g. to publish a themed subset
+    /// of a collection. If `drop_unlabelled` is true, entities left without labels are then
+    /// removed, along with their edges.
+    ///
+    /// Returns the number of entities removed.
+    pub fn retain_labels(&mut self, allowlist: &BTreeSet<Label>, drop_unlabelled: bool) -> usize {
+        for node in &mut self.nodes {
+            node.labels_mut().retain(|label| allowlist.contains(label));
+            node.prune_labels_added_at();
+        }
+        if drop_unlabelled {
+            self.retain(|entity| !entity.labels().is_empty())
+        } else {
+            0
+        }
+    }
+
+    /// Rebuilds the collection from `nodes`, which must correspond one-to-one with the current
+    /// nodes, merging entities that now share a URL and profile.
+    fn rebuild(&mut self, nodes: Vec<Entity>) {
+        let mut ret = Collection::with_capacity(nodes.len());
+        ret.privacy_policy = self.privacy_policy;
+        ret.merge_strategies = self.merge_strategies;
+        let mut remap = Vec::with_capacity(nodes.len());
+        for entity in nodes {
+            remap.push(ret.upsert(entity).index);
+        }
+        for (old, edges) in std::mem::take(&mut self.edges).into_iter().enumerate() {
+            let from = remap[old];
+            for to in edges.into_iter().map(|to| remap[to]) {
+                if from != to && !ret.edges[from].contains(&to) {
+                    ret.edges[from].push(to);
+                }
+            }
+        }
+        *self = ret;
+    }
+
+    /// Replaces the URL of every entity with the result of `f`.
+    ///
+    /// Entities whose new URLs collide are merged, and their edges combined. Edges that would
+    /// become self-loops as a result of a merge are dropped. Because merging renumbers entities,
+    /// any previously obtained `Id`s are invalidated.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error produced by `f`, leaving the collection unchanged.
+    pub fn map_urls<E>(&mut self, mut f: impl FnMut(&Url) -> Result<Url, E>) -> Result<(), E> {
+        let urls = self
+            .nodes
+            .iter()
+            .map(|entity| f(entity.url()))
+            .collect::<Result<Vec<Url>, E>>()?;
+
+        if urls
+            .iter()
+            .zip(&self.nodes)
+            .all(|(url, entity)| url == entity.url())
+        {
+            return Ok(());
+        }
+
+        let mut nodes = std::mem::take(&mut self.nodes);
+        for (entity, url) in nodes.iter_mut().zip(urls) {
+            entity.set_url(url);
+        }
+        self.rebuild(nodes);
+        Ok(())
+    }
+
+    /// Moves every entity in profile `from` into profile `to`.
+    ///
+    /// This is the only way entities cross between profiles. Entities that collide with one
+    /// already in `to` are merged into it. As with [`Collection::map_urls`], previously obtained
+    /// `Id`s are invalidated.
+    pub fn move_profile(&mut self, from: Option<&Profile>, to: Option<&Profile>) {
+        if from == to || self.urls(from).is_none_or(HashMap::is_empty) {
+            return;
+        }
+        let mut nodes = std::mem::take(&mut self.nodes);
+        for entity in &mut nodes {
+            if entity.profile() == from {
+                entity.set_profile(to.cloned());
+            }
+        }
+        self.rebuild(nodes);
+    }
+
+    /// Returns a new collection containing only the entities in the given profile.
+    #[must_use]
+    pub fn in_profile(&self, profile: Option<&Profile>) -> Collection {
+        let indices = self
+            .urls(profile)
+            .map_or_else(Vec::new, |urls| urls.values().copied().collect());
+        self.subset(indices)
+    }
+
+    /// Returns a new collection containing only the entities carrying any of `labels`.
+    #[must_use]
+    pub fn with_any_label(&self, labels: &BTreeSet<Label>) -> Collection {
+        let indices = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, entity)| !entity.labels().is_disjoint(labels))
+            .map(|(index, _)| index)
+            .collect();
+        self.subset(indices)
+    }
+
+    /// Returns a new collection containing only the entities to which `label` was added at or
+    /// after `since`; see [`Entity::label_added_at`].
+    #[must_use]
+    pub fn label_added_since(&self, label: &Label, since: Time) -> Collection {
+        let indices = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, entity)| {
+                entity
+                    .label_added_at(label)
+                    .is_some_and(|time| time >= since)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        self.subset(indices)
+    }
+
+    /// Returns a new collection containing only the pinned entities; see [`Entity::is_pinned`].
+    #[must_use]
+    pub fn pinned(&self) -> Collection {
+        let indices = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, entity)| entity.is_pinned())
+            .map(|(index, _)| index)
+            .collect();
+        self.subset(indices)
+    }
+
+    /// Creates a collection from entities, merging any that share a URL.
+    ///
+    /// Entities are sorted by creation time before being added.
+    pub fn from_entities(entities: impl IntoIterator<Item = Entity>) -> Collection {
+        let mut entities: Vec<Entity> = entities.into_iter().collect();
+        entities.sort_by_key(Entity::created_at);
+        let mut coll = Collection::with_capacity(entities.len());
+        for entity in entities {
+            coll.upsert(entity);
+        }
+        coll
+    }
+
+    fn add(&mut self, entity: Entity, policy: DuplicatePolicy) -> Id {
+        match policy {
+            DuplicatePolicy::Insert => self.insert(entity),
+            DuplicatePolicy::Upsert => self.upsert(entity),
         }
     }
 
-    /// Creates a collection from a vector of Pinboard posts.
+    /// Creates a collection from entities converted from Pinboard posts, merging any that share a
+    /// URL.
     ///
-    /// Posts are sorted by time before being converted to entities.
+    /// Entities are sorted by creation time before being added, as in [`Collection::from_posts`].
+    #[must_use]
+    pub fn from_post_entities(entities: Vec<Entity>) -> Collection {
+        Collection::from_post_entities_with(
+            entities,
+            DuplicatePolicy::default(),
+            PrivacyPolicy::default(),
+            MergeStrategies::default(),
+        )
+    }
+
+    /// Creates a collection from entities converted from Pinboard posts, adding entities that
+    /// share a URL according to `policy`. Merged entities are resolved with `privacy_policy` and
+    /// `merge_strategies`, which also become the collection's.
+    #[must_use]
+    pub fn from_post_entities_with(
+        mut entities: Vec<Entity>,
+        policy: DuplicatePolicy,
+        privacy_policy: PrivacyPolicy,
+        merge_strategies: MergeStrategies,
+    ) -> Collection {
+        entities.sort_by_key(Entity::created_at);
+        let mut coll = Collection::with_capacity(entities.len());
+        coll.set_privacy_policy(privacy_policy);
+        coll.set_merge_strategies(merge_strategies);
+        for entity in entities {
+            coll.add(entity, policy);
+        }
+        coll
+    }
+
+    /// Creates a collection from a vector of Pinboard posts, merging any that share a URL.
+    ///
+    /// Posts are converted to entities, which are sorted by creation time before being added.
     ///
     /// # Errors
     ///
     /// Returns an error if any post cannot be converted to a valid `Entity` (e.g., invalid URL or timestamp).
-    pub fn from_posts(mut posts: Vec<Post>) -> Result<Collection, entity::Error> {
-        posts.sort_by(|a, b| a.time.cmp(&b.time));
-        let mut coll = Collection::with_capacity(posts.len());
-        for post in posts {
-            let entity = Entity::try_from(post)?;
-            coll.insert(entity);
+    pub fn from_posts(posts: Vec<Post>) -> Result<Collection, entity::Error> {
+        Collection::from_posts_with(
+            posts,
+            DuplicatePolicy::default(),
+            PrivacyPolicy::default(),
+            MergeStrategies::default(),
+        )
+    }
+
+    /// Creates a collection from a vector of Pinboard posts, adding posts that share a URL
+    /// according to `policy`, as in [`Collection::from_post_entities_with`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any post cannot be converted to a valid `Entity` (e.g., invalid URL or timestamp).
+    pub fn from_posts_with(
+        posts: Vec<Post>,
+        policy: DuplicatePolicy,
+        privacy_policy: PrivacyPolicy,
+        merge_strategies: MergeStrategies,
+    ) -> Result<Collection, entity::Error> {
+        let entities = posts
+            .into_iter()
+            .map(Entity::try_from)
+            .collect::<Result<Vec<Entity>, entity::Error>>()?;
+        Ok(Collection::from_post_entities_with(
+            entities,
+            policy,
+            privacy_policy,
+            merge_strategies,
+        ))
+    }
+}
+
+/// Clones the entities, edges and policies of a collection. The clone is a distinct collection:
+/// `Id`s obtained from the original are not valid for it.
+impl Clone for Collection {
+    fn clone(&self) -> Collection {
+        Collection {
+            token: Rc::new(()),
+            nodes: self.nodes.clone(),
+            edges: self.edges.clone(),
+            urls: self.urls.clone(),
+            profiles: self.profiles.clone(),
+            duplicates: self.duplicates.clone(),
+            privacy_policy: self.privacy_policy,
+            merge_strategies: self.merge_strategies,
         }
-        Ok(coll)
     }
 }
 
@@ -278,18 +1140,78 @@ impl Default for Collection {
 
 impl PartialEq for Collection {
     fn eq(&self, other: &Collection) -> bool {
-        self.nodes == other.nodes && self.edges == other.edges && self.urls == other.urls
+        self.nodes == other.nodes
+            && self.edges == other.edges
+            && self.urls == other.urls
+            && self.profiles == other.profiles
     }
 }
 
 impl Eq for Collection {}
 
+/// How entities are identified in serialized collections.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, IntoStaticStr, VariantArray)]
+#[strum(serialize_all = "kebab-case")]
+pub enum IdScheme {
+    /// Each entity's position, used by edges.
+    #[default]
+    Positional,
+    /// Each entity's [`Entity::uuid`], used by edges. Entities whose URLs normalize to the same
+    /// URL in the same profile share a UUID, so collections should be normalized first.
+    Uuid,
+    /// Both positions and UUIDs, with edges using positions.
+    Both,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for IdScheme {
+    fn value_variants<'a>() -> &'a [IdScheme] {
+        IdScheme::VARIANTS
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let s: &'static str = self.into();
+        Some(PossibleValue::new(s))
+    }
+}
+
+/// Options controlling how a collection is serialized; see [`CollectionRepr::with_options`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReprOptions {
+    /// List the URL and name of each edge's target alongside its id.
+    pub readable_edges: bool,
+    pub id_scheme: IdScheme,
+}
+
+/// A reference from an edge to its target, by position or by UUID.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(untagged)]
+enum NodeRef {
+    Index(u32),
+    Uuid(Uuid),
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 struct NodeRepr {
-    id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    uuid: Option<Uuid>,
     entity: Entity,
-    edges: Vec<u32>,
+    edges: Vec<NodeRef>,
+    /// The targets of `edges`, for reading by hand. Ignored when deserializing.
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    edge_targets: Option<Vec<EdgeTarget>>,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+struct EdgeTarget {
+    url: Url,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<Name>,
 }
 
 #[derive(Debug, Serialize, Deserialize, JsonSchema)]
@@ -297,9 +1219,28 @@ struct NodeRepr {
 pub struct CollectionRepr {
     version: Version,
     length: u32,
+    /// A BLAKE3 digest of the nodes, checked by [`CollectionRepr::verify`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    checksum: Option<String>,
     value: Vec<NodeRepr>,
 }
 
+/// Computes the checksum of `nodes` in id order, over their ids, UUIDs (if any), entities and edges
+/// serialized as JSON.
+fn checksum<'a>(nodes: impl IntoIterator<Item = &'a NodeRepr>) -> Result<String, Error> {
+    let mut nodes: Vec<&NodeRepr> = nodes.into_iter().collect();
+    nodes.sort_by_key(|node| node.id);
+    let mut hasher = blake3::Hasher::new();
+    for node in nodes {
+        let canonical = match node.uuid {
+            Some(uuid) => serde_json::to_vec(&(node.id, uuid, &node.entity, &node.edges))?,
+            None => serde_json::to_vec(&(node.id, &node.entity, &node.edges))?,
+        };
+        hasher.update(&canonical);
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
 impl TryFrom<&Collection> for CollectionRepr {
     type Error = Error;
 
@@ -310,56 +1251,194 @@ impl TryFrom<&Collection> for CollectionRepr {
 
         let value: Vec<_> = (0..length)
             .map(|i| {
-                let id = u32::try_from(i)?;
+                let id = Some(u32::try_from(i)?);
                 let entity = coll.nodes[i].clone();
                 let edges = coll.edges[i]
                     .iter()
-                    .map(|&i| u32::try_from(i))
-                    .collect::<Result<Vec<u32>, std::num::TryFromIntError>>()?;
-                Ok(NodeRepr { id, entity, edges })
+                    .map(|&i| u32::try_from(i).map(NodeRef::Index))
+                    .collect::<Result<Vec<NodeRef>, std::num::TryFromIntError>>()?;
+                Ok(NodeRepr {
+                    id,
+                    uuid: None,
+                    entity,
+                    edges,
+                    edge_targets: None,
+                })
             })
             .collect::<Result<Vec<NodeRepr>, Error>>()?;
 
         let length = u32::try_from(length)?;
 
+        let checksum = Some(checksum(&value)?);
+
         Ok(CollectionRepr {
             version,
             length,
+            checksum,
             value,
         })
     }
 }
 
-impl TryFrom<CollectionRepr> for Collection {
-    type Error = Error;
+impl CollectionRepr {
+    /// The format version written by this version of hbt.
+    pub const VERSION: semver::Version = semver::Version::new(0, 2, 0);
+
+    /// The format versions read by this version of hbt under the default [`VersionPolicy`].
+    /// Version 0.2.0 added label timestamps, which 0.1 collections lack.
+    pub const VERSION_REQ: &str = ">=0.1.0, <0.3.0";
 
-    fn try_from(mut repr: CollectionRepr) -> Result<Collection, Error> {
-        if !repr.version.matches_requirement()? {
+    /// Returns the format version of the representation.
+    #[must_use]
+    pub fn version(&self) -> &semver::Version {
+        &self.version.0
+    }
+
+    /// Converts the representation into a collection, checking its version against `policy`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the version is not accepted by `policy`, or the representation is
+    /// inconsistent, e.g. an edge refers to an unknown UUID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the positional ids are not numbered consecutively from zero.
+    pub fn into_collection(mut self, policy: VersionPolicy) -> Result<Collection, Error> {
+        if !policy.supports(&self.version.0) {
             return Err(Error::IncompatibleVersion(
-                repr.version.to_string(),
-                Version::EXPECTED_REQ.to_string(),
+                self.version.to_string(),
+                CollectionRepr::VERSION_REQ.to_string(),
             ));
         }
 
-        let mut ret = Collection::with_capacity(usize::try_from(repr.length)?);
+        let mut ret = Collection::with_capacity(usize::try_from(self.length)?);
+
+        // Without positional ids, entities are kept in the order they are listed.
+        if self.value.iter().all(|node| node.id.is_some()) {
+            self.value.sort();
+        }
 
-        repr.value.sort();
+        let positions: HashMap<Uuid, usize> = self
+            .value
+            .iter()
+            .enumerate()
+            .filter_map(|(index, node)| Some((node.uuid?, index)))
+            .collect();
 
-        for NodeRepr { id, entity, edges } in repr.value {
-            assert_eq!(id, u32::try_from(ret.len())?);
+        for NodeRepr {
+            id, entity, edges, ..
+        } in self.value
+        {
+            let index = ret.len();
+            if let Some(id) = id {
+                assert_eq!(id, u32::try_from(index)?);
+            }
             let url = entity.url().clone();
+            let profile = entity.profile().cloned();
             ret.nodes.push(entity);
             ret.edges.push(
                 edges
                     .into_iter()
-                    .map(usize::try_from)
-                    .collect::<Result<Vec<usize>, std::num::TryFromIntError>>()?,
+                    .map(|edge| match edge {
+                        NodeRef::Index(to) => Ok(usize::try_from(to)?),
+                        NodeRef::Uuid(uuid) => positions
+                            .get(&uuid)
+                            .copied()
+                            .ok_or(Error::UnknownUuid(uuid)),
+                    })
+                    .collect::<Result<Vec<usize>, Error>>()?,
             );
-            ret.urls.insert(url, usize::try_from(id)?);
+            ret.urls_mut(profile.as_ref()).insert(url, index);
         }
 
         Ok(ret)
     }
+
+    /// Returns the number of entities.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.value.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+
+    /// Checks that the embedded checksum matches the entities and edges, detecting truncated or
+    /// mistakenly edited archives.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the checksum is missing or does not match.
+    pub fn verify(&self) -> Result<(), Error> {
+        let expected = self.checksum.as_ref().ok_or(Error::MissingChecksum)?;
+        let actual = checksum(&self.value)?;
+        if *expected != actual {
+            return Err(Error::ChecksumMismatch {
+                expected: expected.clone(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// Like [`CollectionRepr::try_from`], but also lists the URL and first name of each edge's
+    /// target alongside its id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the collection is too large to represent.
+    pub fn with_readable_edges(coll: &Collection) -> Result<CollectionRepr, Error> {
+        let options = ReprOptions {
+            readable_edges: true,
+            ..ReprOptions::default()
+        };
+        CollectionRepr::with_options(coll, &options)
+    }
+
+    /// Like [`CollectionRepr::try_from`], but identifies entities and lists edges as described by
+    /// `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the collection is too large to represent.
+    pub fn with_options(coll: &Collection, options: &ReprOptions) -> Result<CollectionRepr, Error> {
+        let mut ret = CollectionRepr::try_from(coll)?;
+        if options.readable_edges {
+            for (node, edges) in ret.value.iter_mut().zip(&coll.edges) {
+                let targets = edges
+                    .iter()
+                    .map(|&to| EdgeTarget {
+                        url: coll.nodes[to].url().clone(),
+                        name: coll.nodes[to].names().iter().next().cloned(),
+                    })
+                    .collect();
+                node.edge_targets = Some(targets);
+            }
+        }
+        if options.id_scheme != IdScheme::Positional {
+            let uuids: Vec<Uuid> = coll.nodes.iter().map(Entity::uuid).collect();
+            for ((node, edges), uuid) in ret.value.iter_mut().zip(&coll.edges).zip(&uuids) {
+                node.uuid = Some(*uuid);
+                if options.id_scheme == IdScheme::Uuid {
+                    node.id = None;
+                    node.edges = edges.iter().map(|&to| NodeRef::Uuid(uuids[to])).collect();
+                }
+            }
+            ret.checksum = Some(checksum(&ret.value)?);
+        }
+        Ok(ret)
+    }
+}
+
+impl TryFrom<CollectionRepr> for Collection {
+    type Error = Error;
+
+    fn try_from(repr: CollectionRepr) -> Result<Collection, Error> {
+        repr.into_collection(VersionPolicy::default())
+    }
 }
 
 impl Serialize for Collection {
@@ -389,9 +1468,28 @@ mod tests {
 
     use chrono::Utc;
 
-    use crate::entity::{Entity, Time, Url};
-
-    use super::Collection;
+    use crate::entity::{
+        self, Entity, Label, MergeStrategies, PrivacyPolicy, Profile, Shared, Time, Url,
+    };
+
+    use super::{
+        Collection, CollectionRepr, DuplicatePolicy, Id, IdScheme, MergePolicy, ReprOptions,
+        VersionPolicy,
+    };
+
+    fn make_chain(len: usize) -> Collection {
+        let mut coll = Collection::new();
+        let mut prev = None;
+        for i in 0..len {
+            let url = Url::parse(&format!("https://example.com/{i}")).unwrap();
+            let id = coll.insert(Entity::new(url, Time::default(), None, BTreeSet::new()));
+            if let Some(prev) = prev {
+                coll.add_edges(&prev, &id);
+            }
+            prev = Some(id);
+        }
+        coll
+    }
 
     fn make_entity(url: &str) -> Entity {
         let url = Url::parse(url).unwrap();
@@ -399,6 +1497,16 @@ mod tests {
         Entity::new(url, now, None, BTreeSet::default())
     }
 
+    #[test]
+    #[should_panic(expected = "Id belongs to a different collection")]
+    fn cloned_collections_have_their_own_ids() {
+        let mut coll = Collection::new();
+        let id = coll.insert(make_entity("https://example.com/"));
+        let clone = coll.clone();
+        assert_eq!(clone, coll);
+        let _ = clone.entity(&id);
+    }
+
     #[test]
     #[should_panic(expected = "Id belongs to a different collection")]
     fn check_id_wrong_collection() {
@@ -424,4 +1532,386 @@ mod tests {
 
         let _ = coll2.entity(&id);
     }
+
+    #[test]
+    fn head_and_tail_keep_internal_edges() {
+        let coll = make_chain(5);
+
+        let head = coll.head(2);
+        assert_eq!(head.len(), 2);
+        let first = head
+            .id(&Url::parse("https://example.com/0").unwrap())
+            .unwrap();
+        assert_eq!(head.edges(&first).len(), 1);
+
+        let tail = coll.tail(2);
+        assert_eq!(tail.len(), 2);
+        let last = tail
+            .id(&Url::parse("https://example.com/4").unwrap())
+            .unwrap();
+        assert_eq!(tail.edges(&last).len(), 1);
+
+        assert_eq!(coll.head(10).len(), 5);
+        assert_eq!(coll.tail(10).len(), 5);
+    }
+
+    #[test]
+    fn sample_is_deterministic() {
+        let coll = make_chain(20);
+        let a = coll.sample(5, 42);
+        let b = coll.sample(5, 42);
+        assert_eq!(a.len(), 5);
+        assert_eq!(a, b);
+        for entity in a.entities() {
+            let id = a.id(entity.url()).unwrap();
+            for edge in a.edges(&id) {
+                assert!(a.edges(&edge).contains(&id));
+            }
+        }
+    }
+
+    #[test]
+    fn profiles_are_isolated() {
+        let work = Profile::from("work");
+        let url = Url::parse("https://example.com/").unwrap();
+        let mut coll = Collection::new();
+        coll.upsert(make_entity("https://example.com/"));
+        coll.upsert(
+            Entity::builder(url.clone(), Time::default())
+                .profile(work.clone())
+                .build(),
+        );
+        assert_eq!(coll.len(), 2);
+        assert!(coll.contains(&url));
+        assert!(coll.contains_in(Some(&work), &url));
+        assert_eq!(coll.in_profile(Some(&work)).len(), 1);
+
+        coll.move_profile(Some(&work), None);
+        assert_eq!(coll.len(), 1);
+        assert_eq!(coll.profiles().count(), 0);
+    }
+
+    #[test]
+    fn undo_labels_reverses_update() {
+        let mut coll = Collection::new();
+        let mut entity = make_entity("https://example.com/");
+        entity
+            .labels_mut()
+            .extend([Label::from("old"), Label::from("new")]);
+        coll.insert(entity);
+        coll.insert(make_entity("https://example.com/other"));
+        let before = coll.entities().to_vec();
+
+        let changelog = coll.update_labels([("old".to_string(), "new".to_string())]);
+        assert_eq!(changelog.changes.len(), 1);
+        assert!(changelog.changes[0].added.is_empty());
+        assert_ne!(coll.entities(), before.as_slice());
+
+        coll.undo_labels(&changelog);
+        assert_eq!(coll.entities(), before.as_slice());
+    }
+
+    #[test]
+    fn retain_labels_keeps_allowlist() {
+        let mut coll = Collection::new();
+        let mut ids = Vec::new();
+        for (i, labels) in [["rust", "web"], ["web", "misc"], ["misc", "old"]]
+            .into_iter()
+            .enumerate()
+        {
+            let mut entity = make_entity(&format!("https://example.com/{i}"));
+            entity.labels_mut().extend(labels.map(Label::from));
+            ids.push(coll.insert(entity));
+        }
+        coll.add_edges(&ids[0], &ids[1]);
+        coll.add_edges(&ids[1], &ids[2]);
+        let allowlist = BTreeSet::from([Label::from("rust"), Label::from("web")]);
+
+        let mut kept = Collection::from_entities(coll.entities().to_vec());
+        assert_eq!(kept.retain_labels(&allowlist, false), 0);
+        assert_eq!(kept.len(), 3);
+        assert_eq!(kept.label_counts().len(), 2);
+
+        assert_eq!(coll.retain_labels(&allowlist, true), 1);
+        assert_eq!(coll.len(), 2);
+        assert_eq!(coll.edge_count(), 2);
+        assert_eq!(
+            coll.entities()[1].labels(),
+            &BTreeSet::from([Label::from("web")])
+        );
+    }
+
+    #[test]
+    fn upsert_records_duplicates() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let time = Time::default();
+        let mut coll = Collection::new();
+        coll.upsert(Entity::new(url.clone(), time, None, BTreeSet::new()));
+        coll.upsert(make_entity("https://example.com/other"));
+        coll.upsert(Entity::new(url.clone(), time, None, BTreeSet::new()));
+        coll.upsert(Entity::new(
+            url.clone(),
+            time,
+            None,
+            BTreeSet::from([Label::from("rust")]),
+        ));
+
+        let duplicates: Vec<_> = coll.duplicates().collect();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].url, url);
+        assert_eq!(duplicates[0].count, 3);
+        assert_eq!(duplicates[0].fields, BTreeSet::from(["labels".to_string()]));
+
+        coll.retain(|entity| entity.url() == &url);
+        assert_eq!(coll.duplicates().count(), 1);
+    }
+
+    #[test]
+    fn readable_edges_are_ignored_when_deserializing() {
+        let coll = make_chain(2);
+        let repr = CollectionRepr::with_readable_edges(&coll).unwrap();
+        let yaml = serde_norway::to_string(&repr).unwrap();
+        assert!(yaml.contains("edgeTargets:\n  - url: https://example.com/1"));
+
+        let roundtripped: Collection = serde_norway::from_str(&yaml).unwrap();
+        assert_eq!(roundtripped, coll);
+    }
+
+    #[test]
+    fn transitive_reduction_removes_shortcuts() {
+        let mut coll = Collection::new();
+        let a = coll.insert(make_entity("https://example.com/a"));
+        let b = coll.insert(make_entity("https://example.com/b"));
+        let c = coll.insert(make_entity("https://example.com/c"));
+        coll.add_edges(&a, &b);
+        coll.add_edges(&a, &c);
+        coll.add_edges(&b, &c);
+
+        assert_eq!(coll.compact(), 2);
+        assert_eq!(coll.edge_count(), 4);
+        assert_eq!(coll.edges(&a), std::slice::from_ref(&b));
+        assert_eq!(coll.edges(&b), [a, c.clone()]);
+        assert_eq!(coll.edges(&c), [b]);
+        assert_eq!(coll.transitive_reduction(), 0);
+    }
+
+    #[test]
+    fn self_loops_are_ignored_or_rejected() {
+        let mut coll = Collection::new();
+        let a = coll.insert(make_entity("https://example.com/a"));
+        assert!(!coll.add_edge(&a, &a));
+        coll.add_edges(&a, &a);
+        assert!(coll.try_add_edge(&a, &a).is_err());
+        assert_eq!(coll.edge_count(), 0);
+    }
+
+    #[test]
+    fn remove_edges() {
+        let mut coll = make_chain(3);
+        let first = Url::parse("https://example.com/0").unwrap();
+        let second = Url::parse("https://example.com/1").unwrap();
+        let (a, b) = (coll.id(&first).unwrap(), coll.id(&second).unwrap());
+
+        assert!(coll.remove_edge(&a, &b));
+        assert!(!coll.remove_edge(&a, &b));
+        assert_eq!(coll.edges(&b).len(), 2);
+
+        assert_eq!(coll.remove_edges_between(&first, &second), 1);
+        assert!(coll.edges(&b).iter().all(|id| id != &a));
+        assert_eq!(coll.edge_count(), 2);
+    }
+
+    #[test]
+    fn checksum_detects_edits() {
+        let coll = make_chain(3);
+        let yaml = serde_norway::to_string(&coll).unwrap();
+        let repr: CollectionRepr = serde_norway::from_str(&yaml).unwrap();
+        repr.verify().unwrap();
+
+        let edited = yaml.replace("https://example.com/1", "https://example.com/one");
+        let repr: CollectionRepr = serde_norway::from_str(&edited).unwrap();
+        assert!(matches!(
+            repr.verify(),
+            Err(super::Error::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn merge_upserts_entities_and_remaps_edges() {
+        let mut a = make_chain(2);
+        let mut b = Collection::new();
+        let x = b.insert(make_entity("https://example.com/1"));
+        let y = b.insert(make_entity("https://example.com/new"));
+        b.add_edge(&x, &y);
+        b.set_source("b.md");
+
+        a.merge(b);
+
+        assert_eq!(a.len(), 3);
+        assert_eq!(a.edge_count(), 3);
+        let id = a.id(&Url::parse("https://example.com/1").unwrap()).unwrap();
+        let new = a
+            .id(&Url::parse("https://example.com/new").unwrap())
+            .unwrap();
+        assert_eq!(a.edges(&id).last().map(Id::index), Some(new.index()));
+        assert_eq!(
+            a.entity(&new)
+                .extras()
+                .get(entity::SOURCE_EXTRA)
+                .map(String::as_str),
+            Some("b.md")
+        );
+        assert_eq!(a.duplicates().count(), 1);
+    }
+
+    #[test]
+    fn merge_policies_skip_entities() {
+        let make_other = || {
+            let mut other = Collection::new();
+            let x = other.insert(make_entity("https://example.com/1"));
+            let y = other.insert(make_entity("https://example.com/new"));
+            other.add_edge(&x, &y);
+            other
+        };
+
+        let mut a = make_chain(2);
+        assert_eq!(
+            a.merge_with_policy(make_other(), MergePolicy::UpdateOnly),
+            1
+        );
+        assert_eq!((a.len(), a.edge_count()), (2, 2));
+        assert_eq!(a.duplicates().count(), 1);
+
+        let mut a = make_chain(2);
+        assert_eq!(
+            a.merge_with_policy(make_other(), MergePolicy::CreateOnly),
+            1
+        );
+        assert_eq!((a.len(), a.edge_count()), (3, 2));
+        assert_eq!(a.duplicates().count(), 0);
+    }
+
+    #[test]
+    fn partition_by_year_keeps_intra_year_edges() {
+        let at = |url: &str, timestamp: i64| {
+            let url = Url::parse(url).unwrap();
+            let time = Time::from_timestamp(timestamp).unwrap();
+            Entity::new(url, time, None, BTreeSet::default())
+        };
+        let mut coll = Collection::new();
+        // 2020-01-01, 2020-06-01 and 2021-01-01
+        let a = coll.insert(at("https://a.example.com/", 1_577_836_800));
+        let b = coll.insert(at("https://b.example.com/", 1_590_969_600));
+        let c = coll.insert(at("https://c.example.com/", 1_609_459_200));
+        coll.add_edges(&a, &b);
+        coll.add_edge(&b, &c);
+
+        let partition = coll.partition_by_year();
+
+        assert_eq!(
+            partition.years.keys().copied().collect::<Vec<_>>(),
+            [2020, 2021]
+        );
+        let y2020 = &partition.years[&2020];
+        assert_eq!(y2020.len(), 2);
+        assert_eq!(y2020.edge_count(), 2);
+        assert_eq!(partition.years[&2021].edge_count(), 0);
+        assert_eq!(partition.cross_year_edges.len(), 1);
+        assert_eq!(
+            partition.cross_year_edges[0].to.as_str(),
+            "https://c.example.com/"
+        );
+    }
+
+    #[test]
+    fn uuid_ids_roundtrip() {
+        let coll = make_chain(3);
+        for id_scheme in [IdScheme::Uuid, IdScheme::Both] {
+            let options = ReprOptions {
+                id_scheme,
+                ..ReprOptions::default()
+            };
+            let repr = CollectionRepr::with_options(&coll, &options).unwrap();
+            repr.verify().unwrap();
+            let yaml = serde_norway::to_string(&repr).unwrap();
+            assert!(yaml.contains(&coll.entities()[1].uuid().to_string()));
+            assert_eq!(yaml.contains("- id: 0"), id_scheme == IdScheme::Both);
+
+            let roundtripped: Collection = serde_norway::from_str(&yaml).unwrap();
+            assert_eq!(roundtripped, coll);
+        }
+    }
+
+    #[test]
+    fn post_entities_are_merged_by_default() {
+        let entities = vec![
+            make_entity("https://example.com/"),
+            make_entity("https://example.com/"),
+        ];
+
+        let coll = Collection::from_post_entities_with(
+            entities.clone(),
+            DuplicatePolicy::Insert,
+            PrivacyPolicy::default(),
+            MergeStrategies::default(),
+        );
+        assert_eq!(coll.len(), 2);
+        assert_eq!(coll.duplicates().count(), 0);
+
+        let coll = Collection::from_post_entities(entities);
+        assert_eq!(coll.len(), 1);
+        assert_eq!(coll.duplicates().count(), 1);
+    }
+
+    #[test]
+    fn post_entities_are_merged_with_the_privacy_policy() {
+        let entities: Vec<Entity> = [true, false]
+            .into_iter()
+            .map(|shared| {
+                let mut entity = make_entity("https://example.com/");
+                entity.set_shared(Shared::new(shared));
+                entity
+            })
+            .collect();
+
+        let coll = Collection::from_post_entities(entities.clone());
+        assert_eq!(coll.entities()[0].shared().get(), Some(true));
+
+        let coll = Collection::from_post_entities_with(
+            entities,
+            DuplicatePolicy::Upsert,
+            PrivacyPolicy::MostRestrictive,
+            MergeStrategies::default(),
+        );
+        assert_eq!(coll.entities()[0].shared().get(), Some(false));
+        assert_eq!(coll.privacy_policy(), PrivacyPolicy::MostRestrictive);
+    }
+
+    #[test]
+    fn newer_minor_versions_are_negotiated() {
+        let current = Collection::current_format_version();
+        assert!(Collection::supports_version(&current));
+        assert!(!Collection::supports_version(&semver::Version::new(
+            1, 0, 0
+        )));
+
+        let yaml = serde_norway::to_string(&make_chain(2)).unwrap();
+        let newer = semver::Version::new(current.major, current.minor + 1, 0);
+        let yaml = yaml.replacen(&current.to_string(), &newer.to_string(), 1);
+        let repr: CollectionRepr = serde_norway::from_str(&yaml).unwrap();
+        assert_eq!(repr.version(), &newer);
+
+        assert!(
+            serde_norway::from_str::<CollectionRepr>(&yaml)
+                .unwrap()
+                .into_collection(VersionPolicy::default())
+                .is_err()
+        );
+        let policy = VersionPolicy {
+            allow_newer_minor: true,
+        };
+        let coll = repr.into_collection(policy).unwrap();
+        assert_eq!(coll.len(), 2);
+        assert_eq!(coll.edge_count(), 2);
+    }
 }
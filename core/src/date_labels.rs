@@ -0,0 +1,65 @@
+use crate::{
+    collection::Collection,
+    entity::{Label, Time},
+};
+
+const YEAR_PREFIX: &str = "year:";
+const MONTH_PREFIX: &str = "month:";
+
+/// Returns the date labels of a bookmark created at `time`, e.g. `year:2023` and `month:2023-11`.
+fn date_labels(time: Time) -> [Label; 2] {
+    let (year, month) = (time.year(), time.month());
+    [
+        Label::from(format!("{YEAR_PREFIX}{year}")),
+        Label::from(format!("{MONTH_PREFIX}{year}-{month:02}")),
+    ]
+}
+
+impl Collection {
+    /// Labels each entity with the year and month it was created, such as `year:2023` and
+    /// `month:2023-11`, so that bookmarks can be filtered by date in tools that only understand
+    /// tags. Returns the number of entities changed.
+    pub fn add_date_labels(&mut self) -> usize {
+        let ids: Vec<_> = self.iter().map(|(id, _)| id).collect();
+        let mut changed = 0;
+        for id in ids {
+            let entity = self.entity_mut(&id);
+            let mut added = false;
+            for label in date_labels(entity.created_at().get()) {
+                added |= entity.labels_mut().insert(label);
+            }
+            if added {
+                changed += 1;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Label, Time, Url},
+    };
+
+    #[test]
+    fn labels_carry_the_creation_date() {
+        let mut coll = Collection::new();
+        let url = Url::parse("https://example.com/").unwrap();
+        let created_at = Time::from_timestamp(1_700_000_000).unwrap();
+        let labels = BTreeSet::from([Label::from("rust")]);
+        coll.insert(Entity::new(url, created_at, None, labels));
+
+        assert_eq!(coll.add_date_labels(), 1);
+        assert_eq!(coll.add_date_labels(), 0);
+        let labels: Vec<&str> = coll.entities()[0]
+            .labels()
+            .iter()
+            .map(Label::as_str)
+            .collect();
+        assert_eq!(labels, ["month:2023-11", "rust", "year:2023"]);
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    collections::BTreeSet,
+    io::{self, Write},
+};
+
+#[cfg(feature = "clap")]
+use clap::{ValueEnum, builder::PossibleValue};
+use minijinja::{Environment, context};
+use serde::Serialize;
+use serde_json::Value;
+use strum::{IntoStaticStr, VariantArray};
+use thiserror::Error;
+
+use crate::{
+    collection::Collection,
+    entity::{Entity, Profile, Url},
+    sync::{Matched, matched},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("JSON serialization error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Template error: {0}")]
+    Template(#[from] minijinja::Error),
+}
+
+/// Serialized fields that identify an entity, rather than describe it.
+const KEY_FIELDS: [&str; 2] = ["uri", "profile"];
+
+/// A field whose value differs between two versions of an entity, with both values as JSON.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    /// The serialized name of the field.
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// An entity present in both collections with differing fields.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Modified {
+    pub url: Url,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<Profile>,
+    pub changes: Vec<FieldChange>,
+}
+
+/// The differences between two collections, matching entities by URL and profile.
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct Diff {
+    /// Entities only in the new collection.
+    pub added: Vec<Entity>,
+    /// Entities only in the old collection.
+    pub removed: Vec<Entity>,
+    pub modified: Vec<Modified>,
+}
+
+/// How a [`Diff`] is rendered.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, IntoStaticStr, VariantArray)]
+#[strum(serialize_all = "lowercase")]
+pub enum Format {
+    /// Per-URL hunks in the style of a unified diff.
+    #[default]
+    Text,
+    /// A report with sections for added, removed and modified entities.
+    Html,
+    Json,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for Format {
+    fn value_variants<'a>() -> &'a [Format] {
+        Format::VARIANTS
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let s: &'static str = self.into();
+        Some(PossibleValue::new(s))
+    }
+}
+
+/// Returns the changed fields of an entity, comparing the serialized forms of both versions.
+fn field_changes(old: &Entity, new: &Entity) -> Result<Vec<FieldChange>, Error> {
+    let Value::Object(old) = serde_json::to_value(old)? else {
+        return Ok(Vec::new());
+    };
+    let Value::Object(new) = serde_json::to_value(new)? else {
+        return Ok(Vec::new());
+    };
+    let fields: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    Ok(fields
+        .into_iter()
+        .filter(|field| !KEY_FIELDS.contains(&field.as_str()))
+        .filter_map(|field| {
+            let old = old.get(field).cloned().unwrap_or(Value::Null);
+            let new = new.get(field).cloned().unwrap_or(Value::Null);
+            (old != new).then(|| FieldChange {
+                field: field.clone(),
+                old,
+                new,
+            })
+        })
+        .collect())
+}
+
+/// Formats an entity's URL, qualified by its profile if it has one.
+fn heading(url: &Url, profile: Option<&Profile>) -> String {
+    match profile {
+        Some(profile) => format!("{} {}", profile.as_str(), url.as_str()),
+        None => url.as_str().to_string(),
+    }
+}
+
+impl Diff {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+
+    /// Writes the diff in the given format.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization, rendering or writing fails.
+    pub fn write(&self, writer: &mut impl Write, format: Format) -> Result<(), Error> {
+        match format {
+            Format::Text => self.write_text(writer),
+            Format::Html => self.write_html(writer),
+            Format::Json => {
+                serde_json::to_writer_pretty(&mut *writer, self)?;
+                writer.write_all(b"\n")?;
+                Ok(())
+            }
+        }
+    }
+
+    fn write_text(&self, writer: &mut impl Write) -> Result<(), Error> {
+        for entity in &self.removed {
+            writeln!(writer, "@@ -{} @@", heading(entity.url(), entity.profile()))?;
+        }
+        for entity in &self.added {
+            writeln!(writer, "@@ +{} @@", heading(entity.url(), entity.profile()))?;
+        }
+        for modified in &self.modified {
+            writeln!(
+                writer,
+                "@@ {} @@",
+                heading(&modified.url, modified.profile.as_ref())
+            )?;
+            for change in &modified.changes {
+                if !change.old.is_null() {
+                    writeln!(writer, "-{}: {}", change.field, change.old)?;
+                }
+                if !change.new.is_null() {
+                    writeln!(writer, "+{}: {}", change.field, change.new)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn write_html(&self, writer: &mut impl Write) -> Result<(), Error> {
+        const TEMPLATE: &str = include_str!("diff/report.html");
+        let mut env = Environment::new();
+        env.add_template("report.html", TEMPLATE)?;
+        let template = env.get_template("report.html")?;
+        template.render_captured_to(
+            context! {
+                added => &self.added,
+                removed => &self.removed,
+                modified => &self.modified,
+            },
+            &mut *writer,
+        )?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+impl Collection {
+    /// Compares this collection (the old version) with `new`, matching entities by URL and
+    /// profile. Fields are compared by their serialized values, so edges are not considered.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an entity cannot be serialized for comparison.
+    pub fn diff(&self, new: &Collection) -> Result<Diff, Error> {
+        let mut ret = Diff::default();
+        for matched in matched(self, new) {
+            match matched {
+                Matched::Left(old) => ret.removed.push(old.clone()),
+                Matched::Right(new) => ret.added.push(new.clone()),
+                Matched::Both(old, new) => {
+                    let changes = field_changes(old, new)?;
+                    if !changes.is_empty() {
+                        ret.modified.push(Modified {
+                            url: old.url().clone(),
+                            profile: old.profile().cloned(),
+                            changes,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Label, Time, Url},
+    };
+
+    use super::Format;
+
+    fn make_entity(url: &str, label: &str) -> Entity {
+        Entity::new(
+            Url::parse(url).unwrap(),
+            Time::from_timestamp(0).unwrap(),
+            None,
+            BTreeSet::from([Label::from(label)]),
+        )
+    }
+
+    fn render(diff: &super::Diff, format: Format) -> String {
+        let mut output = Vec::new();
+        diff.write(&mut output, format).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn diff_renders_each_kind_of_change() {
+        let old = Collection::from_entities([
+            make_entity("https://kept.example.com/", "a"),
+            make_entity("https://gone.example.com/", "a"),
+        ]);
+        let new = Collection::from_entities([
+            make_entity("https://kept.example.com/", "<b>"),
+            make_entity("https://new.example.com/", "a"),
+        ]);
+
+        let diff = old.diff(&new).unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.modified[0].changes[0].field, "labels");
+        assert!(old.diff(&old).unwrap().is_empty());
+
+        let text = render(&diff, Format::Text);
+        assert_eq!(
+            text,
+            "@@ -https://gone.example.com/ @@\n\
+             @@ +https://new.example.com/ @@\n\
+             @@ https://kept.example.com/ @@\n\
+             -labels: [\"a\"]\n\
+             +labels: [\"<b>\"]\n"
+        );
+
+        let html = render(&diff, Format::Html);
+        assert!(html.contains("new.example.com"));
+        assert!(!html.contains("<b>"));
+
+        let json: serde_json::Value = serde_json::from_str(&render(&diff, Format::Json)).unwrap();
+        assert_eq!(json["modified"][0]["changes"][0]["new"][0], "<b>");
+    }
+}
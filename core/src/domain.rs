@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use crate::{collection::Collection, entity::Url};
+
+const WWW_PREFIX: &str = "www.";
+
+/// Returns the registrable domain of a URL's host, e.g. `example.co.uk` for
+/// `https://news.example.co.uk/`.
+///
+/// With the `psl` feature, the registrable domain is found using the public suffix list.
+/// Without it, the host is used as-is, minus any leading `www.`. IP addresses are returned
+/// unchanged, and URLs without a host return `None`.
+#[must_use]
+pub fn registrable_domain(url: &Url) -> Option<String> {
+    let host = url.as_ref().host()?;
+    let url::Host::Domain(domain) = host else {
+        return Some(host.to_string());
+    };
+    #[cfg(feature = "psl")]
+    if let Some(registrable) = psl::domain_str(domain) {
+        return Some(registrable.to_string());
+    }
+    Some(
+        domain
+            .strip_prefix(WWW_PREFIX)
+            .unwrap_or(domain)
+            .to_string(),
+    )
+}
+
+impl Collection {
+    /// Returns the number of entities for each registrable domain. See [`registrable_domain`].
+    ///
+    /// Entities whose URLs have no host (e.g. `mailto:` links) are not counted.
+    #[must_use]
+    pub fn domain_counts(&self) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for entity in self.entities() {
+            if let Some(domain) = registrable_domain(entity.url()) {
+                *counts.entry(domain).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Time, Url},
+    };
+
+    use super::registrable_domain;
+
+    fn domain(url: &str) -> Option<String> {
+        registrable_domain(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn hosts_without_registrable_domains() {
+        assert_eq!(
+            domain("https://www.example.com/").as_deref(),
+            Some("example.com")
+        );
+        assert_eq!(
+            domain("http://127.0.0.1:8080/").as_deref(),
+            Some("127.0.0.1")
+        );
+        assert_eq!(domain("mailto:someone@example.com"), None);
+    }
+
+    #[cfg(feature = "psl")]
+    #[test]
+    fn public_suffixes_are_respected() {
+        assert_eq!(
+            domain("https://news.example.co.uk/").as_deref(),
+            Some("example.co.uk")
+        );
+        assert_eq!(
+            domain("https://user.github.io/").as_deref(),
+            Some("user.github.io")
+        );
+    }
+
+    #[test]
+    fn domain_counts_groups_entities() {
+        let time = Time::default();
+        let coll = Collection::from_entities(
+            [
+                "https://example.com/a",
+                "https://www.example.com/b",
+                "https://rust-lang.org/",
+            ]
+            .map(|url| Entity::new(Url::parse(url).unwrap(), time, None, BTreeSet::new())),
+        );
+        let counts = coll.domain_counts();
+        assert_eq!(counts.get("example.com"), Some(&2));
+        assert_eq!(counts.get("rust-lang.org"), Some(&1));
+    }
+}
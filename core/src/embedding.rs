@@ -0,0 +1,196 @@
+use std::{
+    cmp::Ordering,
+    collections::BTreeSet,
+    io::{self, BufRead, Write},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    collection::Collection,
+    entity::{Entity, Extended, Label, Name, Profile, Url},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("JSON error on line {0}: {1}")]
+    Json(usize, #[source] serde_json::Error),
+}
+
+/// A vector embedding of an entity's text, computed by an external model.
+///
+/// Components are compared by their bit patterns, so that entities holding embeddings can still
+/// be ordered and deduplicated.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+pub struct Embedding(Vec<f32>);
+
+impl Embedding {
+    #[must_use]
+    pub fn new(vector: Vec<f32>) -> Embedding {
+        Embedding(vector)
+    }
+
+    #[must_use]
+    pub fn as_slice(&self) -> &[f32] {
+        &self.0
+    }
+}
+
+impl PartialEq for Embedding {
+    fn eq(&self, other: &Embedding) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Embedding {}
+
+impl PartialOrd for Embedding {
+    fn partial_cmp(&self, other: &Embedding) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Embedding {
+    fn cmp(&self, other: &Embedding) -> Ordering {
+        self.0
+            .iter()
+            .copied()
+            .map(f32::to_bits)
+            .cmp(other.0.iter().copied().map(f32::to_bits))
+    }
+}
+
+/// The text of one entity to be embedded, written as a line of NDJSON by
+/// [`Collection::write_embedding_payloads`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Payload<'a> {
+    /// The entity's position in the collection.
+    pub id: usize,
+    pub url: &'a Url,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<&'a Profile>,
+    /// The entity's names and extended notes, separated by blank lines.
+    pub text: String,
+    pub labels: &'a BTreeSet<Label>,
+}
+
+impl<'a> Payload<'a> {
+    #[must_use]
+    pub fn new(id: usize, entity: &'a Entity) -> Payload<'a> {
+        let text = entity
+            .names()
+            .iter()
+            .map(Name::as_str)
+            .chain(entity.extended().iter().map(Extended::as_str))
+            .collect::<Vec<&str>>()
+            .join("\n\n");
+        Payload {
+            id,
+            url: entity.url(),
+            profile: entity.profile(),
+            text,
+            labels: entity.labels(),
+        }
+    }
+}
+
+/// A computed embedding to attach to the entity with the given URL and profile, read as a line of
+/// NDJSON by [`Collection::attach_embeddings`]. Other fields, such as those of the [`Payload`]
+/// the vector was computed from, are ignored.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Vector {
+    pub url: Url,
+    #[serde(default)]
+    pub profile: Option<Profile>,
+    pub embedding: Embedding,
+}
+
+impl Collection {
+    /// Writes one [`Payload`] per entity as newline-delimited JSON, ready for an embedding
+    /// pipeline.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization or writing fails.
+    pub fn write_embedding_payloads(&self, writer: &mut impl Write) -> Result<(), Error> {
+        for (id, entity) in self.entities().iter().enumerate() {
+            serde_json::to_writer(&mut *writer, &Payload::new(id, entity))
+                .map_err(|err| Error::Json(id + 1, err))?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Reads newline-delimited [`Vector`]s and attaches each to the matching entity, replacing
+    /// any existing embedding. Blank lines and vectors for URLs not in the collection are
+    /// skipped.
+    ///
+    /// Returns the number of embeddings attached.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails or a line is not a valid vector.
+    pub fn attach_embeddings(&mut self, reader: impl BufRead) -> Result<usize, Error> {
+        let mut ret = 0;
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let vector: Vector =
+                serde_json::from_str(&line).map_err(|err| Error::Json(index + 1, err))?;
+            if let Some(id) = self.id_in(vector.profile.as_ref(), &vector.url) {
+                self.entity_mut(&id).set_embedding(Some(vector.embedding));
+                ret += 1;
+            }
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Extended, Name, Time, Url},
+    };
+
+    use super::Embedding;
+
+    #[test]
+    fn payloads_and_vectors_roundtrip() {
+        let mut entity = Entity::new(
+            Url::parse("https://example.com/").unwrap(),
+            Time::default(),
+            Some(Name::from("Example")),
+            BTreeSet::new(),
+        );
+        entity
+            .extended_mut()
+            .push(Extended::new("A note".to_string()));
+        let mut coll = Collection::from_entities([entity]);
+
+        let mut payloads = Vec::new();
+        coll.write_embedding_payloads(&mut payloads).unwrap();
+        let payload: serde_json::Value = serde_json::from_slice(&payloads).unwrap();
+        assert_eq!(payload["text"], "Example\n\nA note");
+
+        let vectors = r#"{"url": "https://example.com/", "embedding": [0.5, -1.0]}
+
+{"url": "https://unknown.example.com/", "embedding": [0.0]}
+"#;
+        assert_eq!(coll.attach_embeddings(vectors.as_bytes()).unwrap(), 1);
+        assert_eq!(
+            coll.entities()[0].embedding(),
+            Some(&Embedding::new(vec![0.5, -1.0]))
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use std::{
+    borrow::Cow,
+    io::{self, BufRead},
+};
+
+#[cfg(feature = "encoding")]
+use encoding_rs::{Encoding, WINDOWS_1252};
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Skips a UTF-8 byte order mark at the start of `reader`, if there is one.
+///
+/// # Errors
+///
+/// Returns an error if reading fails.
+pub fn skip_bom(reader: &mut impl BufRead) -> io::Result<()> {
+    let buf = reader.fill_buf()?;
+    if buf.starts_with(UTF8_BOM) {
+        reader.consume(UTF8_BOM.len());
+    }
+    Ok(())
+}
+
+/// Decodes input in an unknown encoding.
+///
+/// A byte order mark selects UTF-8, UTF-16LE or UTF-16BE, and is removed. Otherwise the input is
+/// used as-is if it is valid UTF-8, and decoded as Windows-1252 (a superset of Latin-1) if not.
+#[cfg(feature = "encoding")]
+#[must_use]
+pub fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (decoded, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return decoded;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(s) => Cow::Borrowed(s),
+        Err(_) => WINDOWS_1252.decode_without_bom_handling(bytes).0,
+    }
+}
+
+/// Decodes UTF-8 input, removing any byte order mark.
+///
+/// Invalid UTF-8 sequences are replaced with U+FFFD. Enable the `encoding` feature to detect
+/// UTF-16 and Windows-1252 input.
+#[cfg(not(feature = "encoding"))]
+#[must_use]
+pub fn decode(bytes: &[u8]) -> Cow<'_, str> {
+    String::from_utf8_lossy(bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::{decode, skip_bom};
+
+    #[test]
+    fn utf8_bom_is_removed() {
+        assert_eq!(decode(b"\xEF\xBB\xBF# Title"), "# Title");
+
+        let mut reader: &[u8] = b"\xEF\xBB\xBF<posts/>";
+        skip_bom(&mut reader).unwrap();
+        let mut s = String::new();
+        reader.read_to_string(&mut s).unwrap();
+        assert_eq!(s, "<posts/>");
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn utf16_with_bom_is_decoded() {
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "<DL>café</DL>".encode_utf16() {
+            bytes.extend(unit.to_le_bytes());
+        }
+        assert_eq!(decode(&bytes), "<DL>café</DL>");
+
+        let mut bytes = vec![0xFE, 0xFF];
+        for unit in "naïve".encode_utf16() {
+            bytes.extend(unit.to_be_bytes());
+        }
+        assert_eq!(decode(&bytes), "naïve");
+    }
+
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn latin1_is_decoded() {
+        assert_eq!(decode(b"caf\xE9 \x93quoted\x94"), "café “quoted”");
+        assert_eq!(decode("café".as_bytes()), "café");
+    }
+}
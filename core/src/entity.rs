@@ -1,14 +1,23 @@
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Write as _},
     hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Arc,
 };
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, SecondsFormat, TimeDelta, Utc};
+#[cfg(feature = "clap")]
+use clap::{ValueEnum, builder::PossibleValue};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use strum::{IntoStaticStr, VariantArray};
 use thiserror::Error;
+use uuid::Uuid;
 
-use hbt_pinboard::Post;
+use hbt_pinboard::{Post, PostRef};
+
+use crate::{embedding::Embedding, intern, normalize};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -26,6 +35,15 @@ pub enum Error {
 
     #[error("chrono parsing error: {0}, {1}")]
     Chrono(#[source] chrono::ParseError, String),
+
+    #[error("URL scheme not allowed: {0}, {1}")]
+    DisallowedScheme(String, String),
+
+    #[error("timestamp is in the future: {0}")]
+    FutureTimestamp(Time),
+
+    #[error("update time {0} precedes creation time {1}")]
+    UpdatedBeforeCreated(Time, Time),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
@@ -43,6 +61,148 @@ impl Url {
             .map(Url)
             .map_err(|err| Error::ParseUrl(err, s.to_string()))
     }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+/// The scheme of URLs that hold the text of an invalid URL; see [`RawUrl`].
+const RAW_SCHEME: &str = "hbt-raw";
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if is_unreserved(byte) {
+            ret.push(char::from(byte));
+        } else {
+            let _ = write!(ret, "%{byte:02X}");
+        }
+    }
+    ret
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut ret = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let maybe_byte = (bytes[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        if let Some(byte) = maybe_byte {
+            ret.push(byte);
+            i += 3;
+        } else {
+            ret.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&ret).into_owned()
+}
+
+/// The text of a URL that could not be parsed, such as one containing spaces or lacking a
+/// scheme.
+///
+/// Entities can keep such URLs, so that they can be reviewed and fixed rather than lost, by
+/// holding a [`Url`] with the `hbt-raw` scheme that wraps the original text; see [`Url::raw`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
+#[serde(transparent)]
+#[schemars(transparent)]
+pub struct RawUrl(String);
+
+impl RawUrl {
+    #[must_use]
+    pub const fn new(raw: String) -> RawUrl {
+        RawUrl(raw)
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl Url {
+    /// Wraps the text of an invalid URL, e.g. `hbt-raw:example.com%2Fa%20b` for `example.com/a b`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the wrapped URL is invalid, which cannot happen as the text is percent-encoded.
+    #[must_use]
+    pub fn raw(raw: &RawUrl) -> Url {
+        let s = format!("{RAW_SCHEME}:{}", percent_encode(raw.as_str()));
+        Url(url::Url::parse(&s).expect("percent-encoded raw URL should be valid"))
+    }
+
+    /// Parses a string into a URL, wrapping it with [`Url::raw`] if it is invalid.
+    #[must_use]
+    pub fn parse_lenient(s: &str) -> Url {
+        Url::parse(s).unwrap_or_else(|_| Url::raw(&RawUrl::new(s.to_string())))
+    }
+
+    /// Returns whether this URL wraps the text of an invalid URL.
+    #[must_use]
+    pub fn is_raw(&self) -> bool {
+        self.0.scheme() == RAW_SCHEME
+    }
+
+    /// Returns the text of the invalid URL wrapped by this URL, if any.
+    #[must_use]
+    pub fn to_raw(&self) -> Option<RawUrl> {
+        self.is_raw()
+            .then(|| RawUrl::new(percent_decode(self.0.path())))
+    }
+}
+
+/// What to do with URLs that cannot be parsed while reading input.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UrlPolicy {
+    /// Fail to parse the input.
+    #[default]
+    Strict,
+    /// Keep the bookmark, with its URL wrapped by [`Url::raw`].
+    KeepInvalid,
+}
+
+impl UrlPolicy {
+    /// Parses a URL according to this policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is invalid and the policy is [`UrlPolicy::Strict`].
+    pub fn parse(self, s: &str) -> Result<Url, Error> {
+        match self {
+            UrlPolicy::Strict => Url::parse(s),
+            UrlPolicy::KeepInvalid => Ok(Url::parse_lenient(s)),
+        }
+    }
+
+    /// Replaces an invalid URL in `href` with its wrapped form, if the policy keeps invalid URLs,
+    /// so that it can be parsed later.
+    pub fn prepare(self, href: &mut String) {
+        if self == UrlPolicy::KeepInvalid && url::Url::parse(href).is_err() {
+            *href = Url::parse_lenient(href).as_str().to_string();
+        }
+    }
+}
+
+impl AsRef<url::Url> for Url {
+    fn as_ref(&self) -> &url::Url {
+        &self.0
+    }
+}
+
+impl From<url::Url> for Url {
+    fn from(url: url::Url) -> Url {
+        Url(url)
+    }
 }
 
 impl Hash for Url {
@@ -85,18 +245,20 @@ impl From<&str> for Name {
     }
 }
 
+/// A tag on an entity. Equal labels share one interned allocation.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema)]
-pub struct Label(String);
+#[serde(from = "String")]
+pub struct Label(Arc<str>);
 
 impl Label {
     #[must_use]
-    pub const fn new(label: String) -> Label {
-        Label(label)
+    pub fn new(label: String) -> Label {
+        Label(intern::intern(label))
     }
 
     #[must_use]
     pub fn as_str(&self) -> &str {
-        self.0.as_str()
+        &self.0
     }
 }
 
@@ -108,22 +270,59 @@ impl Hash for Label {
 
 impl From<String> for Label {
     fn from(label: String) -> Label {
-        Label(label)
+        Label::new(label)
     }
 }
 
 impl From<&String> for Label {
     fn from(label: &String) -> Label {
-        Label(label.to_owned())
+        Label(intern::intern(label.as_str()))
     }
 }
 
 impl From<&str> for Label {
     fn from(label: &str) -> Label {
-        Label(label.into())
+        Label(intern::intern(label))
     }
 }
 
+/// A namespace within a collection, e.g. `work` or `personal`.
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+pub struct Profile(String);
+
+impl Profile {
+    #[must_use]
+    pub const fn new(profile: String) -> Profile {
+        Profile(profile)
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl From<String> for Profile {
+    fn from(profile: String) -> Profile {
+        Profile(profile)
+    }
+}
+
+impl From<&str> for Profile {
+    fn from(profile: &str) -> Profile {
+        Profile(profile.into())
+    }
+}
+
+/// The smallest timestamp read as milliseconds rather than seconds: in seconds, it would be in the
+/// year 5138.
+const MILLIS_THRESHOLD: u64 = 100_000_000_000;
+
+/// The smallest timestamp read as microseconds rather than milliseconds.
+const MICROS_THRESHOLD: u64 = 100_000_000_000_000;
+
 #[derive(
     Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
 )]
@@ -141,16 +340,79 @@ impl Time {
 
     /// Parses a Unix timestamp string into a `Time`.
     ///
+    /// The unit is inferred from the magnitude: timestamps too large to be in seconds are read as
+    /// milliseconds, as some browsers export, and those too large to be in milliseconds as
+    /// microseconds, as Firefox stores them. Any fraction of a second is discarded.
+    ///
     /// # Errors
     ///
     /// Returns an error if the string is not a valid integer or the timestamp is out of range.
     pub fn parse_timestamp(time: &str) -> Result<Time, Error> {
         let timestamp: i64 = time.parse()?;
+        let (divisor, _) = Time::unit(timestamp);
+        Time::from_timestamp(timestamp.div_euclid(divisor))
+    }
+
+    /// Returns the number of units per second of a Unix timestamp, as inferred by
+    /// [`Time::parse_timestamp`], and the name of the unit.
+    fn unit(timestamp: i64) -> (i64, &'static str) {
+        match timestamp.unsigned_abs() {
+            MICROS_THRESHOLD.. => (1_000_000, "microseconds"),
+            MILLIS_THRESHOLD.. => (1_000, "milliseconds"),
+            _ => (1, "seconds"),
+        }
+    }
+
+    /// Returns the unit that [`Time::parse_timestamp`] infers for `time`, if it is a Unix
+    /// timestamp in a unit other than seconds, i.e. if it would be converted.
+    #[must_use]
+    pub fn coerced_unit(time: &str) -> Option<&'static str> {
+        let timestamp: i64 = time.trim().parse().ok()?;
+        let (divisor, unit) = Time::unit(timestamp);
+        (divisor != 1).then_some(unit)
+    }
+
+    /// Converts a Unix timestamp (in seconds) into a `Time`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the timestamp is out of range.
+    pub fn from_timestamp(timestamp: i64) -> Result<Time, Error> {
         let time = DateTime::from_timestamp(timestamp, 0)
-            .ok_or_else(|| Error::ParseTimestamp(timestamp, time.to_string()))?;
+            .ok_or_else(|| Error::ParseTimestamp(timestamp, timestamp.to_string()))?;
         Ok(Time(time))
     }
 
+    /// Returns the time as a Unix timestamp (in seconds).
+    #[must_use]
+    pub fn timestamp(self) -> i64 {
+        self.0.timestamp()
+    }
+
+    /// Returns the year of the time, in UTC.
+    #[must_use]
+    pub fn year(self) -> i32 {
+        self.0.year()
+    }
+
+    /// Returns the month of the time (from 1 to 12), in UTC.
+    #[must_use]
+    pub fn month(self) -> u32 {
+        self.0.month()
+    }
+
+    /// Returns the date of the time, in UTC.
+    #[must_use]
+    pub fn date(self) -> NaiveDate {
+        self.0.date_naive()
+    }
+
+    /// Returns the time of day of the time, in UTC.
+    #[must_use]
+    pub fn time_of_day(self) -> NaiveTime {
+        self.0.time()
+    }
+
     fn parse_iso8601(time: &str) -> Result<Time, Error> {
         let time = DateTime::parse_from_rfc3339(time)
             .map_err(|err| Error::Chrono(err, time.to_string()))?
@@ -175,6 +437,13 @@ impl Time {
     }
 }
 
+/// Formats the time in RFC 3339 format, e.g. `2023-11-15T00:00:00Z`.
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.to_rfc3339_opts(SecondsFormat::Secs, true))
+    }
+}
+
 impl From<DateTime<Utc>> for Time {
     fn from(time: DateTime<Utc>) -> Time {
         Time(time)
@@ -312,6 +581,101 @@ impl From<bool> for Flag {
 #[schemars(transparent)]
 pub struct Shared(Flag);
 
+/// How [`Entity::merge_with`] resolves conflicting [`Shared`] values.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, IntoStaticStr, VariantArray)]
+#[strum(serialize_all = "kebab-case")]
+pub enum PrivacyPolicy {
+    /// Private if either is private.
+    MostRestrictive,
+    /// Shared if either is shared.
+    #[default]
+    MostPermissive,
+    /// The value from the most recently modified entity.
+    PreferNewest,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for PrivacyPolicy {
+    fn value_variants<'a>() -> &'a [PrivacyPolicy] {
+        PrivacyPolicy::VARIANTS
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let s: &'static str = self.into();
+        Some(PossibleValue::new(s))
+    }
+}
+
+/// How [`Entity::merge_with_strategies`] combines the values of a field that holds several, such
+/// as names or labels.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, IntoStaticStr, VariantArray)]
+#[strum(serialize_all = "kebab-case")]
+pub enum MergeStrategy {
+    /// The values of both entities.
+    #[default]
+    Union,
+    /// The values of the entity merged into, unless it has none.
+    PreferSelf,
+    /// The values of the entity being merged, unless it has none.
+    PreferOther,
+    /// The values of the most recently modified entity, unless it has none.
+    NewestWins,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for MergeStrategy {
+    fn value_variants<'a>() -> &'a [MergeStrategy] {
+        MergeStrategy::VARIANTS
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let s: &'static str = self.into();
+        Some(PossibleValue::new(s))
+    }
+}
+
+impl MergeStrategy {
+    /// Merges `theirs` into `ours`, where `other_is_newer` tells whether `theirs` comes from the
+    /// more recently modified entity.
+    fn merge<T>(
+        self,
+        ours: &mut T,
+        theirs: T,
+        other_is_newer: bool,
+        is_empty: impl Fn(&T) -> bool,
+        union: impl FnOnce(&mut T, T),
+    ) {
+        let prefer_other = match self {
+            MergeStrategy::Union => return union(ours, theirs),
+            MergeStrategy::PreferSelf => false,
+            MergeStrategy::PreferOther => true,
+            MergeStrategy::NewestWins => other_is_newer,
+        };
+        if !is_empty(&theirs) && (prefer_other || is_empty(ours)) {
+            *ours = theirs;
+        }
+    }
+}
+
+/// The [`MergeStrategy`] of each field that holds several values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeStrategies {
+    pub names: MergeStrategy,
+    pub labels: MergeStrategy,
+    pub extended: MergeStrategy,
+}
+
+/// Names and labels are combined, while extended notes are kept from the entity merged into.
+impl Default for MergeStrategies {
+    fn default() -> MergeStrategies {
+        MergeStrategies {
+            names: MergeStrategy::Union,
+            labels: MergeStrategy::Union,
+            extended: MergeStrategy::PreferSelf,
+        }
+    }
+}
+
 impl Shared {
     #[must_use]
     pub const fn new(value: bool) -> Shared {
@@ -327,6 +691,28 @@ impl Shared {
     pub const fn merge(self, other: Shared) -> Shared {
         Shared(self.0.merge(other.0))
     }
+
+    /// Merges two values according to `policy`, where `other_is_newer` tells whether `other`
+    /// comes from the more recently modified entity.
+    #[must_use]
+    pub const fn merge_with(
+        self,
+        other: Shared,
+        policy: PrivacyPolicy,
+        other_is_newer: bool,
+    ) -> Shared {
+        match (self.get(), other.get(), policy) {
+            (Some(x), Some(y), PrivacyPolicy::MostRestrictive) => Shared::new(x && y),
+            (Some(_), Some(_), PrivacyPolicy::PreferNewest) => {
+                if other_is_newer {
+                    other
+                } else {
+                    self
+                }
+            }
+            _ => self.merge(other),
+        }
+    }
 }
 
 impl From<bool> for Shared {
@@ -451,6 +837,23 @@ pub struct Entity {
     extended: Vec<Extended>,
     #[serde(skip_serializing_if = "LastVisitedAt::is_none")]
     last_visited_at: LastVisitedAt,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    profile: Option<Profile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    snapshot_path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    extras: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    icon_uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embedding: Option<Embedding>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pinned: bool,
+    /// When each label added by a merge after the entity was created was added.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    labels_added_at: BTreeMap<Label, Time>,
 }
 
 impl Entity {
@@ -472,15 +875,18 @@ impl Entity {
             is_feed: IsFeed::default(),
             extended: Vec::new(),
             last_visited_at: LastVisitedAt::default(),
+            profile: None,
+            snapshot_path: None,
+            extras: BTreeMap::new(),
+            icon: None,
+            icon_uri: None,
+            embedding: None,
+            pinned: false,
+            labels_added_at: BTreeMap::new(),
         }
     }
 
-    fn update(
-        &mut self,
-        updated_at: CreatedAt,
-        names: BTreeSet<Name>,
-        labels: BTreeSet<Label>,
-    ) -> &mut Entity {
+    fn update(&mut self, updated_at: CreatedAt) -> &mut Entity {
         if updated_at < self.created_at {
             self.updated_at.push(UpdatedAt::new(self.created_at.get()));
             self.created_at = updated_at;
@@ -489,25 +895,195 @@ impl Entity {
         }
         // Sort updated_at to maintain chronological order
         self.updated_at.sort();
-        self.names.extend(names);
-        self.labels.extend(labels);
         self
     }
 
+    /// Returns the latest of the creation and update times.
+    #[must_use]
+    pub fn last_modified(&self) -> Time {
+        self.updated_at
+            .iter()
+            .map(|updated_at| updated_at.get())
+            .fold(self.created_at.get(), std::cmp::max)
+    }
+
+    /// Merges `other` into this entity, resolving conflicting [`Shared`] values with
+    /// [`PrivacyPolicy::MostPermissive`].
     pub fn merge(&mut self, other: Entity) -> &mut Entity {
-        self.update(other.created_at, other.names, other.labels);
-        self.shared = self.shared.merge(other.shared);
+        self.merge_with(other, PrivacyPolicy::default())
+    }
+
+    /// Merges `other` into this entity, resolving conflicting [`Shared`] values with `policy`.
+    pub fn merge_with(&mut self, other: Entity, policy: PrivacyPolicy) -> &mut Entity {
+        self.merge_with_strategies(other, policy, MergeStrategies::default())
+    }
+
+    /// Merges `other` into this entity, resolving conflicting [`Shared`] values with `policy`, and
+    /// combining names, labels and extended notes according to `strategies`.
+    pub fn merge_with_strategies(
+        &mut self,
+        other: Entity,
+        policy: PrivacyPolicy,
+        strategies: MergeStrategies,
+    ) -> &mut Entity {
+        let other_is_newer = other.last_modified() >= self.last_modified();
+        let mut labels_added_at: BTreeMap<Label, Time> = BTreeMap::new();
+        for entity in [&*self, &other] {
+            for label in &entity.labels {
+                // A label this entity lacks was added to `other` no later than its last change.
+                let time = match entity.labels_added_at.get(label) {
+                    Some(time) => *time,
+                    None if self.labels.contains(label) => entity.created_at.get(),
+                    None => other.last_modified(),
+                };
+                labels_added_at
+                    .entry(label.clone())
+                    .and_modify(|earliest| *earliest = (*earliest).min(time))
+                    .or_insert(time);
+            }
+        }
+        self.shared = self.shared.merge_with(other.shared, policy, other_is_newer);
+        strategies.names.merge(
+            &mut self.names,
+            other.names,
+            other_is_newer,
+            BTreeSet::is_empty,
+            Extend::extend,
+        );
+        strategies.labels.merge(
+            &mut self.labels,
+            other.labels,
+            other_is_newer,
+            BTreeSet::is_empty,
+            Extend::extend,
+        );
+        strategies.extended.merge(
+            &mut self.extended,
+            other.extended,
+            other_is_newer,
+            Vec::is_empty,
+            |ours, theirs| {
+                for extended in theirs {
+                    if !ours.contains(&extended) {
+                        ours.push(extended);
+                    }
+                }
+            },
+        );
+        self.update(other.created_at);
         self.to_read = self.to_read.merge(other.to_read);
         self.is_feed = self.is_feed.merge(other.is_feed);
         self.last_visited_at = self.last_visited_at.merge(other.last_visited_at);
+        if other.snapshot_path.is_some() {
+            self.snapshot_path = other.snapshot_path;
+        }
+        self.extras.extend(other.extras);
+        if other.icon.is_some() {
+            self.icon = other.icon;
+        }
+        if other.icon_uri.is_some() {
+            self.icon_uri = other.icon_uri;
+        }
+        if other.embedding.is_some() {
+            self.embedding = other.embedding;
+        }
+        self.pinned |= other.pinned;
+        let created_at = self.created_at.get();
+        labels_added_at.retain(|label, time| *time > created_at && self.labels.contains(label));
+        self.labels_added_at = labels_added_at;
         self
     }
 
+    /// Returns the names of the fields in which merging `other` into this entity would change
+    /// or add information, using the serialized field names.
+    #[must_use]
+    pub fn differing_fields(&self, other: &Entity) -> BTreeSet<&'static str> {
+        let mut ret = BTreeSet::new();
+        if self.created_at != other.created_at {
+            ret.insert("createdAt");
+        }
+        if !other.names.is_subset(&self.names) {
+            ret.insert("names");
+        }
+        if !other.labels.is_subset(&self.labels) {
+            ret.insert("labels");
+        }
+        if self.shared != other.shared {
+            ret.insert("shared");
+        }
+        if self.to_read != other.to_read {
+            ret.insert("toRead");
+        }
+        if self.is_feed != other.is_feed {
+            ret.insert("isFeed");
+        }
+        if self.last_visited_at != other.last_visited_at {
+            ret.insert("lastVisitedAt");
+        }
+        if other.snapshot_path.is_some() && self.snapshot_path != other.snapshot_path {
+            ret.insert("snapshotPath");
+        }
+        if other
+            .extras
+            .iter()
+            .any(|(key, value)| self.extras.get(key) != Some(value))
+        {
+            ret.insert("extras");
+        }
+        if other.icon.is_some() && self.icon != other.icon {
+            ret.insert("icon");
+        }
+        if other.icon_uri.is_some() && self.icon_uri != other.icon_uri {
+            ret.insert("iconUri");
+        }
+        if other.embedding.is_some() && self.embedding != other.embedding {
+            ret.insert("embedding");
+        }
+        if other.pinned && !self.pinned {
+            ret.insert("pinned");
+        }
+        ret
+    }
+
     #[must_use]
     pub fn url(&self) -> &Url {
         &self.url
     }
 
+    /// Replaces the URL. Callers must keep the owning collection's URL index in sync.
+    pub(crate) fn set_url(&mut self, url: Url) {
+        self.url = url;
+    }
+
+    #[must_use]
+    pub fn created_at(&self) -> CreatedAt {
+        self.created_at
+    }
+
+    pub fn set_created_at(&mut self, created_at: CreatedAt) {
+        self.created_at = created_at;
+    }
+
+    #[must_use]
+    pub fn updated_at(&self) -> &[UpdatedAt] {
+        &self.updated_at
+    }
+
+    /// Records an update time, keeping update times in chronological order.
+    pub fn push_updated_at(&mut self, updated_at: UpdatedAt) {
+        self.updated_at.push(updated_at);
+        self.updated_at.sort();
+    }
+
+    #[must_use]
+    pub fn names(&self) -> &BTreeSet<Name> {
+        &self.names
+    }
+
+    pub fn names_mut(&mut self) -> &mut BTreeSet<Name> {
+        &mut self.names
+    }
+
     #[must_use]
     pub fn labels(&self) -> &BTreeSet<Label> {
         &self.labels
@@ -516,33 +1092,508 @@ impl Entity {
     pub fn labels_mut(&mut self) -> &mut BTreeSet<Label> {
         &mut self.labels
     }
+
+    #[must_use]
+    pub fn shared(&self) -> Shared {
+        self.shared
+    }
+
+    pub fn set_shared(&mut self, shared: Shared) {
+        self.shared = shared;
+    }
+
+    #[must_use]
+    pub fn to_read(&self) -> ToRead {
+        self.to_read
+    }
+
+    pub fn set_to_read(&mut self, to_read: ToRead) {
+        self.to_read = to_read;
+    }
+
+    #[must_use]
+    pub fn is_feed(&self) -> IsFeed {
+        self.is_feed
+    }
+
+    pub fn set_is_feed(&mut self, is_feed: IsFeed) {
+        self.is_feed = is_feed;
+    }
+
+    #[must_use]
+    pub fn extended(&self) -> &[Extended] {
+        &self.extended
+    }
+
+    pub fn extended_mut(&mut self) -> &mut Vec<Extended> {
+        &mut self.extended
+    }
+
+    #[must_use]
+    pub fn last_visited_at(&self) -> LastVisitedAt {
+        self.last_visited_at
+    }
+
+    pub fn set_last_visited_at(&mut self, last_visited_at: LastVisitedAt) {
+        self.last_visited_at = last_visited_at;
+    }
+
+    /// Returns the profile this entity belongs to, or `None` for the default profile.
+    #[must_use]
+    pub fn profile(&self) -> Option<&Profile> {
+        self.profile.as_ref()
+    }
+
+    /// Returns a stable identifier for this entity: a version 5 UUID of its normalized URL,
+    /// qualified by its profile, in the [`UUID_NAMESPACE`] namespace.
+    #[must_use]
+    pub fn uuid(&self) -> Uuid {
+        let url = normalize::normalize(&self.url);
+        let name = match &self.profile {
+            Some(profile) => format!("{} {}", profile.as_str(), url.as_str()),
+            None => url.as_str().to_string(),
+        };
+        Uuid::new_v5(&UUID_NAMESPACE, name.as_bytes())
+    }
+
+    /// Moves the entity to another profile. Callers must keep the owning collection's URL index in
+    /// sync.
+    pub(crate) fn set_profile(&mut self, profile: Option<Profile>) {
+        self.profile = profile;
+    }
+
+    /// Returns the path of the stored snapshot of the page's content, if any.
+    #[must_use]
+    pub fn snapshot_path(&self) -> Option<&Path> {
+        self.snapshot_path.as_deref()
+    }
+
+    pub fn set_snapshot_path(&mut self, snapshot_path: Option<PathBuf>) {
+        self.snapshot_path = snapshot_path;
+    }
+
+    /// Returns source-specific fields that have no dedicated place in the schema.
+    #[must_use]
+    pub fn extras(&self) -> &BTreeMap<String, String> {
+        &self.extras
+    }
+
+    pub fn extras_mut(&mut self) -> &mut BTreeMap<String, String> {
+        &mut self.extras
+    }
+
+    /// Returns the favicon, typically as a `data:` URI holding base64-encoded image data.
+    #[must_use]
+    pub fn icon(&self) -> Option<&str> {
+        self.icon.as_deref()
+    }
+
+    pub fn set_icon(&mut self, icon: Option<String>) {
+        self.icon = icon;
+    }
+
+    /// Returns the URI the favicon was fetched from.
+    #[must_use]
+    pub fn icon_uri(&self) -> Option<&str> {
+        self.icon_uri.as_deref()
+    }
+
+    pub fn set_icon_uri(&mut self, icon_uri: Option<String>) {
+        self.icon_uri = icon_uri;
+    }
+
+    /// Returns the vector embedding of the entity's text, if one has been attached; see
+    /// [`Collection::attach_embeddings`](crate::collection::Collection::attach_embeddings).
+    #[must_use]
+    pub fn embedding(&self) -> Option<&Embedding> {
+        self.embedding.as_ref()
+    }
+
+    pub fn set_embedding(&mut self, embedding: Option<Embedding>) {
+        self.embedding = embedding;
+    }
+
+    /// Returns true if the entity is pinned, e.g. it was on the browser's bookmarks bar. Pinned
+    /// entities are listed first in HTML output.
+    #[must_use]
+    pub fn is_pinned(&self) -> bool {
+        self.pinned
+    }
+
+    pub fn set_pinned(&mut self, pinned: bool) {
+        self.pinned = pinned;
+    }
+
+    /// Forgets when labels were added by merges, so that [`Entity::label_added_at`] returns the
+    /// creation time for every label.
+    pub fn clear_labels_added_at(&mut self) {
+        self.labels_added_at.clear();
+    }
+
+    /// Forgets when labels that the entity no longer carries were added, e.g. after removing
+    /// labels with [`Entity::labels_mut`].
+    pub fn prune_labels_added_at(&mut self) {
+        let labels = &self.labels;
+        self.labels_added_at
+            .retain(|label, _| labels.contains(label));
+    }
+
+    /// Returns when `label` was added: the time of the merge that added it, or else the creation
+    /// time. Returns `None` if the entity does not carry `label`.
+    #[must_use]
+    pub fn label_added_at(&self, label: &Label) -> Option<Time> {
+        if !self.labels.contains(label) {
+            return None;
+        }
+        Some(
+            self.labels_added_at
+                .get(label)
+                .copied()
+                .unwrap_or(self.created_at.get()),
+        )
+    }
+
+    /// Returns the entity's [`Display`](fmt::Display) line, truncated to at most `width`
+    /// characters with a trailing ellipsis, e.g. for a terminal row.
+    #[must_use]
+    pub fn summary(&self, width: usize) -> String {
+        let line = self.to_string();
+        if line.chars().count() <= width {
+            return line;
+        }
+        let mut ret: String = line.chars().take(width.saturating_sub(1)).collect();
+        if width > 0 {
+            ret.push('\u{2026}');
+        }
+        ret
+    }
+
+    #[must_use]
+    pub fn builder(url: Url, created_at: Time) -> EntityBuilder {
+        EntityBuilder::new(url, created_at)
+    }
+
+    /// Constructs a validated entity from its raw parts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL cannot be parsed, or if validation fails as described in
+    /// [`EntityBuilder::try_build`].
+    pub fn try_from_parts(parts: EntityParts) -> Result<Entity, Error> {
+        let mut builder = Entity::builder(Url::parse(&parts.url)?, parts.created_at)
+            .labels(parts.labels.into_iter().map(Label::new));
+        for updated_at in parts.updated_at {
+            builder = builder.updated_at(updated_at);
+        }
+        for name in parts.names {
+            builder = builder.name(Name::new(name));
+        }
+        for extended in parts.extended {
+            builder = builder.extended(Extended::new(extended));
+        }
+        if let Some(shared) = parts.shared {
+            builder = builder.shared(shared);
+        }
+        if let Some(to_read) = parts.to_read {
+            builder = builder.to_read(to_read);
+        }
+        if let Some(is_feed) = parts.is_feed {
+            builder = builder.is_feed(is_feed);
+        }
+        if let Some(last_visited_at) = parts.last_visited_at {
+            builder = builder.last_visited_at(last_visited_at);
+        }
+        builder.try_build()
+    }
+}
+
+/// Formats the entity as a single line of its creation date, first name, URL and labels, e.g.
+/// `2023-11-15  Example  https://example.com/  #rust #web`.
+impl fmt::Display for Entity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.created_at.get().date())?;
+        if let Some(name) = self.names.first() {
+            write!(f, "  {}", name.as_str())?;
+        }
+        write!(f, "  {}", self.url.as_str())?;
+        for (i, label) in self.labels.iter().enumerate() {
+            let separator = if i == 0 { "  " } else { " " };
+            write!(f, "{separator}#{}", label.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+/// The namespace of [`Entity::uuid`]: the version 5 UUID of `https://github.com/henrytill/hbt` in
+/// the URL namespace.
+pub const UUID_NAMESPACE: Uuid = Uuid::from_u128(0xadf2_1bda_f439_5e1c_b9bc_2f4a_e614_067b);
+
+/// The key of the extra recording where an entity was read from; see
+/// [`Collection::set_source`](crate::collection::Collection::set_source).
+pub const SOURCE_EXTRA: &str = "hbt:source";
+
+/// The key of the extra holding the record an entity was parsed from, when parsers are asked to
+/// keep it.
+pub const RAW_EXTRA: &str = "hbt:raw";
+
+/// URL schemes accepted by [`EntityBuilder::try_build`] unless overridden.
+pub const DEFAULT_SCHEMES: [&str; 5] = ["http", "https", "ftp", "file", "mailto"];
+
+/// How far in the future a timestamp may be before it is rejected, to allow for clock skew.
+const MAX_CLOCK_SKEW: TimeDelta = TimeDelta::days(1);
+
+/// Builds a fully-populated [`Entity`].
+///
+/// Fields that are not set keep the same defaults as [`Entity::new`].
+#[derive(Debug, Clone)]
+pub struct EntityBuilder {
+    entity: Entity,
+    schemes: Vec<String>,
+}
+
+impl EntityBuilder {
+    #[must_use]
+    pub fn new(url: Url, created_at: Time) -> EntityBuilder {
+        EntityBuilder {
+            entity: Entity::new(url, created_at, None, BTreeSet::new()),
+            schemes: DEFAULT_SCHEMES.iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    /// Replaces the URL schemes accepted by [`EntityBuilder::try_build`].
+    #[must_use]
+    pub fn schemes(
+        mut self,
+        schemes: impl IntoIterator<Item = impl Into<String>>,
+    ) -> EntityBuilder {
+        self.schemes = schemes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    #[must_use]
+    pub fn updated_at(mut self, updated_at: Time) -> EntityBuilder {
+        self.entity.push_updated_at(UpdatedAt::new(updated_at));
+        self
+    }
+
+    #[must_use]
+    pub fn name(mut self, name: Name) -> EntityBuilder {
+        self.entity.names.insert(name);
+        self
+    }
+
+    #[must_use]
+    pub fn label(mut self, label: Label) -> EntityBuilder {
+        self.entity.labels.insert(label);
+        self
+    }
+
+    #[must_use]
+    pub fn labels(mut self, labels: impl IntoIterator<Item = Label>) -> EntityBuilder {
+        self.entity.labels.extend(labels);
+        self
+    }
+
+    #[must_use]
+    pub fn shared(mut self, shared: bool) -> EntityBuilder {
+        self.entity.shared = Shared::new(shared);
+        self
+    }
+
+    #[must_use]
+    pub fn to_read(mut self, to_read: bool) -> EntityBuilder {
+        self.entity.to_read = ToRead::new(to_read);
+        self
+    }
+
+    #[must_use]
+    pub fn is_feed(mut self, is_feed: bool) -> EntityBuilder {
+        self.entity.is_feed = IsFeed::new(is_feed);
+        self
+    }
+
+    #[must_use]
+    pub fn extended(mut self, extended: Extended) -> EntityBuilder {
+        self.entity.extended.push(extended);
+        self
+    }
+
+    #[must_use]
+    pub fn last_visited_at(mut self, last_visited_at: Time) -> EntityBuilder {
+        self.entity.last_visited_at = LastVisitedAt::new(last_visited_at);
+        self
+    }
+
+    #[must_use]
+    pub fn profile(mut self, profile: Profile) -> EntityBuilder {
+        self.entity.profile = Some(profile);
+        self
+    }
+
+    #[must_use]
+    pub fn snapshot_path(mut self, snapshot_path: PathBuf) -> EntityBuilder {
+        self.entity.snapshot_path = Some(snapshot_path);
+        self
+    }
+
+    #[must_use]
+    pub fn extra(mut self, key: impl Into<String>, value: impl Into<String>) -> EntityBuilder {
+        self.entity.extras.insert(key.into(), value.into());
+        self
+    }
+
+    #[must_use]
+    pub fn pinned(mut self, pinned: bool) -> EntityBuilder {
+        self.entity.pinned = pinned;
+        self
+    }
+
+    #[must_use]
+    pub fn icon(mut self, icon: impl Into<String>) -> EntityBuilder {
+        self.entity.icon = Some(icon.into());
+        self
+    }
+
+    #[must_use]
+    pub fn icon_uri(mut self, icon_uri: impl Into<String>) -> EntityBuilder {
+        self.entity.icon_uri = Some(icon_uri.into());
+        self
+    }
+
+    /// Returns the entity without validating it.
+    #[must_use]
+    pub fn build(self) -> Entity {
+        self.entity
+    }
+
+    /// Validates and returns the entity.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL scheme is not one of the accepted schemes (by default
+    /// [`DEFAULT_SCHEMES`]), if any timestamp is more than a day in the future, or if an update
+    /// time precedes the creation time.
+    pub fn try_build(self) -> Result<Entity, Error> {
+        let entity = self.entity;
+
+        let scheme = entity.url.0.scheme();
+        if !self.schemes.iter().any(|allowed| allowed == scheme) {
+            return Err(Error::DisallowedScheme(
+                scheme.to_string(),
+                entity.url.as_str().to_string(),
+            ));
+        }
+
+        let latest = Time(Utc::now() + MAX_CLOCK_SKEW);
+        let created_at = entity.created_at.get();
+        let times = std::iter::once(created_at)
+            .chain(entity.updated_at.iter().map(|updated_at| updated_at.get()))
+            .chain(entity.last_visited_at.get());
+        for time in times {
+            if time > latest {
+                return Err(Error::FutureTimestamp(time));
+            }
+        }
+        for updated_at in &entity.updated_at {
+            if updated_at.get() < created_at {
+                return Err(Error::UpdatedBeforeCreated(updated_at.get(), created_at));
+            }
+        }
+
+        Ok(entity)
+    }
+}
+
+/// The raw parts of an [`Entity`], for programmatic imports. See [`Entity::try_from_parts`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EntityParts {
+    pub url: String,
+    pub created_at: Time,
+    pub updated_at: Vec<Time>,
+    pub names: Vec<String>,
+    pub labels: Vec<String>,
+    pub shared: Option<bool>,
+    pub to_read: Option<bool>,
+    pub is_feed: Option<bool>,
+    pub extended: Vec<String>,
+    pub last_visited_at: Option<Time>,
 }
 
 impl TryFrom<Post> for Entity {
     type Error = Error;
 
     fn try_from(post: Post) -> Result<Entity, Error> {
+        Entity::try_from(PostRef::from(post))
+    }
+}
+
+impl TryFrom<PostRef<'_>> for Entity {
+    type Error = Error;
+
+    fn try_from(post: PostRef<'_>) -> Result<Entity, Error> {
         let url = Url::parse(&post.href)?;
         let created_at = CreatedAt::new(Time::parse_flexible(&post.time)?);
-        let extended: Vec<Extended> = post.extended.map(Extended::new).into_iter().collect();
+        let names = post
+            .description
+            .map(|description| Name::new(description.into_owned()));
+        let extended = post
+            .extended
+            .map(|extended| Extended::new(extended.into_owned()));
 
         Ok(Entity {
             url,
             created_at,
             updated_at: Vec::new(),
-            names: post.description.into_iter().map(Name::new).collect(),
-            labels: post.tags.into_iter().map(Label::new).collect(),
+            names: names.into_iter().collect(),
+            labels: post
+                .tags
+                .into_iter()
+                .map(|tag| Label::new(tag.into_owned()))
+                .collect(),
             shared: Shared::new(post.shared),
             to_read: ToRead::new(post.toread),
             is_feed: IsFeed::new(false),
-            extended,
+            extended: extended.into_iter().collect(),
             last_visited_at: LastVisitedAt::default(),
+            profile: None,
+            snapshot_path: None,
+            extras: BTreeMap::new(),
+            icon: None,
+            icon_uri: None,
+            embedding: None,
+            pinned: false,
+            labels_added_at: BTreeMap::new(),
         })
     }
 }
 
+/// Converts an entity into a Pinboard post, the reverse of [`Entity::try_from`].
+///
+/// Pinboard posts have a single title and note, so only the first name is kept, and extended
+/// notes are joined by blank lines. Bookmarks are shared unless they are known to be private.
+impl From<&Entity> for Post {
+    fn from(entity: &Entity) -> Post {
+        let extended: Vec<&str> = entity.extended.iter().map(Extended::as_str).collect();
+        Post {
+            href: entity.url.as_str().to_string(),
+            time: entity.created_at.get().to_string(),
+            description: entity.names.first().map(|name| name.as_str().to_string()),
+            extended: (!extended.is_empty()).then(|| extended.join("\n\n")),
+            tags: entity
+                .labels
+                .iter()
+                .map(|label| label.as_str().to_string())
+                .collect(),
+            meta: None,
+            hash: None,
+            shared: entity.shared.get() != Some(false),
+            toread: entity.to_read.get() == Some(true),
+        }
+    }
+}
+
 pub mod html {
-    use std::collections::{BTreeSet, HashMap};
+    use std::collections::{BTreeMap, BTreeSet};
 
     use super::{
         CreatedAt, Entity, Error, Extended, IsFeed, Label, LastVisitedAt, Name, Shared, Time,
@@ -557,20 +1608,53 @@ pub mod html {
     const KEY_PRIVATE: &str = "private";
     const KEY_TOREAD: &str = "toread";
     const KEY_FEED: &str = "feed";
+    const KEY_ICON: &str = "icon";
+    const KEY_ICON_URI: &str = "icon_uri";
+    const KEY_PINNED: &str = "pinned";
+
+    /// The attribute of the `<H3>` heading of the browser's bookmarks bar, whose bookmarks are
+    /// pinned.
+    pub const KEY_PERSONAL_TOOLBAR_FOLDER: &str = "personal_toolbar_folder";
+
+    /// The attributes read by [`Entity::from_attrs`], in lowercase.
+    pub const KEYS: [&str; 11] = [
+        KEY_HREF,
+        KEY_ADD_DATE,
+        KEY_LAST_MODIFIED,
+        KEY_LAST_VISIT,
+        KEY_TAGS,
+        KEY_PRIVATE,
+        KEY_TOREAD,
+        KEY_FEED,
+        KEY_ICON,
+        KEY_ICON_URI,
+        KEY_PINNED,
+    ];
+
+    /// The attributes read by [`Entity::from_attrs`] as times.
+    pub const TIME_KEYS: [&str; 3] = [KEY_ADD_DATE, KEY_LAST_MODIFIED, KEY_LAST_VISIT];
 
     impl Entity {
         /// Creates an entity from HTML bookmark attributes.
         ///
+        /// Attribute names are case-insensitive. If several names differ only in case, the value
+        /// of the last in sorted order (i.e. the most lowercase) is used, so the result does not
+        /// depend on the order of the attributes.
+        ///
         /// # Errors
         ///
         /// Returns an error if required attributes are missing (e.g., `href`) or if values cannot be parsed
         /// (e.g., invalid URL, invalid timestamp).
         pub fn from_attrs(
-            attrs: HashMap<String, String>,
+            attrs: BTreeMap<String, String>,
             names: BTreeSet<Name>,
             labels: BTreeSet<Label>,
             extended: Vec<Extended>,
         ) -> Result<Entity, Error> {
+            let attrs: BTreeMap<String, String> = attrs
+                .into_iter()
+                .map(|(key, value)| (key.to_lowercase(), value))
+                .collect();
             let href = attrs.get(KEY_HREF).ok_or(Error::MissingUrl)?;
             let url = Url::parse(href)?;
 
@@ -585,22 +1669,30 @@ pub mod html {
                 is_feed: IsFeed::default(),
                 extended,
                 last_visited_at: LastVisitedAt::default(),
+                profile: None,
+                snapshot_path: None,
+                extras: BTreeMap::new(),
+                icon: None,
+                icon_uri: None,
+                embedding: None,
+                pinned: false,
+                labels_added_at: BTreeMap::new(),
             };
 
             let mut tags = String::new();
 
             for (key, value) in attrs {
                 let trimmed = value.trim();
-                match key.to_lowercase().as_str() {
+                match key.as_str() {
                     KEY_ADD_DATE if !trimmed.is_empty() => {
-                        entity.created_at = CreatedAt::new(Time::parse_timestamp(trimmed)?);
+                        entity.created_at = CreatedAt::new(Time::parse_flexible(trimmed)?);
                     }
                     KEY_LAST_MODIFIED if !trimmed.is_empty() => {
-                        let time = Time::parse_timestamp(trimmed)?;
+                        let time = Time::parse_flexible(trimmed)?;
                         entity.updated_at.push(UpdatedAt::new(time));
                     }
                     KEY_LAST_VISIT if !trimmed.is_empty() => {
-                        let time = Time::parse_timestamp(trimmed)?;
+                        let time = Time::parse_flexible(trimmed)?;
                         entity.last_visited_at = LastVisitedAt::new(time);
                     }
                     KEY_TAGS if !trimmed.is_empty() => {
@@ -615,6 +1707,15 @@ pub mod html {
                     KEY_FEED => {
                         entity.is_feed = IsFeed::new(trimmed == "true");
                     }
+                    KEY_ICON if !trimmed.is_empty() => {
+                        entity.icon = Some(trimmed.to_string());
+                    }
+                    KEY_ICON_URI if !trimmed.is_empty() => {
+                        entity.icon_uri = Some(trimmed.to_string());
+                    }
+                    KEY_PINNED => {
+                        entity.pinned = trimmed == "true";
+                    }
                     _ => {}
                 }
             }
@@ -635,3 +1736,353 @@ pub mod html {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        sync::Arc,
+    };
+
+    use chrono::{TimeDelta, Utc};
+    use hbt_pinboard::Post;
+
+    use super::{
+        Entity, EntityParts, Error, Extended, Label, MergeStrategies, MergeStrategy, Name,
+        PrivacyPolicy, RawUrl, Time, UpdatedAt, Url, UrlPolicy,
+    };
+
+    #[test]
+    fn deserialized_labels_are_interned() {
+        let labels: Vec<Label> = serde_json::from_str(r#"["rust", "go", "rust"]"#).unwrap();
+        assert!(Arc::ptr_eq(&labels[0].0, &labels[2].0));
+        assert_eq!(serde_json::to_string(&labels[1]).unwrap(), r#""go""#);
+    }
+
+    #[test]
+    fn attrs_are_read_regardless_of_case() {
+        let attrs = BTreeMap::from([
+            ("HREF".to_string(), "https://example.com/".to_string()),
+            ("ADD_DATE".to_string(), "100".to_string()),
+            ("add_date".to_string(), "200".to_string()),
+        ]);
+        let entity =
+            Entity::from_attrs(attrs, BTreeSet::new(), BTreeSet::new(), Vec::new()).unwrap();
+        assert_eq!(entity.url().as_str(), "https://example.com/");
+        assert_eq!(entity.created_at().get().timestamp(), 200);
+    }
+
+    #[test]
+    fn timestamps_are_read_in_any_browser_convention() {
+        let expected = Time::from_timestamp(1_700_000_000).unwrap();
+        for time in [
+            // Chrome, Safari and Firefox HTML exports: seconds
+            "1700000000",
+            // Milliseconds
+            "1700000000123",
+            // Firefox's places database: microseconds
+            "1700000000123456",
+            // ISO 8601
+            "2023-11-14T22:13:20Z",
+            "2023-11-14T23:13:20+01:00",
+        ] {
+            assert_eq!(Time::parse_flexible(time).unwrap(), expected, "{time}");
+        }
+        assert_eq!(Time::parse_timestamp("99999999999").unwrap().year(), 5138);
+        assert!(matches!(
+            Time::parse_timestamp("2023-11-14"),
+            Err(Error::ParseInt(_))
+        ));
+    }
+
+    #[test]
+    fn builder_populates_fields() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let entity = Entity::builder(url.clone(), Time::default())
+            .name(Name::from("Example"))
+            .label(Label::from("foo"))
+            .shared(false)
+            .to_read(true)
+            .last_visited_at(Time::default())
+            .build();
+
+        assert_eq!(entity.url(), &url);
+        assert!(entity.names().contains(&Name::from("Example")));
+        assert!(entity.labels().contains(&Label::from("foo")));
+        assert_eq!(entity.shared().get(), Some(false));
+        assert_eq!(entity.to_read().get(), Some(true));
+        assert_eq!(entity.is_feed().get(), None);
+        assert_eq!(entity.last_visited_at().get(), Some(Time::default()));
+    }
+
+    #[test]
+    fn extras_survive_merge_and_serialization() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let mut entity = Entity::builder(url.clone(), Time::default())
+            .extra("buku:index", "1")
+            .build();
+        let other = Entity::builder(url, Time::default())
+            .extra("browser:guid", "abc")
+            .extra("buku:index", "2")
+            .build();
+        entity.merge(other);
+
+        assert_eq!(
+            entity.extras().get("buku:index").map(String::as_str),
+            Some("2")
+        );
+        assert_eq!(
+            entity.extras().get("browser:guid").map(String::as_str),
+            Some("abc")
+        );
+
+        let yaml = serde_norway::to_string(&entity).unwrap();
+        let roundtrip: Entity = serde_norway::from_str(&yaml).unwrap();
+        assert_eq!(roundtrip, entity);
+    }
+
+    #[test]
+    fn try_from_parts_validates() {
+        let parts = EntityParts {
+            url: "https://example.com/".to_string(),
+            names: vec!["Example".to_string()],
+            labels: vec!["foo".to_string()],
+            to_read: Some(true),
+            extended: vec!["Notes".to_string()],
+            ..EntityParts::default()
+        };
+        let entity = Entity::try_from_parts(parts.clone()).unwrap();
+        assert_eq!(entity.to_read().get(), Some(true));
+        assert_eq!(entity.extended().len(), 1);
+
+        let javascript = EntityParts {
+            url: "javascript:void(0)".to_string(),
+            ..parts.clone()
+        };
+        assert!(matches!(
+            Entity::try_from_parts(javascript),
+            Err(Error::DisallowedScheme(..))
+        ));
+
+        let future = EntityParts {
+            created_at: Time::new(Utc::now() + TimeDelta::days(30)),
+            ..parts.clone()
+        };
+        assert!(matches!(
+            Entity::try_from_parts(future),
+            Err(Error::FutureTimestamp(_))
+        ));
+
+        let updated_before_created = EntityParts {
+            created_at: Time::from_timestamp(100).unwrap(),
+            updated_at: vec![Time::from_timestamp(50).unwrap()],
+            ..parts.clone()
+        };
+        assert!(matches!(
+            Entity::try_from_parts(updated_before_created),
+            Err(Error::UpdatedBeforeCreated(..))
+        ));
+
+        let later_update_before_created = EntityParts {
+            created_at: Time::from_timestamp(100).unwrap(),
+            updated_at: vec![
+                Time::from_timestamp(200).unwrap(),
+                Time::from_timestamp(50).unwrap(),
+            ],
+            ..parts
+        };
+        assert!(matches!(
+            Entity::try_from_parts(later_update_before_created),
+            Err(Error::UpdatedBeforeCreated(..))
+        ));
+    }
+
+    #[test]
+    fn builder_accepts_custom_schemes() {
+        let url = Url::parse("javascript:void(0)").unwrap();
+        let result = Entity::builder(url, Time::default())
+            .schemes(["javascript"])
+            .try_build();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn raw_urls_roundtrip() {
+        let raw = RawUrl::new("example.com/a b?c=100%".to_string());
+        let url = Url::raw(&raw);
+        assert!(url.is_raw());
+        assert_eq!(url.to_raw(), Some(raw));
+        assert!(Url::parse(url.as_str()).is_ok());
+
+        assert_eq!(
+            Url::parse_lenient("not a url").to_raw().unwrap().as_str(),
+            "not a url"
+        );
+        assert!(!Url::parse_lenient("https://example.com/").is_raw());
+        assert!(UrlPolicy::Strict.parse("not a url").is_err());
+    }
+
+    #[test]
+    fn merge_with_privacy_policy() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let make = |timestamp, shared| {
+            Entity::builder(url.clone(), Time::from_timestamp(timestamp).unwrap())
+                .shared(shared)
+                .build()
+        };
+        let merged = |policy| {
+            let mut entity = make(200, false);
+            entity.merge_with(make(100, true), policy);
+            entity.shared().get()
+        };
+        assert_eq!(merged(PrivacyPolicy::MostPermissive), Some(true));
+        assert_eq!(merged(PrivacyPolicy::MostRestrictive), Some(false));
+        assert_eq!(merged(PrivacyPolicy::PreferNewest), Some(false));
+
+        let mut entity = make(100, false);
+        entity.merge_with(make(200, true), PrivacyPolicy::PreferNewest);
+        assert_eq!(entity.shared().get(), Some(true));
+    }
+
+    #[test]
+    fn merges_record_when_labels_were_added() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let time = |timestamp| Time::from_timestamp(timestamp).unwrap();
+        let make = |timestamp, labels: &[&str]| {
+            let labels = labels.iter().copied().map(Label::from).collect();
+            Entity::new(url.clone(), time(timestamp), None, labels)
+        };
+        let (rust, web) = (Label::from("rust"), Label::from("web"));
+
+        let mut entity = make(100, &["rust"]);
+        entity.merge(make(300, &["rust", "web"]));
+        assert_eq!(entity.label_added_at(&rust), Some(time(100)));
+        assert_eq!(entity.label_added_at(&web), Some(time(300)));
+        assert_eq!(entity.label_added_at(&Label::from("go")), None);
+
+        entity.merge(make(200, &["web"]));
+        assert_eq!(entity.label_added_at(&web), Some(time(200)));
+
+        let mut older = make(50, &["go"]);
+        older.push_updated_at(UpdatedAt::new(time(400)));
+        entity.merge(older);
+        assert_eq!(entity.label_added_at(&Label::from("go")), Some(time(400)));
+
+        entity.labels_mut().remove(&web);
+        entity.prune_labels_added_at();
+        assert!(!serde_norway::to_string(&entity).unwrap().contains("web"));
+
+        let yaml = serde_norway::to_string(&entity).unwrap();
+        let entity: Entity = serde_norway::from_str(&yaml).unwrap();
+        assert_eq!(entity.label_added_at(&Label::from("go")), Some(time(400)));
+    }
+
+    #[test]
+    fn merge_with_strategies() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let make = |timestamp, label: &str, note: &str| {
+            let mut entity = Entity::builder(url.clone(), Time::from_timestamp(timestamp).unwrap())
+                .name(Name::from(label))
+                .label(Label::from(label))
+                .build();
+            entity.extended_mut().push(Extended::new(note.to_string()));
+            entity
+        };
+        let merged = |strategy| {
+            let strategies = MergeStrategies {
+                names: strategy,
+                labels: strategy,
+                extended: strategy,
+            };
+            let mut entity = make(200, "curated", "kept");
+            entity.merge_with_strategies(
+                make(100, "raw", "imported"),
+                PrivacyPolicy::default(),
+                strategies,
+            );
+            let labels: Vec<Label> = entity.labels().iter().cloned().collect();
+            (labels, entity.extended().len())
+        };
+        assert_eq!(
+            merged(MergeStrategy::Union),
+            (vec![Label::from("curated"), Label::from("raw")], 2)
+        );
+        assert_eq!(
+            merged(MergeStrategy::PreferSelf),
+            (vec![Label::from("curated")], 1)
+        );
+        assert_eq!(
+            merged(MergeStrategy::PreferOther),
+            (vec![Label::from("raw")], 1)
+        );
+        assert_eq!(
+            merged(MergeStrategy::NewestWins),
+            (vec![Label::from("curated")], 1)
+        );
+
+        let mut entity = Entity::builder(url.clone(), Time::default()).build();
+        entity.merge_with_strategies(
+            make(100, "raw", "imported"),
+            PrivacyPolicy::default(),
+            MergeStrategies {
+                labels: MergeStrategy::PreferSelf,
+                ..MergeStrategies::default()
+            },
+        );
+        assert_eq!(entity.labels().len(), 1);
+        assert_eq!(entity.extended().len(), 1);
+    }
+
+    #[test]
+    fn posts_round_trip_through_pinboard_formats() {
+        let entity = Entity::builder(
+            Url::parse("https://example.com/?a=1&b=2").unwrap(),
+            Time::from_timestamp(1_700_000_000).unwrap(),
+        )
+        .name(Name::from("Quotes \"and\" <brackets>"))
+        .labels([Label::from("rust"), Label::from("web")])
+        .extended(Extended::from("first"))
+        .extended(Extended::from("second"))
+        .shared(false)
+        .to_read(true)
+        .build();
+
+        let posts = vec![Post::from(&entity)];
+        assert_eq!(posts[0].time, "2023-11-14T22:13:20Z");
+        assert_eq!(posts[0].extended.as_deref(), Some("first\n\nsecond"));
+
+        let mut json = Vec::new();
+        Post::to_json(&mut json, &posts).unwrap();
+        assert_eq!(Post::from_json(&mut json.as_slice()).unwrap(), posts);
+
+        let mut xml = Vec::new();
+        Post::to_xml(&mut xml, &posts).unwrap();
+        assert_eq!(Post::from_xml(&mut xml.as_slice()).unwrap(), posts);
+
+        let parsed = Entity::try_from(posts.into_iter().next().unwrap()).unwrap();
+        assert_eq!(parsed.url(), entity.url());
+        assert_eq!(parsed.created_at(), entity.created_at());
+        assert_eq!(parsed.names(), entity.names());
+        assert_eq!(parsed.labels(), entity.labels());
+        assert_eq!(parsed.shared(), entity.shared());
+        assert_eq!(parsed.to_read(), entity.to_read());
+    }
+
+    #[test]
+    fn display_and_summary() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let time = Time::from_timestamp(1_700_006_400).unwrap();
+        let labels = BTreeSet::from([Label::from("web"), Label::from("rust")]);
+        let entity = Entity::new(url.clone(), time, Some(Name::from("Example")), labels);
+        assert_eq!(
+            entity.to_string(),
+            "2023-11-15  Example  https://example.com/  #rust #web"
+        );
+        assert_eq!(entity.summary(80), entity.to_string());
+        assert_eq!(entity.summary(21), "2023-11-15  Example \u{2026}");
+        assert_eq!(entity.summary(0), "");
+
+        let bare = Entity::new(url, time, None, BTreeSet::new());
+        assert_eq!(bare.to_string(), "2023-11-15  https://example.com/");
+    }
+}
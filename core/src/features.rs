@@ -0,0 +1,45 @@
+/// Reading and writing collections asynchronously, with `tokio`; see [`crate::async_io`].
+pub const ASYNC: bool = cfg!(feature = "async");
+
+/// Writing and extracting portable archive bundles; see [`crate::bundle`].
+pub const BUNDLE: bool = cfg!(feature = "bundle");
+
+/// `clap::ValueEnum` implementations for formats and policies.
+pub const CLAP: bool = cfg!(feature = "clap");
+
+/// Transcoding UTF-16 and Windows-1252 input; see [`crate::encoding::decode`].
+pub const ENCODING: bool = cfg!(feature = "encoding");
+
+/// The HTTP client shared by network features; see [`crate::net`].
+pub const NET: bool = cfg!(feature = "net");
+
+/// Parsing HTML on several threads; see [`crate::collection::Collection::from_html_parallel`].
+pub const PARALLEL: bool = cfg!(feature = "parallel");
+
+/// Grouping domains by the Public Suffix List; see [`crate::domain::registrable_domain`].
+pub const PSL: bool = cfg!(feature = "psl");
+
+/// Storing snapshots of bookmarked pages; see [`crate::snapshot`]. Implies [`NET`].
+pub const SNAPSHOT: bool = cfg!(feature = "snapshot");
+
+/// Reading and writing `SQLite` databases; see [`crate::sqlite`].
+pub const SQLITE: bool = cfg!(feature = "sqlite");
+
+/// Returns the names of the features enabled in this build.
+#[must_use]
+pub fn enabled() -> Vec<&'static str> {
+    [
+        ("async", ASYNC),
+        ("bundle", BUNDLE),
+        ("clap", CLAP),
+        ("encoding", ENCODING),
+        ("net", NET),
+        ("parallel", PARALLEL),
+        ("psl", PSL),
+        ("snapshot", SNAPSHOT),
+        ("sqlite", SQLITE),
+    ]
+    .into_iter()
+    .filter_map(|(name, enabled)| enabled.then_some(name))
+    .collect()
+}
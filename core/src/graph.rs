@@ -0,0 +1,398 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use serde::Serialize;
+
+use crate::{
+    collection::{Collection, Id},
+    entity::Url,
+};
+
+/// The default number of hubs listed by [`Collection::graph_stats`].
+pub const DEFAULT_HUBS: usize = 10;
+
+/// The default number of source entities sampled by [`Collection::betweenness`].
+pub const DEFAULT_SAMPLES: usize = 256;
+
+/// A summary of a collection's link structure, treating edges as undirected.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphStats {
+    /// The number of entities with each degree.
+    pub degree_distribution: BTreeMap<usize, usize>,
+    /// The sizes of the connected components, largest first. Each entity without edges is a
+    /// component of size one.
+    pub component_sizes: Vec<usize>,
+    /// The number of entities without edges.
+    pub isolated: usize,
+    /// The entities with the highest degree, highest first.
+    pub hubs: Vec<Hub>,
+}
+
+/// A highly connected entity.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Hub {
+    pub url: Url,
+    pub degree: usize,
+    /// The entity's approximate betweenness centrality; see [`Collection::betweenness`].
+    pub betweenness: f64,
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn to_f64(n: usize) -> f64 {
+    n as f64
+}
+
+impl Collection {
+    /// Returns the ids of the entities adjacent to each entity, by index. When `undirected` is
+    /// set, incoming edges are included as well as outgoing ones.
+    fn adjacency(&self, undirected: bool) -> Vec<Vec<Id>> {
+        let mut ret: Vec<Vec<Id>> = self.iter().map(|(id, _)| self.edges(&id)).collect();
+        if undirected {
+            for (from, _) in self.iter() {
+                for to in self.edges(&from) {
+                    if !ret[to.index()].contains(&from) {
+                        ret[to.index()].push(from.clone());
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    /// Visits entities breadth-first from `start`, up to `depth` edges away, returning each
+    /// visited id with the id it was reached from.
+    fn traverse(&self, start: &Id, depth: usize, undirected: bool) -> Vec<(Id, Option<Id>)> {
+        let adjacency = self.adjacency(undirected);
+        let mut parents: Vec<Option<Option<Id>>> = vec![None; self.len()];
+        let mut order = Vec::new();
+        let mut queue = VecDeque::from([(start.clone(), 0)]);
+        parents[start.index()] = Some(None);
+        while let Some((id, distance)) = queue.pop_front() {
+            order.push((id.clone(), parents[id.index()].clone().flatten()));
+            if distance == depth {
+                continue;
+            }
+            for next in &adjacency[id.index()] {
+                if parents[next.index()].is_none() {
+                    parents[next.index()] = Some(Some(id.clone()));
+                    queue.push_back((next.clone(), distance + 1));
+                }
+            }
+        }
+        order
+    }
+
+    /// Returns the entities reachable from `start` by following at most `depth` outgoing edges,
+    /// including `start` itself, along with the edges between them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `start` does not belong to this collection.
+    #[must_use]
+    pub fn bfs(&self, start: &Id, depth: usize) -> Collection {
+        let ids: Vec<Id> = self
+            .traverse(start, depth, false)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        self.take(&ids)
+    }
+
+    /// Returns the entities within `radius` edges of `id` in either direction, including `id`
+    /// itself, along with the edges between them.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` does not belong to this collection.
+    #[must_use]
+    pub fn neighborhood(&self, id: &Id, radius: usize) -> Collection {
+        let ids: Vec<Id> = self
+            .traverse(id, radius, true)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        self.take(&ids)
+    }
+
+    /// Returns the ids along a shortest path from `from` to `to`, following outgoing edges, or
+    /// `None` if `to` is unreachable. The path includes both ends; pass it to
+    /// [`Collection::take`] for a sub-collection.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either id does not belong to this collection.
+    #[must_use]
+    pub fn shortest_path(&self, from: &Id, to: &Id) -> Option<Vec<Id>> {
+        let visited = self.traverse(from, usize::MAX, false);
+        let mut parents: Vec<Option<Option<Id>>> = vec![None; self.len()];
+        for (id, parent) in visited {
+            parents[id.index()] = Some(parent);
+        }
+        let mut path = vec![to.clone()];
+        let mut current = parents[to.index()].clone()?;
+        while let Some(parent) = current {
+            current = parents[parent.index()].clone().flatten();
+            path.push(parent);
+        }
+        path.reverse();
+        Some(path)
+    }
+
+    /// Returns a spanning forest of the collection, treating edges as undirected: every entity, in
+    /// breadth-first order, with the id of its parent. Each tree is rooted at the entity of its
+    /// connected component that comes first in the collection, so nested markdown lists become
+    /// trees rooted at their top-level items.
+    #[must_use]
+    pub fn forest(&self) -> Vec<(Id, Option<Id>)> {
+        let adjacency = self.adjacency(true);
+        let mut visited = vec![false; self.len()];
+        let mut ret = Vec::with_capacity(self.len());
+        for (root, _) in self.iter() {
+            if visited[root.index()] {
+                continue;
+            }
+            visited[root.index()] = true;
+            let mut queue = VecDeque::from([(root, None)]);
+            while let Some((id, maybe_parent)) = queue.pop_front() {
+                for next in &adjacency[id.index()] {
+                    if !visited[next.index()] {
+                        visited[next.index()] = true;
+                        queue.push_back((next.clone(), Some(id.clone())));
+                    }
+                }
+                ret.push((id, maybe_parent));
+            }
+        }
+        ret
+    }
+
+    /// Returns the number of distinct entities adjacent to each entity in either direction, by
+    /// index.
+    #[must_use]
+    pub fn degrees(&self) -> Vec<usize> {
+        self.adjacency(true).iter().map(Vec::len).collect()
+    }
+
+    /// Returns the number of entities with each degree; see [`Collection::degrees`].
+    #[must_use]
+    pub fn degree_distribution(&self) -> BTreeMap<usize, usize> {
+        let mut ret = BTreeMap::new();
+        for degree in self.degrees() {
+            *ret.entry(degree).or_insert(0) += 1;
+        }
+        ret
+    }
+
+    /// Returns the sizes of the connected components, treating edges as undirected, largest
+    /// first.
+    #[must_use]
+    pub fn component_sizes(&self) -> Vec<usize> {
+        let mut sizes: BTreeMap<usize, usize> = BTreeMap::new();
+        let mut roots: Vec<usize> = vec![0; self.len()];
+        for (id, maybe_parent) in self.forest() {
+            let root = maybe_parent.map_or(id.index(), |parent| roots[parent.index()]);
+            roots[id.index()] = root;
+            *sizes.entry(root).or_insert(0) += 1;
+        }
+        let mut ret: Vec<usize> = sizes.into_values().collect();
+        ret.sort_unstable_by(|a, b| b.cmp(a));
+        ret
+    }
+
+    /// Returns the approximate betweenness centrality of each entity, by index, treating edges as
+    /// undirected: the number of shortest paths between other entities that pass through it.
+    ///
+    /// Paths are counted exactly from at most `samples` evenly spaced source entities, using
+    /// Brandes' algorithm, and scaled up to estimate the count over all sources.
+    #[must_use]
+    pub fn betweenness(&self, samples: usize) -> Vec<f64> {
+        let len = self.len();
+        let adjacency: Vec<Vec<usize>> = self
+            .adjacency(true)
+            .iter()
+            .map(|ids| ids.iter().map(Id::index).collect())
+            .collect();
+        let samples = samples.min(len);
+        let mut ret = vec![0.0; len];
+        if samples == 0 {
+            return ret;
+        }
+        for sample in 0..samples {
+            let source = sample * len / samples;
+            let mut stack = Vec::with_capacity(len);
+            let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); len];
+            let mut paths = vec![0.0_f64; len];
+            let mut distances: Vec<Option<usize>> = vec![None; len];
+            paths[source] = 1.0;
+            distances[source] = Some(0);
+            let mut queue = VecDeque::from([source]);
+            while let Some(v) = queue.pop_front() {
+                stack.push(v);
+                let next = distances[v].map(|distance| distance + 1);
+                for &w in &adjacency[v] {
+                    if distances[w].is_none() {
+                        distances[w] = next;
+                        queue.push_back(w);
+                    }
+                    if distances[w] == next {
+                        paths[w] += paths[v];
+                        predecessors[w].push(v);
+                    }
+                }
+            }
+            let mut dependencies = vec![0.0_f64; len];
+            while let Some(w) = stack.pop() {
+                for &v in &predecessors[w] {
+                    dependencies[v] += paths[v] / paths[w] * (1.0 + dependencies[w]);
+                }
+                if w != source {
+                    ret[w] += dependencies[w];
+                }
+            }
+        }
+        // Each path is found from both of its ends.
+        let scale = to_f64(len) / to_f64(samples) / 2.0;
+        for value in &mut ret {
+            *value *= scale;
+        }
+        ret
+    }
+
+    /// Summarizes the link structure, listing the `hubs` entities of highest degree with their
+    /// betweenness estimated from `samples` sources.
+    #[must_use]
+    pub fn graph_stats(&self, hubs: usize, samples: usize) -> GraphStats {
+        let degrees = self.degrees();
+        let betweenness = self.betweenness(samples);
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        order.sort_by(|&a, &b| {
+            degrees[b]
+                .cmp(&degrees[a])
+                .then_with(|| betweenness[b].total_cmp(&betweenness[a]))
+        });
+        let entities = self.entities();
+        GraphStats {
+            degree_distribution: self.degree_distribution(),
+            component_sizes: self.component_sizes(),
+            isolated: degrees.iter().filter(|&&degree| degree == 0).count(),
+            hubs: order
+                .into_iter()
+                .take(hubs)
+                .filter(|&index| degrees[index] > 0)
+                .map(|index| Hub {
+                    url: entities[index].url().clone(),
+                    degree: degrees[index],
+                    betweenness: betweenness[index],
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use crate::{
+        collection::{Collection, Id},
+        entity::{Entity, Time, Url},
+    };
+
+    fn url(i: usize) -> Url {
+        Url::parse(&format!("https://example.com/{i}")).unwrap()
+    }
+
+    /// Builds 0 -> 1 -> 2 -> 3, plus 4 -> 0 and an unconnected 5.
+    fn make_graph() -> (Collection, Vec<Id>) {
+        let mut coll = Collection::new();
+        let ids: Vec<Id> = (0..6)
+            .map(|i| coll.insert(Entity::new(url(i), Time::default(), None, BTreeSet::new())))
+            .collect();
+        for (from, to) in [(0, 1), (1, 2), (2, 3), (4, 0)] {
+            coll.add_edge(&ids[from], &ids[to]);
+        }
+        (coll, ids)
+    }
+
+    fn urls(coll: &Collection) -> Vec<Url> {
+        coll.entities().iter().map(|e| e.url().clone()).collect()
+    }
+
+    #[test]
+    fn bfs_follows_outgoing_edges() {
+        let (coll, ids) = make_graph();
+        assert_eq!(urls(&coll.bfs(&ids[0], 2)), [url(0), url(1), url(2)]);
+        assert_eq!(urls(&coll.bfs(&ids[0], 0)), [url(0)]);
+
+        let sub = coll.bfs(&ids[0], 2);
+        assert_eq!(sub.edges(&sub.id(&url(0)).unwrap()).len(), 1);
+        assert!(sub.edges(&sub.id(&url(2)).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn neighborhood_follows_both_directions() {
+        let (coll, ids) = make_graph();
+        assert_eq!(
+            urls(&coll.neighborhood(&ids[0], 1)),
+            [url(0), url(1), url(4)]
+        );
+        assert_eq!(coll.neighborhood(&ids[5], 3).len(), 1);
+    }
+
+    #[test]
+    fn shortest_path_between_entities() {
+        let (coll, ids) = make_graph();
+        assert_eq!(
+            coll.shortest_path(&ids[4], &ids[3]),
+            Some(vec![
+                ids[4].clone(),
+                ids[0].clone(),
+                ids[1].clone(),
+                ids[2].clone(),
+                ids[3].clone()
+            ])
+        );
+        assert_eq!(coll.shortest_path(&ids[3], &ids[0]), None);
+        assert_eq!(
+            coll.shortest_path(&ids[0], &ids[0]),
+            Some(vec![ids[0].clone()])
+        );
+    }
+
+    #[test]
+    fn degree_and_centrality_find_hubs() {
+        let (coll, _) = make_graph();
+        assert_eq!(coll.degrees(), [2, 2, 2, 1, 1, 0]);
+        assert_eq!(
+            coll.degree_distribution(),
+            BTreeMap::from([(0, 1), (1, 2), (2, 3)])
+        );
+        assert_eq!(coll.component_sizes(), [5, 1]);
+
+        // On the path 4 - 0 - 1 - 2 - 3, entity 1 lies between 2 pairs on each side.
+        let betweenness = coll.betweenness(usize::MAX);
+        assert_eq!(betweenness, [3.0, 4.0, 3.0, 0.0, 0.0, 0.0]);
+
+        let stats = coll.graph_stats(1, usize::MAX);
+        assert_eq!(stats.isolated, 1);
+        assert_eq!(stats.hubs.len(), 1);
+        assert_eq!(stats.hubs[0].url, url(1));
+    }
+
+    #[test]
+    fn forest_spans_each_component() {
+        let (coll, ids) = make_graph();
+        let parent = |id: &Id| -> Option<usize> {
+            coll.forest()
+                .into_iter()
+                .find(|(other, _)| other == id)
+                .and_then(|(_, maybe_parent)| maybe_parent)
+                .map(|parent| parent.index())
+        };
+        assert_eq!(coll.forest().len(), 6);
+        assert_eq!(parent(&ids[0]), None);
+        assert_eq!(parent(&ids[1]), Some(0));
+        assert_eq!(parent(&ids[3]), Some(2));
+        assert_eq!(parent(&ids[4]), Some(0));
+        assert_eq!(parent(&ids[5]), None);
+    }
+}
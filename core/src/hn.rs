@@ -0,0 +1,190 @@
+use std::io::BufRead;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    Anomaly,
+    collection::Collection,
+    entity::{self, Entity, Label, Name, Time, Url},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Entity(#[from] entity::Error),
+
+    #[error("JSON parsing error: {0}")]
+    ParseJson(#[from] serde_json::Error),
+}
+
+const LABEL: &str = "hn";
+const ITEM_URL: &str = "https://news.ycombinator.com/item?id=";
+
+#[derive(Debug, Deserialize)]
+struct Hit {
+    #[serde(rename = "objectID")]
+    object_id: String,
+    title: Option<String>,
+    url: Option<String>,
+    created_at: Option<String>,
+    created_at_i: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Export {
+    Search { hits: Vec<Hit> },
+    Hits(Vec<Hit>),
+}
+
+impl Hit {
+    fn has_url(&self) -> bool {
+        !self.object_id.is_empty() || self.url.as_ref().is_some_and(|url| !url.is_empty())
+    }
+
+    fn into_entity(self) -> Result<Entity, entity::Error> {
+        let url = match self.url.filter(|url| !url.is_empty()) {
+            Some(url) => Url::parse(&url)?,
+            None => Url::parse(&format!("{ITEM_URL}{}", self.object_id))?,
+        };
+        let created_at = match (self.created_at_i, self.created_at) {
+            (Some(timestamp), _) => Time::from_timestamp(timestamp)?,
+            (None, Some(created_at)) => Time::parse_flexible(&created_at)?,
+            (None, None) => Time::default(),
+        };
+        let name = self.title.filter(|title| !title.is_empty()).map(Name::new);
+        let labels = [Label::from(LABEL)].into();
+        Ok(Entity::new(url, created_at, name, labels))
+    }
+}
+
+/// Parses the entities of a Hacker News favorites export, as in [`Collection::from_hn`], along
+/// with the hits skipped for having neither a URL nor an ID.
+pub(crate) fn entities(reader: &mut impl BufRead) -> Result<(Vec<Entity>, Vec<Anomaly>), Error> {
+    let hits = match serde_json::from_reader(reader)? {
+        Export::Search { hits } | Export::Hits(hits) => hits,
+    };
+    let mut entities = Vec::with_capacity(hits.len());
+    let mut anomalies = Vec::new();
+    for (index, hit) in hits.into_iter().enumerate() {
+        if hit.has_url() {
+            entities.push(hit.into_entity()?);
+        } else {
+            anomalies.push(Anomaly::Skipped {
+                position: format!("hit #{}", index + 1),
+            });
+        }
+    }
+    Ok((entities, anomalies))
+}
+
+impl Collection {
+    /// Parses a Hacker News favorites export into a collection.
+    ///
+    /// The input is expected in the Algolia search API format, either as a response object with a
+    /// `hits` array or as a bare array of hits. Items without a URL (e.g. "Ask HN" posts) link to
+    /// their discussion page, and items with neither a URL nor an ID are skipped. Every entity is
+    /// labelled `hn`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is malformed or an item has an invalid URL or timestamp.
+    pub fn from_hn(reader: &mut impl BufRead) -> Result<Collection, Error> {
+        let (entities, _) = entities(reader)?;
+        Ok(Collection::from_entities(entities))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Anomaly, InputFormat, ParseOptions,
+        collection::Collection,
+        entity::{Label, MergeStrategies, MergeStrategy, Name, Time, Url},
+    };
+
+    #[test]
+    fn search_results_are_parsed() {
+        let input = r#"{"hits": [
+            {"objectID": "1", "title": "A story", "url": "https://example.com/", "created_at_i": 1700000000},
+            {"objectID": "2", "title": "Ask HN: Anything?", "url": "", "created_at": "2023-11-15T00:00:00Z"},
+            {"objectID": "3", "title": ""}
+        ]}"#;
+        let coll = Collection::from_hn(&mut input.as_bytes()).unwrap();
+        let find = |url| coll.entity(&coll.id(&Url::parse(url).unwrap()).unwrap());
+
+        assert_eq!(coll.len(), 3);
+        let story = find("https://example.com/");
+        assert_eq!(story.url().as_str(), "https://example.com/");
+        assert_eq!(
+            story.names().iter().collect::<Vec<_>>(),
+            [&Name::from("A story")]
+        );
+        assert_eq!(
+            story.created_at().get(),
+            Time::from_timestamp(1_700_000_000).unwrap()
+        );
+        assert_eq!(
+            story.labels().iter().map(Label::as_str).collect::<Vec<_>>(),
+            ["hn"]
+        );
+        let ask = find("https://news.ycombinator.com/item?id=2");
+        assert_eq!(ask.url().as_str(), "https://news.ycombinator.com/item?id=2");
+        assert_eq!(
+            ask.created_at().get(),
+            Time::from_timestamp(1_700_006_400).unwrap()
+        );
+        assert!(
+            find("https://news.ycombinator.com/item?id=3")
+                .names()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn bare_hit_arrays_are_parsed() {
+        let input = r#"[{"objectID": "1", "url": "https://example.com/"}]"#;
+        let coll = Collection::from_hn(&mut input.as_bytes()).unwrap();
+        assert_eq!(coll.len(), 1);
+        assert!(Collection::from_hn(&mut "{}".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn hits_without_a_url_are_reported() {
+        let input = r#"[{"objectID": ""}, {"objectID": "2"}]"#;
+        let (coll, report) = InputFormat::HackerNews
+            .parse_with_report(&mut input.as_bytes(), &ParseOptions::default())
+            .unwrap();
+        assert_eq!(coll.len(), 1);
+        assert_eq!(
+            report.anomalies,
+            [Anomaly::Skipped {
+                position: "hit #1".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn duplicates_are_merged_with_the_parse_options() {
+        let input = r#"[
+            {"objectID": "1", "title": "First", "url": "https://example.com/", "created_at_i": 1},
+            {"objectID": "2", "title": "Second", "url": "https://example.com/", "created_at_i": 2}
+        ]"#;
+        let options = ParseOptions {
+            merge_strategies: MergeStrategies {
+                names: MergeStrategy::PreferSelf,
+                ..MergeStrategies::default()
+            },
+            ..ParseOptions::default()
+        };
+        let coll = InputFormat::HackerNews
+            .parse_with_options(&mut input.as_bytes(), &options)
+            .unwrap();
+
+        assert_eq!(coll.len(), 1);
+        let names: Vec<&Name> = coll.entities()[0].names().iter().collect();
+        assert_eq!(names, [&Name::from("First")]);
+        assert_eq!(coll.merge_strategies(), options.merge_strategies);
+    }
+}
@@ -1,15 +1,20 @@
 use std::{
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet},
+    fmt,
     io::{self, Write},
 };
 
 use minijinja::{Environment, context};
 use scraper::{ElementRef, Html, Selector};
+use serde::Serialize;
 use thiserror::Error;
 
 use crate::{
+    Anomaly,
     collection::Collection,
-    entity::{self, Entity, Extended, Label, Name},
+    entity::{self, Entity, Extended, Label, Name, RAW_EXTRA, Time, UrlPolicy},
+    label_meta::{LabelInfo, LabelMeta},
+    limits::{self, Limits},
 };
 
 #[derive(Debug, Error)]
@@ -17,6 +22,15 @@ pub enum Error {
     #[error(transparent)]
     Entity(#[from] entity::Error),
 
+    #[error("invalid bookmark at {0}: {1}")]
+    Bookmark(Position, #[source] entity::Error),
+
+    #[error(transparent)]
+    Limit(#[from] limits::Error),
+
+    #[error("bookmark at {0} exceeds limits: {1}")]
+    BookmarkLimit(Position, #[source] limits::Error),
+
     #[error("HTML selector error: {0}")]
     HtmlSelector(String),
 
@@ -36,26 +50,172 @@ impl From<scraper::error::SelectorErrorKind<'_>> for Error {
     }
 }
 
+/// The location of a bookmark within a Netscape bookmark HTML file, for reporting errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Position {
+    /// The number of the bookmark's `<DT>` element in document order, counting from 1.
+    pub index: usize,
+    /// The bookmark's `HREF`, if it has one.
+    pub maybe_href: Option<String>,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<DT> #{}", self.index)?;
+        if let Some(href) = &self.maybe_href {
+            write!(f, " ({href})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Options for reading Netscape bookmark HTML.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOptions {
+    pub url_policy: UrlPolicy,
+    /// Record the `<A>` element each bookmark was read from in its [`RAW_EXTRA`] extra.
+    pub keep_raw: bool,
+    /// Bounds on folder nesting, the number of bookmarks and the length of attributes and text.
+    pub limits: Limits,
+}
+
+/// Options for writing Netscape bookmark HTML.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Give each bookmark a stable `ID` attribute derived from its URL.
+    pub anchors: bool,
+    /// Emit an index of labels linking to each labelled bookmark. Implies `anchors`.
+    pub label_index: bool,
+    /// Colors, emoji and descriptions used to render labels in the index.
+    pub label_meta: LabelMeta,
+    /// Group bookmarks into one folder per label, in this order, instead of listing them at the
+    /// root. A bookmark carrying several of the labels appears in each of their folders.
+    pub folders: Vec<Label>,
+    pub layout: Layout,
+}
+
+/// How bookmarks are arranged in Netscape bookmark HTML.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// At the root, or in [`Options::folders`] if any.
+    #[default]
+    List,
+    /// Under a table of contents of labels, with counts, linking to a collapsible section per
+    /// label, colored from [`Options::label_meta`]. Sections follow [`Options::folders`], or every
+    /// label if there are none; bookmarks in none of them are listed after the sections.
+    Toc,
+    /// In folders following the collection's edges, as built from nested markdown lists: each
+    /// bookmark with children becomes a folder, named after it, holding it and its children.
+    /// [`Options::folders`] is ignored.
+    Nest,
+}
+
 #[derive(Debug)]
 enum StackItem<'a> {
     Element(ElementRef<'a>),
     PopGroup,
 }
 
-type Attrs = HashMap<String, String>;
+type Attrs = BTreeMap<String, String>;
 
-fn add(
-    coll: &mut Collection,
+/// A bookmark whose `<DT>` has been read, awaiting a possible `<DD>` description.
+struct Pending {
+    index: usize,
     attrs: Attrs,
+    maybe_desc: Option<String>,
+    maybe_raw: Option<String>,
+    /// Whether the bookmark is in the bookmarks bar.
+    pinned: bool,
+}
+
+impl Pending {
+    /// Reads the bookmark in the `<A>` element of the `index`th `<DT>`.
+    fn new(a_elt: ElementRef, options: &ReadOptions, index: usize, pinned: bool) -> Pending {
+        let mut attrs = extract_attrs(a_elt);
+        if let Some(href) = attrs.get_mut(ATTR_HREF) {
+            options.url_policy.prepare(href);
+        }
+        Pending {
+            index,
+            attrs,
+            maybe_desc: extract_text(a_elt),
+            maybe_raw: options.keep_raw.then(|| a_elt.html()),
+            pinned,
+        }
+    }
+}
+
+/// Returns whether the folder headed by an `<H3>` element is the bookmarks bar.
+fn is_toolbar(h3_elt: ElementRef) -> bool {
+    h3_elt
+        .value()
+        .attr(entity::html::KEY_PERSONAL_TOOLBAR_FOLDER)
+        .is_some_and(|value| value.eq_ignore_ascii_case("true"))
+}
+
+/// Records the attributes of a bookmark that are not read, or whose timestamps are converted.
+fn check_attrs(position: &Position, attrs: &Attrs, anomalies: &mut Vec<Anomaly>) {
+    for (key, value) in attrs {
+        if !entity::html::KEYS.contains(&key.as_str()) {
+            anomalies.push(Anomaly::UnknownAttribute {
+                position: position.to_string(),
+                attribute: key.clone(),
+            });
+        } else if entity::html::TIME_KEYS.contains(&key.as_str())
+            && let Some(unit) = Time::coerced_unit(value)
+        {
+            anomalies.push(Anomaly::CoercedTimestamp {
+                position: position.to_string(),
+                attribute: key.clone(),
+                value: value.clone(),
+                unit: unit.to_string(),
+            });
+        }
+    }
+}
+
+fn add(
+    entities: &mut Vec<Entity>,
+    anomalies: &mut Vec<Anomaly>,
+    limits: &Limits,
+    pending: Pending,
     folders: impl IntoIterator<Item = impl Into<Label>>,
-    maybe_name: Option<impl Into<Name>>,
     ext: Vec<impl Into<Extended>>,
 ) -> Result<(), Error> {
-    let names = maybe_name.into_iter().map(Into::into).collect();
+    let Pending {
+        index,
+        attrs,
+        maybe_desc,
+        maybe_raw,
+        pinned,
+    } = pending;
+    let position = Position {
+        index,
+        maybe_href: attrs.get(ATTR_HREF).cloned(),
+    };
+    limits.check_entities(entities.len() + 1)?;
+    if let Err(err) = attrs
+        .values()
+        .try_for_each(|value| limits.check_field("attribute", value))
+    {
+        return Err(Error::BookmarkLimit(position, err));
+    }
+    check_attrs(&position, &attrs, anomalies);
+    let names = maybe_desc.into_iter().map(Into::into).collect();
     let labels: BTreeSet<Label> = folders.into_iter().map(Into::into).collect();
     let ext = ext.into_iter().map(Into::into).collect();
-    let entity = Entity::from_attrs(attrs, names, labels, ext)?;
-    coll.upsert(entity);
+    let mut entity = Entity::from_attrs(attrs, names, labels, ext)
+        .map_err(|err| Error::Bookmark(position.clone(), err))?;
+    limits
+        .check_entity(&entity)
+        .map_err(|err| Error::BookmarkLimit(position, err))?;
+    if let Some(raw) = maybe_raw {
+        entity.extras_mut().insert(RAW_EXTRA.to_string(), raw);
+    }
+    if pinned {
+        entity.set_pinned(true);
+    }
+    entities.push(entity);
     Ok(())
 }
 
@@ -69,123 +229,994 @@ fn extract_text(elt: ElementRef) -> Option<String> {
 }
 
 fn extract_attrs(elt: ElementRef) -> Attrs {
-    let mut attrs = HashMap::new();
+    let mut attrs = BTreeMap::new();
     for (name, value) in elt.value().attrs() {
         attrs.insert(name.to_lowercase(), value.to_string());
     }
     attrs
 }
 
+/// Returns a stable fragment identifier for the given entity, from its URL and, outside the
+/// default profile, its profile, so that the same URL in two profiles gets two identifiers.
+///
+/// Uses 64-bit FNV-1a, so anchors are identical across runs and platforms.
+fn anchor(entity: &Entity) -> String {
+    let hash = match entity.profile() {
+        Some(profile) => fnv1a(&format!("{}\n{}", profile.as_str(), entity.url().as_str())),
+        None => fnv1a(entity.url().as_str()),
+    };
+    format!("hbt-{hash:016x}")
+}
+
+/// Returns a stable fragment identifier for the section of the given label.
+fn label_anchor(label: &Label) -> String {
+    format!("hbt-label-{:016x}", fnv1a(label.as_str()))
+}
+
+fn fnv1a(s: &str) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    s.bytes().fold(FNV_OFFSET, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct Folder<'a> {
+    label: &'a str,
+    /// The fragment identifier of the folder's section in the table of contents.
+    id: String,
+    info: LabelInfo,
+    /// Indices into the entities of the collection.
+    entities: Vec<usize>,
+}
+
+fn folders<'a>(
+    entities: &[Entity],
+    labels: impl IntoIterator<Item = &'a Label>,
+    label_meta: &LabelMeta,
+) -> Vec<Folder<'a>> {
+    labels
+        .into_iter()
+        .map(|label| Folder {
+            label: label.as_str(),
+            id: label_anchor(label),
+            info: label_meta.get(label).cloned().unwrap_or_default(),
+            entities: entities
+                .iter()
+                .enumerate()
+                .filter(|(_, entity)| entity.labels().contains(label))
+                .map(|(index, _)| index)
+                .collect(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+struct Node {
+    /// Index into the entities of the collection.
+    index: usize,
+    indent: String,
+    children: Vec<Node>,
+}
+
+/// Builds the trees of [`Collection::forest`], with each entity's position in the output given
+/// by `order`, a permutation of the indices of the collection. Roots are listed in that order.
+fn tree(coll: &Collection, order: &[usize]) -> Vec<Node> {
+    let mut positions = vec![0; order.len()];
+    for (position, &index) in order.iter().enumerate() {
+        positions[index] = position;
+    }
+    let forest = coll.forest();
+    let mut depths = vec![0_usize; coll.len()];
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); coll.len()];
+    for (id, maybe_parent) in &forest {
+        if let Some(parent) = maybe_parent {
+            depths[id.index()] = depths[parent.index()] + 1;
+            children[parent.index()].push(id.index());
+        }
+    }
+    // Parents precede their children in the forest, so building it backwards builds each node's
+    // children before the node itself, without recursion.
+    let mut built: Vec<Option<Node>> = (0..coll.len()).map(|_| None).collect();
+    for (id, _) in forest.iter().rev() {
+        let index = id.index();
+        built[index] = Some(Node {
+            index: positions[index],
+            indent: "    ".repeat(depths[index] + 1),
+            children: children[index]
+                .iter()
+                .filter_map(|&child| built[child].take())
+                .collect(),
+        });
+    }
+    let mut roots: Vec<Node> = forest
+        .iter()
+        .filter(|(_, maybe_parent)| maybe_parent.is_none())
+        .filter_map(|(id, _)| built[id.index()].take())
+        .collect();
+    roots.sort_by_key(|node| node.index);
+    roots
+}
+
+#[derive(Debug, Serialize)]
+struct IndexLink<'a> {
+    anchor: &'a str,
+    title: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct IndexEntry<'a> {
+    label: &'a str,
+    info: LabelInfo,
+    links: Vec<IndexLink<'a>>,
+}
+
+fn label_index<'a>(
+    entities: &'a [Entity],
+    anchors: &'a [String],
+    label_meta: &'a LabelMeta,
+) -> Vec<IndexEntry<'a>> {
+    let mut index: BTreeMap<&Label, Vec<IndexLink>> = BTreeMap::new();
+    for (entity, anchor) in entities.iter().zip(anchors) {
+        let title = entity
+            .names()
+            .first()
+            .map_or(entity.url().as_str(), Name::as_str);
+        for label in entity.labels() {
+            index
+                .entry(label)
+                .or_default()
+                .push(IndexLink { anchor, title });
+        }
+    }
+    index
+        .into_iter()
+        .map(|(label, links)| IndexEntry {
+            label: label.as_str(),
+            info: label_meta.get(label).cloned().unwrap_or_default(),
+            links,
+        })
+        .collect()
+}
+
 const TAG_A: &str = "a";
 const TAG_H3: &str = "h3";
 const TAG_DT: &str = "dt";
 const TAG_DD: &str = "dd";
 const TAG_DL: &str = "dl";
 
-impl Collection {
-    /// Parses a Netscape bookmark HTML file into a collection.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the HTML is malformed or contains invalid bookmark data (e.g., missing URLs,
-    /// invalid timestamps).
-    ///
-    /// # Panics
-    ///
-    /// Panics if there are pending bookmarks that were not properly closed at the end of parsing.
-    pub fn from_html(html: &str) -> Result<Collection, Error> {
-        let document = Html::parse_document(html);
-        let root = document.root_element();
+const ATTR_HREF: &str = "href";
 
-        let mut coll = Collection::new();
-        let mut stack: Vec<StackItem> = Vec::new();
-        let mut folders: Vec<String> = Vec::new();
-        let mut pending: Option<(Attrs, Option<String>)> = None;
+/// Parses the bookmarks in a Netscape bookmark HTML file, in document order, along with the
+/// anomalies found in them.
+///
+/// `dt_offset` is the number of `<DT>` elements preceding `html` in the file it was taken from,
+/// so that errors report positions within that file.
+pub(crate) fn parse_entities(
+    html: &str,
+    options: &ReadOptions,
+    dt_offset: usize,
+) -> Result<(Vec<Entity>, Vec<Anomaly>), Error> {
+    let document = Html::parse_document(html);
+    let root = document.root_element();
 
-        let a_selector = Selector::parse(TAG_A)?;
-        let h3_selector = Selector::parse(TAG_H3)?;
+    let mut entities = Vec::new();
+    let mut anomalies = Vec::new();
+    let mut stack: Vec<StackItem> = Vec::new();
+    let mut folders: Vec<String> = Vec::new();
+    let mut pending: Option<Pending> = None;
+    let mut dt_count = dt_offset;
+    let mut depth = 0_usize;
+    // The index in `folders` of the bookmarks bar, while inside it.
+    let mut maybe_toolbar: Option<usize> = None;
 
-        for child in root.children().rev() {
-            if let Some(child_elt) = ElementRef::wrap(child) {
-                stack.push(StackItem::Element(child_elt));
-            }
+    let a_selector = Selector::parse(TAG_A)?;
+    let h3_selector = Selector::parse(TAG_H3)?;
+
+    for child in root.children().rev() {
+        if let Some(child_elt) = ElementRef::wrap(child) {
+            stack.push(StackItem::Element(child_elt));
         }
+    }
 
-        while let Some(item) = stack.pop() {
-            match item {
-                StackItem::Element(elt) => {
-                    match elt.value().name() {
-                        TAG_DT => {
-                            if let Some((attrs, maybe_desc)) = pending.take() {
-                                add(
-                                    &mut coll,
-                                    attrs,
-                                    &folders,
-                                    maybe_desc,
-                                    Vec::<Extended>::new(),
-                                )?;
-                            }
+    while let Some(item) = stack.pop() {
+        match item {
+            StackItem::Element(elt) => {
+                match elt.value().name() {
+                    TAG_DT => {
+                        dt_count += 1;
+                        if let Some(pending) = pending.take() {
+                            add(
+                                &mut entities,
+                                &mut anomalies,
+                                &options.limits,
+                                pending,
+                                &folders,
+                                Vec::<Extended>::new(),
+                            )?;
+                        }
 
-                            if let Some(h3_elt) = elt.select(&h3_selector).next() {
-                                if let Some(folder) = extract_text(h3_elt) {
-                                    folders.push(folder);
+                        if let Some(h3_elt) = elt.select(&h3_selector).next() {
+                            if let Some(folder) = extract_text(h3_elt) {
+                                if maybe_toolbar.is_none() && is_toolbar(h3_elt) {
+                                    maybe_toolbar = Some(folders.len());
                                 }
-                            } else if let Some(a_elt) = elt.select(&a_selector).next() {
-                                let attrs = extract_attrs(a_elt);
-                                let maybe_desc = extract_text(a_elt);
-                                pending = Some((attrs, maybe_desc));
+                                folders.push(folder);
                             }
+                        } else if let Some(a_elt) = elt.select(&a_selector).next() {
+                            let pinned = maybe_toolbar.is_some();
+                            pending = Some(Pending::new(a_elt, options, dt_count, pinned));
+                        } else {
+                            let position = Position {
+                                index: dt_count,
+                                maybe_href: None,
+                            };
+                            anomalies.push(Anomaly::Skipped {
+                                position: position.to_string(),
+                            });
                         }
-                        TAG_DD => {
-                            if let Some((attrs, maybe_desc)) = pending.take() {
-                                let maybe_ext = extract_text(elt).into_iter().collect();
-                                add(&mut coll, attrs, &folders, maybe_desc, maybe_ext)?;
-                            }
-                        }
-                        TAG_DL => {
-                            stack.push(StackItem::PopGroup);
-                        }
-                        _ => {}
                     }
-                    for child in elt.children().rev() {
-                        if let Some(child_elt) = ElementRef::wrap(child) {
-                            stack.push(StackItem::Element(child_elt));
+                    TAG_DD => {
+                        if let Some(pending) = pending.take() {
+                            let maybe_ext = extract_text(elt).into_iter().collect();
+                            add(
+                                &mut entities,
+                                &mut anomalies,
+                                &options.limits,
+                                pending,
+                                &folders,
+                                maybe_ext,
+                            )?;
                         }
                     }
+                    TAG_DL => {
+                        depth += 1;
+                        options.limits.check_depth(depth)?;
+                        stack.push(StackItem::PopGroup);
+                    }
+                    _ => {}
                 }
-                StackItem::PopGroup => {
-                    if let Some((attrs, maybe_desc)) = pending.take() {
-                        add(
-                            &mut coll,
-                            attrs,
-                            &folders,
-                            maybe_desc,
-                            Vec::<Extended>::new(),
-                        )?;
+                for child in elt.children().rev() {
+                    if let Some(child_elt) = ElementRef::wrap(child) {
+                        stack.push(StackItem::Element(child_elt));
                     }
-                    folders.pop();
                 }
             }
+            StackItem::PopGroup => {
+                if let Some(pending) = pending.take() {
+                    add(
+                        &mut entities,
+                        &mut anomalies,
+                        &options.limits,
+                        pending,
+                        &folders,
+                        Vec::<Extended>::new(),
+                    )?;
+                }
+                depth -= 1;
+                folders.pop();
+                if maybe_toolbar.is_some_and(|index| index >= folders.len()) {
+                    maybe_toolbar = None;
+                }
+            }
+        }
+    }
+
+    assert!(pending.is_none());
+
+    Ok((entities, anomalies))
+}
+
+fn collect(coll: &mut Collection, entities: impl IntoIterator<Item = Entity>) {
+    for entity in entities {
+        coll.upsert(entity);
+    }
+}
+
+/// Returns true if `html[pos..]` starts with a tag named `name`, ignoring case.
+#[cfg(feature = "parallel")]
+fn starts_with_tag(html: &[u8], pos: usize, name: &str) -> bool {
+    let end = pos + 1 + name.len();
+    html.get(pos + 1..end)
+        .is_some_and(|tag| tag.eq_ignore_ascii_case(name.as_bytes()))
+        && html
+            .get(end)
+            .is_none_or(|&next| !next.is_ascii_alphanumeric())
+}
+
+/// Splits a Netscape bookmark HTML file into standalone documents, one per entry of the
+/// top-level `<DL>` list, so that each top-level folder can be parsed independently. Each is
+/// paired with the number of `<DT>` elements preceding it.
+///
+/// Returns `None` if no top-level list is found.
+#[cfg(feature = "parallel")]
+fn split_groups(html: &str) -> Option<Vec<(usize, String)>> {
+    let bytes = html.as_bytes();
+    let mut depth = 0_usize;
+    let mut dt_count = 0_usize;
+    let mut starts = Vec::new();
+    let mut end = None;
+    for (pos, _) in html.match_indices('<') {
+        if starts_with_tag(bytes, pos, TAG_DL) {
+            depth += 1;
+        } else if bytes.get(pos + 1) == Some(&b'/') && starts_with_tag(bytes, pos + 1, TAG_DL) {
+            depth = depth.checked_sub(1)?;
+            if depth == 0 {
+                end = Some(pos);
+                break;
+            }
+        } else if starts_with_tag(bytes, pos, TAG_DT) {
+            if depth == 1 {
+                starts.push((dt_count, pos));
+            }
+            dt_count += 1;
         }
+    }
+    let end = end?;
+    let groups = starts
+        .iter()
+        .zip(
+            starts
+                .iter()
+                .map(|&(_, pos)| pos)
+                .skip(1)
+                .chain(std::iter::once(end)),
+        )
+        .map(|(&(dt_offset, start), stop)| {
+            let group = format!("<DL><p>\n{}</DL>\n", &html[start..stop]);
+            (dt_offset, group)
+        })
+        .collect();
+    Some(groups)
+}
+
+impl Collection {
+    /// Parses a Netscape bookmark HTML file into a collection.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTML is malformed or contains invalid bookmark data (e.g., missing URLs,
+    /// invalid timestamps).
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are pending bookmarks that were not properly closed at the end of parsing.
+    pub fn from_html(html: &str) -> Result<Collection, Error> {
+        let mut coll = Collection::new();
+        coll.extend_from_html(html)?;
+        Ok(coll)
+    }
+
+    /// Parses a Netscape bookmark HTML file, merging its bookmarks into this collection with
+    /// [`Collection::upsert`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTML is malformed or contains invalid bookmark data. The collection
+    /// is unchanged if so.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are pending bookmarks that were not properly closed at the end of parsing.
+    pub fn extend_from_html(&mut self, html: &str) -> Result<(), Error> {
+        self.extend_from_html_with(html, &ReadOptions::default())
+    }
+
+    /// Like [`Collection::extend_from_html`], but handles invalid URLs and keeps raw records
+    /// according to `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTML is malformed or contains invalid bookmark data. The collection
+    /// is unchanged if so.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are pending bookmarks that were not properly closed at the end of parsing.
+    pub fn extend_from_html_with(
+        &mut self,
+        html: &str,
+        options: &ReadOptions,
+    ) -> Result<(), Error> {
+        self.extend_from_html_with_report(html, options)?;
+        Ok(())
+    }
 
-        assert!(pending.is_none());
+    /// Like [`Collection::extend_from_html_with`], but returns the anomalies found in the input:
+    /// `<DT>` elements that are neither bookmarks nor folders, attributes that are not read, and
+    /// timestamps converted from milliseconds or microseconds.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTML is malformed or contains invalid bookmark data. The collection
+    /// is unchanged if so.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are pending bookmarks that were not properly closed at the end of parsing.
+    pub fn extend_from_html_with_report(
+        &mut self,
+        html: &str,
+        options: &ReadOptions,
+    ) -> Result<Vec<Anomaly>, Error> {
+        let (entities, anomalies) = parse_entities(html, options, 0)?;
+        collect(self, entities);
+        Ok(anomalies)
+    }
 
+    /// Parses a Netscape bookmark HTML file into a collection, parsing each top-level folder on a
+    /// separate thread.
+    ///
+    /// The result is identical to [`Collection::from_html`]: bookmarks from all folders are merged
+    /// in document order. Files whose bookmarks are all nested inside a single top-level folder
+    /// gain little from this.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTML is malformed or contains invalid bookmark data (e.g., missing URLs,
+    /// invalid timestamps).
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are pending bookmarks that were not properly closed at the end of parsing.
+    #[cfg(feature = "parallel")]
+    pub fn from_html_parallel(html: &str) -> Result<Collection, Error> {
+        let mut coll = Collection::new();
+        coll.extend_from_html_parallel(html)?;
         Ok(coll)
     }
 
+    /// Like [`Collection::extend_from_html`], but parses each top-level folder on a separate
+    /// thread, as in [`Collection::from_html_parallel`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTML is malformed or contains invalid bookmark data. The collection
+    /// is unchanged if so.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are pending bookmarks that were not properly closed at the end of parsing.
+    #[cfg(feature = "parallel")]
+    pub fn extend_from_html_parallel(&mut self, html: &str) -> Result<(), Error> {
+        self.extend_from_html_parallel_with(html, &ReadOptions::default())
+    }
+
+    /// Like [`Collection::extend_from_html_parallel`], but handles invalid URLs and keeps raw
+    /// records according to `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTML is malformed or contains invalid bookmark data. The collection
+    /// is unchanged if so.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are pending bookmarks that were not properly closed at the end of parsing.
+    #[cfg(feature = "parallel")]
+    pub fn extend_from_html_parallel_with(
+        &mut self,
+        html: &str,
+        options: &ReadOptions,
+    ) -> Result<(), Error> {
+        self.extend_from_html_parallel_with_report(html, options)?;
+        Ok(())
+    }
+
+    /// Like [`Collection::extend_from_html_parallel_with`], but returns the anomalies found in
+    /// the input, in document order, as in [`Collection::extend_from_html_with_report`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTML is malformed or contains invalid bookmark data. The collection
+    /// is unchanged if so.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are pending bookmarks that were not properly closed at the end of parsing.
+    #[cfg(feature = "parallel")]
+    pub fn extend_from_html_parallel_with_report(
+        &mut self,
+        html: &str,
+        options: &ReadOptions,
+    ) -> Result<Vec<Anomaly>, Error> {
+        use rayon::prelude::*;
+
+        let Some(groups) = split_groups(html) else {
+            return self.extend_from_html_with_report(html, options);
+        };
+        let parsed = groups
+            .par_iter()
+            .map(|(dt_offset, group)| parse_entities(group, options, *dt_offset))
+            .collect::<Result<Vec<_>, Error>>()?;
+        let (entities, anomalies): (Vec<Vec<Entity>>, Vec<_>) = parsed.into_iter().unzip();
+        options
+            .limits
+            .check_entities(entities.iter().map(Vec::len).sum())?;
+        collect(self, entities.into_iter().flatten());
+        Ok(anomalies.into_iter().flatten().collect())
+    }
+
     /// Writes the collection as a Netscape bookmark HTML file.
     ///
     /// # Errors
     ///
     /// Returns an error if template rendering fails or writing to the output fails.
-    pub fn to_html(&self, mut writer: impl Write) -> Result<(), Error> {
+    pub fn to_html(&self, writer: impl Write) -> Result<(), Error> {
+        self.to_html_with_options(writer, &Options::default())
+    }
+
+    /// Writes the collection as a Netscape bookmark HTML file, using the given options. Pinned
+    /// bookmarks are listed first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if template rendering fails or writing to the output fails.
+    pub fn to_html_with_options(
+        &self,
+        mut writer: impl Write,
+        options: &Options,
+    ) -> Result<(), Error> {
         const TEMPLATE: &str = include_str!("html/netscape_bookmarks.jinja");
         let mut env = Environment::new();
         env.add_template("netscape", TEMPLATE)?;
-        let entities = self.entities();
+        let pinned_first: Vec<Entity>;
+        let mut entities = self.entities();
+        let mut order: Vec<usize> = (0..entities.len()).collect();
+        if entities.iter().any(Entity::is_pinned) {
+            order.sort_by_key(|&index| !entities[index].is_pinned());
+            pinned_first = order.iter().map(|&index| entities[index].clone()).collect();
+            entities = &pinned_first;
+        }
+        let anchors: Option<Vec<String>> =
+            (options.anchors || options.label_index).then(|| entities.iter().map(anchor).collect());
+        let index = match &anchors {
+            Some(anchors) if options.label_index => {
+                Some(label_index(entities, anchors, &options.label_meta))
+            }
+            _ => None,
+        };
+        let all_labels: BTreeSet<&Label>;
+        let toc = options.layout == Layout::Toc;
+        let folders = if toc && options.folders.is_empty() {
+            all_labels = entities.iter().flat_map(Entity::labels).collect();
+            Some(folders(entities, all_labels, &options.label_meta))
+        } else {
+            (!options.folders.is_empty())
+                .then(|| folders(entities, &options.folders, &options.label_meta))
+        };
+        let unfiled: Option<Vec<usize>> = match &folders {
+            Some(folders) if toc => {
+                let filed: BTreeSet<usize> = folders
+                    .iter()
+                    .flat_map(|folder| folder.entities.iter().copied())
+                    .collect();
+                Some(
+                    (0..entities.len())
+                        .filter(|index| !filed.contains(index))
+                        .collect(),
+                )
+            }
+            _ => None,
+        };
+        let tree = (options.layout == Layout::Nest).then(|| tree(self, &order));
         let template = env.get_template("netscape")?;
-        template.render_captured_to(context! { entities }, &mut writer)?;
+        template.render_captured_to(
+            context! { entities, anchors, index, folders, toc, unfiled, tree },
+            &mut writer,
+        )?;
         writer.write_all(b"\n")?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        Anomaly,
+        collection::Collection,
+        entity::{Entity, Label, Name, Profile, RAW_EXTRA, Time, Url},
+    };
+
+    use crate::{
+        label_meta::LabelInfo,
+        limits::{self, Limits},
+    };
+
+    use super::{Error, Layout, Options, Position, ReadOptions, anchor, fnv1a};
+
+    #[test]
+    fn anchor_is_stable() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let entity = Entity::new(url, Time::default(), None, BTreeSet::new());
+        assert_eq!(anchor(&entity), anchor(&entity.clone()));
+        assert_eq!(
+            anchor(&entity),
+            format!("hbt-{:016x}", fnv1a("https://example.com/"))
+        );
+    }
+
+    #[test]
+    fn anchors_differ_between_profiles() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let mut coll = Collection::new();
+        for maybe_profile in [None, Some("work")] {
+            let mut entity = Entity::new(url.clone(), Time::default(), None, BTreeSet::new());
+            entity.set_profile(maybe_profile.map(Profile::from));
+            coll.insert(entity);
+        }
+
+        let options = Options {
+            anchors: true,
+            ..Options::default()
+        };
+        let mut output = Vec::new();
+        coll.to_html_with_options(&mut output, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let anchors: BTreeSet<String> = coll.entities().iter().map(anchor).collect();
+        assert_eq!(anchors.len(), 2);
+        for anchor in anchors {
+            assert_eq!(output.matches(&format!("ID=\"{anchor}\"")).count(), 1);
+        }
+    }
+
+    #[test]
+    fn label_index_links_to_anchors() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let labels = BTreeSet::from([Label::from("rust")]);
+        let mut coll = Collection::new();
+        coll.insert(Entity::new(url.clone(), Time::default(), None, labels));
+
+        let options = Options {
+            anchors: false,
+            label_index: true,
+            ..Options::default()
+        };
+        let mut output = Vec::new();
+        coll.to_html_with_options(&mut output, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let anchor = anchor(&coll.entities()[0]);
+        assert!(output.contains(&format!("ID=\"{anchor}\"")));
+        assert!(output.contains(&format!("<LI>rust: <A HREF=\"#{anchor}\">")));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_parse_matches_sequential() {
+        let html = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><A HREF="https://example.com/" ADD_DATE="1">Root</A>
+    <DD>Root description
+    <DT><H3>Folder</H3>
+    <DL><p>
+        <DT><A HREF="https://example.com/a" ADD_DATE="2">A</A>
+        <DT><H3>Nested</H3>
+        <dl><p>
+            <DT><A HREF="https://example.com/" ADD_DATE="3">Root again</A>
+        </dl><p>
+    </DL><p>
+    <DT><A HREF="https://example.com/b" ADD_DATE="4">B</A>
+</DL><p>
+"#;
+        let groups = super::split_groups(html).unwrap();
+        assert_eq!(groups.len(), 3);
+
+        let sequential = Collection::from_html(html).unwrap();
+        let parallel = Collection::from_html_parallel(html).unwrap();
+        assert_eq!(parallel, sequential);
+        assert_eq!(parallel.len(), 3);
+    }
+
+    #[test]
+    fn label_index_renders_label_meta() {
+        let url = Url::parse("https://example.com/").unwrap();
+        let labels = BTreeSet::from([Label::from("rust")]);
+        let mut coll = Collection::new();
+        coll.insert(Entity::new(url, Time::default(), None, labels));
+
+        let label_meta = [(
+            Label::from("rust"),
+            LabelInfo {
+                color: Some("#dea584".to_string()),
+                emoji: Some("🦀".to_string()),
+                description: Some("Rust".to_string()),
+            },
+        )]
+        .into_iter()
+        .collect();
+        let options = Options {
+            label_index: true,
+            label_meta,
+            ..Options::default()
+        };
+        let mut output = Vec::new();
+        coll.to_html_with_options(&mut output, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(
+            output.contains(r#"<LI TITLE="Rust"><SPAN STYLE="color: #dea584">🦀 rust</SPAN>:"#)
+        );
+    }
+
+    #[test]
+    fn folders_group_labels() {
+        let mut coll = Collection::new();
+        for (url, labels) in [
+            ("https://example.com/a", &["rust"][..]),
+            ("https://example.com/b", &["rust", "web"][..]),
+        ] {
+            let url = Url::parse(url).unwrap();
+            let labels = labels.iter().copied().map(Label::from).collect();
+            coll.insert(Entity::new(url, Time::default(), None, labels));
+        }
+
+        let options = Options {
+            folders: vec![Label::from("web"), Label::from("rust")],
+            ..Options::default()
+        };
+        let mut output = Vec::new();
+        coll.to_html_with_options(&mut output, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let web = output.find("<DT><H3>web</H3>").unwrap();
+        let rust = output.find("<DT><H3>rust</H3>").unwrap();
+        assert!(web < rust);
+        assert_eq!(output.matches("HREF=\"https://example.com/b\"").count(), 2);
+        assert_eq!(Collection::from_html(&output).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn nest_renders_edges_as_folders() {
+        let mut coll = Collection::new();
+        let ids: Vec<_> = ["parent", "child", "grandchild", "other"]
+            .into_iter()
+            .map(|name| {
+                let url = Url::parse(&format!("https://example.com/{name}")).unwrap();
+                let name = Name::from(name);
+                coll.insert(Entity::new(
+                    url,
+                    Time::default(),
+                    Some(name),
+                    BTreeSet::new(),
+                ))
+            })
+            .collect();
+        coll.add_edges(&ids[0], &ids[1]);
+        coll.add_edges(&ids[1], &ids[2]);
+
+        let options = Options {
+            layout: Layout::Nest,
+            ..Options::default()
+        };
+        let mut output = Vec::new();
+        coll.to_html_with_options(&mut output, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains(
+            "    <DT><H3>parent</H3>\n    <DL><p>\n        <DT><A HREF=\"https://example.com/parent\""
+        ));
+        assert!(output.contains(
+            "        <DT><H3>child</H3>\n        <DL><p>\n            <DT><A HREF=\"https://example.com/child\""
+        ));
+        assert!(output.contains("            <DT><A HREF=\"https://example.com/grandchild\""));
+        assert!(output.contains("\n    <DT><A HREF=\"https://example.com/other\""));
+
+        let parsed = Collection::from_html(&output).unwrap();
+        assert_eq!(parsed.len(), 4);
+        let url = Url::parse("https://example.com/grandchild").unwrap();
+        let labels = parsed.entity(&parsed.id(&url).unwrap()).labels();
+        assert!(labels.contains(&Label::from("parent")) && labels.contains(&Label::from("child")));
+    }
+
+    #[test]
+    fn toc_links_to_collapsible_sections() {
+        let mut coll = Collection::new();
+        for (url, labels) in [
+            ("https://example.com/a", &["rust"][..]),
+            ("https://example.com/b", &["rust", "web"][..]),
+            ("https://example.com/c", &[][..]),
+        ] {
+            let url = Url::parse(url).unwrap();
+            let labels = labels.iter().copied().map(Label::from).collect();
+            coll.insert(Entity::new(url, Time::default(), None, labels));
+        }
+
+        let label_meta = [(
+            Label::from("rust"),
+            LabelInfo {
+                color: Some("#dea584".to_string()),
+                ..LabelInfo::default()
+            },
+        )]
+        .into_iter()
+        .collect();
+        let options = Options {
+            layout: Layout::Toc,
+            label_meta,
+            ..Options::default()
+        };
+        let mut output = Vec::new();
+        coll.to_html_with_options(&mut output, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let rust = super::label_anchor(&Label::from("rust"));
+        assert!(output.contains(&format!(
+            "<LI><A HREF=\"#{rust}\" STYLE=\"color: #dea584\">rust</A> (2)"
+        )));
+        assert!(output.contains(&format!(
+            "<DT><DETAILS ID=\"{rust}\" OPEN STYLE=\"border-left: 4px solid #dea584; padding-left: 1em\">"
+        )));
+        assert!(output.contains("<H3 STYLE=\"display: inline\">web</H3> (1)</SUMMARY>"));
+
+        let parsed = Collection::from_html(&output).unwrap();
+        assert_eq!(parsed.len(), 3);
+        let labels = |url: &str| {
+            let url = Url::parse(url).unwrap();
+            let id = parsed.id(&url).unwrap();
+            parsed.entity(&id).labels().clone()
+        };
+        assert_eq!(
+            labels("https://example.com/a"),
+            BTreeSet::from([Label::from("rust")])
+        );
+        assert!(labels("https://example.com/c").is_empty());
+    }
+
+    #[test]
+    fn icons_roundtrip() {
+        let html = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<DL><p>
+    <DT><A HREF="https://example.com/" ADD_DATE="0" ICON_URI="https://example.com/favicon.ico" ICON="data:image/png;base64,iVBORw0KGgo=">Example</A>
+</DL><p>
+"#;
+        let coll = Collection::from_html(html).unwrap();
+        let entity = &coll.entities()[0];
+        assert_eq!(entity.icon_uri(), Some("https://example.com/favicon.ico"));
+        assert_eq!(entity.icon(), Some("data:image/png;base64,iVBORw0KGgo="));
+
+        let mut output = Vec::new();
+        coll.to_html(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(Collection::from_html(&output).unwrap(), coll);
+    }
+
+    #[test]
+    fn raw_anchor_is_kept() {
+        let html = r#"<DL><p>
+    <DT><A HREF="https://example.com/" ADD_DATE="1" LAST_VISIT="0">Example</A>
+</DL><p>
+"#;
+        let options = ReadOptions {
+            keep_raw: true,
+            ..ReadOptions::default()
+        };
+        let mut coll = Collection::new();
+        coll.extend_from_html_with(html, &options).unwrap();
+        let raw = &coll.entities()[0].extras()[RAW_EXTRA];
+        assert!(raw.starts_with("<a "), "{raw}");
+        assert!(raw.contains(r#"last_visit="0""#), "{raw}");
+    }
+
+    #[test]
+    fn toolbar_bookmarks_are_pinned_and_listed_first() {
+        let html = r#"<DL><p>
+    <DT><A HREF="https://example.com/a" ADD_DATE="1">A</A>
+    <DT><H3 PERSONAL_TOOLBAR_FOLDER="true">Bookmarks bar</H3>
+    <DL><p>
+        <DT><A HREF="https://example.com/b" ADD_DATE="2">B</A>
+    </DL><p>
+    <DT><A HREF="https://example.com/c" ADD_DATE="3">C</A>
+</DL><p>
+"#;
+        let coll = Collection::from_html(html).unwrap();
+        let pinned: Vec<bool> = coll.entities().iter().map(Entity::is_pinned).collect();
+        assert_eq!(pinned, [false, true, false]);
+
+        let mut output = Vec::new();
+        coll.to_html(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let position = |url: &str| output.find(url).unwrap();
+        assert!(position("https://example.com/b") < position("https://example.com/a"));
+        assert!(output.contains(r#"PINNED="true""#));
+
+        let reparsed = Collection::from_html(&output).unwrap();
+        assert_eq!(reparsed.pinned().len(), 1);
+    }
+
+    #[test]
+    fn errors_locate_the_bookmark() {
+        let html = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<DL><p>
+    <DT><A HREF="https://example.com/" ADD_DATE="1">Example</A>
+    <DT><H3>Folder</H3>
+    <DL><p>
+        <DT><A HREF="https://example.com/bad" ADD_DATE="yesterday">Bad</A>
+    </DL><p>
+</DL><p>
+"#;
+        let expected = Position {
+            index: 3,
+            maybe_href: Some("https://example.com/bad".to_string()),
+        };
+        let Err(Error::Bookmark(position, _)) = Collection::from_html(html) else {
+            panic!("expected a bookmark error");
+        };
+        assert_eq!(position, expected);
+        assert_eq!(position.to_string(), "<DT> #3 (https://example.com/bad)");
+
+        #[cfg(feature = "parallel")]
+        {
+            let Err(Error::Bookmark(position, _)) = Collection::from_html_parallel(html) else {
+                panic!("expected a bookmark error");
+            };
+            assert_eq!(position, expected);
+        }
+    }
+
+    #[test]
+    fn anomalies_are_reported() {
+        let html = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<DL><p>
+    <DT><A HREF="https://example.com/" ADD_DATE="1700000000000" LAST_CHARSET="UTF-8">Example</A>
+    <DT><HR>
+</DL><p>
+"#;
+        let mut coll = Collection::new();
+        let anomalies = coll
+            .extend_from_html_with_report(html, &ReadOptions::default())
+            .unwrap();
+        let position = "<DT> #1 (https://example.com/)".to_string();
+        assert_eq!(
+            anomalies,
+            [
+                Anomaly::CoercedTimestamp {
+                    position: position.clone(),
+                    attribute: "add_date".to_string(),
+                    value: "1700000000000".to_string(),
+                    unit: "milliseconds".to_string(),
+                },
+                Anomaly::UnknownAttribute {
+                    position,
+                    attribute: "last_charset".to_string(),
+                },
+                Anomaly::Skipped {
+                    position: "<DT> #2".to_string(),
+                },
+            ]
+        );
+        assert_eq!(coll.len(), 1);
+    }
+
+    #[test]
+    fn nesting_deeper_than_the_limit_is_rejected() {
+        let html = format!(
+            "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n{}<DT><A HREF=\"https://example.com/\">Deep</A>{}",
+            "<DL><p><DT><H3>Folder</H3>\n".repeat(3),
+            "</DL><p>\n".repeat(3),
+        );
+        let options = |max_depth| ReadOptions {
+            limits: Limits {
+                max_depth,
+                ..Limits::default()
+            },
+            ..ReadOptions::default()
+        };
+
+        let mut coll = Collection::new();
+        coll.extend_from_html_with(&html, &options(3)).unwrap();
+        assert_eq!(coll.len(), 1);
+
+        let mut coll = Collection::new();
+        assert!(matches!(
+            coll.extend_from_html_with(&html, &options(2)),
+            Err(Error::Limit(limits::Error::Depth(2)))
+        ));
+    }
+}
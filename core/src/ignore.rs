@@ -0,0 +1,191 @@
+use std::str::FromStr;
+
+use regex::Regex;
+use thiserror::Error;
+
+use crate::{collection::Collection, entity::Url};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid pattern on line {1}: {0}")]
+    Regex(#[source] regex::Error, usize),
+}
+
+const COMMENT_PREFIX: char = '#';
+const REGEX_PREFIX: &str = "re:";
+
+/// A single ignore pattern.
+#[derive(Debug, Clone)]
+pub enum Rule {
+    /// Matches a host and all of its subdomains.
+    Domain(String),
+    /// Matches the URL without its scheme, e.g. `www.google.com/search?q=x`, against a glob in
+    /// which `*` matches any run of characters and `?` matches a single character.
+    Glob(Regex),
+    /// Matches anywhere in the full URL.
+    Regex(Regex),
+}
+
+fn glob_to_regex(glob: &str) -> Result<Regex, regex::Error> {
+    let mut pattern = String::with_capacity(glob.len() + 2);
+    pattern.push('^');
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern)
+}
+
+impl Rule {
+    #[must_use]
+    pub fn is_match(&self, url: &Url) -> bool {
+        let url = url.as_ref();
+        match self {
+            Rule::Domain(domain) => url.host_str().is_some_and(|host| {
+                host == domain
+                    || host
+                        .strip_suffix(domain.as_str())
+                        .is_some_and(|sub| sub.ends_with('.'))
+            }),
+            Rule::Glob(glob) => glob.is_match(&url[url::Position::BeforeHost..]),
+            Rule::Regex(regex) => regex.is_match(url.as_str()),
+        }
+    }
+}
+
+/// A list of URL patterns to exclude from a collection.
+///
+/// Ignore files contain one pattern per line. Blank lines and lines starting with `#` are
+/// skipped. A line starting with `re:` is a regular expression matched against the full URL; a
+/// line containing `/`, `*` or `?` is a glob matched against the URL without its scheme; any other
+/// line is a domain, which also matches its subdomains:
+///
+/// ```text
+/// # Search results
+/// *.google.com/search*
+/// re:[?&]utm_source=
+/// doubleclick.net
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct IgnoreList {
+    rules: Vec<Rule>,
+}
+
+impl IgnoreList {
+    #[must_use]
+    pub fn new(rules: Vec<Rule>) -> IgnoreList {
+        IgnoreList { rules }
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    #[must_use]
+    pub fn is_match(&self, url: &Url) -> bool {
+        self.rules.iter().any(|rule| rule.is_match(url))
+    }
+}
+
+impl FromStr for IgnoreList {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<IgnoreList, Error> {
+        let mut rules = Vec::new();
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(COMMENT_PREFIX) {
+                continue;
+            }
+            let rule = if let Some(pattern) = line.strip_prefix(REGEX_PREFIX) {
+                Rule::Regex(Regex::new(pattern.trim()).map_err(|err| Error::Regex(err, index + 1))?)
+            } else if line.contains(['/', '*', '?']) {
+                Rule::Glob(glob_to_regex(line).map_err(|err| Error::Regex(err, index + 1))?)
+            } else {
+                Rule::Domain(line.to_lowercase())
+            };
+            rules.push(rule);
+        }
+        Ok(IgnoreList { rules })
+    }
+}
+
+impl Collection {
+    /// Removes the entities whose URLs match `ignore`, returning the number removed.
+    ///
+    /// See [`Collection::retain`].
+    pub fn remove_matching(&mut self, ignore: &IgnoreList) -> usize {
+        if ignore.is_empty() {
+            return 0;
+        }
+        self.retain(|entity| !ignore.is_match(entity.url()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Time, Url},
+    };
+
+    use super::IgnoreList;
+
+    const IGNORE: &str = "
+# comment
+*.google.com/search*
+re:[?&]utm_source=
+doubleclick.net
+";
+
+    fn is_match(ignore: &IgnoreList, url: &str) -> bool {
+        ignore.is_match(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn patterns_match() {
+        let ignore: IgnoreList = IGNORE.parse().unwrap();
+        assert!(is_match(&ignore, "https://www.google.com/search?q=rust"));
+        assert!(!is_match(&ignore, "https://www.google.com/maps"));
+        assert!(is_match(&ignore, "https://example.com/?utm_source=x"));
+        assert!(is_match(&ignore, "https://doubleclick.net/"));
+        assert!(is_match(&ignore, "https://ads.doubleclick.net/x"));
+        assert!(!is_match(&ignore, "https://notdoubleclick.net/"));
+    }
+
+    #[test]
+    fn invalid_regex_reports_line() {
+        let err = "ok.com\nre:(".parse::<IgnoreList>().unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn remove_matching_keeps_edges() {
+        let time = Time::from_timestamp(0).unwrap();
+        let mut coll = Collection::new();
+        let mut ids = Vec::new();
+        for url in [
+            "https://a.com/",
+            "https://doubleclick.net/",
+            "https://b.com/",
+        ] {
+            let entity = Entity::new(Url::parse(url).unwrap(), time, None, BTreeSet::new());
+            ids.push(coll.insert(entity));
+        }
+        coll.add_edges(&ids[0], &ids[1]);
+        coll.add_edges(&ids[0], &ids[2]);
+
+        let ignore: IgnoreList = IGNORE.parse().unwrap();
+        assert_eq!(coll.remove_matching(&ignore), 1);
+        assert_eq!(coll.len(), 2);
+        let a = coll.id(&Url::parse("https://a.com/").unwrap()).unwrap();
+        assert_eq!(coll.edges(&a).len(), 1);
+    }
+}
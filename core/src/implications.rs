@@ -0,0 +1,209 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    str::FromStr,
+};
+
+use thiserror::Error;
+
+use crate::{
+    collection::Collection,
+    entity::{Label, Url},
+    ignore::Rule,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid rule on line {0}: expected `<label> => <label>, ...`")]
+    InvalidRule(usize),
+
+    #[error("cyclic implication: {0}")]
+    Cycle(String),
+}
+
+const COMMENT_PREFIX: char = '#';
+const ARROW: &str = "=>";
+const DOMAIN_PREFIX: &str = "domain:";
+
+/// Rules under which labels, or the domain of a URL, imply other labels.
+///
+/// Rules files contain one rule per line. Blank lines and lines starting with `#` are skipped. A
+/// rule's left-hand side is a label, or `domain:` followed by a host (which also matches its
+/// subdomains), and its right-hand side is a comma-separated list of labels:
+///
+/// ```text
+/// rust => programming
+/// domain:github.com => code
+/// lang/haskell => haskell, programming
+/// ```
+///
+/// Implications are transitive, so with the rules above, a bookmark labelled `lang/haskell` also
+/// gains `programming`. Rules that imply a label from itself, directly or transitively, are
+/// rejected.
+#[derive(Debug, Default, Clone)]
+pub struct Implications {
+    /// Every label implied by each label, transitively.
+    labels: BTreeMap<Label, BTreeSet<Label>>,
+    domains: Vec<(Rule, BTreeSet<Label>)>,
+}
+
+/// Computes the labels implied by `label`, transitively, failing on a cycle.
+fn closure(
+    rules: &BTreeMap<Label, BTreeSet<Label>>,
+    label: &Label,
+    path: &mut Vec<Label>,
+    done: &mut BTreeMap<Label, BTreeSet<Label>>,
+) -> Result<BTreeSet<Label>, Error> {
+    if let Some(implied) = done.get(label) {
+        return Ok(implied.clone());
+    }
+    if path.contains(label) {
+        path.push(label.clone());
+        let start = path.iter().position(|l| l == label).unwrap_or_default();
+        let cycle: Vec<&str> = path[start..].iter().map(Label::as_str).collect();
+        return Err(Error::Cycle(cycle.join(" => ")));
+    }
+    path.push(label.clone());
+    let mut ret = BTreeSet::new();
+    for next in rules.get(label).into_iter().flatten() {
+        ret.insert(next.clone());
+        ret.extend(closure(rules, next, path, done)?);
+    }
+    path.pop();
+    done.insert(label.clone(), ret.clone());
+    Ok(ret)
+}
+
+impl Implications {
+    /// Returns the labels implied by `labels` and `url`, excluding those already in `labels`.
+    #[must_use]
+    pub fn implied(&self, url: &Url, labels: &BTreeSet<Label>) -> BTreeSet<Label> {
+        let mut ret = BTreeSet::new();
+        for label in labels {
+            ret.extend(self.labels.get(label).into_iter().flatten().cloned());
+        }
+        for (rule, implied) in &self.domains {
+            if rule.is_match(url) {
+                for label in implied {
+                    ret.insert(label.clone());
+                    ret.extend(self.labels.get(label).into_iter().flatten().cloned());
+                }
+            }
+        }
+        ret.retain(|label| !labels.contains(label));
+        ret
+    }
+}
+
+impl FromStr for Implications {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Implications, Error> {
+        let mut rules: BTreeMap<Label, BTreeSet<Label>> = BTreeMap::new();
+        let mut domains: Vec<(Rule, BTreeSet<Label>)> = Vec::new();
+        for (index, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(COMMENT_PREFIX) {
+                continue;
+            }
+            let (lhs, rhs) = line
+                .split_once(ARROW)
+                .ok_or(Error::InvalidRule(index + 1))?;
+            let lhs = lhs.trim();
+            let implied: BTreeSet<Label> = rhs
+                .split(',')
+                .map(str::trim)
+                .filter(|label| !label.is_empty())
+                .map(Label::from)
+                .collect();
+            if lhs.is_empty() || implied.is_empty() {
+                return Err(Error::InvalidRule(index + 1));
+            }
+            if let Some(domain) = lhs.strip_prefix(DOMAIN_PREFIX) {
+                domains.push((Rule::Domain(domain.trim().to_lowercase()), implied));
+            } else {
+                rules.entry(Label::from(lhs)).or_default().extend(implied);
+            }
+        }
+
+        let mut labels = BTreeMap::new();
+        for label in rules.keys() {
+            closure(&rules, label, &mut Vec::new(), &mut labels)?;
+        }
+        Ok(Implications { labels, domains })
+    }
+}
+
+impl Collection {
+    /// Adds the labels implied by each entity's labels and URL, returning the number of entities
+    /// changed.
+    pub fn apply_implications(&mut self, implications: &Implications) -> usize {
+        let ids: Vec<_> = self.iter().map(|(id, _)| id).collect();
+        let mut changed = 0;
+        for id in ids {
+            let entity = self.entity_mut(&id);
+            let implied = implications.implied(entity.url(), entity.labels());
+            if !implied.is_empty() {
+                entity.labels_mut().extend(implied);
+                changed += 1;
+            }
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Label, Time, Url},
+    };
+
+    use super::{Error, Implications};
+
+    const RULES: &str = "
+# comment
+rust => programming
+lang/rust => rust
+domain:github.com => code
+";
+
+    fn labels(labels: &[&str]) -> BTreeSet<Label> {
+        labels.iter().copied().map(Label::from).collect()
+    }
+
+    #[test]
+    fn implications_are_transitive() {
+        let implications: Implications = RULES.parse().unwrap();
+        let mut coll = Collection::new();
+        for (url, initial) in [
+            ("https://example.com/", &["lang/rust"][..]),
+            ("https://gist.github.com/x", &[][..]),
+            ("https://example.org/", &["news"][..]),
+        ] {
+            let url = Url::parse(url).unwrap();
+            coll.insert(Entity::new(url, Time::default(), None, labels(initial)));
+        }
+
+        assert_eq!(coll.apply_implications(&implications), 2);
+        assert_eq!(
+            coll.entities()[0].labels(),
+            &labels(&["lang/rust", "programming", "rust"])
+        );
+        assert_eq!(coll.entities()[1].labels(), &labels(&["code"]));
+        assert_eq!(coll.entities()[2].labels(), &labels(&["news"]));
+    }
+
+    #[test]
+    fn cycles_are_rejected() {
+        let err = "a => b\nb => c\nc => a\n"
+            .parse::<Implications>()
+            .unwrap_err();
+        assert!(matches!(err, Error::Cycle(cycle) if cycle == "a => b => c => a"));
+        assert!(matches!(
+            "a => \n".parse::<Implications>(),
+            Err(Error::InvalidRule(1))
+        ));
+    }
+}
@@ -0,0 +1,87 @@
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    sync::{Arc, LazyLock, Mutex, PoisonError},
+};
+
+/// The strings interned so far, along with the size of the set after it was last pruned.
+struct Interner {
+    strings: HashSet<Arc<str>>,
+    pruned_len: usize,
+}
+
+static INTERNER: LazyLock<Mutex<Interner>> = LazyLock::new(|| {
+    Mutex::new(Interner {
+        strings: HashSet::new(),
+        pruned_len: 0,
+    })
+});
+
+/// The size below which the interner is never pruned.
+const MIN_PRUNE_LEN: usize = 1024;
+
+/// The size at which a thread's cache is cleared.
+const MAX_CACHE_LEN: usize = 4096;
+
+thread_local! {
+    /// The strings this thread has interned recently, so that threads parsing in parallel only
+    /// take the interner's lock for strings they have not seen.
+    static CACHE: RefCell<HashSet<Arc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// Returns a shared copy of `s`, so that equal labels share one allocation.
+///
+/// Strings no longer referenced outside the interner are dropped once the interner has doubled in
+/// size since it was last pruned. A string still held by a thread's cache survives pruning until
+/// the cache fills up and is cleared.
+pub(crate) fn intern(s: impl AsRef<str> + Into<Arc<str>>) -> Arc<str> {
+    CACHE.with_borrow_mut(|cache| {
+        if let Some(ret) = cache.get(s.as_ref()) {
+            return Arc::clone(ret);
+        }
+        if cache.len() >= MAX_CACHE_LEN {
+            cache.clear();
+        }
+        let ret = intern_shared(s);
+        cache.insert(Arc::clone(&ret));
+        ret
+    })
+}
+
+fn intern_shared(s: impl AsRef<str> + Into<Arc<str>>) -> Arc<str> {
+    let mut interner = INTERNER.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(ret) = interner.strings.get(s.as_ref()) {
+        return Arc::clone(ret);
+    }
+    if interner.strings.len() >= MIN_PRUNE_LEN.max(interner.pruned_len * 2) {
+        interner.strings.retain(|s| Arc::strong_count(s) > 1);
+        interner.pruned_len = interner.strings.len();
+    }
+    let ret: Arc<str> = s.into();
+    interner.strings.insert(Arc::clone(&ret));
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use super::intern;
+
+    #[test]
+    fn equal_strings_share_an_allocation() {
+        let a = intern("rust");
+        let b = intern(String::from("rust"));
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &intern("go")));
+    }
+
+    #[test]
+    fn threads_share_an_allocation() {
+        let a = intern("shared across threads");
+        let b = thread::spawn(|| intern("shared across threads"))
+            .join()
+            .unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+    }
+}
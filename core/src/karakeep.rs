@@ -0,0 +1,141 @@
+use std::{
+    io::{self, Write},
+    ops::Range,
+};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    collection::Collection,
+    entity::{Entity, Extended, Label, Name},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// The content of a Karakeep bookmark. Only links are written.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Content<'a> {
+    Link { url: &'a str },
+}
+
+/// A bookmark in Karakeep's (formerly Hoarder's) export format, which its importer accepts.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Bookmark<'a> {
+    /// Seconds since the epoch.
+    created_at: i64,
+    title: Option<&'a str>,
+    tags: Vec<&'a str>,
+    content: Content<'a>,
+    note: Option<String>,
+}
+
+impl<'a> From<&'a Entity> for Bookmark<'a> {
+    fn from(entity: &'a Entity) -> Bookmark<'a> {
+        let extended: Vec<&str> = entity.extended().iter().map(Extended::as_str).collect();
+        Bookmark {
+            created_at: entity.created_at().get().timestamp(),
+            title: entity.names().first().map(Name::as_str),
+            tags: entity.labels().iter().map(Label::as_str).collect(),
+            content: Content::Link {
+                url: entity.url().as_str(),
+            },
+            note: (!extended.is_empty()).then(|| extended.join("\n\n")),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Export<'a> {
+    bookmarks: Vec<Bookmark<'a>>,
+}
+
+impl Collection {
+    /// Writes the collection in Karakeep's import format: each entity as a link bookmark titled
+    /// by its first name, tagged with its labels, and noted with its extended descriptions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization or writing to the output fails.
+    pub fn to_karakeep_json(&self, writer: impl Write) -> Result<(), Error> {
+        self.to_karakeep_json_range(writer, 0..self.len())
+    }
+
+    /// Writes the entities in `range`, by index, in Karakeep's import format, so that a large
+    /// collection can be split into files small enough for the importer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization or writing to the output fails.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds.
+    pub fn to_karakeep_json_range(
+        &self,
+        mut writer: impl Write,
+        range: Range<usize>,
+    ) -> Result<(), Error> {
+        let bookmarks = self.entities()[range].iter().map(Bookmark::from).collect();
+        serde_json::to_writer(&mut writer, &Export { bookmarks })?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Extended, Label, Name, Time, Url},
+    };
+
+    #[test]
+    fn bookmarks_carry_title_tags_and_note() {
+        let mut coll = Collection::new();
+        let mut entity = Entity::new(
+            Url::parse("https://example.com/").unwrap(),
+            Time::from_timestamp(1_700_000_000).unwrap(),
+            Some(Name::from("Example")),
+            BTreeSet::from([Label::from("rust")]),
+        );
+        entity.extended_mut().push(Extended::from("A note"));
+        coll.insert(entity);
+        coll.insert(Entity::new(
+            Url::parse("https://example.org/").unwrap(),
+            Time::default(),
+            None,
+            BTreeSet::new(),
+        ));
+
+        let mut output = Vec::new();
+        coll.to_karakeep_json(&mut output).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let bookmarks = json["bookmarks"].as_array().unwrap();
+        assert_eq!(bookmarks.len(), 2);
+        assert_eq!(bookmarks[0]["createdAt"], 1_700_000_000);
+        assert_eq!(bookmarks[0]["title"], "Example");
+        assert_eq!(bookmarks[0]["tags"], serde_json::json!(["rust"]));
+        assert_eq!(bookmarks[0]["content"]["type"], "link");
+        assert_eq!(bookmarks[0]["content"]["url"], "https://example.com/");
+        assert_eq!(bookmarks[0]["note"], "A note");
+        assert!(bookmarks[1]["title"].is_null());
+        assert!(bookmarks[1]["note"].is_null());
+
+        let mut output = Vec::new();
+        coll.to_karakeep_json_range(&mut output, 1..2).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(json["bookmarks"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::{BTreeMap, btree_map};
+
+use serde::{Deserialize, Serialize};
+
+use crate::entity::Label;
+
+/// Presentation metadata for a label.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LabelInfo {
+    /// A CSS color, e.g. `#dea584` or `teal`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emoji: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// A registry of label metadata, typically read from a sidecar YAML file such as:
+///
+/// ```yaml
+/// rust:
+///   color: "#dea584"
+///   emoji: 🦀
+///   description: The Rust programming language
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LabelMeta(BTreeMap<Label, LabelInfo>);
+
+impl LabelMeta {
+    #[must_use]
+    pub fn new() -> LabelMeta {
+        LabelMeta::default()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[must_use]
+    pub fn get(&self, label: &Label) -> Option<&LabelInfo> {
+        self.0.get(label)
+    }
+
+    pub fn insert(&mut self, label: Label, info: LabelInfo) -> Option<LabelInfo> {
+        self.0.insert(label, info)
+    }
+
+    pub fn iter(&self) -> btree_map::Iter<'_, Label, LabelInfo> {
+        self.0.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a LabelMeta {
+    type Item = (&'a Label, &'a LabelInfo);
+    type IntoIter = btree_map::Iter<'a, Label, LabelInfo>;
+
+    fn into_iter(self) -> btree_map::Iter<'a, Label, LabelInfo> {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<(Label, LabelInfo)> for LabelMeta {
+    fn from_iter<I: IntoIterator<Item = (Label, LabelInfo)>>(iter: I) -> LabelMeta {
+        LabelMeta(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entity::Label;
+
+    use super::LabelMeta;
+
+    #[test]
+    fn reads_sidecar_yaml() {
+        let yaml = "rust:\n  color: \"#dea584\"\n  emoji: 🦀\nnews: {}\n";
+        let meta: LabelMeta = serde_norway::from_str(yaml).unwrap();
+        let rust = meta.get(&Label::from("rust")).unwrap();
+        assert_eq!(rust.color.as_deref(), Some("#dea584"));
+        assert_eq!(rust.emoji.as_deref(), Some("🦀"));
+        assert_eq!(rust.description, None);
+        assert!(meta.get(&Label::from("news")).is_some());
+        assert!(meta.get(&Label::from("other")).is_none());
+    }
+}
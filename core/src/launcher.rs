@@ -0,0 +1,113 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    collection::Collection,
+    entity::{Label, Name},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// An item in an Alfred script filter, which Raycast script commands and most other launchers
+/// accept as well.
+#[derive(Debug, Serialize)]
+struct Item<'a> {
+    uid: &'a str,
+    title: &'a str,
+    subtitle: &'a str,
+    /// What the launcher passes to its action, e.g. to open in a browser.
+    arg: &'a str,
+    /// The words the launcher filters on: the title, labels and URL.
+    #[serde(rename = "match")]
+    words: String,
+    autocomplete: &'a str,
+    quicklookurl: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct Items<'a> {
+    items: Vec<Item<'a>>,
+}
+
+impl Collection {
+    /// Writes the collection as launcher items in Alfred's script filter JSON format, one per
+    /// entity, titled by its first name (or URL) with the URL as subtitle and argument.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization or writing to the output fails.
+    pub fn to_launcher_json(&self, mut writer: impl Write) -> Result<(), Error> {
+        let items = self
+            .entities()
+            .iter()
+            .map(|entity| {
+                let url = entity.url().as_str();
+                let title = entity.names().first().map_or(url, Name::as_str);
+                let mut words = vec![title];
+                words.extend(entity.labels().iter().map(Label::as_str));
+                words.push(url);
+                Item {
+                    uid: url,
+                    title,
+                    subtitle: url,
+                    arg: url,
+                    words: words.join(" "),
+                    autocomplete: title,
+                    quicklookurl: url,
+                }
+            })
+            .collect();
+        serde_json::to_writer(&mut writer, &Items { items })?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Label, Name, Time, Url},
+    };
+
+    #[test]
+    fn launcher_items_carry_title_url_and_labels() {
+        let mut coll = Collection::new();
+        let url = Url::parse("https://example.com/").unwrap();
+        let labels = BTreeSet::from([Label::from("rust")]);
+        coll.insert(Entity::new(
+            url,
+            Time::default(),
+            Some(Name::from("Example")),
+            labels,
+        ));
+        coll.insert(Entity::new(
+            Url::parse("https://example.org/").unwrap(),
+            Time::default(),
+            None,
+            BTreeSet::new(),
+        ));
+
+        let mut output = Vec::new();
+        coll.to_launcher_json(&mut output).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let items = json["items"].as_array().unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0]["title"], "Example");
+        assert_eq!(items[0]["subtitle"], "https://example.com/");
+        assert_eq!(items[0]["arg"], "https://example.com/");
+        assert_eq!(items[0]["match"], "Example rust https://example.com/");
+        assert_eq!(items[1]["title"], "https://example.org/");
+    }
+}
@@ -2,24 +2,83 @@
 #![warn(clippy::pedantic)]
 #![deny(clippy::unwrap_in_result)]
 
+pub mod analysis;
+#[cfg(feature = "async")]
+pub mod async_io;
+pub mod badge;
+#[cfg(feature = "bundle")]
+pub mod bundle;
 pub mod collection;
+pub mod date_labels;
+pub mod diff;
+pub mod domain;
+pub mod embedding;
+pub mod encoding;
 pub mod entity;
+pub mod features;
+pub mod graph;
+pub mod hn;
 pub mod html;
+pub mod ignore;
+pub mod implications;
+mod intern;
+pub mod karakeep;
+pub mod label_meta;
+pub mod launcher;
+pub mod limits;
+pub mod lines;
+pub mod locale;
+pub mod mappings;
 pub mod markdown;
+#[cfg(feature = "net")]
+pub mod net;
+pub mod normalize;
+pub mod notes;
+pub mod obsidian;
+pub mod prelude;
+pub mod redact;
+pub mod reddit;
+pub mod replace;
+pub mod rewrite;
+pub mod search;
+pub mod shortcuts;
+#[cfg(feature = "snapshot")]
+pub mod snapshot;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod static_site;
+pub mod sync;
+pub mod synonyms;
+pub mod transform;
+pub mod txt;
 
 use std::{
+    fmt,
     io::{self, BufRead, Write},
     path::Path,
 };
 
+use chrono::NaiveDate;
 #[cfg(feature = "clap")]
 use clap::{ValueEnum, builder::PossibleValue};
 
+use schemars::JsonSchema;
+use serde::Serialize;
 use strum::{IntoStaticStr, VariantArray};
 
-use hbt_pinboard::{self, Post};
+use hbt_pinboard::Post;
 
-use crate::collection::Collection;
+use crate::{
+    collection::{CollectionRepr, Duplicate, DuplicatePolicy, IdScheme, ReprOptions},
+    entity::{MergeStrategies, PrivacyPolicy, RAW_EXTRA, RawUrl, Time, UrlPolicy},
+};
+
+pub use hbt_pinboard;
+
+pub use crate::{
+    collection::Collection,
+    entity::{Entity, Label},
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum ParseError {
@@ -37,6 +96,24 @@ pub enum ParseError {
 
     #[error(transparent)]
     Pinboard(#[from] hbt_pinboard::Error),
+
+    #[error(transparent)]
+    HackerNews(#[from] hn::Error),
+
+    #[error(transparent)]
+    Reddit(#[from] reddit::Error),
+
+    #[error(transparent)]
+    Notes(#[from] notes::Error),
+
+    #[error(transparent)]
+    Txt(#[from] txt::Error),
+
+    #[error(transparent)]
+    Lines(#[from] lines::Error),
+
+    #[error(transparent)]
+    Limit(#[from] limits::Error),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr, VariantArray)]
@@ -47,6 +124,12 @@ pub enum InputFormat {
     #[strum(serialize = "md")]
     Markdown,
     Html,
+    #[strum(serialize = "hn")]
+    HackerNews,
+    Reddit,
+    Txt,
+    /// One JSON object per entity, as written by [`OutputFormat::Lines`].
+    Lines,
 }
 
 impl InputFormat {
@@ -56,6 +139,8 @@ impl InputFormat {
             "xml" => Some(InputFormat::Xml),
             "md" => Some(InputFormat::Markdown),
             "html" => Some(InputFormat::Html),
+            "txt" => Some(InputFormat::Txt),
+            "jsonl" => Some(InputFormat::Lines),
             _ => None,
         }
     }
@@ -66,29 +151,259 @@ impl InputFormat {
     ///
     /// Returns an error if the input is malformed or cannot be parsed according to the format specification.
     pub fn parse(&self, reader: &mut impl BufRead) -> Result<Collection, ParseError> {
+        self.parse_with_options(reader, &ParseOptions::default())
+    }
+
+    /// Parses input in the specified format into a collection, using the given options.
+    ///
+    /// A leading UTF-8 byte order mark is skipped. With the `encoding` feature, UTF-16 input with a
+    /// byte order mark and Windows-1252 input are transcoded first; see [`encoding::decode`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input is malformed or cannot be parsed according to the format specification.
+    pub fn parse_with_options(
+        &self,
+        reader: &mut impl BufRead,
+        options: &ParseOptions,
+    ) -> Result<Collection, ParseError> {
+        self.parse_reporting(reader, options, &mut Vec::new())
+    }
+
+    /// Like [`InputFormat::parse_with_options`], but also returns a report of the non-fatal
+    /// anomalies in the input: merged duplicates, kept invalid URLs, records skipped for not being
+    /// bookmarks (in HTML, Pinboard, Hacker News and Reddit input) and, in HTML input, converted
+    /// timestamps and attributes that are not read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input is malformed or cannot be parsed according to the format specification.
+    pub fn parse_with_report(
+        &self,
+        reader: &mut impl BufRead,
+        options: &ParseOptions,
+    ) -> Result<(Collection, ParseReport), ParseError> {
+        let mut anomalies = Vec::new();
+        let coll = self.parse_reporting(reader, options, &mut anomalies)?;
+        let mut report = ParseReport::from(&coll);
+        report.anomalies = anomalies;
+        Ok((coll, report))
+    }
+
+    fn parse_reporting(
+        self,
+        reader: &mut impl BufRead,
+        options: &ParseOptions,
+        anomalies: &mut Vec<Anomaly>,
+    ) -> Result<Collection, ParseError> {
+        #[cfg(feature = "encoding")]
+        let mut coll = {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+            self.parse_collection(&mut encoding::decode(&bytes).as_bytes(), options, anomalies)?
+        };
+        #[cfg(not(feature = "encoding"))]
+        let mut coll = {
+            encoding::skip_bom(reader)?;
+            self.parse_collection(reader, options, anomalies)?
+        };
+        if matches!(
+            self,
+            InputFormat::Json | InputFormat::HackerNews | InputFormat::Reddit | InputFormat::Lines
+        ) {
+            options.limits.check_collection(&coll)?;
+        }
+        coll.set_privacy_policy(options.privacy_policy);
+        coll.set_merge_strategies(options.merge_strategies);
+        coll.remove_matching(&options.ignore);
+        Ok(coll)
+    }
+
+    fn parse_collection(
+        self,
+        reader: &mut impl BufRead,
+        options: &ParseOptions,
+        anomalies: &mut Vec<Anomaly>,
+    ) -> Result<Collection, ParseError> {
         match self {
             InputFormat::Json => {
-                let posts = Post::from_json(reader)?;
-                Collection::from_posts(posts).map_err(Into::into)
-            }
-            InputFormat::Xml => {
-                let posts = Post::from_xml(reader)?;
-                Collection::from_posts(posts).map_err(Into::into)
+                if options.keep_raw {
+                    return parse_json_keeping_raw(reader, options, anomalies);
+                }
+                let mut posts = Post::from_json(reader)?;
+                let mut index = 0;
+                posts.retain(|post| {
+                    index += 1;
+                    !skip_post(index, &post.href, anomalies)
+                });
+                for post in &mut posts {
+                    options.url_policy.prepare(&mut post.href);
+                }
+                Collection::from_posts_with(
+                    posts,
+                    options.duplicate_policy,
+                    options.privacy_policy,
+                    options.merge_strategies,
+                )
+                .map_err(Into::into)
             }
+            InputFormat::Xml => parse_xml(reader, options, anomalies),
             InputFormat::Markdown => {
                 let mut buf = String::new();
                 reader.read_to_string(&mut buf)?;
-                Collection::from_markdown(&buf).map_err(Into::into)
+                let mut coll = Collection::new();
+                coll.set_privacy_policy(options.privacy_policy);
+                coll.set_merge_strategies(options.merge_strategies);
+                let markdown_options = markdown::Options {
+                    url_policy: options.url_policy,
+                    default_date: options.default_date,
+                    keep_raw: options.keep_raw,
+                    limits: options.limits,
+                    locale: options.locale,
+                };
+                coll.extend_from_markdown_with(&buf, &markdown_options)?;
+                Ok(coll)
             }
             InputFormat::Html => {
                 let mut buf = String::new();
                 reader.read_to_string(&mut buf)?;
-                Collection::from_html(&buf).map_err(Into::into)
+                let mut coll = Collection::new();
+                coll.set_privacy_policy(options.privacy_policy);
+                coll.set_merge_strategies(options.merge_strategies);
+                let html_options = html::ReadOptions {
+                    url_policy: options.url_policy,
+                    keep_raw: options.keep_raw,
+                    limits: options.limits,
+                };
+                #[cfg(feature = "parallel")]
+                anomalies.extend(coll.extend_from_html_parallel_with_report(&buf, &html_options)?);
+                #[cfg(not(feature = "parallel"))]
+                anomalies.extend(coll.extend_from_html_with_report(&buf, &html_options)?);
+                Ok(coll)
+            }
+            InputFormat::HackerNews => {
+                let (entities, skipped) = hn::entities(reader)?;
+                anomalies.extend(skipped);
+                Ok(merged(entities, options))
+            }
+            InputFormat::Reddit => {
+                let mut buf = String::new();
+                reader.read_to_string(&mut buf)?;
+                let (entities, skipped) = reddit::entities(&buf)?;
+                anomalies.extend(skipped);
+                Ok(merged(entities, options))
+            }
+            InputFormat::Txt => {
+                let mut buf = String::new();
+                reader.read_to_string(&mut buf)?;
+                let mut coll = Collection::new();
+                coll.set_privacy_policy(options.privacy_policy);
+                coll.set_merge_strategies(options.merge_strategies);
+                let txt_options = txt::Options {
+                    url_policy: options.url_policy,
+                    default_date: options.default_date,
+                    keep_raw: options.keep_raw,
+                    limits: options.limits,
+                };
+                coll.extend_from_txt(&buf, &txt_options)?;
+                Ok(coll)
             }
+            InputFormat::Lines => Collection::from_lines(reader).map_err(Into::into),
         }
     }
 }
 
+/// Creates a collection from `entities`, merging any that share a URL with the privacy policy and
+/// merge strategies of `options`.
+fn merged(entities: Vec<Entity>, options: &ParseOptions) -> Collection {
+    let mut coll = Collection::with_capacity(entities.len());
+    coll.set_privacy_policy(options.privacy_policy);
+    coll.set_merge_strategies(options.merge_strategies);
+    for entity in entities {
+        coll.upsert(entity);
+    }
+    coll
+}
+
+/// Returns whether the `index`th Pinboard post, counting from 1, has no URL and is skipped,
+/// recording it in `anomalies` if so.
+fn skip_post(index: usize, href: &str, anomalies: &mut Vec<Anomaly>) -> bool {
+    let skip = href.trim().is_empty();
+    if skip {
+        anomalies.push(Anomaly::Skipped {
+            position: format!("post #{index}"),
+        });
+    }
+    skip
+}
+
+/// Parses Pinboard XML, checking limits on each post as it is read.
+fn parse_xml(
+    reader: &mut impl BufRead,
+    options: &ParseOptions,
+    anomalies: &mut Vec<Anomaly>,
+) -> Result<Collection, ParseError> {
+    let mut entities = Vec::new();
+    let mut index = 0;
+    Post::for_each_xml(reader, |mut post| {
+        index += 1;
+        if skip_post(index, &post.href, anomalies) {
+            return Ok(());
+        }
+        if options.url_policy == UrlPolicy::KeepInvalid {
+            options.url_policy.prepare(post.href.to_mut());
+        }
+        options.limits.check_entities(entities.len() + 1)?;
+        let entity = if options.keep_raw {
+            let post = Post::from(post);
+            let raw = serde_json::to_string(&post).map_err(hbt_pinboard::Error::from)?;
+            let mut entity = Entity::try_from(post)?;
+            entity.extras_mut().insert(RAW_EXTRA.to_string(), raw);
+            entity
+        } else {
+            Entity::try_from(post)?
+        };
+        options.limits.check_entity(&entity)?;
+        entities.push(entity);
+        Ok::<(), ParseError>(())
+    })?;
+    Ok(Collection::from_post_entities_with(
+        entities,
+        options.duplicate_policy,
+        options.privacy_policy,
+        options.merge_strategies,
+    ))
+}
+
+/// Parses Pinboard JSON like [`Collection::from_posts_with`], recording each post's JSON object in
+/// its entity's [`RAW_EXTRA`] extra.
+fn parse_json_keeping_raw(
+    reader: &mut impl BufRead,
+    options: &ParseOptions,
+    anomalies: &mut Vec<Anomaly>,
+) -> Result<Collection, ParseError> {
+    let values: Vec<serde_json::Value> =
+        serde_json::from_reader(reader).map_err(hbt_pinboard::Error::from)?;
+    let mut entities = Vec::with_capacity(values.len());
+    for (index, value) in values.into_iter().enumerate() {
+        let raw = value.to_string();
+        let mut post: Post = serde_json::from_value(value).map_err(hbt_pinboard::Error::from)?;
+        if skip_post(index + 1, &post.href, anomalies) {
+            continue;
+        }
+        options.url_policy.prepare(&mut post.href);
+        let mut entity = Entity::try_from(post)?;
+        entity.extras_mut().insert(RAW_EXTRA.to_string(), raw);
+        entities.push(entity);
+    }
+    Ok(Collection::from_post_entities_with(
+        entities,
+        options.duplicate_policy,
+        options.privacy_policy,
+        options.merge_strategies,
+    ))
+}
+
 #[cfg(feature = "clap")]
 impl ValueEnum for InputFormat {
     fn value_variants<'a>() -> &'a [InputFormat] {
@@ -111,6 +426,191 @@ pub enum UnparseError {
 
     #[error(transparent)]
     Yaml(#[from] serde_norway::Error),
+
+    #[error(transparent)]
+    Collection(#[from] collection::Error),
+
+    #[error(transparent)]
+    Pinboard(#[from] hbt_pinboard::Error),
+
+    #[error(transparent)]
+    Launcher(#[from] launcher::Error),
+
+    #[error(transparent)]
+    Karakeep(#[from] karakeep::Error),
+
+    #[error(transparent)]
+    Lines(#[from] lines::Error),
+
+    #[error(transparent)]
+    Markdown(#[from] markdown::Error),
+
+    #[cfg(feature = "sqlite")]
+    #[error(transparent)]
+    Sqlite(#[from] sqlite::Error),
+
+    #[error("{0} output is not supported by this build")]
+    Unsupported(&'static str),
+}
+
+/// Options controlling how input is parsed.
+#[derive(Debug, Default, Clone)]
+pub struct ParseOptions {
+    /// URLs to exclude from the parsed collection.
+    pub ignore: ignore::IgnoreList,
+    /// How to merge the privacy of bookmarks that appear more than once. This is also the
+    /// policy of the parsed collection.
+    pub privacy_policy: PrivacyPolicy,
+    /// How to merge the names, labels and extended notes of bookmarks that appear more than
+    /// once. These are also the strategies of the parsed collection.
+    pub merge_strategies: MergeStrategies,
+    /// How to add Pinboard posts that share a URL.
+    pub duplicate_policy: DuplicatePolicy,
+    /// Whether to keep bookmarks with invalid URLs in Pinboard, HTML, markdown and text input,
+    /// rather than failing.
+    pub url_policy: UrlPolicy,
+    /// The date of markdown bookmarks without a date heading (see
+    /// [`markdown::date_from_file_name`]), and of every bookmark in text input. Text bookmarks are
+    /// dated now if this is `None`.
+    pub default_date: Option<NaiveDate>,
+    /// Whether to record the source record of each bookmark in its [`entity::RAW_EXTRA`] extra:
+    /// the JSON object of a Pinboard JSON post, the attributes of a Pinboard XML post (as JSON),
+    /// the `<A>` element of an HTML bookmark, or the line of a markdown link or text bookmark.
+    pub keep_raw: bool,
+    /// Bounds on nesting depth, the number of bookmarks and the length of their fields, checked
+    /// while parsing HTML, markdown, Pinboard XML and text. Other formats are only checked once
+    /// fully parsed (see [`limits::Limits`]).
+    pub limits: limits::Limits,
+    /// The language of month names in markdown date headings not in the form `November 15, 2023`,
+    /// e.g. `15 de noviembre de 2023`.
+    pub locale: Option<locale::Locale>,
+}
+
+/// A non-fatal anomaly in a record of the input, located by its position in the input (e.g.
+/// `<DT> #3 (https://example.com/)`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Anomaly {
+    /// A record that is not a bookmark, and was skipped.
+    Skipped { position: String },
+    /// A timestamp in milliseconds or microseconds, converted to seconds.
+    CoercedTimestamp {
+        position: String,
+        attribute: String,
+        value: String,
+        unit: String,
+    },
+    /// An attribute that is not read.
+    UnknownAttribute { position: String, attribute: String },
+}
+
+impl fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Anomaly::Skipped { position } => write!(f, "{position}: skipped, not a bookmark"),
+            Anomaly::CoercedTimestamp {
+                position,
+                attribute,
+                value,
+                unit,
+            } => write!(f, "{position}: read {attribute}={value} as {unit}"),
+            Anomaly::UnknownAttribute {
+                position,
+                attribute,
+            } => write!(f, "{position}: ignored unknown attribute {attribute}"),
+        }
+    }
+}
+
+/// Non-fatal anomalies found while parsing input.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct ParseReport {
+    /// URLs that occurred more than once and were merged.
+    pub duplicates: Vec<Duplicate>,
+    /// Invalid URLs kept under [`UrlPolicy::KeepInvalid`], for review.
+    pub invalid_urls: Vec<RawUrl>,
+    /// Anomalies in individual records, in input order. These are only found by
+    /// [`InputFormat::parse_with_report`].
+    pub anomalies: Vec<Anomaly>,
+}
+
+impl ParseReport {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.duplicates.is_empty() && self.invalid_urls.is_empty() && self.anomalies.is_empty()
+    }
+}
+
+impl From<&Collection> for ParseReport {
+    fn from(coll: &Collection) -> ParseReport {
+        ParseReport {
+            duplicates: coll.duplicates().cloned().collect(),
+            invalid_urls: coll
+                .entities()
+                .iter()
+                .filter_map(|entity| entity.url().to_raw())
+                .collect(),
+            anomalies: Vec::new(),
+        }
+    }
+}
+
+/// A summary of a collection, for tracking an archive over time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Info {
+    /// The input format the collection was read from, if known, e.g. `html`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    pub entities: usize,
+    /// The number of distinct labels.
+    pub labels: usize,
+    /// The number of named profiles.
+    pub profiles: usize,
+    pub edges: usize,
+    /// The earliest creation time of any entity.
+    pub earliest: Option<Time>,
+    /// The latest creation or update time of any entity.
+    pub latest: Option<Time>,
+}
+
+impl From<&Collection> for Info {
+    fn from(coll: &Collection) -> Info {
+        let entities = coll.entities();
+        Info {
+            format: None,
+            entities: entities.len(),
+            labels: coll.label_counts().len(),
+            profiles: coll.profiles().count(),
+            edges: coll.edge_count(),
+            earliest: entities
+                .iter()
+                .map(|entity| entity.created_at().get())
+                .min(),
+            latest: entities.iter().map(Entity::last_modified).max(),
+        }
+    }
+}
+
+impl Collection {
+    /// Returns a summary of the collection; see [`Info`].
+    #[must_use]
+    pub fn stats(&self) -> Info {
+        Info::from(self)
+    }
+}
+
+/// Options controlling how a collection is written.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UnparseOptions {
+    pub html: html::Options,
+    /// List the URL and name of each edge's target alongside its id in YAML output.
+    pub readable_edges: bool,
+    /// How entities are identified in YAML output.
+    pub id_scheme: IdScheme,
+    /// Split Karakeep output written to a file into files of at most this many bookmarks, named
+    /// like `archive-1.json`, for importers with size limits. Must not be zero.
+    pub karakeep_chunk_size: Option<usize>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr, VariantArray)]
@@ -118,31 +618,112 @@ pub enum UnparseError {
 pub enum OutputFormat {
     Html,
     Yaml,
+    Sqlite,
+    /// Pinboard's JSON export, which Pinboard can import.
+    #[strum(serialize = "pinboard-json")]
+    PinboardJson,
+    /// Pinboard's XML export, which Pinboard can import.
+    #[strum(serialize = "pinboard-xml")]
+    PinboardXml,
+    /// Alfred script filter JSON, for feeding bookmarks to a launcher such as Alfred or Raycast.
+    Launcher,
+    /// Karakeep's (formerly Hoarder's) JSON export, which Karakeep can import.
+    Karakeep,
+    /// One JSON object per entity, sorted by URL, for archives tracked in version control.
+    Lines,
+    /// Markdown in the form read by [`InputFormat::Markdown`].
+    Markdown,
 }
 
 impl OutputFormat {
+    /// Detects the output format from the extension of `path`.
+    ///
+    /// `.json` and `.xml` are not detected: several formats are written as JSON, and Pinboard XML
+    /// is easily mistaken for another XML format. See [`OutputFormat::with_extension`].
     pub fn detect(path: impl AsRef<Path>) -> Option<OutputFormat> {
         match path.as_ref().extension()?.to_str()? {
             "html" => Some(OutputFormat::Html),
             "yaml" | "yml" => Some(OutputFormat::Yaml),
+            "sqlite" | "sqlite3" | "db" => Some(OutputFormat::Sqlite),
+            "jsonl" => Some(OutputFormat::Lines),
+            "md" => Some(OutputFormat::Markdown),
             _ => None,
         }
     }
 
+    /// Returns the formats whose usual file extension is `ext`, e.g. for naming the formats a file
+    /// may be meant to be written in when [`OutputFormat::detect`] cannot tell.
+    pub fn with_extension(ext: &str) -> impl Iterator<Item = OutputFormat> {
+        OutputFormat::VARIANTS
+            .iter()
+            .copied()
+            .filter(move |format| format.extension() == ext)
+    }
+
+    /// Returns the usual file extension of the format.
+    #[must_use]
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::PinboardJson | OutputFormat::Launcher | OutputFormat::Karakeep => "json",
+            OutputFormat::PinboardXml => "xml",
+            OutputFormat::Lines => "jsonl",
+            OutputFormat::Markdown => "md",
+            _ => self.into(),
+        }
+    }
+
     /// Writes a collection in the specified output format.
     ///
     /// # Errors
     ///
     /// Returns an error if writing to the output fails or if serialization encounters an issue.
     pub fn unparse(&self, writer: &mut impl Write, coll: &Collection) -> Result<(), UnparseError> {
+        self.unparse_with_options(writer, coll, &UnparseOptions::default())
+    }
+
+    /// Writes a collection in the specified output format, using the given options.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the output fails or if serialization encounters an issue.
+    pub fn unparse_with_options(
+        &self,
+        writer: &mut impl Write,
+        coll: &Collection,
+        options: &UnparseOptions,
+    ) -> Result<(), UnparseError> {
         match self {
-            OutputFormat::Html => coll.to_html(writer)?,
-            OutputFormat::Yaml => serde_norway::to_writer(writer, coll)?,
+            OutputFormat::Html => coll.to_html_with_options(writer, &options.html)?,
+            OutputFormat::Yaml => {
+                let repr_options = ReprOptions {
+                    readable_edges: options.readable_edges,
+                    id_scheme: options.id_scheme,
+                };
+                serde_norway::to_writer(
+                    writer,
+                    &CollectionRepr::with_options(coll, &repr_options)?,
+                )?;
+            }
+            OutputFormat::PinboardJson => Post::to_json(writer, &posts(coll))?,
+            OutputFormat::PinboardXml => Post::to_xml(writer, &posts(coll))?,
+            OutputFormat::Launcher => coll.to_launcher_json(writer)?,
+            OutputFormat::Karakeep => coll.to_karakeep_json(writer)?,
+            OutputFormat::Lines => coll.to_lines(writer)?,
+            OutputFormat::Markdown => coll.to_markdown(writer)?,
+            #[cfg(feature = "sqlite")]
+            OutputFormat::Sqlite => return Err(sqlite::Error::NotAFile.into()),
+            #[cfg(not(feature = "sqlite"))]
+            OutputFormat::Sqlite => return Err(UnparseError::Unsupported(self.into())),
         }
         Ok(())
     }
 }
 
+/// Converts the entities of a collection into Pinboard posts, in order.
+fn posts(coll: &Collection) -> Vec<Post> {
+    coll.entities().iter().map(Post::from).collect()
+}
+
 #[cfg(feature = "clap")]
 impl ValueEnum for OutputFormat {
     fn value_variants<'a>() -> &'a [OutputFormat] {
@@ -154,3 +735,39 @@ impl ValueEnum for OutputFormat {
         Some(PossibleValue::new(s))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Anomaly, InputFormat, ParseOptions};
+
+    #[test]
+    fn pinboard_posts_without_a_url_are_reported() {
+        let skipped = vec![Anomaly::Skipped {
+            position: "post #1".to_string(),
+        }];
+        let post = |href: &str| {
+            format!(
+                r#"{{"href": "{href}", "time": "2023-11-15T00:00:00Z", "description": "",
+                "extended": "", "meta": "", "hash": "", "shared": "no", "toread": "no"}}"#
+            )
+        };
+        let json = format!("[{}, {}]", post(""), post("https://example.com/"));
+        let xml = r#"<posts>
+            <post href="" time="2023-11-15T00:00:00Z"/>
+            <post href="https://example.com/" time="2023-11-15T00:00:00Z"/>
+        </posts>"#;
+        for (format, input) in [(InputFormat::Json, json.as_str()), (InputFormat::Xml, xml)] {
+            for keep_raw in [false, true] {
+                let options = ParseOptions {
+                    keep_raw,
+                    ..ParseOptions::default()
+                };
+                let (coll, report) = format
+                    .parse_with_report(&mut input.as_bytes(), &options)
+                    .unwrap();
+                assert_eq!(coll.len(), 1);
+                assert_eq!(report.anomalies, skipped);
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use thiserror::Error;
+
+use crate::{collection::Collection, entity::Entity};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Error {
+    #[error("nesting deeper than the limit of {0} levels")]
+    Depth(usize),
+
+    #[error("more bookmarks than the limit of {0}")]
+    Entities(usize),
+
+    #[error("{field} longer than the limit of {limit} bytes")]
+    FieldLength { field: &'static str, limit: usize },
+}
+
+/// The default maximum nesting depth of folders, lists or blockquotes.
+pub const DEFAULT_MAX_DEPTH: usize = 256;
+
+/// The default maximum number of bookmarks in one input.
+pub const DEFAULT_MAX_ENTITIES: usize = 10_000_000;
+
+/// The default maximum length of a URL, name, label, extended note or attribute, in bytes.
+pub const DEFAULT_MAX_FIELD_LEN: usize = 1 << 20;
+
+/// Bounds on the shape of parsed input, so that untrusted files cannot exhaust the stack or
+/// memory.
+///
+/// HTML, markdown, Pinboard XML and text input are checked while they are parsed, so parsing
+/// stops at the first bookmark over a limit. Pinboard JSON, Hacker News, Reddit and lines input
+/// are only checked once parsed, with [`Limits::check_collection`]. For those formats the limits
+/// bound the resulting collection, but not the memory used to parse it.
+///
+/// The defaults are far beyond what any real bookmark export contains.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum nesting depth of HTML folders, or of markdown lists and blockquotes.
+    pub max_depth: usize,
+    /// The maximum number of bookmarks read from one input.
+    pub max_entities: usize,
+    /// The maximum length of any one field of a bookmark, in bytes.
+    pub max_field_len: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_entities: DEFAULT_MAX_ENTITIES,
+            max_field_len: DEFAULT_MAX_FIELD_LEN,
+        }
+    }
+}
+
+impl Limits {
+    /// Limits that accept any input.
+    pub const UNLIMITED: Limits = Limits {
+        max_depth: usize::MAX,
+        max_entities: usize::MAX,
+        max_field_len: usize::MAX,
+    };
+
+    /// # Errors
+    ///
+    /// Returns an error if `depth` exceeds the maximum nesting depth.
+    pub fn check_depth(&self, depth: usize) -> Result<(), Error> {
+        if depth > self.max_depth {
+            return Err(Error::Depth(self.max_depth));
+        }
+        Ok(())
+    }
+
+    /// # Errors
+    ///
+    /// Returns an error if `count` exceeds the maximum number of bookmarks.
+    pub fn check_entities(&self, count: usize) -> Result<(), Error> {
+        if count > self.max_entities {
+            return Err(Error::Entities(self.max_entities));
+        }
+        Ok(())
+    }
+
+    /// Checks the length of a field named `field`, e.g. `"URL"`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `value` exceeds the maximum field length.
+    pub fn check_field(&self, field: &'static str, value: &str) -> Result<(), Error> {
+        if value.len() > self.max_field_len {
+            return Err(Error::FieldLength {
+                field,
+                limit: self.max_field_len,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the length of every field of `entity`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first field that exceeds the maximum field length.
+    pub fn check_entity(&self, entity: &Entity) -> Result<(), Error> {
+        self.check_field("URL", entity.url().as_str())?;
+        for name in entity.names() {
+            self.check_field("name", name.as_str())?;
+        }
+        for label in entity.labels() {
+            self.check_field("label", label.as_str())?;
+        }
+        for extended in entity.extended() {
+            self.check_field("extended note", extended.as_str())?;
+        }
+        for value in entity.extras().values() {
+            self.check_field("extra", value)?;
+        }
+        Ok(())
+    }
+
+    /// Checks the number of entities in `coll`, and every field of each, for input that is only
+    /// checked after parsing.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first limit that `coll` exceeds.
+    pub fn check_collection(&self, coll: &Collection) -> Result<(), Error> {
+        self.check_entities(coll.len())?;
+        for entity in coll.entities() {
+            self.check_entity(entity)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::entity::{Entity, Name, Time, Url};
+
+    use super::{Error, Limits};
+
+    #[test]
+    fn limits_name_what_was_exceeded() {
+        let limits = Limits {
+            max_depth: 2,
+            max_entities: 1,
+            max_field_len: 32,
+        };
+        assert_eq!(limits.check_depth(2), Ok(()));
+        assert_eq!(limits.check_depth(3), Err(Error::Depth(2)));
+        assert_eq!(limits.check_entities(2), Err(Error::Entities(1)));
+
+        let url = Url::parse("https://example.com/").unwrap();
+        let name = Name::from("x".repeat(33).as_str());
+        let entity = Entity::new(url, Time::default(), Some(name), BTreeSet::new());
+        assert_eq!(
+            limits.check_entity(&entity),
+            Err(Error::FieldLength {
+                field: "name",
+                limit: 32
+            })
+        );
+        assert_eq!(Limits::UNLIMITED.check_entity(&entity), Ok(()));
+    }
+}
@@ -0,0 +1,215 @@
+use std::io::{self, BufRead, Write};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    collection::Collection,
+    entity::{Entity, Profile, Url},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("invalid record on line {0}: {1}")]
+    Line(usize, #[source] serde_json::Error),
+
+    #[error("duplicate record on line {0}: {1}")]
+    Duplicate(usize, String),
+
+    #[error("edge on line {0} to unknown URL: {1}")]
+    UnknownEdge(usize, String),
+}
+
+/// The target of an edge, identified by its URL and profile.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct Target {
+    #[serde(rename = "uri")]
+    url: Url,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    profile: Option<Profile>,
+}
+
+/// An entity with its edges' targets, written as one line of JSON.
+#[derive(Debug, Serialize, Deserialize)]
+struct Record<T> {
+    #[serde(flatten)]
+    entity: T,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    edges: Vec<Target>,
+}
+
+impl Collection {
+    /// Writes the collection one entity per line, each as a JSON object with its fields in a fixed
+    /// order followed by its edges' sorted targets, each an object with a `uri` and, outside the
+    /// default profile, a `profile`. Lines are sorted by URL and profile, so that a small change
+    /// to a collection is a small change to its file, as suits archives tracked in version
+    /// control.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization or writing to the output fails.
+    pub fn to_lines(&self, mut writer: impl Write) -> Result<(), Error> {
+        let mut order: Vec<&Entity> = self.entities().iter().collect();
+        order.sort_by(|a, b| (a.url(), a.profile()).cmp(&(b.url(), b.profile())));
+        for entity in order {
+            let Some(id) = self.id_in(entity.profile(), entity.url()) else {
+                continue;
+            };
+            let mut edges: Vec<Target> = self
+                .edges(&id)
+                .iter()
+                .map(|target| {
+                    let target = self.entity(target);
+                    Target {
+                        url: target.url().clone(),
+                        profile: target.profile().cloned(),
+                    }
+                })
+                .collect();
+            edges.sort();
+            let record = Record { entity, edges };
+            serde_json::to_writer(&mut writer, &record)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Reads a collection written by [`Collection::to_lines`]. Blank lines are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, a line is not a valid record, two records share a URL
+    /// and profile, or an edge's target is missing.
+    pub fn from_lines(reader: impl BufRead) -> Result<Collection, Error> {
+        let mut coll = Collection::new();
+        let mut pending = Vec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let number = index + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: Record<Entity> =
+                serde_json::from_str(&line).map_err(|err| Error::Line(number, err))?;
+            let entity = record.entity;
+            if coll.contains_in(entity.profile(), entity.url()) {
+                return Err(Error::Duplicate(number, entity.url().as_str().to_string()));
+            }
+            let id = coll.insert(entity);
+            pending.push((number, id, record.edges));
+        }
+        for (number, from, edges) in pending {
+            for target in edges {
+                let Some(to) = coll.id_in(target.profile.as_ref(), &target.url) else {
+                    return Err(Error::UnknownEdge(number, target.url.as_str().to_string()));
+                };
+                coll.add_edge(&from, &to);
+            }
+        }
+        Ok(coll)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Label, Name, Profile, Time, Url},
+    };
+
+    use super::Error;
+
+    #[test]
+    fn lines_round_trip_sorted_by_url() {
+        let mut coll = Collection::new();
+        let b = coll.insert(Entity::new(
+            Url::parse("https://b.example/").unwrap(),
+            Time::default(),
+            Some(Name::from("B")),
+            BTreeSet::from([Label::from("rust")]),
+        ));
+        let a = coll.insert(Entity::new(
+            Url::parse("https://a.example/").unwrap(),
+            Time::default(),
+            None,
+            BTreeSet::new(),
+        ));
+        coll.add_edge(&b, &a);
+
+        let mut output = Vec::new();
+        coll.to_lines(&mut output).unwrap();
+        let text = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(r#"{"uri":"https://a.example/""#));
+        assert!(lines[1].ends_with(r#""edges":[{"uri":"https://a.example/"}]}"#));
+
+        let parsed = Collection::from_lines(text.as_bytes()).unwrap();
+        let a = parsed
+            .id(&Url::parse("https://a.example/").unwrap())
+            .unwrap();
+        let b = parsed
+            .id(&Url::parse("https://b.example/").unwrap())
+            .unwrap();
+        assert_eq!(parsed.edges(&b), [a]);
+        assert_eq!(
+            parsed.entity(&b).labels(),
+            &BTreeSet::from([Label::from("rust")])
+        );
+
+        let mut again = Vec::new();
+        parsed.to_lines(&mut again).unwrap();
+        assert_eq!(String::from_utf8(again).unwrap(), text);
+
+        let dangling = r#"{"uri":"https://a.example/","createdAt":0,"updatedAt":[],"names":[],"labels":[],"shared":false,"toRead":false,"isFeed":false,"edges":[{"uri":"https://c.example/"}]}"#;
+        assert!(matches!(
+            Collection::from_lines(dangling.as_bytes()),
+            Err(Error::UnknownEdge(1, _))
+        ));
+    }
+
+    #[test]
+    fn edges_across_profiles_round_trip() {
+        let make = |url: &str, profile: Option<&str>| {
+            let mut entity = Entity::new(
+                Url::parse(url).unwrap(),
+                Time::default(),
+                None,
+                BTreeSet::new(),
+            );
+            entity.set_profile(profile.map(Profile::from));
+            entity
+        };
+        let mut coll = Collection::new();
+        let default = coll.insert(make("https://example.com/", None));
+        let work = coll.insert(make("https://example.com/", Some("work")));
+        let other = coll.insert(make("https://other.example.com/", Some("work")));
+        coll.add_edge(&other, &default);
+        coll.add_edge(&default, &work);
+
+        let mut output = Vec::new();
+        coll.to_lines(&mut output).unwrap();
+        let parsed = Collection::from_lines(output.as_slice()).unwrap();
+
+        let url = Url::parse("https://example.com/").unwrap();
+        let work_profile = Profile::from("work");
+        let default = parsed.id_in(None, &url).unwrap();
+        let work = parsed.id_in(Some(&work_profile), &url).unwrap();
+        let other = parsed
+            .id_in(
+                Some(&work_profile),
+                &Url::parse("https://other.example.com/").unwrap(),
+            )
+            .unwrap();
+        assert_eq!(parsed.edges(&other), std::slice::from_ref(&default));
+        assert_eq!(parsed.edges(&default), [work]);
+    }
+}
@@ -0,0 +1,243 @@
+use chrono::NaiveDate;
+#[cfg(feature = "clap")]
+use clap::{ValueEnum, builder::PossibleValue};
+use strum::{IntoStaticStr, VariantArray};
+
+/// A language whose month names are recognized in dates such as `15 de noviembre de 2023` or
+/// `15. November 2023`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, IntoStaticStr, VariantArray)]
+#[strum(serialize_all = "lowercase")]
+pub enum Locale {
+    En,
+    De,
+    Es,
+    Fr,
+    It,
+    Nl,
+    Pt,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for Locale {
+    fn value_variants<'a>() -> &'a [Locale] {
+        Locale::VARIANTS
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let s: &'static str = self.into();
+        Some(PossibleValue::new(s))
+    }
+}
+
+type Months = [&'static [&'static str]; 12];
+
+const EN_MONTHS: Months = [
+    &["january", "jan"],
+    &["february", "feb"],
+    &["march", "mar"],
+    &["april", "apr"],
+    &["may"],
+    &["june", "jun"],
+    &["july", "jul"],
+    &["august", "aug"],
+    &["september", "sep", "sept"],
+    &["october", "oct"],
+    &["november", "nov"],
+    &["december", "dec"],
+];
+
+const DE_MONTHS: Months = [
+    &["januar", "jänner", "jan"],
+    &["februar", "feb"],
+    &["märz", "mär"],
+    &["april", "apr"],
+    &["mai"],
+    &["juni", "jun"],
+    &["juli", "jul"],
+    &["august", "aug"],
+    &["september", "sep", "sept"],
+    &["oktober", "okt"],
+    &["november", "nov"],
+    &["dezember", "dez"],
+];
+
+const ES_MONTHS: Months = [
+    &["enero", "ene"],
+    &["febrero", "feb"],
+    &["marzo", "mar"],
+    &["abril", "abr"],
+    &["mayo", "may"],
+    &["junio", "jun"],
+    &["julio", "jul"],
+    &["agosto", "ago"],
+    &["septiembre", "setiembre", "sep", "sept"],
+    &["octubre", "oct"],
+    &["noviembre", "nov"],
+    &["diciembre", "dic"],
+];
+
+const FR_MONTHS: Months = [
+    &["janvier", "janv"],
+    &["février", "fevrier", "févr", "fevr"],
+    &["mars"],
+    &["avril", "avr"],
+    &["mai"],
+    &["juin"],
+    &["juillet", "juil"],
+    &["août", "aout"],
+    &["septembre", "sept"],
+    &["octobre", "oct"],
+    &["novembre", "nov"],
+    &["décembre", "decembre", "déc", "dec"],
+];
+
+const IT_MONTHS: Months = [
+    &["gennaio", "gen"],
+    &["febbraio", "feb"],
+    &["marzo", "mar"],
+    &["aprile", "apr"],
+    &["maggio", "mag"],
+    &["giugno", "giu"],
+    &["luglio", "lug"],
+    &["agosto", "ago"],
+    &["settembre", "set"],
+    &["ottobre", "ott"],
+    &["novembre", "nov"],
+    &["dicembre", "dic"],
+];
+
+const NL_MONTHS: Months = [
+    &["januari", "jan"],
+    &["februari", "feb"],
+    &["maart", "mrt"],
+    &["april", "apr"],
+    &["mei"],
+    &["juni", "jun"],
+    &["juli", "jul"],
+    &["augustus", "aug"],
+    &["september", "sep", "sept"],
+    &["oktober", "okt"],
+    &["november", "nov"],
+    &["december", "dec"],
+];
+
+const PT_MONTHS: Months = [
+    &["janeiro", "jan"],
+    &["fevereiro", "fev"],
+    &["março", "marco", "mar"],
+    &["abril", "abr"],
+    &["maio", "mai"],
+    &["junho", "jun"],
+    &["julho", "jul"],
+    &["agosto", "ago"],
+    &["setembro", "set"],
+    &["outubro", "out"],
+    &["novembro", "nov"],
+    &["dezembro", "dez"],
+];
+
+impl Locale {
+    fn months(self) -> &'static Months {
+        match self {
+            Locale::En => &EN_MONTHS,
+            Locale::De => &DE_MONTHS,
+            Locale::Es => &ES_MONTHS,
+            Locale::Fr => &FR_MONTHS,
+            Locale::It => &IT_MONTHS,
+            Locale::Nl => &NL_MONTHS,
+            Locale::Pt => &PT_MONTHS,
+        }
+    }
+
+    /// Words that may join the day, month and year, as in `15 de noviembre de 2023`.
+    fn joiners(self) -> &'static [&'static str] {
+        match self {
+            Locale::En => &["of", "the"],
+            Locale::Es | Locale::Pt => &["de", "del"],
+            Locale::Fr => &["le"],
+            Locale::De | Locale::It | Locale::Nl => &[],
+        }
+    }
+
+    /// Suffixes of ordinal days, as in `1st` or `1er`.
+    fn ordinals(self) -> &'static [&'static str] {
+        match self {
+            Locale::En => &["st", "nd", "rd", "th"],
+            Locale::Fr => &["er"],
+            Locale::Es | Locale::It | Locale::Pt => &["º", "°"],
+            Locale::De | Locale::Nl => &[],
+        }
+    }
+
+    fn month(self, word: &str) -> Option<u32> {
+        let index = self
+            .months()
+            .iter()
+            .position(|names| names.contains(&word))?;
+        u32::try_from(index + 1).ok()
+    }
+
+    fn day(self, word: &str) -> Option<u32> {
+        let digits = self
+            .ordinals()
+            .iter()
+            .find_map(|suffix| word.strip_suffix(suffix))
+            .unwrap_or(word);
+        if digits.len() > 2 {
+            return None;
+        }
+        digits.parse().ok()
+    }
+
+    /// Parses a date written with the day, month name and year in any order, ignoring case,
+    /// punctuation and joining words, e.g. `15 de noviembre de 2023`, `15. November 2023` or
+    /// `November 15th, 2023`. Returns `None` unless exactly one of each is found.
+    #[must_use]
+    pub fn parse_date(self, s: &str) -> Option<NaiveDate> {
+        let lowercase = s.to_lowercase();
+        let (mut maybe_day, mut maybe_month, mut maybe_year) = (None, None, None);
+        for word in lowercase
+            .split(|c: char| c.is_whitespace() || c == ',' || c == '.')
+            .filter(|word| !word.is_empty())
+        {
+            if self.joiners().contains(&word) {
+                continue;
+            }
+            let repeated = if let Some(month) = self.month(word) {
+                maybe_month.replace(month).is_some()
+            } else if word.len() == 4 && word.bytes().all(|b| b.is_ascii_digit()) {
+                maybe_year.replace(word.parse::<i32>().ok()?).is_some()
+            } else {
+                maybe_day.replace(self.day(word)?).is_some()
+            };
+            if repeated {
+                return None;
+            }
+        }
+        NaiveDate::from_ymd_opt(maybe_year?, maybe_month?, maybe_day?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::Locale;
+
+    #[test]
+    fn month_names_are_localized() {
+        let date = NaiveDate::from_ymd_opt(2023, 11, 15);
+        assert_eq!(Locale::Es.parse_date("15 de noviembre de 2023"), date);
+        assert_eq!(Locale::De.parse_date("15. November 2023"), date);
+        assert_eq!(Locale::Fr.parse_date("Mercredi 15 novembre 2023"), None);
+        assert_eq!(Locale::Fr.parse_date("15 novembre 2023"), date);
+        assert_eq!(Locale::En.parse_date("November 15th, 2023"), date);
+        assert_eq!(
+            Locale::Pt.parse_date("1º de março de 2024"),
+            NaiveDate::from_ymd_opt(2024, 3, 1)
+        );
+        assert_eq!(Locale::Es.parse_date("15 de noviembre"), None);
+        assert_eq!(Locale::Es.parse_date("31 de febrero de 2023"), None);
+        assert_eq!(Locale::De.parse_date("15. November 2023 Notizen"), None);
+    }
+}
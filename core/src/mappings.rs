@@ -0,0 +1,413 @@
+use std::{
+    collections::BTreeSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::entity::Label;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Yaml(#[from] serde_norway::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Toml(#[from] toml::de::Error),
+
+    #[error("expected two columns on line {0}")]
+    Csv(usize),
+
+    #[error("mappings must be a mapping of labels to labels")]
+    NotAMapping,
+
+    #[error("cannot detect mappings format of: {0}")]
+    UnknownFormat(PathBuf),
+
+    #[error(
+        "invalid mapping for {0}: expected a label, {{to: <label>, when: [<label>, ...]}}, or a list of these"
+    )]
+    InvalidRule(String),
+
+    #[error("unreachable mapping for {0}: it follows a mapping without conditions")]
+    UnreachableRule(String),
+}
+
+/// A label mapping, which renames `from` to `to` on the entities that also carry every label in
+/// `when`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub from: Label,
+    pub to: Label,
+    /// The labels an entity must also carry for the rule to apply. Empty if the rule applies to
+    /// every entity carrying `from`.
+    pub when: BTreeSet<Label>,
+}
+
+impl Rule {
+    /// Returns whether the rule applies to an entity carrying `labels`.
+    #[must_use]
+    pub fn applies_to(&self, labels: &BTreeSet<Label>) -> bool {
+        labels.contains(&self.from) && self.when.is_subset(labels)
+    }
+}
+
+impl From<(String, String)> for Rule {
+    fn from((from, to): (String, String)) -> Rule {
+        Rule {
+            from: Label::from(from),
+            to: Label::from(to),
+            when: BTreeSet::new(),
+        }
+    }
+}
+
+/// The value of an entry in a mappings file.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Target {
+    Label(String),
+    Conditional(Conditional),
+    Many(Vec<Conditional>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Conditional {
+    to: String,
+    #[serde(default)]
+    when: Vec<String>,
+}
+
+/// Converts an entry of a mappings file into rules, in order.
+fn rules(from: &str, target: Target) -> Result<Vec<Rule>, Error> {
+    let conditionals = match target {
+        Target::Label(to) => vec![Conditional {
+            to,
+            when: Vec::new(),
+        }],
+        Target::Conditional(conditional) => vec![conditional],
+        Target::Many(conditionals) => conditionals,
+    };
+    let mut ret: Vec<Rule> = Vec::with_capacity(conditionals.len());
+    for Conditional { to, when } in conditionals {
+        let is_blank = |label: &String| label.trim().is_empty();
+        if from.trim().is_empty() || is_blank(&to) || when.iter().any(is_blank) {
+            return Err(Error::InvalidRule(from.to_string()));
+        }
+        if ret.iter().any(|rule| rule.when.is_empty()) {
+            return Err(Error::UnreachableRule(from.to_string()));
+        }
+        ret.push(Rule {
+            from: Label::from(from),
+            to: Label::from(to),
+            when: when.into_iter().map(Label::from).collect(),
+        });
+    }
+    Ok(ret)
+}
+
+/// A file format for label mappings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Yaml,
+    Json,
+    Toml,
+    /// Two columns, the old label and the new one, without a header.
+    Csv,
+}
+
+impl Format {
+    #[must_use]
+    pub fn detect(path: impl AsRef<Path>) -> Option<Format> {
+        match path.as_ref().extension()?.to_str()? {
+            "yaml" | "yml" => Some(Format::Yaml),
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+
+    /// Parses label mappings, returning each old label with the label replacing it. Entries whose
+    /// key or value is not a string are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the contents are malformed or are not a mapping.
+    pub fn parse(self, contents: &str) -> Result<Vec<(String, String)>, Error> {
+        match self {
+            Format::Yaml => {
+                let yaml: serde_norway::Value = serde_norway::from_str(contents)?;
+                let mapping = yaml.as_mapping().ok_or(Error::NotAMapping)?;
+                Ok(mapping
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.as_str()?.to_string(), v.as_str()?.to_string())))
+                    .collect())
+            }
+            Format::Json => {
+                let json: serde_json::Value = serde_json::from_str(contents)?;
+                let object = json.as_object().ok_or(Error::NotAMapping)?;
+                Ok(object
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                    .collect())
+            }
+            Format::Toml => {
+                let table: toml::Table = toml::from_str(contents)?;
+                Ok(table
+                    .iter()
+                    .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                    .collect())
+            }
+            Format::Csv => parse_csv(contents),
+        }
+    }
+
+    /// Parses label mappings as rules, in order. Unlike [`Format::parse`], the YAML, JSON and TOML
+    /// formats may restrict a mapping to the entities carrying other labels, and give several
+    /// such mappings for one label, of which the first that applies is used:
+    ///
+    /// ```yaml
+    /// lang/rust: rust
+    /// misc:
+    ///   - to: reading
+    ///     when: [books]
+    ///   - to: watching
+    ///     when: [film]
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the contents are malformed or are not a mapping, an entry is not a
+    /// valid mapping, or a mapping follows one without conditions for the same label.
+    pub fn parse_rules(self, contents: &str) -> Result<Vec<Rule>, Error> {
+        let invalid = |key: &str| Error::InvalidRule(key.to_string());
+        let entries: Vec<(String, Target)> = match self {
+            Format::Yaml => {
+                let yaml: serde_norway::Value = serde_norway::from_str(contents)?;
+                let mapping = yaml.as_mapping().ok_or(Error::NotAMapping)?;
+                mapping
+                    .iter()
+                    .map(|(k, v)| {
+                        let key = k.as_str().ok_or_else(|| invalid(&format!("{k:?}")))?;
+                        let target: Target =
+                            serde_norway::from_value(v.clone()).map_err(|_| invalid(key))?;
+                        Ok((key.to_string(), target))
+                    })
+                    .collect::<Result<_, Error>>()?
+            }
+            Format::Json => {
+                let json: serde_json::Value = serde_json::from_str(contents)?;
+                let object = json.as_object().ok_or(Error::NotAMapping)?;
+                object
+                    .iter()
+                    .map(|(k, v)| {
+                        let target: Target =
+                            serde_json::from_value(v.clone()).map_err(|_| invalid(k))?;
+                        Ok((k.clone(), target))
+                    })
+                    .collect::<Result<_, Error>>()?
+            }
+            Format::Toml => {
+                let table: toml::Table = toml::from_str(contents)?;
+                table
+                    .iter()
+                    .map(|(k, v)| {
+                        let target: Target = v.clone().try_into().map_err(|_| invalid(k))?;
+                        Ok((k.clone(), target))
+                    })
+                    .collect::<Result<_, Error>>()?
+            }
+            Format::Csv => parse_csv(contents)?
+                .into_iter()
+                .map(|(from, to)| (from, Target::Label(to)))
+                .collect(),
+        };
+        let mut ret = Vec::with_capacity(entries.len());
+        for (from, target) in entries {
+            ret.extend(rules(&from, target)?);
+        }
+        Ok(ret)
+    }
+}
+
+/// Splits a CSV record into fields, unquoting fields in double quotes.
+fn split_record(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn parse_csv(contents: &str) -> Result<Vec<(String, String)>, Error> {
+    let mut ret = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match split_record(line).as_slice() {
+            [from, to] => ret.push((from.trim().to_string(), to.trim().to_string())),
+            _ => return Err(Error::Csv(index + 1)),
+        }
+    }
+    Ok(ret)
+}
+
+/// Reads label mappings from `path`, detecting the format from its extension.
+///
+/// # Errors
+///
+/// Returns an error if the format cannot be detected, or the file cannot be read or parsed.
+pub fn load(path: &Path) -> Result<Vec<(String, String)>, Error> {
+    let format = Format::detect(path).ok_or_else(|| Error::UnknownFormat(path.to_path_buf()))?;
+    let contents = fs::read_to_string(path)?;
+    format.parse(&contents)
+}
+
+/// Reads label mappings from `path` as rules, detecting the format from its extension. See
+/// [`Format::parse_rules`].
+///
+/// # Errors
+///
+/// Returns an error if the format cannot be detected, or the file cannot be read or parsed.
+pub fn load_rules(path: &Path) -> Result<Vec<Rule>, Error> {
+    let format = Format::detect(path).ok_or_else(|| Error::UnknownFormat(path.to_path_buf()))?;
+    let contents = fs::read_to_string(path)?;
+    format.parse_rules(&contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Label, Time, Url},
+    };
+
+    use super::{Error, Format};
+
+    fn expected() -> Vec<(String, String)> {
+        vec![
+            ("lang/rust".to_string(), "rust".to_string()),
+            ("c, c++".to_string(), "c".to_string()),
+        ]
+    }
+
+    #[test]
+    fn formats_agree() {
+        let yaml = "lang/rust: rust\n\"c, c++\": c\n";
+        let json = r#"{"lang/rust": "rust", "c, c++": "c"}"#;
+        let toml = "\"lang/rust\" = \"rust\"\n\"c, c++\" = \"c\"\n";
+        let csv = "lang/rust,rust\n\n\"c, c++\",c\n";
+
+        let mut parsed = [
+            Format::Yaml.parse(yaml).unwrap(),
+            Format::Json.parse(json).unwrap(),
+            Format::Toml.parse(toml).unwrap(),
+            Format::Csv.parse(csv).unwrap(),
+        ];
+        for mappings in &mut parsed {
+            mappings.sort();
+        }
+        let mut expected = expected();
+        expected.sort();
+        for mappings in parsed {
+            assert_eq!(mappings, expected);
+        }
+
+        assert!(Format::Csv.parse("a,b,c\n").is_err());
+        assert_eq!(Format::detect("mappings.yml"), Some(Format::Yaml));
+    }
+
+    #[test]
+    fn conditional_rules() {
+        let yaml = "
+misc:
+  - to: reading
+    when: [books]
+  - to: watching
+    when: [film, video]
+lang/rust: rust
+";
+        let rules = Format::Yaml.parse_rules(yaml).unwrap();
+        assert_eq!(rules.len(), 3);
+        let json = r#"{"misc": [{"to": "reading", "when": ["books"]}, {"to": "watching", "when": ["film", "video"]}], "lang/rust": "rust"}"#;
+        let json_rules = Format::Json.parse_rules(json).unwrap();
+        assert_eq!(json_rules.len(), rules.len());
+        assert!(json_rules.iter().all(|rule| rules.contains(rule)));
+
+        let mut coll = Collection::new();
+        for (i, labels) in [
+            &["misc", "books"][..],
+            &["misc", "film", "video"][..],
+            &["misc", "film"][..],
+            &["misc", "books", "lang/rust"][..],
+        ]
+        .iter()
+        .enumerate()
+        {
+            let url = Url::parse(&format!("https://example.com/{i}")).unwrap();
+            let labels = labels.iter().copied().map(Label::from).collect();
+            coll.insert(Entity::new(url, Time::default(), None, labels));
+        }
+
+        let changelog = coll.update_labels_with(&rules);
+
+        assert_eq!(changelog.changes.len(), 3);
+        let labels = |i: usize| -> Vec<&str> {
+            coll.entities()[i]
+                .labels()
+                .iter()
+                .map(Label::as_str)
+                .collect()
+        };
+        assert_eq!(labels(0), ["books", "reading"]);
+        assert_eq!(labels(1), ["film", "video", "watching"]);
+        assert_eq!(labels(2), ["film", "misc"]);
+        assert_eq!(labels(3), ["books", "reading", "rust"]);
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        let unreachable = "misc:\n  - to: reading\n  - to: watching\n    when: [film]\n";
+        assert!(matches!(
+            Format::Yaml.parse_rules(unreachable),
+            Err(Error::UnreachableRule(label)) if label == "misc"
+        ));
+        assert!(matches!(
+            Format::Yaml.parse_rules("misc: {to: reading, if: [books]}\n"),
+            Err(Error::InvalidRule(_))
+        ));
+        assert!(matches!(
+            Format::Yaml.parse_rules("misc: {to: reading, when: ['']}\n"),
+            Err(Error::InvalidRule(_))
+        ));
+        assert_eq!(
+            Format::Csv.parse_rules("misc,reading\n").unwrap()[0].when,
+            BTreeSet::new()
+        );
+    }
+}
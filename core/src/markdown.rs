@@ -1,10 +1,19 @@
-use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    ops::Range,
+    path::Path,
+};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
 use pulldown_cmark::{Event, HeadingLevel, LinkType, Parser, Tag, TagEnd};
 use thiserror::Error;
 
 use crate::{
     collection::{Collection, Id},
-    entity::{self, Entity, Label, Name, Url},
+    entity::{self, Entity, Extended, Label, Name, RAW_EXTRA, Url, UrlPolicy},
+    limits::{self, Limits},
+    locale::Locale,
 };
 
 #[derive(Debug, Error)]
@@ -23,6 +32,12 @@ pub enum Error {
 
     #[error("invalid time construction for date: {0}")]
     InvalidTime(String),
+
+    #[error(transparent)]
+    Limit(#[from] limits::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
 }
 
 #[derive(Copy, Clone)]
@@ -49,56 +64,183 @@ impl From<HeadingLevelExt> for usize {
 
 const DATE_FORMAT: &str = "%B %-d, %Y";
 
-fn parse_date(s: &str) -> Result<DateTime<Utc>, Error> {
-    let date = NaiveDate::parse_from_str(s, DATE_FORMAT)
-        .map_err(|err| Error::ParseDate(err, s.to_string()))?;
+fn midnight(date: NaiveDate) -> Result<DateTime<Utc>, Error> {
     let datetime = date
         .and_hms_opt(0, 0, 0)
-        .ok_or_else(|| Error::InvalidTime(s.to_string()))?;
+        .ok_or_else(|| Error::InvalidTime(date.to_string()))?;
     Ok(Utc.from_utc_datetime(&datetime))
 }
 
+/// Parses a date heading such as `November 15, 2023`, or else one with month names in `locale`.
+fn parse_date(s: &str, maybe_locale: Option<Locale>) -> Result<DateTime<Utc>, Error> {
+    let date = match NaiveDate::parse_from_str(s, DATE_FORMAT) {
+        Ok(date) => date,
+        Err(err) => maybe_locale
+            .and_then(|locale| locale.parse_date(s))
+            .ok_or_else(|| Error::ParseDate(err, s.to_string()))?,
+    };
+    midnight(date)
+}
+
+/// The default pattern for [`date_from_file_name`], matching names such as `2023-11-15.md`.
+pub const FILE_NAME_DATE_PATTERN: &str = "%Y-%m-%d";
+
+/// Parses a date from the start of the file stem of `path`, according to `pattern` (a
+/// [`chrono::format::strftime`] pattern). Anything in the stem after the date is ignored, so
+/// `2023-11-15-reading.md` matches `%Y-%m-%d`.
+#[must_use]
+pub fn date_from_file_name(path: &Path, pattern: &str) -> Option<NaiveDate> {
+    let stem = path.file_stem()?.to_str()?;
+    NaiveDate::parse_and_remainder(stem, pattern)
+        .ok()
+        .map(|(date, _)| date)
+}
+
+/// Options controlling how markdown is parsed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    pub url_policy: UrlPolicy,
+    /// The date of bookmarks that precede every date heading, e.g. one inferred from the file name
+    /// with [`date_from_file_name`]. Without it, such bookmarks are an error.
+    pub default_date: Option<NaiveDate>,
+    /// Record the line each bookmark was read from in its [`RAW_EXTRA`] extra.
+    pub keep_raw: bool,
+    /// Bounds on list and blockquote nesting, the number of links and the length of their text.
+    pub limits: Limits,
+    /// The language of month names in date headings that are not in the form `November 15, 2023`.
+    pub locale: Option<Locale>,
+}
+
+const TIME_FORMAT: &str = "%H:%M";
+
+/// Returns the line of `input` containing the byte at `pos`, without its line ending.
+fn line_at(input: &str, pos: usize) -> &str {
+    let start = input[..pos].rfind('\n').map_or(0, |i| i + 1);
+    let end = input[pos..].find('\n').map_or(input.len(), |i| pos + i);
+    input[start..end].trim_end_matches('\r')
+}
+
+const TIME_SUFFIX_PREFIX: char = '@';
+
+/// Marks a link as pinned when it is next to the link, outside it.
+const PINNED_MARKER: char = '\u{2b50}';
+
+/// Returns true if a [`PINNED_MARKER`] is next to the link of `input` at `range`: just before it,
+/// e.g. `- \u{2b50} [Foo](https://foo.com)`, or just after it or its time suffix, unless it is
+/// also just before another link.
+fn is_pinned(input: &str, range: &Range<usize>) -> bool {
+    let start = input[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let end = input[range.end..]
+        .find('\n')
+        .map_or(input.len(), |i| range.end + i);
+    if input[start..range.start]
+        .trim_end()
+        .ends_with(PINNED_MARKER)
+    {
+        return true;
+    }
+    let mut after = input[range.end..end].trim_start();
+    if parse_time_suffix(after).is_some() {
+        let suffix_end = after.find(char::is_whitespace).unwrap_or(after.len());
+        after = after[suffix_end..].trim_start();
+    }
+    after
+        .strip_prefix(PINNED_MARKER)
+        .is_some_and(|rest| !rest.trim_start().starts_with(['[', '<']))
+}
+
+/// Parses a time-of-day heading, e.g. `14:32`.
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s.trim(), TIME_FORMAT).ok()
+}
+
+/// Parses a time-of-day suffix at the start of the text following a link, e.g. ` @14:32`.
+fn parse_time_suffix(s: &str) -> Option<NaiveTime> {
+    let s = s.trim_start().strip_prefix(TIME_SUFFIX_PREFIX)?;
+    let end = s.find(char::is_whitespace).unwrap_or(s.len());
+    parse_time(&s[..end])
+}
+
 struct ParserState<'a> {
     name: Option<Name>,
+    title: Option<Extended>,
     name_parts: Vec<String>,
     date: Option<DateTime<Utc>>,
+    default_date: Option<DateTime<Utc>>,
+    time: Option<NaiveTime>,
+    link_time: Option<NaiveTime>,
     url: Option<Url>,
     labels: Vec<Label>,
+    /// The number of labels kept from enclosing headings, applied once the current heading turns
+    /// out not to be a time-of-day heading.
+    maybe_label_depth: Option<usize>,
     current_tag: Option<Tag<'a>>,
     current_heading_level: HeadingLevel,
     maybe_parent: Option<Id>,
     parents: Vec<Id>,
+    maybe_last: Option<Id>,
+    /// The entity a blockquote's text is attached to: the last one saved before it started.
+    maybe_quote_owner: Option<Id>,
+    quote: String,
+    quote_depth: usize,
+    /// Whether the events are those of a link in a blockquote.
+    quote_link: bool,
+    raw: Option<&'a str>,
 }
 
 impl<'a> ParserState<'a> {
     fn new() -> ParserState<'a> {
         ParserState {
             name: None,
+            title: None,
             name_parts: Vec::new(),
             date: None,
+            default_date: None,
+            time: None,
+            link_time: None,
             url: None,
             labels: Vec::new(),
+            maybe_label_depth: None,
             current_tag: None,
             current_heading_level: HeadingLevel::H1,
             maybe_parent: None,
             parents: Vec::new(),
+            maybe_last: None,
+            maybe_quote_owner: None,
+            quote: String::new(),
+            quote_depth: 0,
+            quote_link: false,
+            raw: None,
         }
     }
 
     fn reset(&mut self) {
         self.name = None;
+        self.title = None;
         self.name_parts.clear();
         self.date = None;
+        self.time = None;
+        self.link_time = None;
         self.url = None;
         self.labels.clear();
+        self.maybe_label_depth = None;
         self.current_heading_level = HeadingLevel::H1;
         self.maybe_parent = None;
         self.parents.clear();
+        self.maybe_last = None;
+        self.maybe_quote_owner = None;
+        self.quote.clear();
+        self.quote_depth = 0;
+        self.quote_link = false;
+        self.raw = None;
     }
 
     fn save_entity(&mut self, coll: &mut Collection) -> Result<(), Error> {
         let url = self.url.take().ok_or(Error::MissingUrl)?;
-        let date = self.date.ok_or(Error::MissingDate)?;
+        let mut date = self.date.or(self.default_date).ok_or(Error::MissingDate)?;
+        if let Some(time) = self.link_time.take().or(self.time) {
+            date = Utc.from_utc_datetime(&date.date_naive().and_time(time));
+        }
         let name = if self.name_parts.is_empty() {
             self.name.take()
         } else {
@@ -108,30 +250,191 @@ impl<'a> ParserState<'a> {
         let labels = self.labels.iter().cloned().collect();
         let entity = Entity::new(url, date.into(), name, labels);
         let id = coll.upsert(entity);
+        if let Some(raw) = self.raw.take() {
+            let extras = coll.entity_mut(&id).extras_mut();
+            extras.insert(RAW_EXTRA.to_string(), raw.to_string());
+        }
+        if let Some(title) = self.title.take() {
+            let extended = coll.entity_mut(&id).extended_mut();
+            if !extended.contains(&title) {
+                extended.push(title);
+            }
+        }
         if let Some(parent) = self.parents.last() {
             coll.add_edges(parent, &id);
         }
-        self.maybe_parent = Some(id);
+        self.maybe_parent = Some(id.clone());
+        self.maybe_last = Some(id);
+        Ok(())
+    }
+
+    /// Accumulates blockquote content, attaching it to the entity saved before the blockquote
+    /// started as extended text once the outermost blockquote ends.
+    fn quote_event(&mut self, event: &Event, coll: &mut Collection) {
+        match event {
+            Event::Start(Tag::BlockQuote(_)) => {
+                if self.quote_depth == 0 {
+                    self.maybe_quote_owner.clone_from(&self.maybe_last);
+                }
+                self.quote_depth += 1;
+            }
+            Event::End(TagEnd::BlockQuote(_)) => {
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+                if self.quote_depth == 0 {
+                    self.save_quote(coll);
+                }
+            }
+            Event::Text(text) => self.quote.push_str(text),
+            Event::Code(text) => {
+                let _ = write!(self.quote, "`{text}`");
+            }
+            Event::SoftBreak | Event::HardBreak => self.quote.push('\n'),
+            Event::End(TagEnd::Paragraph) => self.quote.push_str("\n\n"),
+            _ => {}
+        }
+    }
+
+    /// Passes `event` to [`ParserState::quote_event`] if it is part of a blockquote, returning
+    /// true if it should not also be handled as a bookmark. Links in a blockquote are bookmarks
+    /// too, so their events are handled both ways.
+    fn in_quote(
+        &mut self,
+        event: &Event,
+        coll: &mut Collection,
+        limits: &Limits,
+    ) -> Result<bool, Error> {
+        let starts_quote = matches!(event, Event::Start(Tag::BlockQuote(_)));
+        if self.quote_depth == 0 && !starts_quote {
+            return Ok(false);
+        }
+        if starts_quote {
+            limits.check_depth(self.quote_depth + 1)?;
+        }
+        self.quote_event(event, coll);
+        limits.check_field("extended note", &self.quote)?;
+        let ends_link = self.quote_link && matches!(event, Event::End(TagEnd::Link));
+        self.quote_link = !ends_link
+            && (self.quote_link
+                || matches!(
+                    event,
+                    Event::Start(Tag::Link {
+                        link_type: LinkType::Inline | LinkType::Autolink,
+                        ..
+                    })
+                ));
+        Ok(!self.quote_link && !ends_link)
+    }
+
+    /// Handles text, which is a date in an H1 heading, a time of day or a label in other headings,
+    /// and part of the name in an inline link.
+    fn text(&mut self, text: &str, maybe_locale: Option<Locale>) -> Result<(), Error> {
+        match (&self.current_tag, self.current_heading_level) {
+            (Some(Tag::Heading { .. }), HeadingLevel::H1) => {
+                self.date = Some(parse_date(text, maybe_locale)?);
+            }
+            (Some(Tag::Heading { .. }), _) => {
+                let maybe_label_depth = self.maybe_label_depth.take();
+                if let Some(time) = parse_time(text) {
+                    self.time = Some(time);
+                } else {
+                    if let Some(depth) = maybe_label_depth {
+                        self.labels.truncate(depth);
+                    }
+                    self.labels.push(Label::new(text.to_string()));
+                }
+            }
+            (
+                Some(Tag::Link {
+                    link_type: LinkType::Inline,
+                    ..
+                }),
+                _,
+            ) => {
+                self.name_parts.push(text.to_string());
+            }
+            _ => {}
+        }
         Ok(())
     }
+
+    fn save_quote(&mut self, coll: &mut Collection) {
+        let maybe_owner = self.maybe_quote_owner.take();
+        let quote = std::mem::take(&mut self.quote);
+        let quote = quote.trim();
+        if let Some(owner) = &maybe_owner {
+            self.maybe_last = Some(owner.clone());
+        }
+        if quote.is_empty() {
+            return;
+        }
+        if let Some(id) = &maybe_owner {
+            let extended = Extended::new(quote.to_string());
+            coll.entity_mut(id).extended_mut().push(extended);
+        }
+    }
 }
 
 impl Collection {
     /// Parses a markdown document into a collection of entities.
     ///
     /// The markdown format expects H1 headings with dates, links with optional labels from H2+ headings,
-    /// and supports nested list structures for parent-child relationships.
+    /// and supports nested list structures for parent-child relationships. A blockquote following a
+    /// link becomes that entity's extended text, with line breaks preserved, as does a link title
+    /// (e.g. `[Foo](https://foo.com "A title")`).
+    ///
+    /// Bookmarks are created at midnight on their date, unless a time of day is given, either by a
+    /// heading (e.g. `## 14:32`), which applies to the bookmarks after it until the next time or
+    /// date heading, or by a suffix after a link (e.g. `[Foo](https://foo.com) @14:32`), which
+    /// applies to that link only.
+    ///
+    /// A link with a star (`\u{2b50}`) next to it is pinned.
     ///
     /// # Errors
     ///
     /// Returns an error if the markdown contains invalid dates, malformed URLs, or missing required information.
     pub fn from_markdown(input: &str) -> Result<Collection, Error> {
-        let parser = Parser::new(input);
-
         let mut coll = Collection::new();
+        coll.extend_from_markdown(input)?;
+        Ok(coll)
+    }
+
+    /// Parses markdown as in [`Collection::from_markdown`], merging its bookmarks into this
+    /// collection with [`Collection::upsert`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the markdown contains invalid dates, malformed URLs, or missing required
+    /// information. Bookmarks parsed before the error remain in the collection.
+    pub fn extend_from_markdown(&mut self, input: &str) -> Result<(), Error> {
+        self.extend_from_markdown_with(input, &Options::default())
+    }
+
+    /// Like [`Collection::extend_from_markdown`], but handles invalid URLs and bookmarks without
+    /// a date heading according to `options`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the markdown contains invalid dates, malformed URLs that the policy
+    /// does not keep, or missing required information.
+    pub fn extend_from_markdown_with(
+        &mut self,
+        input: &str,
+        options: &Options,
+    ) -> Result<(), Error> {
+        let url_policy = options.url_policy;
+        let limits = &options.limits;
+        let mut list_depth = 0_usize;
+        let mut count = 0_usize;
+        let mut events = Parser::new(input).into_offset_iter().peekable();
+
         let mut state = ParserState::new();
+        state.default_date = options.default_date.map(midnight).transpose()?;
+
+        while let Some((event, range)) = events.next() {
+            if state.in_quote(&event, self, limits)? {
+                continue;
+            }
 
-        for event in parser {
             match event {
                 // Start
                 Event::Start(
@@ -147,9 +450,11 @@ impl Collection {
                     state.current_tag = Some(tag);
                     state.current_heading_level = level;
                     let level = usize::from(HeadingLevelExt::from(level));
-                    state.labels.truncate(level - 2);
+                    state.maybe_label_depth = Some(level - 2);
                 }
                 Event::Start(tag @ Tag::List(_)) => {
+                    list_depth += 1;
+                    limits.check_depth(list_depth)?;
                     state.current_tag = Some(tag);
                     if let Some(parent) = state.maybe_parent.as_ref() {
                         state.parents.push(parent.clone());
@@ -157,51 +462,26 @@ impl Collection {
                 }
                 Event::Start(
                     ref tag @ Tag::Link {
-                        link_type: LinkType::Inline,
-                        ref dest_url,
-                        ..
-                    },
-                ) => {
-                    state.current_tag = Some(tag.to_owned());
-                    state.name_parts.clear();
-                    state.url = Some(Url::parse(dest_url)?);
-                }
-                Event::Start(
-                    ref tag @ Tag::Link {
-                        link_type: LinkType::Autolink,
+                        link_type: link_type @ (LinkType::Inline | LinkType::Autolink),
                         ref dest_url,
+                        ref title,
                         ..
                     },
                 ) => {
+                    if link_type == LinkType::Autolink {
+                        state.name = None;
+                    }
                     state.current_tag = Some(tag.to_owned());
-                    state.name = None;
                     state.name_parts.clear();
-                    state.url = Some(Url::parse(dest_url)?);
+                    state.url = Some(url_policy.parse(dest_url)?);
+                    state.raw = options.keep_raw.then(|| line_at(input, range.start));
+                    state.title = (!title.is_empty()).then(|| Extended::new(title.to_string()));
                 }
                 Event::Start(tag) => {
                     state.current_tag = Some(tag);
                 }
                 // Text
-                Event::Text(text) => match (&state.current_tag, state.current_heading_level) {
-                    (Some(Tag::Heading { .. }), HeadingLevel::H1) => {
-                        let parsed = parse_date(text.as_ref())?;
-                        state.date = Some(parsed);
-                    }
-                    (Some(Tag::Heading { .. }), _) => {
-                        let label = Label::new(text.to_string());
-                        state.labels.push(label);
-                    }
-                    (
-                        Some(Tag::Link {
-                            link_type: LinkType::Inline,
-                            ..
-                        }),
-                        _,
-                    ) => {
-                        state.name_parts.push(text.to_string());
-                    }
-                    _ => {}
-                },
+                Event::Text(text) => state.text(&text, options.locale)?,
                 // Code (for handling backticks in link text)
                 Event::Code(text) => {
                     if let Some(Tag::Link {
@@ -214,16 +494,379 @@ impl Collection {
                 }
                 // End
                 Event::End(TagEnd::List(_)) => {
+                    list_depth = list_depth.saturating_sub(1);
                     let _ = state.parents.pop();
                     state.maybe_parent = None;
                 }
+                Event::End(TagEnd::Heading(_)) => {
+                    if let Some(depth) = state.maybe_label_depth.take() {
+                        state.labels.truncate(depth);
+                    }
+                }
                 Event::End(TagEnd::Link) => {
-                    state.save_entity(&mut coll)?;
+                    if let Some((Event::Text(text), _)) = events.peek() {
+                        state.link_time = parse_time_suffix(text);
+                    }
+                    count += 1;
+                    limits.check_entities(count)?;
+                    state.save_entity(self)?;
+                    if let Some(id) = &state.maybe_last {
+                        if is_pinned(input, &range) {
+                            self.entity_mut(id).set_pinned(true);
+                        }
+                        limits.check_entity(self.entity(id))?;
+                    }
                 }
                 _ => {}
             }
         }
 
-        Ok(coll)
+        Ok(())
+    }
+}
+
+/// Escapes the characters of `s` that markdown would read as formatting.
+fn escape(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '[' | ']' | '*' | '_' | '`' | '<' | '>' | '#') {
+            ret.push('\\');
+        }
+        ret.push(c);
+    }
+    ret
+}
+
+impl Collection {
+    /// Writes the collection as markdown that [`Collection::from_markdown`] reads back.
+    ///
+    /// Entities are grouped under H1 headings of their dates and then under headings of their
+    /// labels, an H2 for the first label, an H3 for the second, and so on. Each is a list item
+    /// linking to its URL with its first name, starred if it is pinned, and followed by a time
+    /// suffix unless it was created at midnight and by a blockquote for each extended
+    /// description. Other names, edges, seconds and labels after the fifth are not written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the output fails.
+    pub fn to_markdown(&self, writer: &mut impl Write) -> Result<(), Error> {
+        let mut order: Vec<(NaiveDate, Vec<&Label>, &Entity)> = self
+            .entities()
+            .iter()
+            .map(|entity| {
+                let created_at = entity.created_at().get();
+                (created_at.date(), entity.labels().iter().collect(), entity)
+            })
+            .collect();
+        order.sort_by(|(a_date, a_labels, a), (b_date, b_labels, b)| {
+            (a_date, a_labels, a.created_at(), a.url()).cmp(&(
+                b_date,
+                b_labels,
+                b.created_at(),
+                b.url(),
+            ))
+        });
+        let mut maybe_group: Option<(NaiveDate, &[&Label])> = None;
+        // Whether the output so far ends with a blank line, as it should before a heading.
+        let mut spaced = true;
+        for (date, labels, entity) in &order {
+            if maybe_group.is_none_or(|group| group != (*date, labels.as_slice())) {
+                if !spaced {
+                    writeln!(writer)?;
+                }
+                if maybe_group.is_none_or(|(prev_date, _)| prev_date != *date) {
+                    writeln!(writer, "# {}\n", date.format(DATE_FORMAT))?;
+                }
+                for (depth, label) in labels.iter().enumerate() {
+                    let hashes = "#".repeat((depth + 2).min(6));
+                    writeln!(writer, "{hashes} {}\n", escape(label.as_str()))?;
+                }
+            }
+            maybe_group = Some((*date, labels));
+            write_item(writer, entity)?;
+            spaced = !entity.extended().is_empty();
+        }
+        Ok(())
+    }
+}
+
+/// Writes `entity` as a list item, as described in [`Collection::to_markdown`].
+fn write_item(writer: &mut impl Write, entity: &Entity) -> Result<(), Error> {
+    write!(writer, "- ")?;
+    if entity.is_pinned() {
+        write!(writer, "{PINNED_MARKER} ")?;
+    }
+    let url = entity.url().as_str();
+    match entity.names().first() {
+        Some(name) => write!(writer, "[{}](<{url}>)", escape(name.as_str()))?,
+        None => write!(writer, "<{url}>")?,
+    }
+    let time = entity.created_at().get().time_of_day();
+    if time != NaiveTime::MIN {
+        write!(writer, " {TIME_SUFFIX_PREFIX}{}", time.format(TIME_FORMAT))?;
+    }
+    writeln!(writer)?;
+    for extended in entity.extended() {
+        writeln!(writer)?;
+        for line in extended.as_str().lines() {
+            if line.is_empty() {
+                writeln!(writer, "  >")?;
+            } else {
+                writeln!(writer, "  > {}", escape(line))?;
+            }
+        }
+    }
+    if !entity.extended().is_empty() {
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{
+        collection::Collection,
+        entity::{Extended, RAW_EXTRA, Url},
+        limits::{self, Limits},
+        locale::Locale,
+    };
+
+    use super::{Error, FILE_NAME_DATE_PATTERN, Options, date_from_file_name};
+
+    #[test]
+    fn blockquote_becomes_extended() {
+        let input = "\
+# November 15, 2023
+
+- [Foo](https://foo.com/)
+  > first line
+  > second line
+- [Bar](https://bar.com/)
+";
+        let coll = Collection::from_markdown(input).unwrap();
+
+        let foo = coll.id(&Url::parse("https://foo.com/").unwrap()).unwrap();
+        assert_eq!(
+            coll.entity(&foo).extended(),
+            [Extended::from("first line\nsecond line")]
+        );
+
+        let bar = coll.id(&Url::parse("https://bar.com/").unwrap()).unwrap();
+        assert!(coll.entity(&bar).extended().is_empty());
+    }
+
+    #[test]
+    fn links_in_blockquotes_are_kept() {
+        let input = "\
+# November 15, 2023
+
+- [Foo](https://foo.com/)
+  > see [Bar](https://bar.com/) and <https://baz.com/>
+";
+        let coll = Collection::from_markdown(input).unwrap();
+        assert_eq!(coll.len(), 3);
+
+        let foo = coll.id(&Url::parse("https://foo.com/").unwrap()).unwrap();
+        assert_eq!(
+            coll.entity(&foo).extended(),
+            [Extended::from("see Bar and https://baz.com/")]
+        );
+
+        let bar = coll.id(&Url::parse("https://bar.com/").unwrap()).unwrap();
+        assert_eq!(coll.entity(&bar).names().first().unwrap().as_str(), "Bar");
+        assert!(coll.entity(&bar).extended().is_empty());
+        assert!(coll.id(&Url::parse("https://baz.com/").unwrap()).is_some());
+    }
+
+    #[test]
+    fn link_title_becomes_extended() {
+        let input = "\
+# November 15, 2023
+
+- [Foo](https://foo.com/ \"A title\")
+";
+        let coll = Collection::from_markdown(input).unwrap();
+
+        let foo = coll.id(&Url::parse("https://foo.com/").unwrap()).unwrap();
+        assert_eq!(coll.entity(&foo).extended(), [Extended::from("A title")]);
+    }
+
+    #[test]
+    fn time_of_day_is_preserved() {
+        let input = "\
+# November 15, 2023
+
+## rust
+
+- [Foo](https://foo.com/)
+
+## 14:32
+
+- [Bar](https://bar.com/)
+- [Baz](https://baz.com/) @09:05 via someone
+";
+        let coll = Collection::from_markdown(input).unwrap();
+
+        let times: Vec<String> = coll
+            .entities()
+            .iter()
+            .map(|entity| entity.created_at().get().to_string())
+            .collect();
+        let labels: Vec<usize> = coll.entities().iter().map(|e| e.labels().len()).collect();
+        assert_eq!(
+            times,
+            [
+                "2023-11-15T00:00:00Z",
+                "2023-11-15T14:32:00Z",
+                "2023-11-15T09:05:00Z"
+            ]
+        );
+        assert_eq!(labels, [1, 1, 1]);
+    }
+
+    #[test]
+    fn date_can_come_from_file_name() {
+        let input = "- [Foo](https://foo.com/)\n";
+        assert!(matches!(
+            Collection::from_markdown(input),
+            Err(Error::MissingDate)
+        ));
+
+        let path = Path::new("journal/2023-11-15-reading.md");
+        let options = Options {
+            default_date: date_from_file_name(path, FILE_NAME_DATE_PATTERN),
+            ..Options::default()
+        };
+        let mut coll = Collection::new();
+        coll.extend_from_markdown_with(input, &options).unwrap();
+        assert_eq!(
+            coll.entities()[0].created_at().get().to_string(),
+            "2023-11-15T00:00:00Z"
+        );
+        assert_eq!(date_from_file_name(Path::new("notes.md"), "%Y-%m-%d"), None);
+    }
+
+    #[test]
+    fn date_headings_can_be_localized() {
+        let input = "# 15 de noviembre de 2023\n\n- [Foo](https://foo.com/)\n";
+        assert!(matches!(
+            Collection::from_markdown(input),
+            Err(Error::ParseDate(..))
+        ));
+
+        let options = Options {
+            locale: Some(Locale::Es),
+            ..Options::default()
+        };
+        let mut coll = Collection::new();
+        coll.extend_from_markdown_with(input, &options).unwrap();
+        assert_eq!(
+            coll.entities()[0].created_at().get().to_string(),
+            "2023-11-15T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn raw_line_is_kept() {
+        let input = "# November 15, 2023\n\n- [Foo](https://foo.com/) @14:32\n";
+        let options = Options {
+            keep_raw: true,
+            ..Options::default()
+        };
+        let mut coll = Collection::new();
+        coll.extend_from_markdown_with(input, &options).unwrap();
+        assert_eq!(
+            coll.entities()[0]
+                .extras()
+                .get(RAW_EXTRA)
+                .map(String::as_str),
+            Some("- [Foo](https://foo.com/) @14:32")
+        );
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let input = "\
+# November 15, 2023
+
+- [Foo](https://foo.com/)
+  - [Bar](https://bar.com/)
+    - [Baz](https://baz.com/)
+";
+        let mut options = Options {
+            limits: Limits {
+                max_depth: 2,
+                ..Limits::default()
+            },
+            ..Options::default()
+        };
+        let mut coll = Collection::new();
+        assert!(matches!(
+            coll.extend_from_markdown_with(input, &options),
+            Err(Error::Limit(limits::Error::Depth(2)))
+        ));
+
+        options.limits.max_depth = 3;
+        let mut coll = Collection::new();
+        coll.extend_from_markdown_with(input, &options).unwrap();
+        assert_eq!(coll.len(), 3);
+    }
+
+    #[test]
+    fn star_pins_the_adjacent_link() {
+        let input = "\
+# November 15, 2023
+
+- \u{2b50} [Foo](https://foo.com/) [Bar](https://bar.com/)
+- [Baz](https://baz.com/) @14:32 \u{2b50}
+- [Qux](https://qux.com/) \u{2b50} [Quux](https://quux.com/)
+";
+        let coll = Collection::from_markdown(input).unwrap();
+        let pinned: Vec<&str> = coll
+            .entities()
+            .iter()
+            .filter(|entity| entity.is_pinned())
+            .map(|entity| entity.url().as_str())
+            .collect();
+        assert_eq!(
+            pinned,
+            ["https://foo.com/", "https://baz.com/", "https://quux.com/"]
+        );
+    }
+
+    #[test]
+    fn markdown_round_trips() {
+        let input = "\
+# November 15, 2023
+
+- \u{2b50} [Foo \\[1\\]](<https://foo.com/>) @14:32
+
+  > first line
+  > second line
+
+## async
+
+### rust
+
+- <https://bar.com/>
+
+# November 16, 2023
+
+- [Baz](<https://baz.com/(x)>)
+";
+        let coll = Collection::from_markdown(input).unwrap();
+        let mut output = Vec::new();
+        coll.to_markdown(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output, input);
+
+        let reparsed = Collection::from_markdown(&output).unwrap();
+        assert_eq!(reparsed, coll);
+        let foo = reparsed
+            .id(&Url::parse("https://foo.com/").unwrap())
+            .unwrap();
+        assert!(reparsed.entity(&foo).is_pinned());
     }
 }
@@ -0,0 +1,262 @@
+use std::{
+    collections::HashMap,
+    sync::{Condvar, Mutex, MutexGuard, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+use ureq::Agent;
+
+use crate::entity::Url;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("HTTP error: {0}")]
+    Http(#[from] ureq::Error),
+}
+
+/// The `User-Agent` header sent with every request.
+pub const USER_AGENT: &str = concat!("hbt/", env!("CARGO_PKG_VERSION"));
+
+/// Limits on how a [`Client`] makes requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// The maximum number of requests in flight at once, across all hosts.
+    pub max_concurrency: usize,
+    /// The number of times a request is retried after a transient failure.
+    pub max_retries: u32,
+    /// The delay before the first retry, doubled before each subsequent retry.
+    pub initial_backoff: Duration,
+    /// The longest delay between retries.
+    pub max_backoff: Duration,
+    /// The minimum time between the start of two requests to the same host.
+    pub per_host_delay: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            max_concurrency: 4,
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            per_host_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl Config {
+    /// Returns the delay before retry number `attempt`, counting from zero.
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt).unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Returns whether a request that failed with `err` may succeed if retried.
+fn is_transient(err: &ureq::Error) -> bool {
+    matches!(
+        err,
+        ureq::Error::StatusCode(429 | 500..=599)
+            | ureq::Error::Io(_)
+            | ureq::Error::Timeout(_)
+            | ureq::Error::ConnectionFailed
+    )
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// A slot for one request in flight, released when dropped.
+struct Permit<'a> {
+    client: &'a Client,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *lock(&self.client.in_flight) -= 1;
+        self.client.released.notify_one();
+    }
+}
+
+/// An HTTP client shared by the features that fetch pages, which limits concurrency, waits
+/// between requests to the same host, and retries transient failures with exponential backoff.
+///
+/// A `Client` can be shared between threads; the limits apply to all of them.
+pub struct Client {
+    agent: Agent,
+    config: Config,
+    in_flight: Mutex<usize>,
+    released: Condvar,
+    next_request: Mutex<HashMap<String, Instant>>,
+}
+
+impl Client {
+    #[must_use]
+    pub fn new(config: Config) -> Client {
+        let agent = Agent::new_with_config(Agent::config_builder().user_agent(USER_AGENT).build());
+        Client {
+            agent,
+            config,
+            in_flight: Mutex::new(0),
+            released: Condvar::new(),
+            next_request: Mutex::new(HashMap::new()),
+        }
+    }
+
+    #[must_use]
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    fn acquire(&self) -> Permit<'_> {
+        let max = self.config.max_concurrency.max(1);
+        let mut in_flight = lock(&self.in_flight);
+        while *in_flight >= max {
+            in_flight = self
+                .released
+                .wait(in_flight)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        *in_flight += 1;
+        Permit { client: self }
+    }
+
+    /// Reserves the next slot for a request to `url`'s host, returning how long to wait for it.
+    fn reserve(&self, url: &Url) -> Duration {
+        let host = url.as_ref().host_str().unwrap_or_default().to_string();
+        let now = Instant::now();
+        let mut next_request = lock(&self.next_request);
+        let slot = next_request.get(&host).map_or(now, |next| (*next).max(now));
+        next_request.insert(host, slot + self.config.per_host_delay);
+        slot - now
+    }
+
+    /// Fetches `url`, returning the response body.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails with a non-transient error, or still fails after
+    /// [`Config::max_retries`] retries.
+    pub fn get(&self, url: &Url) -> Result<String, Error> {
+        self.get_with(url, || {
+            self.agent
+                .get(url.as_str())
+                .call()
+                .and_then(|mut response| response.body_mut().read_to_string())
+        })
+    }
+
+    /// Makes a request to `url` with `fetch`, within the client's limits. A slot for the request
+    /// is only taken once any wait for its host or backoff is over, so that waiting requests do
+    /// not hold up requests to other hosts.
+    fn get_with<T>(
+        &self,
+        url: &Url,
+        mut fetch: impl FnMut() -> Result<T, ureq::Error>,
+    ) -> Result<T, Error> {
+        let mut attempt = 0;
+        loop {
+            thread::sleep(self.reserve(url));
+            let result = {
+                let _permit = self.acquire();
+                fetch()
+            };
+            match result {
+                Ok(ret) => return Ok(ret),
+                Err(err) if attempt < self.config.max_retries && is_transient(&err) => {
+                    thread::sleep(self.config.backoff(attempt));
+                    attempt += 1;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+}
+
+impl Default for Client {
+    fn default() -> Client {
+        Client::new(Config::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::entity::Url;
+
+    use super::{Client, Config};
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let config = Config::default();
+        assert_eq!(config.backoff(0), Duration::from_millis(500));
+        assert_eq!(config.backoff(1), Duration::from_secs(1));
+        assert_eq!(config.backoff(2), Duration::from_secs(2));
+        assert_eq!(config.backoff(10), Duration::from_secs(30));
+        assert_eq!(config.backoff(40), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn requests_to_the_same_host_are_spaced() {
+        let client = Client::default();
+        let a = Url::parse("https://a.example.com/1").unwrap();
+        let b = Url::parse("https://b.example.com/1").unwrap();
+        assert_eq!(client.reserve(&a), Duration::ZERO);
+        assert_eq!(client.reserve(&b), Duration::ZERO);
+        let wait = client.reserve(&a);
+        assert!(wait > Duration::ZERO && wait <= client.config().per_host_delay);
+    }
+
+    /// Asserts that a request to `other` is made straight away while `waiting` is under way.
+    fn assert_not_held_up(client: &Client, waiting: impl FnOnce() + Send, other: &Url) {
+        thread::scope(|scope| {
+            scope.spawn(waiting);
+            thread::sleep(Duration::from_millis(100));
+            let start = Instant::now();
+            client.get_with(other, || Ok(())).unwrap();
+            assert!(start.elapsed() < Duration::from_millis(200));
+        });
+    }
+
+    #[test]
+    fn waiting_requests_do_not_hold_up_other_hosts() {
+        let wait = Duration::from_millis(400);
+        let client = Client::new(Config {
+            max_concurrency: 1,
+            initial_backoff: wait,
+            per_host_delay: wait,
+            ..Config::default()
+        });
+        let a = Url::parse("https://a.example.com/").unwrap();
+        let b = Url::parse("https://b.example.com/").unwrap();
+        let c = Url::parse("https://c.example.com/").unwrap();
+
+        // A request waiting for its host
+        let _ = client.reserve(&a);
+        assert_not_held_up(&client, || client.get_with(&a, || Ok(())).unwrap(), &b);
+
+        // A request backing off after a transient failure
+        let mut failed = false;
+        let fail_once = || {
+            if failed {
+                Ok(())
+            } else {
+                failed = true;
+                Err(ureq::Error::ConnectionFailed)
+            }
+        };
+        thread::sleep(wait);
+        assert_not_held_up(&client, || client.get_with(&a, fail_once).unwrap(), &c);
+        assert!(failed);
+    }
+}
@@ -0,0 +1,146 @@
+use std::convert::Infallible;
+
+use crate::{collection::Collection, entity::Url};
+
+fn hex_value(byte: u8) -> Option<u8> {
+    char::from(byte)
+        .to_digit(16)
+        .and_then(|digit| u8::try_from(digit).ok())
+}
+
+/// Returns true for the characters RFC 3986 calls unreserved, which never need escaping.
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+/// Decodes percent-escaped unreserved characters and uppercases the hex digits of all other
+/// escapes, so that equivalent encodings compare equal.
+fn normalize_escapes(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut ret = String::with_capacity(s.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(&[hi, lo]) = bytes.get(i + 1..i + 3)
+            && let (Some(hi_value), Some(lo_value)) = (hex_value(hi), hex_value(lo))
+        {
+            let byte = (hi_value << 4) | lo_value;
+            if is_unreserved(byte) {
+                ret.push(char::from(byte));
+            } else {
+                ret.push('%');
+                ret.push(char::from(hi.to_ascii_uppercase()));
+                ret.push(char::from(lo.to_ascii_uppercase()));
+            }
+            i += 3;
+        } else {
+            ret.push(char::from(bytes[i]));
+            i += 1;
+        }
+    }
+    ret
+}
+
+/// Normalizes a URL so that equivalent URLs compare equal.
+///
+/// Parsing already lowercases the scheme and host, converts internationalized domain names to
+/// their punycode form, and drops default ports. This additionally normalizes percent-encoding in
+/// the path, query and fragment: escaped unreserved characters are decoded, and the hex digits of
+/// the remaining escapes are uppercased.
+#[must_use]
+pub fn normalize(url: &Url) -> Url {
+    let mut ret: url::Url = url.as_ref().clone();
+    ret.set_path(&normalize_escapes(url.as_ref().path()));
+    if let Some(query) = url.as_ref().query() {
+        ret.set_query(Some(&normalize_escapes(query)));
+    }
+    if let Some(fragment) = url.as_ref().fragment() {
+        ret.set_fragment(Some(&normalize_escapes(fragment)));
+    }
+    Url::from(ret)
+}
+
+impl Collection {
+    /// Normalizes the URL of every entity. See [`normalize`].
+    ///
+    /// Entities whose normalized URLs collide are merged. See [`Collection::map_urls`].
+    pub fn normalize_urls(&mut self) {
+        let Ok(()) = self.map_urls(|url| Ok::<Url, Infallible>(normalize(url)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Time, Url},
+    };
+
+    use super::normalize;
+
+    fn normalized(s: &str) -> String {
+        normalize(&Url::parse(s).unwrap()).as_str().to_string()
+    }
+
+    #[test]
+    fn unicode_domains_use_punycode() {
+        assert_eq!(
+            normalized("https://bücher.de/"),
+            "https://xn--bcher-kva.de/"
+        );
+        assert_eq!(
+            normalized("https://BÜCHER.de"),
+            normalized("https://xn--bcher-kva.de/")
+        );
+    }
+
+    #[test]
+    fn percent_escapes_are_normalized() {
+        assert_eq!(
+            normalized("https://example.com/a%2fb?q=%3a#%e2%9c%93"),
+            "https://example.com/a%2Fb?q=%3A#%E2%9C%93"
+        );
+        assert_eq!(
+            normalized("https://example.com/%7Euser/%41%2D"),
+            "https://example.com/~user/A-"
+        );
+    }
+
+    #[test]
+    fn default_ports_are_dropped() {
+        assert_eq!(
+            normalized("https://example.com:443/"),
+            "https://example.com/"
+        );
+        assert_eq!(normalized("http://example.com:80"), "http://example.com/");
+        assert_eq!(
+            normalized("http://example.com:8080/"),
+            "http://example.com:8080/"
+        );
+    }
+
+    #[test]
+    fn equivalent_urls_are_merged() {
+        let time = Time::from_timestamp(0).unwrap();
+        let mut coll = Collection::from_entities([
+            Entity::new(
+                Url::parse("https://bücher.de/%7efoo").unwrap(),
+                time,
+                None,
+                BTreeSet::new(),
+            ),
+            Entity::new(
+                Url::parse("https://xn--bcher-kva.de:443/~foo").unwrap(),
+                time,
+                None,
+                BTreeSet::new(),
+            ),
+        ]);
+        assert_eq!(coll.len(), 2);
+
+        coll.normalize_urls();
+        assert_eq!(coll.len(), 1);
+    }
+}
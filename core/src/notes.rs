@@ -0,0 +1,345 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Component, Path, PathBuf},
+};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use pulldown_cmark::{Event, HeadingLevel, Parser, Tag, TagEnd};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    collection::{Collection, Id},
+    entity::{self, Entity, Label, Name, Time, Url},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Entity(#[from] entity::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("front matter error in {1}: {0}")]
+    FrontMatter(#[source] serde_norway::Error, String),
+
+    #[error("invalid note path: {0}")]
+    InvalidPath(String),
+}
+
+const NOTE_EXT: &str = "md";
+const FRONT_MATTER_DELIMITER: &str = "---";
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Tags {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Default for Tags {
+    fn default() -> Tags {
+        Tags::Many(Vec::new())
+    }
+}
+
+impl Tags {
+    fn into_labels(self) -> impl Iterator<Item = Label> {
+        let tags = match self {
+            Tags::One(tags) => tags
+                .split([',', ' '])
+                .map(ToOwned::to_owned)
+                .collect::<Vec<_>>(),
+            Tags::Many(tags) => tags,
+        };
+        tags.into_iter().filter_map(|tag| {
+            let tag = tag.trim().trim_start_matches('#');
+            (!tag.is_empty()).then(|| Label::from(tag))
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FrontMatter {
+    url: Option<String>,
+    title: Option<String>,
+    #[serde(default)]
+    tags: Tags,
+    created: Option<String>,
+}
+
+/// Splits a note into its YAML front matter (if any) and body.
+fn split_front_matter(contents: &str) -> (Option<&str>, &str) {
+    let Some(rest) = contents
+        .strip_prefix(FRONT_MATTER_DELIMITER)
+        .and_then(|rest| {
+            rest.strip_prefix('\n')
+                .or_else(|| rest.strip_prefix("\r\n"))
+        })
+    else {
+        return (None, contents);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == FRONT_MATTER_DELIMITER {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, contents)
+}
+
+fn parse_created(created: &str) -> Result<Time, entity::Error> {
+    match NaiveDate::parse_from_str(created.trim(), "%Y-%m-%d") {
+        Ok(date) => Ok(Time::new(date.and_time(chrono::NaiveTime::MIN).and_utc())),
+        Err(_) => Time::parse_flexible(created),
+    }
+}
+
+/// Decodes `%XX` escapes, leaving the input unchanged if the result is not valid UTF-8.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut ret = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = s.get(i + 1..i + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            ret.push(byte);
+            i += 3;
+        } else {
+            ret.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(ret).unwrap_or_else(|_| s.to_string())
+}
+
+/// Resolves `.` and `..` components without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut ret = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                ret.pop();
+            }
+            component => ret.push(component),
+        }
+    }
+    ret
+}
+
+#[derive(Debug)]
+enum Link {
+    Wiki(String),
+    Relative(PathBuf),
+}
+
+/// Extracts `[[wiki links]]` (including `![[embeds]]`), ignoring aliases and heading anchors.
+fn wiki_links(body: &str) -> impl Iterator<Item = Link> + '_ {
+    body.split("[[").skip(1).filter_map(|rest| {
+        let (inner, _) = rest.split_once("]]")?;
+        let target = inner.split(['|', '#']).next()?.trim();
+        (!target.is_empty()).then(|| Link::Wiki(target.to_string()))
+    })
+}
+
+/// Extracts relative links to other notes, along with the first H1 heading of the note.
+fn markdown_links(body: &str) -> (Vec<Link>, Option<String>) {
+    let mut links = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut in_h1 = false;
+    for event in Parser::new(body) {
+        match event {
+            Event::Start(Tag::Link { dest_url, .. }) => {
+                let dest = dest_url.split('#').next().unwrap_or_default();
+                if !dest.contains("://")
+                    && Path::new(dest)
+                        .extension()
+                        .is_some_and(|ext| ext == NOTE_EXT)
+                {
+                    links.push(Link::Relative(PathBuf::from(percent_decode(dest))));
+                }
+            }
+            Event::Start(Tag::Heading {
+                level: HeadingLevel::H1,
+                ..
+            }) if heading.is_none() => in_h1 = true,
+            Event::Text(text) if in_h1 => heading.get_or_insert_default().push_str(&text),
+            Event::End(TagEnd::Heading(_)) => in_h1 = false,
+            _ => {}
+        }
+    }
+    (links, heading)
+}
+
+/// Collects the files under `dir` with one of the given extensions, skipping hidden files and
+/// directories.
+pub(crate) fn walk(dir: &Path, extensions: &[&str], paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            walk(&path, extensions, paths)?;
+        } else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| extensions.contains(&ext))
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+struct Note {
+    id: Id,
+    dir: PathBuf,
+    links: Vec<Link>,
+}
+
+impl Collection {
+    /// Imports a directory of markdown notes, such as an Obsidian vault or Zettelkasten.
+    ///
+    /// Each note becomes an entity. Its URL, title, tags and creation date are taken from YAML
+    /// front matter when present; otherwise the note's `file://` URL, first H1 heading (or file
+    /// name) and modification time are used. `[[wiki links]]` and relative markdown links between
+    /// notes become edges from the linking note to the linked one. Hidden files and directories
+    /// are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read or a note has invalid front matter.
+    pub fn from_notes(root: &Path) -> Result<Collection, Error> {
+        let root = root.canonicalize()?;
+        let mut paths = Vec::new();
+        walk(&root, &[NOTE_EXT], &mut paths)?;
+        paths.sort();
+
+        let mut coll = Collection::with_capacity(paths.len());
+        let mut notes = Vec::with_capacity(paths.len());
+        let mut by_stem: HashMap<String, Id> = HashMap::new();
+        let mut by_path: HashMap<PathBuf, Id> = HashMap::new();
+
+        for path in paths {
+            let display = path.display().to_string();
+            let contents = fs::read_to_string(&path)?;
+            let (front_matter, body) = split_front_matter(&contents);
+            let front_matter: FrontMatter = match front_matter {
+                Some(yaml) if !yaml.trim().is_empty() => serde_norway::from_str(yaml)
+                    .map_err(|err| Error::FrontMatter(err, display.clone()))?,
+                _ => FrontMatter::default(),
+            };
+
+            let (mut links, heading) = markdown_links(body);
+            links.extend(wiki_links(body));
+
+            let stem = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .ok_or_else(|| Error::InvalidPath(display.clone()))?
+                .to_string();
+
+            let url = match front_matter.url {
+                Some(url) => Url::parse(&url)?,
+                None => url::Url::from_file_path(&path)
+                    .map(Url::from)
+                    .map_err(|()| Error::InvalidPath(display.clone()))?,
+            };
+            let created_at = match front_matter.created {
+                Some(created) => parse_created(&created)?,
+                None => Time::new(DateTime::<Utc>::from(fs::metadata(&path)?.modified()?)),
+            };
+            let name = front_matter
+                .title
+                .or(heading)
+                .unwrap_or_else(|| stem.clone());
+            let labels = front_matter.tags.into_labels().collect();
+
+            let entity = Entity::new(url, created_at, Some(Name::from(name)), labels);
+            let id = coll.upsert(entity);
+
+            by_stem
+                .entry(stem.to_lowercase())
+                .or_insert_with(|| id.clone());
+            if let Ok(relative) = path.strip_prefix(&root) {
+                by_path.insert(relative.to_path_buf(), id.clone());
+            }
+
+            let dir = path
+                .parent()
+                .and_then(|dir| dir.strip_prefix(&root).ok())
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+            notes.push(Note { id, dir, links });
+        }
+
+        for note in notes {
+            for link in note.links {
+                let target = match link {
+                    Link::Wiki(target) => {
+                        let stem = target.rsplit('/').next().unwrap_or(&target);
+                        let stem = stem.strip_suffix(".md").unwrap_or(stem);
+                        by_stem.get(&stem.to_lowercase())
+                    }
+                    Link::Relative(path) => by_path.get(&normalize(&note.dir.join(path))),
+                };
+                if let Some(target) = target
+                    && *target != note.id
+                {
+                    coll.add_edge(&note.id, target);
+                }
+            }
+        }
+
+        Ok(coll)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{Link, normalize, percent_decode, split_front_matter, wiki_links};
+
+    #[test]
+    fn front_matter_is_split() {
+        let (front_matter, body) = split_front_matter("---\nurl: x\n---\nbody\n");
+        assert_eq!(front_matter, Some("url: x\n"));
+        assert_eq!(body, "body\n");
+
+        let (front_matter, body) = split_front_matter("body\n");
+        assert_eq!(front_matter, None);
+        assert_eq!(body, "body\n");
+    }
+
+    #[test]
+    fn wiki_link_targets() {
+        let targets: Vec<String> = wiki_links("see [[Foo|the foo]] and ![[Bar#Section]] and [[]]")
+            .map(|link| match link {
+                Link::Wiki(target) => target,
+                Link::Relative(_) => unreachable!(),
+            })
+            .collect();
+        assert_eq!(targets, ["Foo", "Bar"]);
+    }
+
+    #[test]
+    fn relative_paths() {
+        assert_eq!(
+            normalize(&Path::new("a/b").join("../c.md")),
+            PathBuf::from("a/c.md")
+        );
+        assert_eq!(percent_decode("my%20note.md"), "my note.md");
+    }
+}
@@ -0,0 +1,223 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write as _,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::{
+    collection::{Collection, Id},
+    entity::{Entity, Name},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("YAML serialization error: {0}")]
+    Yaml(#[from] serde_norway::Error),
+}
+
+const LABELS_DIR: &str = "labels";
+const MAX_STEM_LEN: usize = 100;
+const UNNAMED_LABEL: &str = "untitled";
+
+#[derive(Debug, Serialize)]
+struct FrontMatter<'a> {
+    url: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    aliases: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    created: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated: Option<String>,
+}
+
+/// Replaces characters that are not allowed in Obsidian note names.
+fn sanitize(s: &str) -> String {
+    let sanitized: String = s
+        .chars()
+        .map(|c| match c {
+            '*' | '"' | '\\' | '/' | '<' | '>' | ':' | '|' | '?' | '#' | '^' | '[' | ']' => ' ',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    let collapsed = sanitized.split_whitespace().collect::<Vec<_>>().join(" ");
+    collapsed
+        .chars()
+        .take(MAX_STEM_LEN)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// Obsidian tags cannot contain whitespace.
+fn tag(label: &str) -> String {
+    label.split_whitespace().collect::<Vec<_>>().join("-")
+}
+
+fn title(entity: &Entity) -> &str {
+    entity
+        .names()
+        .first()
+        .map_or(entity.url().as_str(), Name::as_str)
+}
+
+/// Assigns each entity a unique note name, in collection order.
+fn stems(entities: &[Entity]) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    entities
+        .iter()
+        .map(|entity| {
+            let mut base = sanitize(title(entity));
+            if base.is_empty() {
+                base = sanitize(entity.url().as_str());
+            }
+            let mut stem = base.clone();
+            let mut n = 2;
+            while !seen.insert(stem.to_lowercase()) {
+                stem = format!("{base} ({n})");
+                n += 1;
+            }
+            stem
+        })
+        .collect()
+}
+
+/// Assigns each label a unique index note name, in label order. Labels with nothing left after
+/// sanitizing are named after [`UNNAMED_LABEL`].
+fn label_stems<'a>(labels: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    labels
+        .into_iter()
+        .map(|label| {
+            let mut base = sanitize(label);
+            if base.is_empty() {
+                base = UNNAMED_LABEL.to_string();
+            }
+            let mut stem = base.clone();
+            let mut n = 2;
+            while !seen.insert(stem.to_lowercase()) {
+                stem = format!("{base} ({n})");
+                n += 1;
+            }
+            stem
+        })
+        .collect()
+}
+
+fn note(coll: &Collection, id: &Id, stems: &[String]) -> Result<String, Error> {
+    let entity = coll.entity(id);
+    let names: Vec<&str> = entity.names().iter().map(Name::as_str).collect();
+    let front_matter = FrontMatter {
+        url: entity.url().as_str(),
+        aliases: names.iter().skip(1).copied().collect(),
+        tags: entity
+            .labels()
+            .iter()
+            .map(|label| tag(label.as_str()))
+            .collect(),
+        created: entity.created_at().get().to_string(),
+        updated: entity
+            .updated_at()
+            .last()
+            .map(|updated_at| updated_at.get().to_string()),
+    };
+
+    let mut ret = String::from("---\n");
+    ret.push_str(&serde_norway::to_string(&front_matter)?);
+    ret.push_str("---\n");
+
+    for extended in entity.extended() {
+        let _ = write!(ret, "\n{}\n", extended.as_str());
+    }
+
+    let related = coll.edges(id);
+    if !related.is_empty() {
+        ret.push_str("\n## Related\n\n");
+        for other in related {
+            let _ = writeln!(ret, "- [[{}]]", stems[other.index()]);
+        }
+    }
+
+    Ok(ret)
+}
+
+fn write_file(path: &Path, contents: &str) -> io::Result<()> {
+    let mut file = fs::File::create(path)?;
+    file.write_all(contents.as_bytes())?;
+    file.flush()
+}
+
+impl Collection {
+    /// Writes the collection as an Obsidian vault.
+    ///
+    /// Each entity becomes a note whose YAML front matter holds its URL, tags and dates, and whose
+    /// body holds its extended text and wiki-links to related entities. Each label gets an index
+    /// note under `labels/` linking to its entities.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be written or front matter cannot be serialized.
+    pub fn to_obsidian(&self, dir: &Path) -> Result<(), Error> {
+        fs::create_dir_all(dir.join(LABELS_DIR))?;
+
+        let entities = self.entities();
+        let stems = stems(entities);
+
+        for ((id, _), stem) in self.iter().zip(&stems) {
+            let contents = note(self, &id, &stems)?;
+            write_file(&dir.join(format!("{stem}.md")), &contents)?;
+        }
+
+        let mut labels: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for (entity, stem) in entities.iter().zip(&stems) {
+            for label in entity.labels() {
+                labels.entry(label.as_str()).or_default().push(stem);
+            }
+        }
+
+        let label_stems = label_stems(labels.keys().copied());
+        for ((label, stems), label_stem) in labels.into_iter().zip(label_stems) {
+            let mut contents = format!("# {label}\n\n");
+            for stem in stems {
+                let _ = writeln!(contents, "- [[{stem}]]");
+            }
+            let path = dir.join(LABELS_DIR).join(format!("{label_stem}.md"));
+            write_file(&path, &contents)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{label_stems, sanitize, tag};
+
+    #[test]
+    fn sanitize_note_names() {
+        assert_eq!(sanitize("Foo: Bar / Baz?"), "Foo Bar Baz");
+        assert_eq!(sanitize("  [x]  "), "x");
+    }
+
+    #[test]
+    fn label_notes_have_unique_names() {
+        assert_eq!(
+            label_stems(["", "a/b", "a:b", "A B", "?"]),
+            ["untitled", "a b", "a b (2)", "A B (3)", "untitled (2)"]
+        );
+    }
+
+    #[test]
+    fn tags_have_no_whitespace() {
+        assert_eq!(tag("machine learning"), "machine-learning");
+    }
+}
@@ -0,0 +1,13 @@
+pub use hbt_pinboard::Post;
+
+pub use crate::{
+    Anomaly, InputFormat, OutputFormat, ParseError, ParseOptions, ParseReport, UnparseError,
+    UnparseOptions,
+    collection::{Collection, Id},
+    entity::{Entity, Label, Name, Profile, Time, Url},
+};
+
+#[cfg(feature = "bundle")]
+pub use crate::bundle::Bundle;
+#[cfg(feature = "net")]
+pub use crate::net::Client;
@@ -0,0 +1,286 @@
+use std::{collections::BTreeSet, convert::Infallible};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    collection::Collection,
+    entity::{Label, LastVisitedAt, Url},
+    ignore::IgnoreList,
+    transform::ignore_list,
+};
+
+/// The scheme of URLs replaced by their hash.
+const REDACTED_SCHEME: &str = "hbt-redacted";
+
+/// The context from which the key of URL hashes is derived from the salt.
+const KEY_CONTEXT: &str = "hbt 2026-10-16 redacted URL hash";
+
+fn default_true() -> bool {
+    true
+}
+
+/// What to remove or obscure before sharing a collection, as read from a YAML policy file such
+/// as:
+///
+/// ```yaml
+/// remove-labels: [personal, health]
+/// hash-urls: ["*.internal.example.com"]
+/// hash-salt: "a long random string"
+/// remove-urls: [bank.example.com]
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Policy {
+    /// Remove entities that are not known to be shared. Defaults to true.
+    #[serde(default = "default_true")]
+    pub drop_private: bool,
+    /// Remove extended notes. Defaults to true.
+    #[serde(default = "default_true")]
+    pub strip_extended: bool,
+    /// Labels to remove from every entity.
+    #[serde(default)]
+    pub remove_labels: BTreeSet<Label>,
+    /// Replace matching URLs by a hash, and remove the names of their entities.
+    #[serde(default, deserialize_with = "ignore_list")]
+    pub hash_urls: IgnoreList,
+    /// The salt of URL hashes, so that hashes of guessable URLs cannot be reversed by hashing
+    /// guesses. Exports redacted with the same salt can be compared; without a salt, a random one
+    /// is used.
+    #[serde(default)]
+    pub hash_salt: Option<String>,
+    /// Remove entities with matching URLs.
+    #[serde(default, deserialize_with = "ignore_list")]
+    pub remove_urls: IgnoreList,
+}
+
+impl Default for Policy {
+    fn default() -> Policy {
+        Policy {
+            drop_private: true,
+            strip_extended: true,
+            remove_labels: BTreeSet::new(),
+            hash_urls: IgnoreList::default(),
+            hash_salt: None,
+            remove_urls: IgnoreList::default(),
+        }
+    }
+}
+
+/// What [`Collection::redact`] did.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    /// The number of entities removed, either as private or by URL.
+    pub removed: usize,
+    /// The number of URLs replaced by a hash.
+    pub hashed: usize,
+}
+
+/// Returns the URL replacing `url`: an opaque `hbt-redacted:` URL holding its BLAKE3 hash keyed
+/// by `key`.
+fn hashed(url: &Url, key: &[u8; 32]) -> Url {
+    let hash = blake3::keyed_hash(key, url.as_str().as_bytes());
+    Url::parse(&format!("{REDACTED_SCHEME}:{}", hash.to_hex()))
+        .expect("hex-encoded hash should form a valid URL")
+}
+
+impl Collection {
+    /// Removes private data according to `policy`, so that the collection can be shared.
+    ///
+    /// Entities are removed first, then labels and notes are stripped, along with extras, snapshot
+    /// paths, icons, visit times, embeddings and the times labels were added, which are never
+    /// shared. Finally the remaining sensitive URLs are hashed. Because removal renumbers
+    /// entities, any previously obtained `Id`s are invalidated.
+    pub fn redact(&mut self, policy: &Policy) -> Report {
+        let mut ret = Report {
+            removed: self.retain(|entity| {
+                let private = policy.drop_private && entity.shared().get() != Some(true);
+                !private && !policy.remove_urls.is_match(entity.url())
+            }),
+            ..Report::default()
+        };
+
+        let ids: Vec<_> = self.iter().map(|(id, _)| id).collect();
+        for id in &ids {
+            let entity = self.entity_mut(id);
+            entity
+                .labels_mut()
+                .retain(|label| !policy.remove_labels.contains(label));
+            if policy.strip_extended {
+                entity.extended_mut().clear();
+            }
+            entity.extras_mut().clear();
+            entity.set_snapshot_path(None);
+            entity.set_icon(None);
+            entity.set_icon_uri(None);
+            entity.set_last_visited_at(LastVisitedAt::default());
+            entity.set_embedding(None);
+            entity.clear_labels_added_at();
+            if policy.hash_urls.is_match(entity.url()) {
+                entity.names_mut().clear();
+                ret.hashed += 1;
+            }
+        }
+
+        if ret.hashed > 0 {
+            let key = match &policy.hash_salt {
+                Some(salt) => blake3::derive_key(KEY_CONTEXT, salt.as_bytes()),
+                None => blake3::derive_key(KEY_CONTEXT, Uuid::new_v4().as_bytes()),
+            };
+            let result = self.map_urls(|url| {
+                Ok::<Url, Infallible>(if policy.hash_urls.is_match(url) {
+                    hashed(url, &key)
+                } else {
+                    url.clone()
+                })
+            });
+            let Ok(()) = result;
+        }
+
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        embedding::Embedding,
+        entity::{Entity, Extended, Label, LastVisitedAt, Name, Shared, Time, Url},
+    };
+
+    use super::Policy;
+
+    const POLICY: &str = "
+remove-labels: [personal]
+hash-urls: [secret.example.com]
+remove-urls: [bank.example.com]
+";
+
+    #[test]
+    fn redact_applies_policy() {
+        let mut coll = Collection::new();
+        for (url, shared) in [
+            ("https://public.example.com/", true),
+            ("https://private.example.com/", false),
+            ("https://bank.example.com/", true),
+            ("https://secret.example.com/page", true),
+        ] {
+            let labels = BTreeSet::from([Label::from("personal"), Label::from("rust")]);
+            let mut entity = Entity::new(
+                Url::parse(url).unwrap(),
+                Time::default(),
+                Some(Name::from("Title")),
+                labels,
+            );
+            entity.set_shared(Shared::new(shared));
+            entity
+                .extended_mut()
+                .push(Extended::new("note".to_string()));
+            coll.insert(entity);
+        }
+
+        let policy: Policy = serde_norway::from_str(POLICY).unwrap();
+        let report = coll.redact(&policy);
+
+        assert_eq!((report.removed, report.hashed), (2, 1));
+        let public = &coll.entities()[0];
+        assert_eq!(public.labels(), &BTreeSet::from([Label::from("rust")]));
+        assert!(public.extended().is_empty());
+        let secret = &coll.entities()[1];
+        assert!(secret.url().as_str().starts_with("hbt-redacted:"));
+        assert!(secret.names().is_empty());
+    }
+
+    #[test]
+    fn redacted_entities_keep_nothing_private() {
+        let url = Url::parse("https://secret.example.com/page").unwrap();
+        let mut entity = Entity::new(
+            url.clone(),
+            Time::from_timestamp(1_000).unwrap(),
+            Some(Name::from("secret title")),
+            BTreeSet::from([Label::from("rust")]),
+        );
+        entity.set_shared(Shared::new(true));
+        entity.extended_mut().push(Extended::from("secret note"));
+        entity.extras_mut().extend([
+            (
+                "hbt:source".to_string(),
+                "/home/secret/source.html".to_string(),
+            ),
+            ("hbt:raw".to_string(), "secret raw line".to_string()),
+        ]);
+        entity.set_snapshot_path(Some("/home/secret/snapshot.html".into()));
+        entity.set_icon(Some("data:image/png;base64,c2VjcmV0".to_string()));
+        entity.set_icon_uri(Some("https://icons.example.com/secret.ico".to_string()));
+        entity.set_last_visited_at(LastVisitedAt::new(Time::from_timestamp(2_000).unwrap()));
+        entity.set_embedding(Some(Embedding::new(vec![0.5])));
+        entity.merge(Entity::new(
+            url,
+            Time::from_timestamp(3_000).unwrap(),
+            None,
+            BTreeSet::from([Label::from("secret-label")]),
+        ));
+        assert!(
+            entity
+                .label_added_at(&Label::from("secret-label"))
+                .is_some()
+        );
+
+        let mut coll = Collection::new();
+        coll.insert(entity);
+        coll.insert(Entity::new(
+            Url::parse("https://unknown.example.com/").unwrap(),
+            Time::default(),
+            None,
+            BTreeSet::new(),
+        ));
+
+        let policy: Policy = serde_norway::from_str(
+            "
+remove-labels: [secret-label]
+hash-urls: [secret.example.com]
+hash-salt: salt
+",
+        )
+        .unwrap();
+        let report = coll.redact(&policy);
+        assert_eq!((report.removed, report.hashed), (1, 1));
+
+        let json = serde_json::to_string(coll.entities()).unwrap();
+        for private in [
+            "secret",
+            "unknown",
+            "extras",
+            "snapshotPath",
+            "icon",
+            "lastVisitedAt",
+            "embedding",
+            "labelsAddedAt",
+        ] {
+            assert!(!json.contains(private), "{private} survived in {json}");
+        }
+
+        let redacted_url = |policy: &Policy| {
+            let mut coll = Collection::new();
+            let mut entity = Entity::new(
+                Url::parse("https://secret.example.com/page").unwrap(),
+                Time::default(),
+                None,
+                BTreeSet::new(),
+            );
+            entity.set_shared(Shared::new(true));
+            coll.insert(entity);
+            coll.redact(policy);
+            coll.entities()[0].url().clone()
+        };
+        assert_eq!(&redacted_url(&policy), coll.entities()[0].url());
+        let unsalted = Policy {
+            hash_salt: None,
+            ..policy
+        };
+        assert_ne!(&redacted_url(&unsalted), coll.entities()[0].url());
+    }
+}
@@ -0,0 +1,317 @@
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    Anomaly,
+    collection::Collection,
+    entity::{self, Entity, Label, Name, Time, Url},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Entity(#[from] entity::Error),
+
+    #[error("JSON parsing error: {0}")]
+    ParseJson(#[from] serde_json::Error),
+
+    #[error("CSV missing required column: {0}")]
+    MissingColumn(String),
+}
+
+const LABEL: &str = "reddit";
+const BASE_URL: &str = "https://www.reddit.com";
+const COLUMN_PERMALINK: &str = "permalink";
+
+#[derive(Debug, Deserialize)]
+struct Listing {
+    data: ListingData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListingData {
+    children: Vec<Thing>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Thing {
+    data: Saved,
+}
+
+/// A saved post (`t3`) or comment (`t1`).
+#[derive(Debug, Deserialize)]
+struct Saved {
+    title: Option<String>,
+    link_title: Option<String>,
+    url: Option<String>,
+    link_url: Option<String>,
+    permalink: Option<String>,
+    created_utc: Option<f64>,
+    subreddit: Option<String>,
+}
+
+fn absolute(permalink: &str) -> String {
+    if permalink.starts_with('/') {
+        format!("{BASE_URL}{permalink}")
+    } else {
+        permalink.to_string()
+    }
+}
+
+fn subreddit(permalink: &str) -> Option<&str> {
+    let mut segments = permalink.split('/').skip_while(|segment| *segment != "r");
+    segments.next()?;
+    segments.next().filter(|segment| !segment.is_empty())
+}
+
+fn labels(subreddit: Option<&str>) -> impl Iterator<Item = Label> {
+    let subreddit = subreddit.map(|subreddit| Label::new(format!("r/{subreddit}")));
+    std::iter::once(Label::from(LABEL)).chain(subreddit)
+}
+
+impl Saved {
+    /// Returns the item as an entity, or `None` if it has no URL.
+    // Reddit timestamps are whole seconds encoded as floats.
+    #[allow(clippy::cast_possible_truncation)]
+    fn into_entity(self) -> Result<Option<Entity>, Error> {
+        let non_empty = |s: Option<String>| s.filter(|s| !s.is_empty());
+        let permalink = self.permalink.as_deref().map(absolute);
+        let Some(href) = non_empty(self.url)
+            .or_else(|| non_empty(self.link_url))
+            .or_else(|| non_empty(permalink))
+        else {
+            return Ok(None);
+        };
+        let url = Url::parse(&href)?;
+        let created_at = match self.created_utc {
+            Some(created_utc) => Time::from_timestamp(created_utc as i64)?,
+            None => Time::default(),
+        };
+        let name = non_empty(self.title)
+            .or_else(|| non_empty(self.link_title))
+            .map(Name::new);
+        let maybe_subreddit = self.subreddit.or_else(|| {
+            let permalink = self.permalink.as_deref()?;
+            subreddit(permalink).map(ToOwned::to_owned)
+        });
+        let labels = labels(maybe_subreddit.as_deref()).collect();
+        Ok(Some(Entity::new(url, created_at, name, labels)))
+    }
+}
+
+/// Splits CSV input into records of fields, honouring double-quoted fields, which may contain
+/// commas, escaped quotes and line breaks. Blank lines are skipped.
+fn split_records(input: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+    let mut end_record = |record: &mut Vec<String>, field: &mut String| {
+        record.push(std::mem::take(field));
+        if record.len() > 1 || record.first().is_some_and(|field| !field.trim().is_empty()) {
+            records.push(std::mem::take(record));
+        }
+        record.clear();
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => end_record(&mut record, &mut field),
+            _ => field.push(c),
+        }
+    }
+    end_record(&mut record, &mut field);
+    records
+}
+
+fn skipped(kind: &str, index: usize) -> Anomaly {
+    Anomaly::Skipped {
+        position: format!("{kind} #{}", index + 1),
+    }
+}
+
+fn from_csv(input: &str) -> Result<(Vec<Entity>, Vec<Anomaly>), Error> {
+    let mut records = split_records(input).into_iter();
+    let header = records.next().unwrap_or_default();
+    let column = header
+        .iter()
+        .position(|name| name.trim() == COLUMN_PERMALINK)
+        .ok_or_else(|| Error::MissingColumn(COLUMN_PERMALINK.to_string()))?;
+
+    let mut entities = Vec::new();
+    let mut anomalies = Vec::new();
+    for (index, record) in records.enumerate() {
+        let Some(permalink) = record
+            .get(column)
+            .map(|permalink| permalink.trim())
+            .filter(|permalink| !permalink.is_empty())
+        else {
+            anomalies.push(skipped("row", index));
+            continue;
+        };
+        let url = Url::parse(&absolute(permalink))?;
+        let labels = labels(subreddit(permalink)).collect();
+        entities.push(Entity::new(url, Time::default(), None, labels));
+    }
+    Ok((entities, anomalies))
+}
+
+fn from_json(input: &str) -> Result<(Vec<Entity>, Vec<Anomaly>), Error> {
+    let listing: Listing = serde_json::from_str(input)?;
+    let mut entities = Vec::new();
+    let mut anomalies = Vec::new();
+    for (index, thing) in listing.data.children.into_iter().enumerate() {
+        match thing.data.into_entity()? {
+            Some(entity) => entities.push(entity),
+            None => anomalies.push(skipped("item", index)),
+        }
+    }
+    Ok((entities, anomalies))
+}
+
+/// Parses the entities of a Reddit saved-items export, as in [`Collection::from_reddit`], along
+/// with the rows or items skipped for having no URL.
+pub(crate) fn entities(input: &str) -> Result<(Vec<Entity>, Vec<Anomaly>), Error> {
+    if input.trim_start().starts_with('{') {
+        from_json(input)
+    } else {
+        from_csv(input)
+    }
+}
+
+impl Collection {
+    /// Parses a Reddit saved-items export into a collection.
+    ///
+    /// Accepts either the `saved_posts.csv` file from a Reddit data request (which must have a
+    /// `permalink` column) or a saved-items listing in Reddit's JSON API format. Every entity is
+    /// labelled `reddit`, plus `r/<subreddit>` when the subreddit is known. Rows and items without
+    /// a URL are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input is malformed or an item has an invalid URL or timestamp.
+    pub fn from_reddit(input: &str) -> Result<Collection, Error> {
+        let (entities, _) = entities(input)?;
+        Ok(Collection::from_entities(entities))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Anomaly, InputFormat, ParseOptions,
+        collection::Collection,
+        entity::{Label, Name, Time, Url},
+    };
+
+    use super::{split_records, subreddit};
+
+    #[test]
+    fn split_quoted_records() {
+        assert_eq!(
+            split_records("a,\"b,c\",\"d\"\"e\"\r\n\r\n1,\"two\nlines\",3\n"),
+            [vec!["a", "b,c", "d\"e"], vec!["1", "two\nlines", "3"]]
+        );
+    }
+
+    #[test]
+    fn subreddit_from_permalink() {
+        assert_eq!(
+            subreddit("https://www.reddit.com/r/rust/comments/abc/title/"),
+            Some("rust")
+        );
+        assert_eq!(subreddit("/r/rust/comments/abc/"), Some("rust"));
+        assert_eq!(subreddit("https://www.reddit.com/user/foo"), None);
+    }
+
+    #[test]
+    fn csv_records_may_span_lines() {
+        let input = "id,title,permalink\n\
+                     abc,\"A title\nover two lines\",/r/rust/comments/abc/title/\n\
+                     def,Other,https://www.reddit.com/r/programming/comments/def/other/\n";
+        let coll = Collection::from_reddit(input).unwrap();
+
+        assert_eq!(coll.len(), 2);
+        let entity = &coll.entities()[0];
+        assert_eq!(
+            entity.url().as_str(),
+            "https://www.reddit.com/r/rust/comments/abc/title/"
+        );
+        let labels: Vec<&str> = entity.labels().iter().map(Label::as_str).collect();
+        assert_eq!(labels, ["r/rust", "reddit"]);
+
+        assert!(matches!(
+            Collection::from_reddit("id,title\n1,x\n"),
+            Err(super::Error::MissingColumn(_))
+        ));
+    }
+
+    #[test]
+    fn json_listing_is_parsed() {
+        let input = r#"{"kind": "Listing", "data": {"children": [
+            {"kind": "t3", "data": {"title": "A post", "url": "https://example.com/post",
+             "permalink": "/r/rust/comments/abc/a_post/", "created_utc": 1700000000.0,
+             "subreddit": "rust"}},
+            {"kind": "t1", "data": {"link_title": "A comment", "link_url": "",
+             "permalink": "/r/programming/comments/def/x/ghi/"}}
+        ]}}"#;
+        let coll = Collection::from_reddit(input).unwrap();
+        let find = |url| coll.entity(&coll.id(&Url::parse(url).unwrap()).unwrap());
+
+        assert_eq!(coll.len(), 2);
+        let post = find("https://example.com/post");
+        assert_eq!(post.url().as_str(), "https://example.com/post");
+        assert_eq!(
+            post.names().iter().collect::<Vec<_>>(),
+            [&Name::from("A post")]
+        );
+        assert_eq!(
+            post.created_at().get(),
+            Time::from_timestamp(1_700_000_000).unwrap()
+        );
+        let comment = find("https://www.reddit.com/r/programming/comments/def/x/ghi/");
+        assert_eq!(
+            comment.url().as_str(),
+            "https://www.reddit.com/r/programming/comments/def/x/ghi/"
+        );
+        let labels: Vec<&str> = comment.labels().iter().map(Label::as_str).collect();
+        assert_eq!(labels, ["r/programming", "reddit"]);
+    }
+
+    #[test]
+    fn records_without_a_url_are_reported() {
+        let parse = |input: &str| {
+            let (coll, report) = InputFormat::Reddit
+                .parse_with_report(&mut input.as_bytes(), &ParseOptions::default())
+                .unwrap();
+            let positions: Vec<String> = report
+                .anomalies
+                .into_iter()
+                .map(|anomaly| match anomaly {
+                    Anomaly::Skipped { position } => position,
+                    anomaly => panic!("unexpected anomaly: {anomaly}"),
+                })
+                .collect();
+            (coll.len(), positions)
+        };
+
+        let csv = "id,permalink\nabc,/r/rust/comments/abc/\ndef,\nghi\n";
+        assert_eq!(
+            parse(csv),
+            (1, vec!["row #2".to_string(), "row #3".to_string()])
+        );
+
+        let json = r#"{"data": {"children": [
+            {"data": {"title": "No URL"}},
+            {"data": {"url": "https://example.com/"}}
+        ]}}"#;
+        assert_eq!(parse(json), (1, vec!["item #1".to_string()]));
+    }
+}
@@ -0,0 +1,206 @@
+use std::{collections::BTreeSet, fmt};
+
+#[cfg(feature = "clap")]
+use clap::{ValueEnum, builder::PossibleValue};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use strum::{IntoStaticStr, VariantArray};
+
+use crate::{
+    collection::Collection,
+    entity::{Entity, Extended, Name, Url},
+};
+
+/// A text field of an entity that [`Collection::replace_text`] can change.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    IntoStaticStr,
+    VariantArray,
+)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum TextField {
+    Names,
+    Extended,
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for TextField {
+    fn value_variants<'a>() -> &'a [TextField] {
+        TextField::VARIANTS
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let s: &'static str = self.into();
+        Some(PossibleValue::new(s))
+    }
+}
+
+/// One value changed by [`Collection::replace_text`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Change {
+    pub url: Url,
+    pub field: TextField,
+    pub old: String,
+    /// The replaced text, or `None` if nothing remained and the value was removed.
+    pub new: Option<String>,
+}
+
+/// Formats the change as in a unified diff, e.g. `https://example.com/ names: -old +new`.
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field: &'static str = self.field.into();
+        write!(f, "{} {field}: -{:?}", self.url.as_str(), self.old)?;
+        match &self.new {
+            Some(new) => write!(f, " +{new:?}"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Replaces matches of `pattern` in `old`, returning the trimmed result, which may be empty, if
+/// it differs.
+fn replaced(pattern: &Regex, replacement: &str, old: &str) -> Option<String> {
+    let new = pattern.replace_all(old, replacement);
+    if new == old {
+        return None;
+    }
+    Some(new.trim().to_string())
+}
+
+fn replace_in_entity(
+    entity: &mut Entity,
+    pattern: &Regex,
+    replacement: &str,
+    fields: &BTreeSet<TextField>,
+    changes: &mut Vec<Change>,
+) {
+    let url = entity.url().clone();
+    let mut replace = |field, old: &str| {
+        let new = replaced(pattern, replacement, old)?;
+        let maybe_new = (!new.is_empty()).then_some(new);
+        changes.push(Change {
+            url: url.clone(),
+            field,
+            old: old.to_string(),
+            new: maybe_new.clone(),
+        });
+        Some(maybe_new)
+    };
+
+    if fields.contains(&TextField::Names) {
+        let mut names = BTreeSet::new();
+        for name in std::mem::take(entity.names_mut()) {
+            match replace(TextField::Names, name.as_str()) {
+                Some(maybe_new) => names.extend(maybe_new.map(Name::from)),
+                None => {
+                    names.insert(name);
+                }
+            }
+        }
+        *entity.names_mut() = names;
+    }
+
+    if fields.contains(&TextField::Extended) {
+        let mut extended = Vec::new();
+        for ext in std::mem::take(entity.extended_mut()) {
+            match replace(TextField::Extended, ext.as_str()) {
+                Some(maybe_new) => extended.extend(maybe_new.map(Extended::from)),
+                None => extended.push(ext),
+            }
+        }
+        *entity.extended_mut() = extended;
+    }
+}
+
+impl Collection {
+    /// Replaces every match of `pattern` with `replacement`, which may refer to capture groups
+    /// (e.g. `$1`), in the given text fields of every entity. Replaced values are trimmed, and
+    /// removed if nothing remains.
+    ///
+    /// For example, the pattern `\s*\|\s*Example Site$` strips a site's suffix from titles.
+    ///
+    /// Returns each value changed, in order.
+    pub fn replace_text(
+        &mut self,
+        pattern: &Regex,
+        replacement: &str,
+        fields: &BTreeSet<TextField>,
+    ) -> Vec<Change> {
+        let mut ret = Vec::new();
+        let ids: Vec<_> = self.iter().map(|(id, _)| id).collect();
+        for id in &ids {
+            replace_in_entity(self.entity_mut(id), pattern, replacement, fields, &mut ret);
+        }
+        ret
+    }
+
+    /// Returns the values that [`Collection::replace_text`] would change, without changing them.
+    #[must_use]
+    pub fn preview_replace_text(
+        &self,
+        pattern: &Regex,
+        replacement: &str,
+        fields: &BTreeSet<TextField>,
+    ) -> Vec<Change> {
+        let mut ret = Vec::new();
+        for entity in self.entities() {
+            replace_in_entity(&mut entity.clone(), pattern, replacement, fields, &mut ret);
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use regex::Regex;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Extended, Name, Time, Url},
+    };
+
+    use super::TextField;
+
+    #[test]
+    fn replace_text_strips_title_suffixes() {
+        let mut entity = Entity::new(
+            Url::parse("https://example.com/").unwrap(),
+            Time::default(),
+            Some(Name::from("Article | Example Site")),
+            BTreeSet::new(),
+        );
+        entity.names_mut().insert(Name::from(" | Example Site"));
+        entity
+            .extended_mut()
+            .push(Extended::from("Read on Example Site"));
+        let mut coll = Collection::from_entities([entity]);
+        let pattern = Regex::new(r"\s*\|\s*Example Site$").unwrap();
+        let fields = BTreeSet::from([TextField::Names, TextField::Extended]);
+
+        let preview = coll.preview_replace_text(&pattern, "", &fields);
+        assert_eq!(preview.len(), 2);
+        assert_eq!(coll.entities()[0].names().len(), 2);
+
+        let changes = coll.replace_text(&pattern, "", &fields);
+        assert_eq!(changes, preview);
+        assert_eq!(changes[0].new, None);
+        assert_eq!(
+            changes[1].to_string(),
+            r#"https://example.com/ names: -"Article | Example Site" +"Article""#
+        );
+        let entity = &coll.entities()[0];
+        assert_eq!(entity.names(), &BTreeSet::from([Name::from("Article")]));
+        assert_eq!(entity.extended(), &[Extended::from("Read on Example Site")]);
+    }
+}
@@ -0,0 +1,226 @@
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use url::form_urlencoded;
+
+use crate::{
+    collection::Collection,
+    entity::{Error, Url},
+};
+
+const SCHEME_HTTP: &str = "http";
+const SCHEME_HTTPS: &str = "https";
+
+pub(crate) fn regex<'de, D>(deserializer: D) -> Result<Regex, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = String::deserialize(deserializer)?;
+    Regex::new(&s).map_err(serde::de::Error::custom)
+}
+
+/// A rule for rewriting entity URLs.
+///
+/// Rules are typically read with [`parse_rules`] from a YAML file containing a list such as:
+///
+/// ```yaml
+/// - force-https
+/// - strip-query: [utm_*, fbclid]
+/// - replace:
+///     pattern: ^https://mobile\.twitter\.com/
+///     replacement: https://twitter.com/
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// Replaces every match of `pattern` in the URL with `replacement`, which may refer to capture
+    /// groups (e.g. `$1`).
+    Replace {
+        #[serde(deserialize_with = "regex")]
+        pattern: Regex,
+        replacement: String,
+    },
+    /// Removes the named query parameters. A trailing `*` matches any parameter with that prefix.
+    /// The rest of the query is kept as written.
+    StripQuery(Vec<String>),
+    /// Changes the `http` scheme to `https`.
+    ForceHttps,
+}
+
+fn matches_key(keys: &[String], key: &str) -> bool {
+    keys.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => key.starts_with(prefix),
+        None => key == pattern,
+    })
+}
+
+/// Returns the decoded key of a `key=value` query segment.
+fn segment_key(segment: &str) -> Cow<'_, str> {
+    form_urlencoded::parse(segment.as_bytes())
+        .next()
+        .map_or(Cow::Borrowed(""), |(key, _)| key)
+}
+
+impl Rule {
+    fn apply(&self, mut url: url::Url) -> Result<url::Url, Error> {
+        match self {
+            Rule::Replace {
+                pattern,
+                replacement,
+            } => {
+                let rewritten = pattern.replace_all(url.as_str(), replacement.as_str());
+                url::Url::parse(&rewritten).map_err(|err| Error::ParseUrl(err, rewritten.into()))
+            }
+            Rule::StripQuery(keys) => {
+                let Some(query) = url.query() else {
+                    return Ok(url);
+                };
+                let segments: Vec<&str> = query.split('&').collect();
+                let kept: Vec<&str> = segments
+                    .iter()
+                    .copied()
+                    .filter(|segment| !matches_key(keys, &segment_key(segment)))
+                    .collect();
+                if kept.len() != segments.len() {
+                    let query = kept.join("&");
+                    url.set_query((!query.is_empty()).then_some(query.as_str()));
+                }
+                Ok(url)
+            }
+            Rule::ForceHttps => {
+                if url.scheme() == SCHEME_HTTP {
+                    // Switching between special schemes always succeeds
+                    let _ = url.set_scheme(SCHEME_HTTPS);
+                }
+                Ok(url)
+            }
+        }
+    }
+}
+
+/// Parses a YAML list of rules, as shown in [`Rule`].
+///
+/// # Errors
+///
+/// Returns an error if the YAML is malformed or a rule is invalid.
+pub fn parse_rules(contents: &str) -> Result<Vec<Rule>, serde_norway::Error> {
+    serde_norway::with::singleton_map_recursive::deserialize(serde_norway::Deserializer::from_str(
+        contents,
+    ))
+}
+
+/// Applies `rules` to `url` in order.
+///
+/// # Errors
+///
+/// Returns an error if a replacement produces an invalid URL.
+pub fn rewrite(rules: &[Rule], url: &Url) -> Result<Url, Error> {
+    let url: &url::Url = url.as_ref();
+    rules
+        .iter()
+        .try_fold(url.clone(), |url, rule| rule.apply(url))
+        .map(Url::from)
+}
+
+impl Collection {
+    /// Rewrites the URL of every entity according to `rules`.
+    ///
+    /// Entities whose rewritten URLs collide are merged. See [`Collection::map_urls`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a replacement produces an invalid URL, leaving the collection unchanged.
+    pub fn rewrite_urls(&mut self, rules: &[Rule]) -> Result<(), Error> {
+        self.map_urls(|url| rewrite(rules, url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Time, Url},
+    };
+
+    use super::{Rule, parse_rules, rewrite};
+
+    fn rules(yaml: &str) -> Vec<Rule> {
+        parse_rules(yaml).unwrap()
+    }
+
+    fn rewritten(rules: &[Rule], url: &str) -> String {
+        let url = Url::parse(url).unwrap();
+        rewrite(rules, &url).unwrap().as_str().to_string()
+    }
+
+    #[test]
+    fn force_https() {
+        let rules = rules("- force-https");
+        assert_eq!(
+            rewritten(&rules, "http://example.com/"),
+            "https://example.com/"
+        );
+        assert_eq!(
+            rewritten(&rules, "ftp://example.com/"),
+            "ftp://example.com/"
+        );
+    }
+
+    #[test]
+    fn strip_query() {
+        let rules = rules("- strip-query: [utm_*, fbclid]");
+        assert_eq!(
+            rewritten(&rules, "https://example.com/?utm_source=x&id=1&fbclid=y"),
+            "https://example.com/?id=1"
+        );
+        assert_eq!(
+            rewritten(&rules, "https://example.com/?utm_source=x"),
+            "https://example.com/"
+        );
+        assert_eq!(
+            rewritten(
+                &rules,
+                "https://example.com/?q=a%20b&utm%5Fsource=x&path=/c&&flag"
+            ),
+            "https://example.com/?q=a%20b&path=/c&&flag"
+        );
+    }
+
+    #[test]
+    fn replace() {
+        let rules = rules(
+            "- replace:\n    pattern: ^https://mobile\\.example\\.com/(.*)$\n    replacement: https://example.com/$1\n",
+        );
+        assert_eq!(
+            rewritten(&rules, "https://mobile.example.com/a"),
+            "https://example.com/a"
+        );
+    }
+
+    #[test]
+    fn colliding_urls_are_merged() {
+        let mut coll = Collection::new();
+        for url in ["http://example.com/", "https://example.com/"] {
+            let url = Url::parse(url).unwrap();
+            coll.insert(Entity::new(url, Time::default(), None, BTreeSet::new()));
+        }
+        let a = coll
+            .id(&Url::parse("http://example.com/").unwrap())
+            .unwrap();
+        let b = coll
+            .id(&Url::parse("https://example.com/").unwrap())
+            .unwrap();
+        coll.add_edges(&a, &b);
+
+        coll.rewrite_urls(&rules("- force-https")).unwrap();
+
+        assert_eq!(coll.len(), 1);
+        let id = coll
+            .id(&Url::parse("https://example.com/").unwrap())
+            .unwrap();
+        assert!(coll.edges(&id).is_empty());
+    }
+}
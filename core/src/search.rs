@@ -0,0 +1,124 @@
+use crate::{
+    collection::{Collection, Id},
+    entity::{Entity, Label, Name},
+};
+
+const MATCH_SCORE: u32 = 1;
+const CONSECUTIVE_BONUS: u32 = 4;
+const WORD_START_BONUS: u32 = 3;
+
+/// Scores `term` as a case-insensitive subsequence of `haystack`, favouring consecutive matches
+/// and matches at the start of words. Returns `None` if `term` does not match.
+fn score(term: &str, haystack: &[char]) -> Option<u32> {
+    let mut total = 0;
+    let mut pos = 0;
+    let mut prev_matched = false;
+    for c in term.chars().flat_map(char::to_lowercase) {
+        let mut found = false;
+        while pos < haystack.len() {
+            let h = haystack[pos];
+            pos += 1;
+            if h == c {
+                total += MATCH_SCORE;
+                if prev_matched {
+                    total += CONSECUTIVE_BONUS;
+                }
+                if pos == 1 || !haystack[pos - 2].is_alphanumeric() {
+                    total += WORD_START_BONUS;
+                }
+                prev_matched = true;
+                found = true;
+                break;
+            }
+            prev_matched = false;
+        }
+        if !found {
+            return None;
+        }
+    }
+    Some(total)
+}
+
+/// Returns the lowercased text searched for an entity: its names, URL and labels.
+fn haystack(entity: &Entity) -> Vec<char> {
+    let names = entity.names().iter().map(Name::as_str);
+    let labels = entity.labels().iter().map(Label::as_str);
+    names
+        .chain(std::iter::once(entity.url().as_str()))
+        .chain(labels)
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl Collection {
+    /// Fuzzy-searches entity names, URLs and labels.
+    ///
+    /// Each whitespace-separated term of `query` must match as a case-insensitive subsequence.
+    /// Returns the matching entities with their scores, best first; ties keep collection order.
+    /// An empty query matches every entity with a score of zero.
+    #[must_use]
+    pub fn fuzzy_search(&self, query: &str) -> Vec<(Id, u32)> {
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        let mut ret: Vec<(Id, u32)> = self
+            .iter()
+            .filter_map(|(id, entity)| {
+                let haystack = haystack(entity);
+                let total = terms
+                    .iter()
+                    .map(|term| score(term, &haystack))
+                    .sum::<Option<u32>>()?;
+                Some((id, total))
+            })
+            .collect();
+        ret.sort_by(|(_, a), (_, b)| b.cmp(a));
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Label, Name, Time, Url},
+    };
+
+    use super::score;
+
+    #[test]
+    fn score_prefers_consecutive_word_starts() {
+        let haystack: Vec<char> = "rust programming language".chars().collect();
+        assert!(score("rust", &haystack) > score("rpl", &haystack));
+        assert!(score("RPL", &haystack).is_some());
+        assert_eq!(score("xyz", &haystack), None);
+    }
+
+    #[test]
+    fn fuzzy_search_ranks_matches() -> Result<(), Box<dyn std::error::Error>> {
+        let mut coll = Collection::new();
+        let time = Time::from_timestamp(0)?;
+        coll.insert(Entity::new(
+            Url::parse("https://example.com/")?,
+            time,
+            Some(Name::from("Example")),
+            BTreeSet::new(),
+        ));
+        let rust = coll.insert(Entity::new(
+            Url::parse("https://www.rust-lang.org/")?,
+            time,
+            Some(Name::from("Rust Programming Language")),
+            BTreeSet::from([Label::from("programming")]),
+        ));
+
+        let results = coll.fuzzy_search("rust prog");
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0, rust);
+
+        assert_eq!(coll.fuzzy_search("").len(), 2);
+        Ok(())
+    }
+}
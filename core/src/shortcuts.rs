@@ -0,0 +1,146 @@
+use std::{fs, io, path::Path};
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+use crate::{
+    collection::Collection,
+    entity::{self, Entity, Name, Time, Url},
+    notes::walk,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Entity(#[from] entity::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("no URL in shortcut: {0}")]
+    MissingUrl(String),
+
+    #[error("binary property lists are not supported: {0}")]
+    BinaryPlist(String),
+}
+
+const WEBLOC_EXT: &str = "webloc";
+const URL_EXT: &str = "url";
+const BINARY_PLIST_MAGIC: &[u8] = b"bplist";
+const INTERNET_SHORTCUT_SECTION: &str = "[InternetShortcut]";
+const KEY_URL: &str = "URL";
+
+/// Replaces the predefined XML entities.
+fn unescape_xml(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Reads the URL of a macOS `.webloc` file, an XML property list with a `URL` key.
+fn webloc_url(contents: &str) -> Option<String> {
+    let (_, rest) = contents.split_once(&format!("<key>{KEY_URL}</key>"))?;
+    let (_, rest) = rest.split_once("<string>")?;
+    let (url, _) = rest.split_once("</string>")?;
+    Some(unescape_xml(url.trim()))
+}
+
+/// Reads the URL of a Windows `.url` file, an INI file with a `URL` entry in its
+/// `[InternetShortcut]` section.
+fn internet_shortcut_url(contents: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_section = line.eq_ignore_ascii_case(INTERNET_SHORTCUT_SECTION);
+        } else if in_section
+            && let Some((key, value)) = line.split_once('=')
+            && key.trim().eq_ignore_ascii_case(KEY_URL)
+        {
+            return Some(value.trim().to_string());
+        }
+    }
+    None
+}
+
+fn read_shortcut(path: &Path) -> Result<Entity, Error> {
+    let display = path.display().to_string();
+    let bytes = fs::read(path)?;
+    let contents = String::from_utf8_lossy(&bytes);
+    let maybe_url = if path.extension().is_some_and(|ext| ext == WEBLOC_EXT) {
+        if bytes.starts_with(BINARY_PLIST_MAGIC) {
+            return Err(Error::BinaryPlist(display));
+        }
+        webloc_url(&contents)
+    } else {
+        internet_shortcut_url(&contents)
+    };
+    let url = maybe_url.ok_or(Error::MissingUrl(display))?;
+    let created_at = Time::new(DateTime::<Utc>::from(fs::metadata(path)?.modified()?));
+    let maybe_name = path
+        .file_stem()
+        .map(|stem| Name::new(stem.to_string_lossy().into_owned()));
+    let mut builder = Entity::builder(Url::parse(&url)?, created_at);
+    if let Some(name) = maybe_name {
+        builder = builder.name(name);
+    }
+    Ok(builder.build())
+}
+
+impl Collection {
+    /// Imports the single-bookmark files in a directory: macOS `.webloc` files and Windows `.url`
+    /// (Internet Shortcut) files.
+    ///
+    /// Each file becomes an entity named after the file, created at the file's modification time.
+    /// Files that share a URL are merged. Hidden files and directories are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read, or a file has no valid URL or is a binary
+    /// property list.
+    pub fn from_shortcuts(root: &Path) -> Result<Collection, Error> {
+        let mut paths = Vec::new();
+        walk(root, &[WEBLOC_EXT, URL_EXT], &mut paths)?;
+        paths.sort();
+        let entities = paths
+            .iter()
+            .map(|path| read_shortcut(path))
+            .collect::<Result<Vec<Entity>, Error>>()?;
+        Ok(Collection::from_post_entities(entities))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{internet_shortcut_url, webloc_url};
+
+    #[test]
+    fn webloc_url_is_read() {
+        let contents = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>URL</key>
+	<string>https://example.com/?a=1&amp;b=2</string>
+</dict>
+</plist>
+"#;
+        assert_eq!(
+            webloc_url(contents).as_deref(),
+            Some("https://example.com/?a=1&b=2")
+        );
+        assert_eq!(webloc_url("<plist></plist>"), None);
+    }
+
+    #[test]
+    fn internet_shortcut_url_is_read() {
+        let contents = "[{000214A0-0000-0000-C000-000000000046}]\r\nURL=https://wrong.example/\r\n[InternetShortcut]\r\nIDList=\r\nURL=https://example.com/\r\n";
+        assert_eq!(
+            internet_shortcut_url(contents).as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(internet_shortcut_url("URL=https://example.com/"), None);
+    }
+}
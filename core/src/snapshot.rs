@@ -0,0 +1,277 @@
+use std::{
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "clap")]
+use clap::{ValueEnum, builder::PossibleValue};
+use scraper::{ElementRef, Html, Node, Selector};
+use sha2::{Digest, Sha256};
+use strum::{IntoStaticStr, VariantArray};
+use thiserror::Error;
+
+use crate::{
+    collection::{Collection, Id},
+    entity::Url,
+    net::{self, Client},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Net(#[from] net::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Elements tried in order when looking for the main content of a page.
+const CONTENT_SELECTORS: [&str; 3] = ["article", "main", "body"];
+
+const SKIPPED_ELEMENTS: [&str; 4] = ["script", "style", "noscript", "template"];
+
+/// Elements whose text starts on a line of its own. Text in other elements runs on.
+const BLOCK_ELEMENTS: [&str; 35] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "caption",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+/// How a page is stored in a snapshot.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, IntoStaticStr, VariantArray)]
+#[strum(serialize_all = "lowercase")]
+pub enum Format {
+    /// The page as fetched.
+    #[default]
+    Html,
+    /// The readable text of the page's main content.
+    Text,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Html => "html",
+            Format::Text => "txt",
+        }
+    }
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for Format {
+    fn value_variants<'a>() -> &'a [Format] {
+        Format::VARIANTS
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let s: &'static str = self.into();
+        Some(PossibleValue::new(s))
+    }
+}
+
+/// Extracts the text of the main content of an HTML page, one block of text per line.
+///
+/// The first `<article>`, `<main>` or `<body>` element is used as the main content, and text
+/// inside scripts, styles and templates is skipped. Text in inline elements such as `<a>` and `<b>`
+/// runs on with the text around it, and whitespace within a line is collapsed.
+#[must_use]
+pub fn extract_text(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let root = CONTENT_SELECTORS
+        .iter()
+        .filter_map(|selector| Selector::parse(selector).ok())
+        .find_map(|selector| document.select(&selector).next())
+        .unwrap_or_else(|| document.root_element());
+
+    let mut ret = String::new();
+    let mut line = String::new();
+    push_text(root, &mut line, &mut ret);
+    end_line(&mut line, &mut ret);
+    ret
+}
+
+/// Gathers the text within `elt` into `line`, ending the line in `ret` around block elements.
+fn push_text(elt: ElementRef, line: &mut String, ret: &mut String) {
+    for child in elt.children() {
+        if let Some(child) = ElementRef::wrap(child) {
+            let name = child.value().name();
+            if SKIPPED_ELEMENTS.contains(&name) {
+                continue;
+            }
+            let block = BLOCK_ELEMENTS.contains(&name);
+            if block {
+                end_line(line, ret);
+            }
+            push_text(child, line, ret);
+            if block {
+                end_line(line, ret);
+            }
+        } else if let Node::Text(text) = child.value() {
+            line.push_str(text);
+        }
+    }
+}
+
+/// Appends `line` to `ret` with its whitespace collapsed, unless it is blank, and clears it.
+fn end_line(line: &mut String, ret: &mut String) {
+    let mut words = line.split_whitespace();
+    if let Some(first) = words.next() {
+        ret.push_str(first);
+        for word in words {
+            ret.push(' ');
+            ret.push_str(word);
+        }
+        ret.push('\n');
+    }
+    line.clear();
+}
+
+/// Returns the content-addressed path of `contents` within `dir`.
+fn content_path(dir: &Path, contents: &[u8], format: Format) -> PathBuf {
+    let digest = Sha256::digest(contents);
+    let mut hex = String::with_capacity(digest.len() * 2);
+    for byte in digest {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    dir.join(&hex[..2])
+        .join(format!("{hex}.{}", format.extension()))
+}
+
+fn fetch(client: &Client, url: &Url, format: Format) -> Result<String, Error> {
+    let body = client.get(url)?;
+    Ok(match format {
+        Format::Html => body,
+        Format::Text => extract_text(&body),
+    })
+}
+
+/// Fetches `url` and stores its content in `dir`, returning the path of the snapshot.
+///
+/// Snapshots are content-addressed: each is named after the SHA-256 digest of its contents, so
+/// identical pages are stored once.
+///
+/// # Errors
+///
+/// Returns an error if the page cannot be fetched or the snapshot cannot be written.
+pub fn snapshot(url: &Url, dir: &Path, format: Format) -> Result<PathBuf, Error> {
+    snapshot_with(&Client::default(), url, dir, format)
+}
+
+/// Like [`snapshot`], but fetches `url` with `client`, so that its limits are shared with other
+/// requests.
+///
+/// # Errors
+///
+/// Returns an error if the page cannot be fetched or the snapshot cannot be written.
+pub fn snapshot_with(
+    client: &Client,
+    url: &Url,
+    dir: &Path,
+    format: Format,
+) -> Result<PathBuf, Error> {
+    let contents = fetch(client, url, format)?;
+    let path = content_path(dir, contents.as_bytes(), format);
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+    }
+    Ok(path)
+}
+
+impl Collection {
+    /// Stores a snapshot of every entity's page in `dir`, recording its path on the entity.
+    ///
+    /// Entities whose recorded snapshot still exists are skipped. A failure to snapshot one page
+    /// does not stop the others; failures are returned along with the URL of the page.
+    pub fn snapshot(&mut self, dir: &Path, format: Format) -> Vec<(Url, Error)> {
+        let ids: Vec<Id> = self
+            .iter()
+            .filter(|(_, entity)| !entity.snapshot_path().is_some_and(Path::exists))
+            .map(|(id, _)| id)
+            .collect();
+
+        let client = Client::default();
+        let mut failures = Vec::new();
+        for id in ids {
+            let entity = self.entity_mut(&id);
+            match snapshot_with(&client, entity.url(), dir, format) {
+                Ok(path) => entity.set_snapshot_path(Some(path)),
+                Err(err) => failures.push((entity.url().clone(), err)),
+            }
+        }
+        failures
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{Format, content_path, extract_text};
+
+    #[test]
+    fn extract_text_prefers_main_content() {
+        let html = r"<html><head><title>T</title></head><body>
+            <nav>Menu</nav>
+            <article><h1>Title</h1><script>var x;</script><p>Body <b>text</b></p></article>
+            </body></html>";
+        assert_eq!(extract_text(html), "Title\nBody text\n");
+    }
+
+    #[test]
+    fn extract_text_runs_inline_text_on() {
+        let html = r"<body><p>A <a href='/'>link</a>, <em>emphasis</em>
+            and<br>a break</p><ul><li>one</li><li>two</li></ul>trailing</body>";
+        assert_eq!(
+            extract_text(html),
+            "A link, emphasis and\na break\none\ntwo\ntrailing\n"
+        );
+    }
+
+    #[test]
+    fn content_path_is_content_addressed() {
+        let dir = Path::new("snapshots");
+        let path = content_path(dir, b"hello", Format::Text);
+        assert_eq!(
+            path,
+            dir.join("2c")
+                .join("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824.txt")
+        );
+        assert_eq!(path, content_path(dir, b"hello", Format::Text));
+    }
+}
@@ -0,0 +1,264 @@
+use std::{collections::HashMap, path::Path};
+
+use rusqlite::{Connection, Transaction, params};
+use thiserror::Error;
+
+use crate::{
+    collection::Collection,
+    entity::{Entity, Extended, Time},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+
+    #[error("SQLite output must be written to a file")]
+    NotAFile,
+}
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS entities (
+    id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL,
+    profile TEXT NOT NULL DEFAULT '',
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER,
+    last_visited_at INTEGER,
+    shared INTEGER,
+    to_read INTEGER,
+    is_feed INTEGER,
+    extended TEXT,
+    UNIQUE (profile, url)
+);
+CREATE TABLE IF NOT EXISTS entity_names (
+    entity_id INTEGER NOT NULL REFERENCES entities (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    PRIMARY KEY (entity_id, name)
+);
+CREATE TABLE IF NOT EXISTS labels (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS entity_labels (
+    entity_id INTEGER NOT NULL REFERENCES entities (id) ON DELETE CASCADE,
+    label_id INTEGER NOT NULL REFERENCES labels (id) ON DELETE CASCADE,
+    PRIMARY KEY (entity_id, label_id)
+);
+CREATE TABLE IF NOT EXISTS edges (
+    from_id INTEGER NOT NULL REFERENCES entities (id) ON DELETE CASCADE,
+    to_id INTEGER NOT NULL REFERENCES entities (id) ON DELETE CASCADE,
+    PRIMARY KEY (from_id, to_id)
+);
+";
+
+const CLEAR: &str = "
+DELETE FROM edges;
+DELETE FROM entity_labels;
+DELETE FROM entity_names;
+DELETE FROM labels;
+DELETE FROM entities;
+";
+
+const UPSERT_ENTITY: &str = "
+INSERT INTO entities
+    (url, profile, created_at, updated_at, last_visited_at, shared, to_read, is_feed, extended)
+VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+ON CONFLICT (profile, url) DO UPDATE SET
+    created_at = excluded.created_at,
+    updated_at = excluded.updated_at,
+    last_visited_at = excluded.last_visited_at,
+    shared = excluded.shared,
+    to_read = excluded.to_read,
+    is_feed = excluded.is_feed,
+    extended = excluded.extended
+RETURNING id
+";
+
+const DELETE_CHILDREN: [&str; 3] = [
+    "DELETE FROM entity_names WHERE entity_id = ?1",
+    "DELETE FROM entity_labels WHERE entity_id = ?1",
+    "DELETE FROM edges WHERE from_id = ?1",
+];
+
+const INSERT_NAME: &str = "INSERT INTO entity_names (entity_id, name) VALUES (?1, ?2)";
+
+const UPSERT_LABEL: &str = "
+INSERT INTO labels (name) VALUES (?1)
+ON CONFLICT (name) DO UPDATE SET name = excluded.name
+RETURNING id
+";
+
+const INSERT_ENTITY_LABEL: &str = "INSERT INTO entity_labels (entity_id, label_id) VALUES (?1, ?2)";
+
+const INSERT_EDGE: &str = "INSERT OR IGNORE INTO edges (from_id, to_id) VALUES (?1, ?2)";
+
+fn insert_entity(tx: &Transaction<'_>, entity: &Entity) -> Result<i64, rusqlite::Error> {
+    let updated_at = entity.updated_at().last().map(|t| t.get().timestamp());
+    let extended = entity
+        .extended()
+        .iter()
+        .map(Extended::as_str)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let id: i64 = tx.query_row(
+        UPSERT_ENTITY,
+        params![
+            entity.url().as_str(),
+            entity.profile().map_or("", |profile| profile.as_str()),
+            entity.created_at().get().timestamp(),
+            updated_at,
+            entity.last_visited_at().get().map(Time::timestamp),
+            entity.shared().get(),
+            entity.to_read().get(),
+            entity.is_feed().get(),
+            (!extended.is_empty()).then_some(extended),
+        ],
+        |row| row.get(0),
+    )?;
+
+    // Names, labels and edges removed since a previous export must not survive an upsert.
+    for statement in DELETE_CHILDREN {
+        tx.prepare_cached(statement)?.execute(params![id])?;
+    }
+
+    let mut insert_name = tx.prepare_cached(INSERT_NAME)?;
+    for name in entity.names() {
+        insert_name.execute(params![id, name.as_str()])?;
+    }
+
+    let mut upsert_label = tx.prepare_cached(UPSERT_LABEL)?;
+    let mut insert_entity_label = tx.prepare_cached(INSERT_ENTITY_LABEL)?;
+    for label in entity.labels() {
+        let label_id: i64 = upsert_label.query_row(params![label.as_str()], |row| row.get(0))?;
+        insert_entity_label.execute(params![id, label_id])?;
+    }
+
+    Ok(id)
+}
+
+impl Collection {
+    /// Writes the collection to the `SQLite` database at `path`, creating it if necessary.
+    ///
+    /// Entities, labels and edges are stored in the normalized `entities`, `entity_names`,
+    /// `labels`, `entity_labels` and `edges` tables. Unless `append` is set, any existing rows are
+    /// deleted first; otherwise entities are upserted by profile and URL, and their names, labels
+    /// and outgoing edges replaced, so that repeated exports update the database incrementally.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or written.
+    pub fn to_sqlite(&self, path: &Path, append: bool) -> Result<(), Error> {
+        let mut conn = Connection::open(path)?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+        conn.execute_batch(SCHEMA)?;
+
+        let tx = conn.transaction()?;
+        if !append {
+            tx.execute_batch(CLEAR)?;
+        }
+
+        let mut ids = HashMap::with_capacity(self.len());
+        for (id, entity) in self.iter() {
+            ids.insert(id.index(), insert_entity(&tx, entity)?);
+        }
+
+        {
+            let mut insert_edge = tx.prepare_cached(INSERT_EDGE)?;
+            for (id, _) in self.iter() {
+                for to in self.edges(&id) {
+                    insert_edge.execute(params![ids[&id.index()], ids[&to.index()]])?;
+                }
+            }
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use rusqlite::Connection;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Label, Time, Url},
+    };
+
+    #[test]
+    fn append_upserts_entities() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("hbt-sqlite-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("append.db");
+        let _ = std::fs::remove_file(&path);
+
+        let mut coll = Collection::new();
+        let a = coll.insert(Entity::new(
+            Url::parse("https://a.example.com/")?,
+            Time::from_timestamp(1)?,
+            None,
+            BTreeSet::from([Label::from("x")]),
+        ));
+        let b = coll.insert(Entity::new(
+            Url::parse("https://b.example.com/")?,
+            Time::from_timestamp(2)?,
+            None,
+            BTreeSet::from([Label::from("x"), Label::from("y")]),
+        ));
+        coll.add_edges(&a, &b);
+
+        coll.to_sqlite(&path, false)?;
+        coll.to_sqlite(&path, true)?;
+
+        let conn = Connection::open(&path)?;
+        let count = |table: &str| -> rusqlite::Result<i64> {
+            conn.query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+        };
+        assert_eq!(count("entities")?, 2);
+        assert_eq!(count("labels")?, 2);
+        assert_eq!(count("entity_labels")?, 3);
+        assert_eq!(count("edges")?, 2);
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn append_replaces_removed_labels() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = std::env::temp_dir().join(format!("hbt-sqlite-labels-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("append.db");
+        let _ = std::fs::remove_file(&path);
+
+        let url = Url::parse("https://a.example.com/")?;
+        let mut coll = Collection::new();
+        let id = coll.insert(Entity::new(
+            url.clone(),
+            Time::from_timestamp(1)?,
+            None,
+            BTreeSet::from([Label::from("x"), Label::from("y")]),
+        ));
+        coll.to_sqlite(&path, false)?;
+
+        coll.entity_mut(&id).labels_mut().remove(&Label::from("y"));
+        coll.to_sqlite(&path, true)?;
+
+        let conn = Connection::open(&path)?;
+        let mut statement = conn.prepare(
+            "SELECT labels.name FROM entity_labels
+             JOIN labels ON labels.id = entity_labels.label_id",
+        )?;
+        let labels = statement
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(labels, ["x"]);
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "clap")]
+use clap::{ValueEnum, builder::PossibleValue};
+use serde::Serialize;
+use strum::{IntoStaticStr, VariantArray};
+use thiserror::Error;
+
+use crate::{
+    collection::Collection,
+    entity::{Entity, Extended, Name},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("YAML serialization error: {0}")]
+    Yaml(#[from] serde_norway::Error),
+}
+
+const BOOKMARKS: &str = "bookmarks.yaml";
+const LABELS_DIR: &str = "labels";
+const PARTIAL: &str = "bookmarks.html";
+
+const HUGO_PARTIAL: &str = include_str!("static_site/hugo.html");
+const JEKYLL_INCLUDE: &str = include_str!("static_site/jekyll.html");
+
+/// A static site generator whose data files and templates are written by
+/// [`Collection::to_static_site`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, IntoStaticStr, VariantArray)]
+#[strum(serialize_all = "lowercase")]
+pub enum Generator {
+    #[default]
+    Hugo,
+    Jekyll,
+}
+
+impl Generator {
+    fn data_dir(self) -> &'static str {
+        match self {
+            Generator::Hugo => "data",
+            Generator::Jekyll => "_data",
+        }
+    }
+
+    fn template(self) -> (PathBuf, &'static str) {
+        match self {
+            Generator::Hugo => (
+                Path::new("layouts").join("partials").join(PARTIAL),
+                HUGO_PARTIAL,
+            ),
+            Generator::Jekyll => (Path::new("_includes").join(PARTIAL), JEKYLL_INCLUDE),
+        }
+    }
+}
+
+#[cfg(feature = "clap")]
+impl ValueEnum for Generator {
+    fn value_variants<'a>() -> &'a [Generator] {
+        Generator::VARIANTS
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let s: &'static str = self.into();
+        Some(PossibleValue::new(s))
+    }
+}
+
+/// Converts a label to a lowercase, URL-safe slug, e.g. `Machine Learning/NLP` becomes
+/// `machine-learning-nlp`.
+#[must_use]
+pub fn slugify(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            ret.push(c);
+        } else if !ret.is_empty() && !ret.ends_with('-') {
+            ret.push('-');
+        }
+    }
+    while ret.ends_with('-') {
+        ret.pop();
+    }
+    ret
+}
+
+/// A flattened entity, shaped for use in templates.
+#[derive(Debug, Serialize)]
+struct Bookmark<'a> {
+    title: &'a str,
+    url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<&'a str>,
+    tags: Vec<String>,
+    date: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    updated: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    shared: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    to_read: Option<bool>,
+}
+
+impl<'a> From<&'a Entity> for Bookmark<'a> {
+    fn from(entity: &'a Entity) -> Bookmark<'a> {
+        let mut tags: Vec<String> = entity
+            .labels()
+            .iter()
+            .map(|label| slugify(label.as_str()))
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        Bookmark {
+            title: entity
+                .names()
+                .first()
+                .map_or(entity.url().as_str(), Name::as_str),
+            url: entity.url().as_str(),
+            description: entity.extended().first().map(Extended::as_str),
+            tags,
+            date: entity.created_at().get().to_string(),
+            updated: entity
+                .updated_at()
+                .last()
+                .map(|updated_at| updated_at.get().to_string()),
+            shared: entity.shared().get(),
+            to_read: entity.to_read().get(),
+        }
+    }
+}
+
+fn write_yaml(path: &Path, value: &impl Serialize) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_norway::to_string(value)?)?;
+    Ok(())
+}
+
+impl Collection {
+    /// Writes the collection as data files for a static site rooted at `dir`.
+    ///
+    /// All entities are written to `bookmarks.yaml` in the generator's data directory (`data/` for
+    /// Hugo, `_data/` for Jekyll) as a list of flat records with slugified tags and ISO 8601
+    /// dates. With `per_label`, the entities carrying each label are also written to
+    /// `labels/<slug>.yaml` there. An example template for rendering the list is written to
+    /// `layouts/partials/bookmarks.html` (Hugo) or `_includes/bookmarks.html` (Jekyll), unless one
+    /// already exists.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be written or serialized.
+    pub fn to_static_site(
+        &self,
+        dir: &Path,
+        generator: Generator,
+        per_label: bool,
+    ) -> Result<(), Error> {
+        let data_dir = dir.join(generator.data_dir());
+        let bookmarks: Vec<Bookmark> = self.entities().iter().map(Bookmark::from).collect();
+        write_yaml(&data_dir.join(BOOKMARKS), &bookmarks)?;
+
+        if per_label {
+            let mut labels: BTreeMap<String, Vec<&Bookmark>> = BTreeMap::new();
+            for bookmark in &bookmarks {
+                for tag in &bookmark.tags {
+                    labels.entry(tag.clone()).or_default().push(bookmark);
+                }
+            }
+            for (tag, bookmarks) in labels {
+                let path = data_dir.join(LABELS_DIR).join(format!("{tag}.yaml"));
+                write_yaml(&path, &bookmarks)?;
+            }
+        }
+
+        let (template_path, template) = generator.template();
+        let template_path = dir.join(template_path);
+        if !template_path.exists() {
+            if let Some(parent) = template_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(template_path, template)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::slugify;
+
+    #[test]
+    fn slugify_labels() {
+        assert_eq!(slugify("Machine Learning/NLP"), "machine-learning-nlp");
+        assert_eq!(slugify("  c++ "), "c");
+        assert_eq!(slugify("Café"), "café");
+        assert_eq!(slugify("--"), "");
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::{
+    collection::{Collection, Id, MergePolicy},
+    entity::{Entity, Profile, Time, Url},
+};
+
+type Key = (Option<Profile>, Url);
+
+fn key(entity: &Entity) -> Key {
+    (entity.profile().cloned(), entity.url().clone())
+}
+
+/// An entity of one collection matched by URL and profile with its counterpart in another.
+pub(crate) enum Matched<'a> {
+    /// The entity is in both collections.
+    Both(&'a Entity, &'a Entity),
+    /// The entity is only in the first collection.
+    Left(&'a Entity),
+    /// The entity is only in the second collection.
+    Right(&'a Entity),
+}
+
+/// Matches the entities of `left` with those of `right` by URL and profile, yielding those of
+/// `left` in order, followed by those only in `right`.
+pub(crate) fn matched<'a>(
+    left: &'a Collection,
+    right: &'a Collection,
+) -> impl Iterator<Item = Matched<'a>> {
+    let ours =
+        left.entities()
+            .iter()
+            .map(|entity| match right.id_in(entity.profile(), entity.url()) {
+                Some(id) => Matched::Both(entity, right.entity(&id)),
+                None => Matched::Left(entity),
+            });
+    let theirs = right
+        .entities()
+        .iter()
+        .filter(|entity| !left.contains_in(entity.profile(), entity.url()))
+        .map(Matched::Right);
+    ours.chain(theirs)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "change", content = "url", rename_all = "kebab-case")]
+pub enum Change {
+    /// The entity was added remotely and copied into the archive.
+    Added(Url),
+    /// The entity was modified remotely and replaced in the archive.
+    Updated(Url),
+    /// The entity was deleted remotely and removed from the archive.
+    Removed(Url),
+}
+
+/// An entity that was modified on both sides since the last sync. The local version is kept.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Conflict {
+    pub local: Entity,
+    pub remote: Entity,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub applied: Vec<Change>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Report {
+    #[must_use]
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+
+    /// Returns the number of entities added to the archive.
+    #[must_use]
+    pub fn added(&self) -> usize {
+        self.applied
+            .iter()
+            .filter(|change| matches!(change, Change::Added(_)))
+            .count()
+    }
+
+    /// Returns the number of entities removed from the archive.
+    #[must_use]
+    pub fn removed(&self) -> usize {
+        self.applied
+            .iter()
+            .filter(|change| matches!(change, Change::Removed(_)))
+            .count()
+    }
+}
+
+impl Collection {
+    /// Synchronizes this collection (the local archive) with `remote`, a fresh export of the same
+    /// bookmarks, given the time of the last sync.
+    ///
+    /// Changes are attributed to a side by comparing each entity's last creation or update time
+    /// against `since`:
+    ///
+    /// - an entity only present remotely is added if it is newer than `since`, and otherwise
+    ///   assumed to have been deleted locally;
+    /// - an entity only present locally is removed if it is older than `since`, and otherwise
+    ///   assumed to have been added locally;
+    /// - an entity present on both sides with differing contents is replaced by the remote version
+    ///   if only the remote side changed, and recorded as a [`Conflict`] if both sides changed.
+    ///
+    /// Edges from both collections are preserved between the entities that remain.
+    #[must_use]
+    pub fn sync(&self, remote: &Collection, since: Time) -> (Collection, Report) {
+        self.sync_with_policy(remote, since, MergePolicy::All)
+    }
+
+    /// Synchronizes as in [`Collection::sync`], but only applying the changes that `policy`
+    /// allows: with [`MergePolicy::UpdateOnly`], remote additions are not copied; with
+    /// [`MergePolicy::CreateOnly`], entities in the archive are neither replaced nor removed.
+    #[must_use]
+    pub fn sync_with_policy(
+        &self,
+        remote: &Collection,
+        since: Time,
+        policy: MergePolicy,
+    ) -> (Collection, Report) {
+        let mut report = Report::default();
+        let mut kept: Vec<&Entity> = Vec::with_capacity(self.len());
+
+        for matched in matched(self, remote) {
+            match matched {
+                Matched::Left(local) => {
+                    if local.last_modified() > since || !policy.updates() {
+                        kept.push(local);
+                    } else {
+                        report.applied.push(Change::Removed(local.url().clone()));
+                    }
+                }
+                Matched::Both(local, remote_entity) if local == remote_entity => kept.push(local),
+                Matched::Both(local, remote_entity) => {
+                    let local_changed = local.last_modified() > since;
+                    let remote_changed = remote_entity.last_modified() > since;
+                    match (local_changed, remote_changed) {
+                        (true, true) => {
+                            report.conflicts.push(Conflict {
+                                local: local.clone(),
+                                remote: remote_entity.clone(),
+                            });
+                            kept.push(local);
+                        }
+                        (false, true) if policy.updates() => {
+                            report.applied.push(Change::Updated(local.url().clone()));
+                            kept.push(remote_entity);
+                        }
+                        _ => kept.push(local),
+                    }
+                }
+                Matched::Right(entity) => {
+                    if policy.creates() && entity.last_modified() > since {
+                        report.applied.push(Change::Added(entity.url().clone()));
+                        kept.push(entity);
+                    }
+                }
+            }
+        }
+
+        let mut ret = Collection::with_capacity(kept.len());
+        ret.set_privacy_policy(self.privacy_policy());
+        ret.set_merge_strategies(self.merge_strategies());
+        let mut ids: HashMap<Key, Id> = HashMap::with_capacity(kept.len());
+        for entity in &kept {
+            let id = ret.insert((*entity).clone());
+            ids.insert(key(entity), id);
+        }
+
+        for entity in &kept {
+            let from = &ids[&key(entity)];
+            for coll in [self, remote] {
+                let Some(id) = coll.id_in(entity.profile(), entity.url()) else {
+                    continue;
+                };
+                for to in coll.edges(&id) {
+                    if let Some(to) = ids.get(&key(coll.entity(&to))) {
+                        ret.add_edge(from, to);
+                    }
+                }
+            }
+        }
+
+        (ret, report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::{Collection, MergePolicy},
+        entity::{Entity, Label, Time, Url},
+    };
+
+    use super::Change;
+
+    fn make_entity(url: &str, created_at: i64, label: &str) -> Entity {
+        Entity::new(
+            Url::parse(url).unwrap(),
+            Time::from_timestamp(created_at).unwrap(),
+            None,
+            BTreeSet::from([Label::from(label)]),
+        )
+    }
+
+    #[test]
+    fn non_conflicting_changes_are_applied() {
+        let since = Time::from_timestamp(100).unwrap();
+        let archive = Collection::from_entities([
+            make_entity("https://old.example.com/", 10, "a"),
+            make_entity("https://local.example.com/", 200, "a"),
+            make_entity("https://gone.example.com/", 10, "a"),
+        ]);
+        let remote = Collection::from_entities([
+            make_entity("https://old.example.com/", 10, "a"),
+            make_entity("https://new.example.com/", 200, "a"),
+            make_entity("https://deleted.example.com/", 10, "a"),
+        ]);
+
+        let (synced, report) = archive.sync(&remote, since);
+
+        let urls: Vec<&str> = synced.entities().iter().map(|e| e.url().as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://old.example.com/",
+                "https://local.example.com/",
+                "https://new.example.com/"
+            ]
+        );
+        assert_eq!(
+            report.applied,
+            [
+                Change::Removed(Url::parse("https://gone.example.com/").unwrap()),
+                Change::Added(Url::parse("https://new.example.com/").unwrap()),
+            ]
+        );
+        assert_eq!((report.added(), report.removed()), (1, 1));
+        assert!(!report.has_conflicts());
+    }
+
+    #[test]
+    fn policies_restrict_changes() {
+        let since = Time::from_timestamp(100).unwrap();
+        let archive = Collection::from_entities([
+            make_entity("https://changed.example.com/", 10, "a"),
+            make_entity("https://gone.example.com/", 10, "a"),
+        ]);
+        let remote = Collection::from_entities([
+            make_entity("https://changed.example.com/", 200, "b"),
+            make_entity("https://new.example.com/", 200, "a"),
+        ]);
+
+        let (_, report) = archive.sync_with_policy(&remote, since, MergePolicy::UpdateOnly);
+        assert_eq!(
+            report.applied,
+            [
+                Change::Updated(Url::parse("https://changed.example.com/").unwrap()),
+                Change::Removed(Url::parse("https://gone.example.com/").unwrap()),
+            ]
+        );
+
+        let (synced, report) = archive.sync_with_policy(&remote, since, MergePolicy::CreateOnly);
+        assert_eq!(
+            report.applied,
+            [Change::Added(
+                Url::parse("https://new.example.com/").unwrap()
+            )]
+        );
+        assert_eq!(synced.len(), 3);
+        assert_eq!(synced.entities()[0], archive.entities()[0]);
+    }
+
+    #[test]
+    fn changes_on_both_sides_conflict() {
+        let since = Time::from_timestamp(100).unwrap();
+        let archive = Collection::from_entities([make_entity("https://example.com/", 200, "a")]);
+        let remote = Collection::from_entities([make_entity("https://example.com/", 300, "b")]);
+
+        let (synced, report) = archive.sync(&remote, since);
+
+        assert_eq!(synced, archive);
+        assert_eq!(report.conflicts.len(), 1);
+        assert!(report.applied.is_empty());
+    }
+}
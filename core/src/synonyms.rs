@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+
+use crate::{collection::Collection, entity::Label};
+
+/// Consonants that are legitimately doubled at the end of a word, as in `install` or `css`.
+const DOUBLED_ENDINGS: [char; 3] = ['l', 's', 'z'];
+
+/// Strips `suffix` from `word` if at least `min_stem` characters remain.
+fn strip<'a>(word: &'a str, suffix: &str, min_stem: usize) -> Option<&'a str> {
+    word.strip_suffix(suffix)
+        .filter(|stem| stem.chars().count() >= min_stem)
+}
+
+/// Reduces a word to a crude stem, so that inflections such as `test`, `tests` and `testing`, or
+/// `library` and `libraries`, share one.
+///
+/// This is a small subset of Porter stemming, tuned for short, English labels. It handles
+/// plurals and the `-ing` and `-ed` endings, and otherwise leaves words unchanged.
+#[must_use]
+pub fn stem(word: &str) -> String {
+    let word = word.to_lowercase();
+    let mut ret = if let Some(stem) = strip(&word, "ies", 2) {
+        format!("{stem}y")
+    } else if let Some(stem) = strip(&word, "sses", 1) {
+        format!("{stem}ss")
+    } else if let Some(stem) = strip(&word, "es", 2)
+        .filter(|stem| ["ch", "sh", "x", "z"].iter().any(|end| stem.ends_with(end)))
+    {
+        stem.to_string()
+    } else if word.ends_with("ss") || word.ends_with("us") || word.ends_with("is") {
+        word.clone()
+    } else if let Some(stem) = strip(&word, "s", 3) {
+        stem.to_string()
+    } else {
+        word.clone()
+    };
+
+    if let Some(stem) = strip(&ret, "ing", 3).or_else(|| strip(&ret, "ed", 3)) {
+        let mut stem = stem.to_string();
+        let mut chars = stem.chars().rev();
+        if let (Some(last), Some(prev)) = (chars.next(), chars.next())
+            && last == prev
+            && !DOUBLED_ENDINGS.contains(&last)
+            && !"aeiou".contains(last)
+        {
+            stem.pop();
+        }
+        ret = stem;
+    }
+
+    if ret.chars().count() > 3 && ret.ends_with('e') {
+        ret.pop();
+    }
+    ret
+}
+
+/// Returns the stem of a label, stemming each word of a multi-word label.
+fn label_stem(label: &Label) -> String {
+    label
+        .as_str()
+        .split(|c: char| !c.is_alphanumeric())
+        .map(stem)
+        .collect::<Vec<String>>()
+        .join("-")
+}
+
+impl Collection {
+    /// Suggests label merges, grouping labels that share a stem, such as `test`, `tests` and
+    /// `testing`, or that differ only in case.
+    ///
+    /// Each label in a group is mapped to the group's most common label (the shortest, and then
+    /// the first alphabetically, in a tie). The result is in the format of a mappings file for
+    /// [`Collection::update_labels`].
+    #[must_use]
+    pub fn suggest_label_merges(&self) -> BTreeMap<Label, Label> {
+        let mut groups: BTreeMap<String, Vec<(&Label, usize)>> = BTreeMap::new();
+        for (label, count) in self.label_counts() {
+            groups
+                .entry(label_stem(label))
+                .or_default()
+                .push((label, count));
+        }
+
+        let mut ret = BTreeMap::new();
+        for labels in groups.into_values().filter(|labels| labels.len() > 1) {
+            let Some(&(canonical, _)) = labels.iter().min_by(|(a, a_count), (b, b_count)| {
+                b_count
+                    .cmp(a_count)
+                    .then(a.as_str().len().cmp(&b.as_str().len()))
+                    .then(a.cmp(b))
+            }) else {
+                continue;
+            };
+            for (label, _) in labels {
+                if label != canonical {
+                    ret.insert(label.clone(), canonical.clone());
+                }
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Label, Time, Url},
+    };
+
+    use super::stem;
+
+    #[test]
+    fn inflections_share_a_stem() {
+        let groups: [&[&str]; 5] = [
+            &["test", "tests", "testing", "tested"],
+            &["library", "libraries"],
+            &["run", "running", "runs"],
+            &["code", "coding", "codes"],
+            &["box", "boxes"],
+        ];
+        for group in groups {
+            let stems: BTreeSet<String> = group.iter().map(|word| stem(word)).collect();
+            assert_eq!(stems.len(), 1, "{group:?} -> {stems:?}");
+        }
+        assert_ne!(stem("css"), stem("cs"));
+        assert_eq!(stem("status"), "status");
+    }
+
+    #[test]
+    fn merges_map_to_the_most_common_label() {
+        let mut coll = Collection::new();
+        for (i, label) in ["testing", "test", "test", "Tests", "rust"]
+            .iter()
+            .enumerate()
+        {
+            let url = Url::parse(&format!("https://example.com/{i}")).unwrap();
+            let labels = BTreeSet::from([Label::from(*label)]);
+            coll.insert(Entity::new(url, Time::default(), None, labels));
+        }
+
+        let merges = coll.suggest_label_merges();
+
+        assert_eq!(merges.len(), 2);
+        assert_eq!(merges[&Label::from("testing")], Label::from("test"));
+        assert_eq!(merges[&Label::from("Tests")], Label::from("test"));
+    }
+}
@@ -0,0 +1,282 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use thiserror::Error;
+
+use crate::{
+    collection::Collection,
+    entity::{self, Entity, Profile},
+    ignore::IgnoreList,
+    redact,
+    replace::TextField,
+    rewrite,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("{0}: {1}")]
+    Step(String, #[source] entity::Error),
+}
+
+/// A step that modifies a collection in place.
+pub trait Transform {
+    /// Returns a short description of the step, used in its [`Summary`].
+    fn name(&self) -> String;
+
+    /// Applies the step to `coll`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the step cannot be applied, e.g. a URL rewrite produces an invalid URL.
+    fn apply(&self, coll: &mut Collection) -> Result<(), entity::Error>;
+}
+
+pub(crate) fn ignore_list<'de, D>(deserializer: D) -> Result<IgnoreList, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let patterns = Vec::<String>::deserialize(deserializer)?;
+    patterns
+        .join("\n")
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+/// A built-in pipeline step, as read from a pipeline file.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Step {
+    /// Moves the entities in the default profile into the named profile.
+    SetProfile(String),
+    /// See [`Collection::normalize_urls`].
+    NormalizeUrls,
+    /// See [`Collection::rewrite_urls`].
+    RewriteUrls(Vec<rewrite::Rule>),
+    /// Removes entities matching the given patterns, in the format of an ignore file. See
+    /// [`IgnoreList`].
+    Ignore(#[serde(deserialize_with = "ignore_list")] IgnoreList),
+    /// Renames labels. See [`Collection::update_labels`].
+    Mappings(BTreeMap<String, String>),
+    /// Removes private data. See [`Collection::redact`].
+    Redact(redact::Policy),
+    /// Replaces text in names, or in the given fields. See [`Collection::replace_text`].
+    ReplaceText {
+        #[serde(deserialize_with = "rewrite::regex")]
+        pattern: Regex,
+        replacement: String,
+        #[serde(default = "default_text_fields")]
+        fields: BTreeSet<TextField>,
+    },
+    /// Adds labels for the year and month of creation. See [`Collection::add_date_labels`].
+    DateLabels,
+}
+
+fn default_text_fields() -> BTreeSet<TextField> {
+    BTreeSet::from([TextField::Names])
+}
+
+impl Transform for Step {
+    fn name(&self) -> String {
+        let name = match self {
+            Step::SetProfile(_) => "set-profile",
+            Step::NormalizeUrls => "normalize-urls",
+            Step::RewriteUrls(_) => "rewrite-urls",
+            Step::Ignore(_) => "ignore",
+            Step::Mappings(_) => "mappings",
+            Step::Redact(_) => "redact",
+            Step::ReplaceText { .. } => "replace-text",
+            Step::DateLabels => "date-labels",
+        };
+        name.to_string()
+    }
+
+    fn apply(&self, coll: &mut Collection) -> Result<(), entity::Error> {
+        match self {
+            Step::SetProfile(profile) => {
+                coll.move_profile(None, Some(&Profile::from(profile.as_str())));
+            }
+            Step::NormalizeUrls => coll.normalize_urls(),
+            Step::RewriteUrls(rules) => coll.rewrite_urls(rules)?,
+            Step::Ignore(ignore) => {
+                coll.remove_matching(ignore);
+            }
+            Step::Mappings(mappings) => {
+                coll.update_labels(mappings.clone());
+            }
+            Step::Redact(policy) => {
+                coll.redact(policy);
+            }
+            Step::ReplaceText {
+                pattern,
+                replacement,
+                fields,
+            } => {
+                coll.replace_text(pattern, replacement, fields);
+            }
+            Step::DateLabels => {
+                coll.add_date_labels();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What a step did to a collection.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub step: String,
+    /// The number of entities before the step.
+    pub before: usize,
+    /// The number of entities after the step.
+    pub after: usize,
+    /// The number of entities after the step that differ from every entity before it.
+    pub changed: usize,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {} entities, {} changed",
+            self.step, self.before, self.after, self.changed
+        )
+    }
+}
+
+/// An ordered list of transforms.
+///
+/// Pipelines of built-in steps are typically read from a YAML file such as:
+///
+/// ```yaml
+/// - normalize-urls
+/// - rewrite-urls: [force-https, strip-query: [utm_*]]
+/// - ignore: [doubleclick.net, "*.google.com/search*"]
+/// - mappings:
+///     programming/rust: rust
+/// - replace-text:
+///     pattern: '\s*\|\s*Example Site$'
+///     replacement: ''
+/// - date-labels
+/// ```
+#[derive(Default)]
+pub struct Pipeline {
+    steps: Vec<Box<dyn Transform>>,
+}
+
+impl Pipeline {
+    #[must_use]
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// Appends a step to the pipeline.
+    pub fn push(&mut self, step: impl Transform + 'static) {
+        self.steps.push(Box::new(step));
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Applies each step to `coll` in order, returning a summary of each.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from a step, naming the step. Earlier steps remain applied.
+    pub fn run(&self, coll: &mut Collection) -> Result<Vec<Summary>, Error> {
+        let mut ret = Vec::with_capacity(self.steps.len());
+        for step in &self.steps {
+            let before: Vec<Entity> = coll.entities().to_vec();
+            step.apply(coll)
+                .map_err(|err| Error::Step(step.name(), err))?;
+            let unchanged: BTreeSet<&Entity> = before.iter().collect();
+            ret.push(Summary {
+                step: step.name(),
+                before: before.len(),
+                after: coll.len(),
+                changed: coll
+                    .entities()
+                    .iter()
+                    .filter(|entity| !unchanged.contains(entity))
+                    .count(),
+            });
+        }
+        Ok(ret)
+    }
+}
+
+impl From<Vec<Step>> for Pipeline {
+    fn from(steps: Vec<Step>) -> Pipeline {
+        let mut ret = Pipeline::new();
+        for step in steps {
+            ret.push(step);
+        }
+        ret
+    }
+}
+
+impl<'de> Deserialize<'de> for Pipeline {
+    fn deserialize<D>(deserializer: D) -> Result<Pipeline, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // Steps are written as single-key maps (e.g. `- rewrite-urls: [force-https]`)
+        serde_norway::with::singleton_map_recursive::deserialize::<Vec<Step>, D>(deserializer)
+            .map(Pipeline::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use crate::{
+        collection::Collection,
+        entity::{Entity, Label, Time, Url},
+    };
+
+    use super::Pipeline;
+
+    const PIPELINE: &str = "
+- rewrite-urls: [force-https]
+- ignore: [doubleclick.net]
+- mappings:
+    old: new
+";
+
+    #[test]
+    fn pipeline_applies_steps_in_order() {
+        let time = Time::default();
+        let mut coll = Collection::new();
+        for url in [
+            "http://example.com/",
+            "https://example.com/",
+            "https://doubleclick.net/",
+        ] {
+            let labels = BTreeSet::from([Label::from("old")]);
+            coll.upsert(Entity::new(Url::parse(url).unwrap(), time, None, labels));
+        }
+
+        let pipeline: Pipeline = serde_norway::from_str(PIPELINE).unwrap();
+        assert_eq!(pipeline.len(), 3);
+        let summaries = pipeline.run(&mut coll).unwrap();
+
+        let counts: Vec<(usize, usize)> = summaries.iter().map(|s| (s.before, s.after)).collect();
+        assert_eq!(counts, [(3, 2), (2, 1), (1, 1)]);
+        assert_eq!(summaries[2].changed, 1);
+        assert_eq!(
+            coll.entities()[0].labels(),
+            &BTreeSet::from([Label::from("new")])
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::BTreeSet;
+
+use chrono::{NaiveDate, Utc};
+use thiserror::Error;
+
+use crate::{
+    collection::Collection,
+    entity::{self, Entity, Label, Name, RAW_EXTRA, Time, UrlPolicy},
+    limits::{self, Limits},
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid bookmark on line {0}: {1}")]
+    Line(usize, #[source] entity::Error),
+
+    #[error("invalid date: {0}")]
+    InvalidDate(NaiveDate),
+
+    #[error("line {0} exceeds limits: {1}")]
+    Limit(usize, #[source] limits::Error),
+}
+
+const COMMENT_PREFIX: char = '#';
+const FIELD_SEPARATOR: char = '\t';
+
+/// Options for reading lists of URLs.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    pub url_policy: UrlPolicy,
+    /// The creation date of every bookmark. Bookmarks are created now if this is `None`.
+    pub default_date: Option<NaiveDate>,
+    /// Record the line each bookmark was read from in its [`RAW_EXTRA`] extra.
+    pub keep_raw: bool,
+    /// Bounds on the number of bookmarks and the length of their fields.
+    pub limits: Limits,
+}
+
+/// Splits a tags field on commas and whitespace.
+fn labels(tags: &str) -> BTreeSet<Label> {
+    tags.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|tag| !tag.is_empty())
+        .map(Label::from)
+        .collect()
+}
+
+impl Collection {
+    /// Adds the bookmarks in a plain-text list of URLs, one per line, such as the output of
+    /// `grep -o`.
+    ///
+    /// A line may also give a title and tags, separated from the URL by tabs:
+    /// `<url>\t<title>\t<tags>`, where tags are separated by commas or spaces. Blank lines and
+    /// lines starting with `#` are skipped, and URLs listed more than once are merged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the line of the first invalid URL, or if the default date cannot be
+    /// represented.
+    pub fn extend_from_txt(&mut self, input: &str, options: &Options) -> Result<(), Error> {
+        let created_at = match options.default_date {
+            Some(date) => Time::new(
+                date.and_hms_opt(0, 0, 0)
+                    .ok_or(Error::InvalidDate(date))?
+                    .and_utc(),
+            ),
+            None => Time::new(Utc::now()),
+        };
+
+        let mut count = 0_usize;
+        for (index, line) in input.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with(COMMENT_PREFIX) {
+                continue;
+            }
+            count += 1;
+            let limit_error = |err| Error::Limit(index + 1, err);
+            options.limits.check_entities(count).map_err(limit_error)?;
+            let mut fields = trimmed.split(FIELD_SEPARATOR).map(str::trim);
+            let href = fields.next().unwrap_or_default();
+            let url = options
+                .url_policy
+                .parse(href)
+                .map_err(|err| Error::Line(index + 1, err))?;
+            let maybe_name = fields
+                .next()
+                .filter(|title| !title.is_empty())
+                .map(|title| Name::new(title.to_string()));
+            let labels = fields.next().map(labels).unwrap_or_default();
+            let mut entity = Entity::new(url, created_at, maybe_name, labels);
+            if options.keep_raw {
+                entity
+                    .extras_mut()
+                    .insert(RAW_EXTRA.to_string(), line.to_string());
+            }
+            options.limits.check_entity(&entity).map_err(limit_error)?;
+            self.upsert(entity);
+        }
+        Ok(())
+    }
+
+    /// Parses a plain-text list of URLs. See [`Collection::extend_from_txt`].
+    ///
+    /// # Errors
+    ///
+    /// See [`Collection::extend_from_txt`].
+    pub fn from_txt(input: &str, options: &Options) -> Result<Collection, Error> {
+        let mut ret = Collection::new();
+        ret.extend_from_txt(input, options)?;
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::{
+        collection::Collection,
+        entity::{Label, Name, Time},
+    };
+
+    use crate::limits::{self, Limits};
+
+    use super::{Error, Options};
+
+    #[test]
+    fn urls_titles_and_tags() {
+        let input = "\
+# saved links
+https://example.com/
+
+https://example.org/\tExample\trust, web
+https://example.com/\tExample Site
+";
+        let options = Options {
+            default_date: NaiveDate::from_ymd_opt(2023, 11, 15),
+            ..Options::default()
+        };
+        let coll = Collection::from_txt(input, &options).unwrap();
+
+        assert_eq!(coll.len(), 2);
+        let entities = coll.entities();
+        assert_eq!(
+            entities[0].names().iter().collect::<Vec<_>>(),
+            [&Name::from("Example Site")]
+        );
+        let labels: Vec<&str> = entities[1].labels().iter().map(Label::as_str).collect();
+        assert_eq!(labels, ["rust", "web"]);
+        assert_eq!(
+            entities[1].created_at().get(),
+            Time::from_timestamp(1_700_006_400).unwrap()
+        );
+
+        assert!(matches!(
+            Collection::from_txt("https://example.com/\nnot a url\n", &options),
+            Err(Error::Line(2, _))
+        ));
+    }
+
+    #[test]
+    fn limits_are_checked_per_line() {
+        let input = format!(
+            "https://example.com/\tExample\nhttps://example.org/\t{}\n",
+            "x".repeat(40)
+        );
+        let options = |max_entities, max_field_len| Options {
+            limits: Limits {
+                max_entities,
+                max_field_len,
+                ..Limits::default()
+            },
+            ..Options::default()
+        };
+
+        assert_eq!(
+            Collection::from_txt(&input, &options(2, 64)).unwrap().len(),
+            2
+        );
+        assert!(matches!(
+            Collection::from_txt(&input, &options(1, 64)),
+            Err(Error::Limit(2, limits::Error::Entities(1)))
+        ));
+        assert!(matches!(
+            Collection::from_txt(&input, &options(2, 32)),
+            Err(Error::Limit(
+                2,
+                limits::Error::FieldLength { field: "name", .. }
+            ))
+        ));
+    }
+}
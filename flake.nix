@@ -38,6 +38,7 @@
           ".jinja"
           ".json"
           ".md"
+          ".svg"
           ".xml"
           ".yaml"
         ];
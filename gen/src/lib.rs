@@ -0,0 +1,236 @@
+#![forbid(unsafe_code)]
+#![warn(clippy::pedantic)]
+#![deny(clippy::unwrap_in_result)]
+
+use std::fmt::Write as _;
+
+use chrono::{DateTime, Datelike, Utc};
+use clap::ValueEnum;
+use serde_json::json;
+
+/// Input formats that synthetic data can be generated in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Pinboard JSON export
+    Json,
+    /// Pinboard XML export
+    Xml,
+    /// Markdown with date headings
+    Md,
+    /// Netscape bookmark file
+    Html,
+    /// Netscape bookmark file with a top-level folder per year, like a large browser export
+    HtmlFolders,
+}
+
+const WORDS: [&str; 16] = [
+    "rust", "graph", "bookmark", "parser", "archive", "notes", "search", "index", "async",
+    "format", "browser", "export", "import", "label", "snapshot", "sync",
+];
+
+const DOMAINS: [&str; 8] = [
+    "example.com",
+    "example.org",
+    "example.net",
+    "docs.example.com",
+    "blog.example.org",
+    "news.example.net",
+    "wiki.example.com",
+    "code.example.org",
+];
+
+/// 2010-01-01T00:00:00Z
+const START: i64 = 1_262_304_000;
+
+/// The longest gap between consecutive bookmarks, in seconds.
+const MAX_GAP: u64 = 6 * 60 * 60;
+
+/// A small, seedable pseudo-random number generator, so output is identical across runs and
+/// platforms.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // The remainder is less than `bound`, so it always fits in a usize.
+    #[allow(clippy::cast_possible_truncation)]
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+/// A synthetic bookmark.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub url: String,
+    pub time: DateTime<Utc>,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub extended: Option<String>,
+    pub shared: bool,
+    pub to_read: bool,
+}
+
+/// Generates `count` synthetic bookmarks in chronological order. The same `seed` always yields
+/// the same bookmarks.
+#[must_use]
+pub fn records(count: usize, seed: u64) -> Vec<Record> {
+    let mut rng = SplitMix64(seed);
+    let mut secs = START;
+    let mut ret = Vec::with_capacity(count);
+    for i in 0..count {
+        secs += i64::try_from(rng.next() % MAX_GAP).unwrap_or_default();
+        let domain = rng.pick(&DOMAINS);
+        let title_words: Vec<&str> = (0..=rng.below(4)).map(|_| rng.pick(&WORDS)).collect();
+        let mut tags: Vec<String> = (0..rng.below(4))
+            .map(|_| rng.pick(&WORDS).to_string())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        let extended = (rng.below(4) == 0).then(|| format!("Notes on {}", title_words.join(" ")));
+        ret.push(Record {
+            url: format!("https://{domain}/{}/{i}", title_words.join("-")),
+            time: DateTime::from_timestamp(secs, 0).unwrap_or_default(),
+            title: title_words.join(" "),
+            tags,
+            extended,
+            shared: rng.below(2) == 0,
+            to_read: rng.below(8) == 0,
+        });
+    }
+    ret
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn to_json(records: &[Record]) -> String {
+    let posts: Vec<_> = records
+        .iter()
+        .map(|record| {
+            json!({
+                "href": record.url,
+                "description": record.title,
+                "extended": record.extended.clone().unwrap_or_default(),
+                "meta": "",
+                "hash": "",
+                "time": record.time.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+                "shared": yes_no(record.shared),
+                "toread": yes_no(record.to_read),
+                "tags": record.tags.join(" "),
+            })
+        })
+        .collect();
+    let mut ret = serde_json::Value::from(posts).to_string();
+    ret.push('\n');
+    ret
+}
+
+fn to_xml(records: &[Record]) -> String {
+    let mut ret =
+        String::from("<?xml version=\"1.0\" encoding=\"UTF-8\" ?>\n<posts user=\"hbt-gen\">\n");
+    for record in records {
+        let _ = writeln!(
+            ret,
+            "<post href=\"{}\" time=\"{}\" description=\"{}\" extended=\"{}\" tag=\"{}\" hash=\"\" shared=\"{}\" toread=\"{}\" />",
+            escape(&record.url),
+            record.time.format("%Y-%m-%dT%H:%M:%SZ"),
+            escape(&record.title),
+            escape(record.extended.as_deref().unwrap_or_default()),
+            escape(&record.tags.join(" ")),
+            yes_no(record.shared),
+            yes_no(record.to_read),
+        );
+    }
+    ret.push_str("</posts>\n");
+    ret
+}
+
+fn to_markdown(records: &[Record]) -> String {
+    let mut ret = String::new();
+    let mut maybe_date = None;
+    for record in records {
+        let date = record.time.date_naive();
+        if maybe_date != Some(date) {
+            if maybe_date.is_some() {
+                ret.push('\n');
+            }
+            let _ = writeln!(ret, "# {}\n", date.format("%B %-d, %Y"));
+            maybe_date = Some(date);
+        }
+        let _ = writeln!(ret, "- [{}]({})", record.title, record.url);
+    }
+    ret
+}
+
+fn to_html(records: &[Record], folders: bool) -> String {
+    let mut ret = String::from(
+        "<!DOCTYPE NETSCAPE-Bookmark-file-1>\n<META HTTP-EQUIV=\"Content-Type\" CONTENT=\"text/html; charset=UTF-8\">\n<TITLE>Bookmarks</TITLE>\n<H1>Bookmarks</H1>\n<DL><p>\n",
+    );
+    let mut maybe_year = None;
+    for record in records {
+        let year = record.time.year();
+        if folders && maybe_year != Some(year) {
+            if maybe_year.is_some() {
+                ret.push_str("    </DL><p>\n");
+            }
+            let _ = writeln!(ret, "    <DT><H3>{year}</H3>\n    <DL><p>");
+            maybe_year = Some(year);
+        }
+        let _ = write!(
+            ret,
+            "    <DT><A HREF=\"{}\" ADD_DATE=\"{}\"",
+            escape(&record.url),
+            record.time.timestamp()
+        );
+        if !record.tags.is_empty() {
+            let _ = write!(ret, " TAGS=\"{}\"", escape(&record.tags.join(",")));
+        }
+        let _ = writeln!(
+            ret,
+            " PRIVATE=\"{}\" TOREAD=\"{}\">{}</A>",
+            u8::from(!record.shared),
+            u8::from(record.to_read),
+            escape(&record.title)
+        );
+        if let Some(extended) = &record.extended {
+            let _ = writeln!(ret, "    <DD>{}", escape(extended));
+        }
+    }
+    if maybe_year.is_some() {
+        ret.push_str("    </DL><p>\n");
+    }
+    ret.push_str("</DL><p>\n");
+    ret
+}
+
+/// Generates `count` synthetic bookmarks in the given format.
+#[must_use]
+pub fn generate(format: Format, count: usize, seed: u64) -> String {
+    let records = records(count, seed);
+    match format {
+        Format::Json => to_json(&records),
+        Format::Xml => to_xml(&records),
+        Format::Md => to_markdown(&records),
+        Format::Html => to_html(&records, false),
+        Format::HtmlFolders => to_html(&records, true),
+    }
+}
@@ -0,0 +1,60 @@
+#![forbid(unsafe_code)]
+#![warn(clippy::pedantic)]
+#![deny(clippy::unwrap_in_result)]
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+
+use clap::Parser;
+
+use hbt_gen::{Format, generate};
+
+/// Parses a count such as `1000`, `100k` or `1m`.
+fn parse_count(s: &str) -> Result<usize, String> {
+    let lower = s.to_lowercase();
+    let (digits, multiplier) = if let Some(digits) = lower.strip_suffix('k') {
+        (digits, 1_000)
+    } else if let Some(digits) = lower.strip_suffix('m') {
+        (digits, 1_000_000)
+    } else {
+        (lower.as_str(), 1)
+    };
+    digits
+        .parse::<usize>()
+        .ok()
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid count: {s}"))
+}
+
+/// Generates deterministic synthetic bookmarks for benchmarks and stress tests
+#[derive(Parser, Debug)]
+#[command(about, long_about = None)]
+struct Args {
+    /// Output format
+    #[arg(short = 't', long = "to", value_enum)]
+    to: Format,
+
+    /// Number of bookmarks, e.g. 1000, 100k or 1m
+    #[arg(short = 'n', long = "count", value_parser = parse_count, default_value = "1k")]
+    count: usize,
+
+    /// Seed for the generator
+    #[arg(long = "seed", default_value_t = 0)]
+    seed: u64,
+
+    /// Output file (defaults to stdout)
+    #[arg(short = 'o', long = "output")]
+    output: Option<PathBuf>,
+}
+
+fn main() -> io::Result<()> {
+    let args = Args::parse();
+    let output = generate(args.to, args.count, args.seed);
+    match &args.output {
+        Some(path) => fs::write(path, output),
+        None => io::stdout().lock().write_all(output.as_bytes()),
+    }
+}
@@ -2,9 +2,16 @@
 #![warn(clippy::pedantic)]
 #![deny(clippy::unwrap_in_result)]
 
-use std::io::BufRead;
-
-use serde::{Deserialize, Serialize};
+use std::{
+    borrow::Cow,
+    fmt,
+    io::{BufRead, Write},
+};
+
+use serde::{
+    Deserialize, Deserializer, Serialize,
+    de::{self, SeqAccess, Visitor},
+};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -20,6 +27,9 @@ pub enum Error {
 
     #[error("JSON parsing error: {0}")]
     ParseJson(#[from] serde_json::Error),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 #[derive(Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
@@ -28,28 +38,140 @@ pub struct Post {
 
     pub time: String,
 
-    #[serde(deserialize_with = "json::empty_string")]
+    #[serde(
+        deserialize_with = "json::empty_string",
+        serialize_with = "json::ser::empty_string"
+    )]
     pub description: Option<String>,
 
-    #[serde(deserialize_with = "json::empty_string")]
+    #[serde(
+        deserialize_with = "json::empty_string",
+        serialize_with = "json::ser::empty_string"
+    )]
     pub extended: Option<String>,
 
-    #[serde(deserialize_with = "json::tags", default)]
+    #[serde(
+        deserialize_with = "json::tags",
+        serialize_with = "json::ser::tags",
+        default
+    )]
     pub tags: Vec<String>,
 
-    #[serde(deserialize_with = "json::empty_string")]
+    #[serde(
+        deserialize_with = "json::empty_string",
+        serialize_with = "json::ser::empty_string"
+    )]
     pub meta: Option<String>,
 
-    #[serde(deserialize_with = "json::empty_string")]
+    #[serde(
+        deserialize_with = "json::empty_string",
+        serialize_with = "json::ser::empty_string"
+    )]
     pub hash: Option<String>,
 
-    #[serde(deserialize_with = "json::yes_no")]
+    #[serde(
+        deserialize_with = "json::yes_no",
+        serialize_with = "json::ser::yes_no"
+    )]
     pub shared: bool,
 
-    #[serde(deserialize_with = "json::yes_no")]
+    #[serde(
+        deserialize_with = "json::yes_no",
+        serialize_with = "json::ser::yes_no"
+    )]
     pub toread: bool,
 }
 
+/// A Pinboard post borrowing its fields from the input where possible.
+///
+/// Fields only need to be allocated when they contain escaped characters.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PostRef<'a> {
+    pub href: Cow<'a, str>,
+    pub time: Cow<'a, str>,
+    pub description: Option<Cow<'a, str>>,
+    pub extended: Option<Cow<'a, str>>,
+    pub tags: Vec<Cow<'a, str>>,
+    pub meta: Option<Cow<'a, str>>,
+    pub hash: Option<Cow<'a, str>>,
+    pub shared: bool,
+    pub toread: bool,
+}
+
+fn split_tags(tags: Cow<'_, str>) -> Vec<Cow<'_, str>> {
+    match tags {
+        Cow::Borrowed(tags) => tags.split_whitespace().map(Cow::Borrowed).collect(),
+        Cow::Owned(tags) => tags
+            .split_whitespace()
+            .map(|tag| Cow::Owned(tag.to_owned()))
+            .collect(),
+    }
+}
+
+/// Visits a JSON array of posts, passing each to `f`. An error from `f` is kept in `maybe_err`,
+/// as the deserializer can only carry its own errors.
+struct EachPost<'a, F, E> {
+    f: &'a mut F,
+    maybe_err: &'a mut Option<E>,
+}
+
+impl<'de, F, E> Visitor<'de> for EachPost<'_, F, E>
+where
+    F: FnMut(Post) -> Result<(), E>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str("an array of posts")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        while let Some(post) = seq.next_element::<Post>()? {
+            if let Err(err) = (self.f)(post) {
+                *self.maybe_err = Some(err);
+                return Err(de::Error::custom("stopped by callback"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<PostRef<'_>> for Post {
+    fn from(post: PostRef<'_>) -> Post {
+        Post {
+            href: post.href.into_owned(),
+            time: post.time.into_owned(),
+            description: post.description.map(Cow::into_owned),
+            extended: post.extended.map(Cow::into_owned),
+            tags: post.tags.into_iter().map(Cow::into_owned).collect(),
+            meta: post.meta.map(Cow::into_owned),
+            hash: post.hash.map(Cow::into_owned),
+            shared: post.shared,
+            toread: post.toread,
+        }
+    }
+}
+
+/// Wraps an owned post's fields without copying them.
+impl From<Post> for PostRef<'static> {
+    fn from(post: Post) -> PostRef<'static> {
+        PostRef {
+            href: Cow::Owned(post.href),
+            time: Cow::Owned(post.time),
+            description: post.description.map(Cow::Owned),
+            extended: post.extended.map(Cow::Owned),
+            tags: post.tags.into_iter().map(Cow::Owned).collect(),
+            meta: post.meta.map(Cow::Owned),
+            hash: post.hash.map(Cow::Owned),
+            shared: post.shared,
+            toread: post.toread,
+        }
+    }
+}
+
 impl Post {
     /// Parse Pinboard posts from JSON format.
     ///
@@ -59,6 +181,45 @@ impl Post {
     pub fn from_json(input: &mut impl BufRead) -> Result<Vec<Post>, Error> {
         serde_json::from_reader(input).map_err(Into::into)
     }
+
+    /// Parse Pinboard posts from JSON format, passing each one to `f` as it is read, without
+    /// holding the whole array in memory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is malformed or cannot be deserialized into `Post` structures,
+    /// or the first error returned by `f`.
+    pub fn for_each_json<E>(
+        input: &mut impl BufRead,
+        mut f: impl FnMut(Post) -> Result<(), E>,
+    ) -> Result<(), E>
+    where
+        E: From<Error>,
+    {
+        let mut maybe_err = None;
+        let mut deserializer = serde_json::Deserializer::from_reader(input);
+        let result = (&mut deserializer).deserialize_seq(EachPost {
+            f: &mut f,
+            maybe_err: &mut maybe_err,
+        });
+        if let Some(err) = maybe_err {
+            return Err(err);
+        }
+        result.map_err(Error::from)?;
+        deserializer.end().map_err(Error::from)?;
+        Ok(())
+    }
+
+    /// Write Pinboard posts in JSON format, as exported by Pinboard.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing to the output fails.
+    pub fn to_json(writer: &mut impl Write, posts: &[Post]) -> Result<(), Error> {
+        serde_json::to_writer(&mut *writer, posts)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    }
 }
 
 mod json {
@@ -100,17 +261,46 @@ mod json {
             ))),
         }
     }
+
+    /// Serializers writing fields as Pinboard does, the inverse of the deserializers above.
+    pub mod ser {
+        use serde::Serializer;
+
+        #[allow(clippy::ref_option)]
+        pub fn empty_string<S>(value: &Option<String>, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_str(value.as_deref().unwrap_or_default())
+        }
+
+        pub fn tags<S>(tags: &[String], serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_str(&tags.join(" "))
+        }
+
+        #[allow(clippy::trivially_copy_pass_by_ref)]
+        pub fn yes_no<S>(value: &bool, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            serializer.serialize_str(if *value { "yes" } else { "no" })
+        }
+    }
 }
 
 pub mod xml {
-    use std::io::BufRead;
+    use std::io::{BufRead, Write};
 
     use quick_xml::{
-        events::{Event, attributes::Attributes},
+        events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event, attributes::Attributes},
         reader::Reader,
+        writer::Writer,
     };
 
-    use super::{Error, Post};
+    use super::{Error, Post, PostRef, split_tags};
 
     const KEY_HREF: &[u8] = b"href";
     const KEY_TIME: &[u8] = b"time";
@@ -123,13 +313,14 @@ pub mod xml {
     const KEY_TOREAD: &[u8] = b"toread";
 
     const YES: &str = "yes";
+    const NO: &str = "no";
 
-    const EVENT_POSTS: &[u8] = b"posts";
+    const EVENT_POSTS: &str = "posts";
     const EVENT_POST: &[u8] = b"post";
 
-    impl Post {
-        fn from_attrs(attrs: Attributes) -> Result<Post, Error> {
-            let mut ret = Post::default();
+    impl<'a> PostRef<'a> {
+        fn from_attrs(attrs: Attributes<'a>) -> Result<PostRef<'a>, Error> {
+            let mut ret = PostRef::default();
 
             for result in attrs {
                 let attr = result?;
@@ -137,29 +328,25 @@ pub mod xml {
                 let value = attr.unescape_value()?;
                 match key.local_name().as_ref() {
                     KEY_HREF => {
-                        ret.href = value.into_owned();
+                        ret.href = value;
                     }
                     KEY_TIME => {
-                        ret.time = value.into_owned();
+                        ret.time = value;
                     }
                     KEY_DESCRIPTION if !value.is_empty() => {
-                        ret.description = Some(value.into_owned());
+                        ret.description = Some(value);
                     }
                     KEY_EXTENDED if !value.is_empty() => {
-                        ret.extended = Some(value.into_owned());
+                        ret.extended = Some(value);
                     }
                     KEY_TAG if !value.is_empty() => {
-                        ret.tags = value
-                            .into_owned()
-                            .split_whitespace()
-                            .map(ToOwned::to_owned)
-                            .collect();
+                        ret.tags = split_tags(value);
                     }
                     KEY_META if !value.is_empty() => {
-                        ret.meta = Some(value.into_owned());
+                        ret.meta = Some(value);
                     }
                     KEY_HASH if !value.is_empty() => {
-                        ret.hash = Some(value.into_owned());
+                        ret.hash = Some(value);
                     }
                     KEY_SHARED => {
                         ret.shared = value.as_ref() == YES;
@@ -173,7 +360,9 @@ pub mod xml {
 
             Ok(ret)
         }
+    }
 
+    impl Post {
         /// Parse Pinboard posts from XML format.
         ///
         /// # Errors
@@ -181,26 +370,87 @@ pub mod xml {
         /// Returns an error if the XML is malformed, contains invalid UTF-8, or has missing required attributes.
         pub fn from_xml(reader: &mut impl BufRead) -> Result<Vec<Post>, Error> {
             let mut ret = Vec::new();
+            Post::for_each_xml(reader, |post| {
+                ret.push(Post::from(post));
+                Ok::<(), Error>(())
+            })?;
+            Ok(ret)
+        }
+
+        /// Parse Pinboard posts from XML format, passing each one to `f` without copying it.
+        ///
+        /// Each [`PostRef`] borrows from an internal buffer that is reused for the next post, so
+        /// `f` must convert it into an owned form if it needs to keep it.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if the XML is malformed or contains invalid UTF-8, or the first error
+        /// returned by `f`.
+        pub fn for_each_xml<E>(
+            reader: &mut impl BufRead,
+            mut f: impl FnMut(PostRef<'_>) -> Result<(), E>,
+        ) -> Result<(), E>
+        where
+            E: From<Error>,
+        {
             let mut reader = Reader::from_reader(reader);
             reader.config_mut().trim_text(true);
 
             let mut buf = Vec::new();
 
             loop {
-                match reader.read_event_into(&mut buf)? {
-                    Event::Start(e) if e.name().as_ref() == EVENT_POSTS => {
-                        // nothing at the moment
-                    }
+                match reader.read_event_into(&mut buf).map_err(Error::from)? {
                     Event::Empty(e) if e.name().as_ref() == EVENT_POST => {
-                        let post = Post::from_attrs(e.attributes())?;
-                        ret.push(post);
+                        let post = PostRef::from_attrs(e.attributes())?;
+                        f(post)?;
                     }
                     Event::Eof => break,
                     _ => (),
                 }
+                buf.clear();
             }
 
-            Ok(ret)
+            Ok(())
+        }
+
+        /// Write Pinboard posts in XML format, as exported by Pinboard.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if writing to the output fails.
+        pub fn to_xml(writer: &mut impl Write, posts: &[Post]) -> Result<(), Error> {
+            let mut writer = Writer::new(writer);
+            writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+            writer.write_event(Event::Text(BytesText::new("\n")))?;
+            writer.write_event(Event::Start(BytesStart::new(EVENT_POSTS)))?;
+            for post in posts {
+                writer.write_event(Event::Text(BytesText::new("\n")))?;
+                writer.write_event(Event::Empty(post.to_elem()))?;
+            }
+            writer.write_event(Event::Text(BytesText::new("\n")))?;
+            writer.write_event(Event::End(BytesEnd::new(EVENT_POSTS)))?;
+            writer.write_event(Event::Text(BytesText::new("\n")))?;
+            Ok(())
+        }
+
+        fn to_elem(&self) -> BytesStart<'_> {
+            let yes_no = |value: bool| if value { YES } else { NO };
+            let mut ret = BytesStart::new("post");
+            ret.push_attribute(("href", self.href.as_str()));
+            ret.push_attribute(("time", self.time.as_str()));
+            ret.push_attribute((
+                "description",
+                self.description.as_deref().unwrap_or_default(),
+            ));
+            ret.push_attribute(("extended", self.extended.as_deref().unwrap_or_default()));
+            ret.push_attribute(("tag", self.tags.join(" ").as_str()));
+            ret.push_attribute(("hash", self.hash.as_deref().unwrap_or_default()));
+            if let Some(meta) = &self.meta {
+                ret.push_attribute(("meta", meta.as_str()));
+            }
+            ret.push_attribute(("shared", yes_no(self.shared)));
+            ret.push_attribute(("toread", yes_no(self.toread)));
+            ret
         }
     }
 }
@@ -31,17 +31,39 @@ impl Parse for Args {
     }
 }
 
+/// Arguments whose extension is optional, e.g. `("test-data/html")` or `("test-data/html", "html")`.
+struct FormatterArgs {
+    path: LitStr,
+    maybe_ext: Option<LitStr>,
+}
+
+impl Parse for FormatterArgs {
+    fn parse(input: ParseStream) -> syn::Result<FormatterArgs> {
+        let path: LitStr = input.parse()?;
+        let maybe_ext = if input.is_empty() {
+            None
+        } else {
+            input.parse::<Token![,]>()?;
+            Some(input.parse()?)
+        };
+        Ok(FormatterArgs { path, maybe_ext })
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 struct TestCase {
     stem: String,
     input_path: String,
     expected_path: String,
+    /// Whether `expected_path` is an `*.error.txt` fixture describing how parsing fails.
+    expects_error: bool,
 }
 
 struct TestCaseBuilder {
     stem: String,
     input_path: Option<PathBuf>,
     expected_path: Option<PathBuf>,
+    error_path: Option<PathBuf>,
 }
 
 impl TestCaseBuilder {
@@ -50,6 +72,7 @@ impl TestCaseBuilder {
             stem,
             input_path: None,
             expected_path: None,
+            error_path: None,
         }
     }
 
@@ -61,11 +84,21 @@ impl TestCaseBuilder {
         self.expected_path = Some(path);
     }
 
+    fn set_error(&mut self, path: PathBuf) {
+        self.error_path = Some(path);
+    }
+
     fn build(self) -> Option<TestCase> {
+        let expects_error = self.expected_path.is_none();
         Some(TestCase {
             stem: self.stem,
             input_path: self.input_path?.to_str()?.to_string(),
-            expected_path: self.expected_path?.to_str()?.to_string(),
+            expected_path: self
+                .expected_path
+                .or(self.error_path)?
+                .to_str()?
+                .to_string(),
+            expects_error,
         })
     }
 }
@@ -114,6 +147,12 @@ fn discover_parser_tests(base_path: &Path, input_ext: &str) -> Result<Vec<TestCa
                     .or_insert_with(|| TestCaseBuilder::new((*stem).to_string()));
                 builder.set_expected(path.to_path_buf());
             }
+            [stem, "error", "txt"] => {
+                let builder = builders
+                    .entry((*stem).to_string())
+                    .or_insert_with(|| TestCaseBuilder::new((*stem).to_string()));
+                builder.set_error(path.to_path_buf());
+            }
             _ => {}
         }
     }
@@ -127,7 +166,13 @@ fn discover_parser_tests(base_path: &Path, input_ext: &str) -> Result<Vec<TestCa
     Ok(test_cases)
 }
 
-fn discover_formatter_tests(base_path: &Path, output_ext: &str) -> Result<Vec<TestCase>, String> {
+/// Discovers formatter tests, pairing each input with every expected output whose extension is
+/// `maybe_output_ext`, or with every expected output if it is `None`. In the latter case, test
+/// names are suffixed with the output extension.
+fn discover_formatter_tests(
+    base_path: &Path,
+    maybe_output_ext: Option<&str>,
+) -> Result<Vec<TestCase>, String> {
     if !base_path.exists() {
         return Err(format!(
             "Test data directory does not exist: {}",
@@ -135,7 +180,8 @@ fn discover_formatter_tests(base_path: &Path, output_ext: &str) -> Result<Vec<Te
         ));
     }
 
-    let mut builders: BTreeMap<String, TestCaseBuilder> = BTreeMap::new();
+    let mut inputs: BTreeMap<String, PathBuf> = BTreeMap::new();
+    let mut expected: Vec<(String, String, PathBuf)> = Vec::new();
 
     for entry in WalkDir::new(base_path)
         .min_depth(1)
@@ -156,30 +202,75 @@ fn discover_formatter_tests(base_path: &Path, output_ext: &str) -> Result<Vec<Te
 
         match parts.as_slice() {
             [stem, "input", _] => {
-                let builder = builders
-                    .entry((*stem).to_string())
-                    .or_insert_with(|| TestCaseBuilder::new((*stem).to_string()));
-                builder.set_input(path.to_path_buf());
+                inputs.insert((*stem).to_string(), path.to_path_buf());
             }
-            [stem, "expected", ext] if *ext == output_ext => {
-                let builder = builders
-                    .entry((*stem).to_string())
-                    .or_insert_with(|| TestCaseBuilder::new((*stem).to_string()));
-                builder.set_expected(path.to_path_buf());
+            [stem, "expected", ext]
+                if maybe_output_ext.is_none_or(|output_ext| *ext == output_ext) =>
+            {
+                expected.push(((*stem).to_string(), (*ext).to_string(), path.to_path_buf()));
             }
             _ => {}
         }
     }
 
-    let mut test_cases: Vec<TestCase> = builders
-        .into_values()
-        .filter_map(TestCaseBuilder::build)
+    let mut test_cases: Vec<TestCase> = expected
+        .into_iter()
+        .filter_map(|(stem, ext, expected_path)| {
+            let input_path = inputs.get(&stem)?;
+            let stem = if maybe_output_ext.is_some() {
+                stem
+            } else {
+                format!("{stem}_{ext}")
+            };
+            Some(TestCase {
+                stem,
+                input_path: input_path.to_str()?.to_string(),
+                expected_path: expected_path.to_str()?.to_string(),
+                expects_error: false,
+            })
+        })
         .collect();
 
     test_cases.sort();
     Ok(test_cases)
 }
 
+fn discover_inputs(base_path: &Path) -> Result<Vec<(String, String)>, String> {
+    if !base_path.exists() {
+        return Err(format!(
+            "Test data directory does not exist: {}",
+            base_path.display()
+        ));
+    }
+
+    let mut inputs = Vec::new();
+
+    for entry in WalkDir::new(base_path)
+        .min_depth(1)
+        .max_depth(2)
+        .into_iter()
+        .filter_map(Result::ok)
+    {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        let Some(filename) = path.file_name().and_then(OsStr::to_str) else {
+            continue;
+        };
+
+        if let [stem, "input", _] = split_filename(filename).as_slice()
+            && let Some(path) = path.to_str()
+        {
+            inputs.push(((*stem).to_string(), path.to_string()));
+        }
+    }
+
+    inputs.sort();
+    Ok(inputs)
+}
+
 fn resolve_path(rel_path: &str) -> Result<PathBuf, String> {
     let manifest_dir = std::env::var("CARGO_MANIFEST_DIR")
         .map_err(|_| "CARGO_MANIFEST_DIR environment variable not set".to_string())?;
@@ -225,22 +316,64 @@ pub fn test_parser(input: TokenStream) -> TokenStream {
         let input_path = &tc.input_path;
         let expected_path = &tc.expected_path;
 
-        quote! {
-            #[test]
-            fn #test_ident() -> Result<(), Box<dyn std::error::Error>> {
-                test_parser_input(#input_path, #expected_path)?;
-                Ok(())
+        if tc.expects_error {
+            quote! {
+                #[test]
+                fn #test_ident() -> Result<(), Box<dyn std::error::Error>> {
+                    test_parser_error(#input_path, #expected_path)?;
+                    Ok(())
+                }
+            }
+        } else {
+            quote! {
+                #[test]
+                fn #test_ident() -> Result<(), Box<dyn std::error::Error>> {
+                    test_parser_input(#input_path, #expected_path)?;
+                    Ok(())
+                }
             }
         }
     });
 
     let expanded = quote! {
         use std::io::BufReader;
-        use std::fs::File;
+        use std::fs::{File, read_to_string};
 
         use hbt_core::InputFormat;
         use hbt_core::collection::Collection;
 
+        /// Asserts that parsing fails, with each non-empty line of the expected file appearing in
+        /// either the error's message or its `Debug` representation, which names its kind.
+        #[allow(dead_code)]
+        fn test_parser_error(input_path: &str, expected_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+            let input_format = InputFormat::detect(input_path)
+                .ok_or_else(|| format!("Could not detect format for: {}", input_path))?;
+
+            let input_file = File::open(input_path)?;
+            let mut input_reader = BufReader::new(input_file);
+            let Err(err) = input_format.parse(&mut input_reader) else {
+                return Err(format!("Expected parsing to fail for input: {}", input_path).into());
+            };
+
+            let message = err.to_string();
+            let debug = format!("{:?}", err);
+            let expected = read_to_string(expected_path)?;
+            for line in expected.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                assert!(
+                    message.contains(line) || debug.contains(line),
+                    "Error mismatch for input: {}\nExpected from: {}\nMissing: {}\nActual: {} ({})",
+                    input_path,
+                    expected_path,
+                    line,
+                    message,
+                    debug
+                );
+            }
+
+            Ok(())
+        }
+
+        #[allow(dead_code)]
         fn test_parser_input(input_path: &str, expected_path: &str) -> Result<(), Box<dyn std::error::Error>> {
             let input_format = InputFormat::detect(input_path)
                 .ok_or_else(|| format!("Could not detect format for: {}", input_path))?;
@@ -272,7 +405,7 @@ pub fn test_parser(input: TokenStream) -> TokenStream {
 
 #[proc_macro]
 pub fn test_formatter(input: TokenStream) -> TokenStream {
-    let args: Args = syn::parse_macro_input!(input);
+    let args: FormatterArgs = syn::parse_macro_input!(input);
 
     let base_path = match resolve_path(&args.path.value()) {
         Ok(path) => path,
@@ -282,7 +415,8 @@ pub fn test_formatter(input: TokenStream) -> TokenStream {
         }
     };
 
-    let test_cases = match discover_formatter_tests(&base_path, &args.ext.value()) {
+    let maybe_ext = args.maybe_ext.as_ref().map(LitStr::value);
+    let test_cases = match discover_formatter_tests(&base_path, maybe_ext.as_deref()) {
         Ok(cases) => cases,
         Err(err) => {
             let error = Error::new(args.path.span(), err);
@@ -343,3 +477,85 @@ pub fn test_formatter(input: TokenStream) -> TokenStream {
 
     TokenStream::from(expanded)
 }
+
+/// Generates a test for each `*.input.*` fixture that parses it, writes it in the output format
+/// named by the extension argument, parses the result again, and asserts that both collections are
+/// equal.
+#[proc_macro]
+pub fn test_roundtrip(input: TokenStream) -> TokenStream {
+    let args: Args = syn::parse_macro_input!(input);
+
+    let base_path = match resolve_path(&args.path.value()) {
+        Ok(path) => path,
+        Err(err) => {
+            let error = Error::new(args.path.span(), err);
+            return error.to_compile_error().into();
+        }
+    };
+
+    let inputs = match discover_inputs(&base_path) {
+        Ok(inputs) => inputs,
+        Err(err) => {
+            let error = Error::new(args.path.span(), err);
+            return error.to_compile_error().into();
+        }
+    };
+
+    let ext = args.ext.value();
+    let tests = inputs.iter().map(|(stem, input_path)| {
+        let test_ident = Ident::new(&format!("test_{stem}"), Span::call_site());
+
+        quote! {
+            #[test]
+            fn #test_ident() -> Result<(), Box<dyn std::error::Error>> {
+                test_roundtrip_input(#input_path, #ext)?;
+                Ok(())
+            }
+        }
+    });
+
+    let expanded = quote! {
+        use std::io::BufReader;
+        use std::fs::File;
+
+        use hbt_core::{InputFormat, OutputFormat};
+        use hbt_core::collection::Collection;
+
+        fn test_roundtrip_input(input_path: &str, ext: &str) -> Result<(), Box<dyn std::error::Error>> {
+            let input_format = InputFormat::detect(input_path)
+                .ok_or_else(|| format!("Could not detect format for: {}", input_path))?;
+            let output_path = format!("output.{}", ext);
+            let output_format = OutputFormat::detect(&output_path)
+                .ok_or_else(|| format!("Could not detect output format for: {}", ext))?;
+
+            let input_file = File::open(input_path)?;
+            let mut input_reader = BufReader::new(input_file);
+            let collection = input_format.parse(&mut input_reader)?;
+
+            let mut output = Vec::new();
+            output_format.unparse(&mut output, &collection)?;
+
+            let roundtripped: Collection = if output_format == OutputFormat::Yaml {
+                serde_norway::from_slice(&output)?
+            } else {
+                let reparse_format = InputFormat::detect(&output_path)
+                    .ok_or_else(|| format!("Could not detect input format for: {}", ext))?;
+                reparse_format.parse(&mut output.as_slice())?
+            };
+
+            assert_eq!(
+                collection,
+                roundtripped,
+                "Roundtrip mismatch for input: {}\nThrough format: {}",
+                input_path,
+                ext
+            );
+
+            Ok(())
+        }
+
+        #(#tests)*
+    };
+
+    TokenStream::from(expanded)
+}
@@ -0,0 +1,37 @@
+use hbt_core::InputFormat;
+use hbt_gen::{Format, generate};
+
+const COUNT: usize = 500;
+const SEED: u64 = 42;
+
+fn parse(format: Format, input_format: InputFormat) {
+    let input = generate(format, COUNT, SEED);
+    assert_eq!(input, generate(format, COUNT, SEED));
+    let coll = input_format.parse(&mut input.as_bytes()).unwrap();
+    assert_eq!(coll.len(), COUNT);
+}
+
+#[test]
+fn json() {
+    parse(Format::Json, InputFormat::Json);
+}
+
+#[test]
+fn xml() {
+    parse(Format::Xml, InputFormat::Xml);
+}
+
+#[test]
+fn markdown() {
+    parse(Format::Md, InputFormat::Markdown);
+}
+
+#[test]
+fn html() {
+    parse(Format::Html, InputFormat::Html);
+}
+
+#[test]
+fn html_folders() {
+    parse(Format::HtmlFolders, InputFormat::Html);
+}
@@ -15,3 +15,14 @@ mod pinboard {
         hbt_test_macros::test_parser!("test-data/pinboard/xml", "xml");
     }
 }
+
+/// Fixtures kept in this repository rather than in `test-data`.
+mod fixtures {
+    mod html {
+        hbt_test_macros::test_parser!("test/fixtures/html", "html");
+    }
+
+    mod markdown {
+        hbt_test_macros::test_parser!("test/fixtures/markdown", "md");
+    }
+}
@@ -0,0 +1,7 @@
+mod html {
+    hbt_test_macros::test_roundtrip!("test-data/html", "yaml");
+}
+
+mod markdown {
+    hbt_test_macros::test_roundtrip!("test-data/markdown", "yaml");
+}